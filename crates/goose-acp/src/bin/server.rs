@@ -31,6 +31,9 @@ async fn main() -> Result<()> {
         .init();
 
     register_builtin_extensions(goose_mcp::BUILTIN_EXTENSIONS.clone());
+    if let Some(embedder) = goose::providers::embedding::memory_embedder() {
+        goose_mcp::set_memory_embedder(embedder);
+    }
 
     let cli = Cli::parse();
 
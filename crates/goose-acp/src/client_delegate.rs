@@ -0,0 +1,165 @@
+//! Routes the developer tools' file and shell work through the ACP client, using whichever of
+//! `fs/read_text_file`, `fs/write_text_file` and the terminal methods it advertised.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use goose::agents::{ClientCommandOutput, ClientDelegate};
+use sacp::schema::{
+    ClientCapabilities, CreateTerminalRequest, KillTerminalCommandRequest, ReadTextFileRequest,
+    ReleaseTerminalRequest, TerminalOutputRequest, WaitForTerminalExitRequest,
+    WriteTextFileRequest,
+};
+use sacp::{AgentToClient, JrConnectionCx};
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+/// Output the client keeps per terminal; the shell tool truncates well below this anyway
+const TERMINAL_OUTPUT_BYTE_LIMIT: u64 = 1_000_000;
+
+pub(crate) struct AcpClientDelegate {
+    cx: JrConnectionCx<AgentToClient>,
+    capabilities: ClientCapabilities,
+}
+
+impl AcpClientDelegate {
+    /// None when the client offers neither file access nor a terminal
+    pub(crate) fn new(
+        cx: JrConnectionCx<AgentToClient>,
+        capabilities: ClientCapabilities,
+    ) -> Option<Self> {
+        let offers_any = capabilities.fs.read_text_file
+            || capabilities.fs.write_text_file
+            || capabilities.terminal;
+        offers_any.then_some(Self { cx, capabilities })
+    }
+}
+
+/// The program and arguments that run a command line the way the local shell tool would
+fn shell_invocation(command_line: &str) -> (String, Vec<String>) {
+    if cfg!(windows) {
+        return (
+            "cmd".to_string(),
+            vec!["/C".to_string(), command_line.to_string()],
+        );
+    }
+    let shell = if Path::new("/bin/bash").is_file() {
+        "/bin/bash".to_string()
+    } else {
+        std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string())
+    };
+    (shell, vec!["-c".to_string(), command_line.to_string()])
+}
+
+#[async_trait]
+impl ClientDelegate for AcpClientDelegate {
+    fn can_read_files(&self) -> bool {
+        self.capabilities.fs.read_text_file
+    }
+
+    fn can_write_files(&self) -> bool {
+        self.capabilities.fs.write_text_file
+    }
+
+    fn has_terminal(&self) -> bool {
+        self.capabilities.terminal
+    }
+
+    async fn read_text_file(&self, session_id: &str, path: &Path) -> Result<String> {
+        let response = self
+            .cx
+            .send_request(ReadTextFileRequest::new(session_id.to_string(), path))
+            .block_task()
+            .await?;
+        Ok(response.content)
+    }
+
+    async fn write_text_file(&self, session_id: &str, path: &Path, content: &str) -> Result<()> {
+        self.cx
+            .send_request(WriteTextFileRequest::new(
+                session_id.to_string(),
+                path,
+                content,
+            ))
+            .block_task()
+            .await?;
+        Ok(())
+    }
+
+    async fn run_command(
+        &self,
+        session_id: &str,
+        command_line: &str,
+        working_dir: Option<&Path>,
+        timeout_secs: Option<u64>,
+    ) -> Result<ClientCommandOutput> {
+        let (program, args) = shell_invocation(command_line);
+        let terminal_id = self
+            .cx
+            .send_request(
+                CreateTerminalRequest::new(session_id.to_string(), program)
+                    .args(args)
+                    .cwd(working_dir.map(Path::to_path_buf))
+                    .output_byte_limit(TERMINAL_OUTPUT_BYTE_LIMIT),
+            )
+            .block_task()
+            .await?
+            .terminal_id;
+
+        let wait = self
+            .cx
+            .send_request(WaitForTerminalExitRequest::new(
+                session_id.to_string(),
+                terminal_id.clone(),
+            ))
+            .block_task();
+        let (exit_status, timed_out) = match timeout_secs {
+            Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), wait).await {
+                Ok(response) => (Some(response?.exit_status), false),
+                Err(_) => {
+                    self.cx
+                        .send_request(KillTerminalCommandRequest::new(
+                            session_id.to_string(),
+                            terminal_id.clone(),
+                        ))
+                        .block_task()
+                        .await?;
+                    (None, true)
+                }
+            },
+            None => (Some(wait.await?.exit_status), false),
+        };
+
+        let output = self
+            .cx
+            .send_request(TerminalOutputRequest::new(
+                session_id.to_string(),
+                terminal_id.clone(),
+            ))
+            .block_task()
+            .await?;
+        // The client keeps showing a released terminal's output wherever it is embedded
+        if let Err(e) = self
+            .cx
+            .send_request(ReleaseTerminalRequest::new(
+                session_id.to_string(),
+                terminal_id.clone(),
+            ))
+            .block_task()
+            .await
+        {
+            warn!(terminal_id = %terminal_id, error = ?e, "failed to release terminal");
+        }
+
+        let exit_code = exit_status
+            .or(output.exit_status)
+            .and_then(|status| status.exit_code)
+            .and_then(|code| i32::try_from(code).ok());
+        Ok(ClientCommandOutput {
+            output: output.output,
+            exit_code,
+            timed_out,
+            terminal_id: Some(terminal_id.to_string()),
+        })
+    }
+}
@@ -1,6 +1,7 @@
 #![recursion_limit = "256"]
 
 mod adapters;
+mod client_delegate;
 pub mod custom_requests;
 pub mod server;
 pub mod server_factory;
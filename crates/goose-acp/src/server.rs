@@ -1,14 +1,16 @@
+use crate::client_delegate::AcpClientDelegate;
 use crate::custom_requests::*;
 use anyhow::Result;
 use fs_err as fs;
 use goose::agents::extension::{Envs, PLATFORM_EXTENSIONS};
+use goose::agents::platform_extensions::developer::shell::TERMINAL_ID_META;
 use goose::agents::{Agent, AgentConfig, ExtensionConfig, GoosePlatform, SessionConfig};
 use goose::builtin_extension::register_builtin_extensions;
 use goose::config::base::CONFIG_YAML_NAME;
 use goose::config::extensions::get_enabled_extensions_with_config;
 use goose::config::paths::Paths;
 use goose::config::permission::PermissionManager;
-use goose::config::Config;
+use goose::config::{Config, GooseMode};
 use goose::conversation::message::{ActionRequiredData, Message, MessageContent};
 use goose::conversation::Conversation;
 use goose::mcp_utils::ToolResult;
@@ -16,20 +18,24 @@ use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::{Permission, PermissionConfirmation};
 use goose::providers::base::Provider;
 use goose::providers::provider_registry::ProviderConstructor;
+use goose::providers::response_cache::CachingProvider;
+use goose::session::extension_data::{TodoItem, TodoList, TodoStatus};
 use goose::session::session_manager::SessionType;
 use goose::session::{Session, SessionManager};
 use goose_acp_macros::custom_methods;
 use rmcp::model::{CallToolResult, RawContent, ResourceContents, Role};
 use sacp::schema::{
     AgentCapabilities, AuthMethod, AuthenticateRequest, AuthenticateResponse, BlobResourceContents,
-    CancelNotification, Content, ContentBlock, ContentChunk, EmbeddedResource,
-    EmbeddedResourceResource, ImageContent, InitializeRequest, InitializeResponse,
-    ListSessionsResponse, LoadSessionRequest, LoadSessionResponse, McpCapabilities, McpServer,
-    ModelId, ModelInfo, NewSessionRequest, NewSessionResponse, PermissionOption,
-    PermissionOptionKind, PromptCapabilities, PromptRequest, PromptResponse,
-    RequestPermissionOutcome, RequestPermissionRequest, ResourceLink, SessionCapabilities,
-    SessionId, SessionInfo, SessionListCapabilities, SessionModelState, SessionNotification,
-    SessionUpdate, SetSessionModelRequest, SetSessionModelResponse, StopReason, TextContent,
+    CancelNotification, ClientCapabilities, Content, ContentBlock, ContentChunk, CurrentModeUpdate,
+    EmbeddedResource, EmbeddedResourceResource, ImageContent, InitializeRequest,
+    InitializeResponse, ListSessionsResponse, LoadSessionRequest, LoadSessionResponse,
+    McpCapabilities, McpServer, ModelId, ModelInfo, NewSessionRequest, NewSessionResponse,
+    PermissionOption, PermissionOptionKind, Plan, PlanEntry, PlanEntryPriority, PlanEntryStatus,
+    PromptCapabilities, PromptRequest, PromptResponse, RequestPermissionOutcome,
+    RequestPermissionRequest, ResourceLink, SessionCapabilities, SessionId, SessionInfo,
+    SessionListCapabilities, SessionMode, SessionModeId, SessionModeState, SessionModelState,
+    SessionNotification, SessionUpdate, SetSessionModeRequest, SetSessionModeResponse,
+    SetSessionModelRequest, SetSessionModelResponse, StopReason, Terminal, TextContent,
     TextResourceContents, ToolCall, ToolCallContent, ToolCallId, ToolCallLocation, ToolCallStatus,
     ToolCallUpdate, ToolCallUpdateFields, ToolKind,
 };
@@ -57,9 +63,10 @@ pub struct GooseAcpAgent {
     config_dir: std::path::PathBuf,
     session_manager: Arc<SessionManager>,
     permission_manager: Arc<PermissionManager>,
-    goose_mode: goose::config::GooseMode,
+    goose_mode: GooseMode,
     disable_session_naming: bool,
     builtins: Vec<String>,
+    client_capabilities: Mutex<ClientCapabilities>,
 }
 
 fn mcp_server_to_extension_config(mcp_server: McpServer) -> Result<ExtensionConfig, String> {
@@ -287,6 +294,83 @@ async fn add_extensions(agent: &Agent, extensions: Vec<ExtensionConfig>) {
     }
 }
 
+fn build_mode_state(current: GooseMode) -> SessionModeState {
+    let modes = [
+        (
+            GooseMode::Auto,
+            "Auto",
+            "Edit, create and delete files and use extensions freely",
+        ),
+        (
+            GooseMode::Approve,
+            "Approve",
+            "All tools, extensions and file modifications require approval",
+        ),
+        (
+            GooseMode::SmartApprove,
+            "Smart Approve",
+            "Only tools that modify files or use extensions require approval",
+        ),
+        (
+            GooseMode::Chat,
+            "Chat",
+            "Chat with the provider without using tools or extensions",
+        ),
+    ];
+    SessionModeState::new(
+        mode_id(current),
+        modes
+            .into_iter()
+            .map(|(mode, name, description)| {
+                SessionMode::new(mode_id(mode), name).description(description.to_string())
+            })
+            .collect(),
+    )
+}
+
+fn mode_id(mode: GooseMode) -> SessionModeId {
+    let id = serde_json::to_value(mode).unwrap();
+    SessionModeId::new(id.as_str().unwrap())
+}
+
+fn is_todo_write_tool(tool_name: &str) -> bool {
+    matches!(tool_name, "todo__todo_add" | "todo__todo_update")
+}
+
+/// Flatten the todo tree into plan entries, children right after their parent. ACP has no
+/// blocked status, so blocked items stay pending with a marker in their text.
+fn todo_list_to_plan(list: &TodoList) -> Plan {
+    fn push_entries(list: &TodoList, item: &TodoItem, entries: &mut Vec<PlanEntry>) {
+        let (status, content) = match item.status {
+            TodoStatus::Pending => (PlanEntryStatus::Pending, item.title.clone()),
+            TodoStatus::InProgress => (PlanEntryStatus::InProgress, item.title.clone()),
+            TodoStatus::Done => (PlanEntryStatus::Completed, item.title.clone()),
+            TodoStatus::Blocked => (
+                PlanEntryStatus::Pending,
+                format!("{} (blocked)", item.title),
+            ),
+        };
+        entries.push(PlanEntry::new(content, PlanEntryPriority::Medium, status));
+        for child in list
+            .items
+            .iter()
+            .filter(|child| child.parent_id == Some(item.id))
+        {
+            push_entries(list, child, entries);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for item in list
+        .items
+        .iter()
+        .filter(|item| item.parent_id.is_none_or(|p| list.get(p).is_none()))
+    {
+        push_entries(list, item, &mut entries);
+    }
+    Plan::new(entries)
+}
+
 async fn build_model_state(provider: &dyn Provider, current_model: &str) -> SessionModelState {
     let models = match provider.fetch_recommended_models().await {
         Ok(models) => models,
@@ -314,7 +398,7 @@ impl GooseAcpAgent {
         builtins: Vec<String>,
         data_dir: std::path::PathBuf,
         config_dir: std::path::PathBuf,
-        goose_mode: goose::config::GooseMode,
+        goose_mode: GooseMode,
         disable_session_naming: bool,
     ) -> Result<Self> {
         let session_manager = Arc::new(SessionManager::new(data_dir));
@@ -329,19 +413,24 @@ impl GooseAcpAgent {
             goose_mode,
             disable_session_naming,
             builtins,
+            client_capabilities: Mutex::new(ClientCapabilities::default()),
         })
     }
 
-    async fn create_agent_for_session(&self) -> Arc<Agent> {
-        let agent = Agent::with_config(AgentConfig::new(
+    async fn create_agent_for_session(&self, cx: &JrConnectionCx<AgentToClient>) -> Arc<Agent> {
+        let mut config = AgentConfig::new(
             Arc::clone(&self.session_manager),
             Arc::clone(&self.permission_manager),
             None,
             self.goose_mode,
             self.disable_session_naming,
             GoosePlatform::GooseCli,
-        ));
-        let agent = Arc::new(agent);
+        );
+        let capabilities = self.client_capabilities.lock().await.clone();
+        if let Some(delegate) = AcpClientDelegate::new(cx.clone(), capabilities) {
+            config = config.with_client_delegate(Arc::new(delegate));
+        }
+        let agent = Arc::new(Agent::with_config(config));
 
         let config_path = self.config_dir.join(CONFIG_YAML_NAME);
         if let Ok(config_file) = Config::new(&config_path, "goose") {
@@ -412,6 +501,14 @@ impl GooseAcpAgent {
             MessageContent::ToolResponse(tool_response) => {
                 self.handle_tool_response(tool_response, session_id, session, cx)
                     .await?;
+                let updates_todo = session
+                    .tool_requests
+                    .get(&tool_response.id)
+                    .and_then(|request| request.tool_call.as_ref().ok())
+                    .is_some_and(|call| is_todo_write_tool(&call.name));
+                if updates_todo {
+                    self.send_plan(session_id, cx).await?;
+                }
             }
             MessageContent::Thinking(thinking) => {
                 cx.send_notification(SessionNotification::new(
@@ -445,6 +542,27 @@ impl GooseAcpAgent {
         Ok(())
     }
 
+    /// Send the session's todo list as the client's plan, replacing whatever it showed before
+    async fn send_plan(
+        &self,
+        session_id: &SessionId,
+        cx: &JrConnectionCx<AgentToClient>,
+    ) -> Result<(), sacp::Error> {
+        let session = match self.session_manager.get_session(&session_id.0, false).await {
+            Ok(session) => session,
+            Err(e) => {
+                warn!(session_id = %session_id.0, error = %e, "failed to read todo list");
+                return Ok(());
+            }
+        };
+        let plan = todo_list_to_plan(&TodoList::load(&session.extension_data));
+        cx.send_notification(SessionNotification::new(
+            session_id.clone(),
+            SessionUpdate::Plan(plan),
+        ))?;
+        Ok(())
+    }
+
     async fn handle_tool_request(
         &self,
         tool_request: &goose::conversation::message::ToolRequest,
@@ -488,7 +606,10 @@ impl GooseAcpAgent {
             Err(_) => ToolCallStatus::Failed,
         };
 
-        let content = build_tool_call_content(&tool_response.tool_result);
+        let mut content = build_tool_call_content(&tool_response.tool_result);
+        if let Some(terminal_id) = tool_terminal_id(&tool_response.tool_result) {
+            content.push(ToolCallContent::Terminal(Terminal::new(terminal_id)));
+        }
 
         let locations = if let Some(tool_request) = session.tool_requests.get(&tool_response.id) {
             extract_tool_locations(tool_request, tool_response)
@@ -612,6 +733,15 @@ fn outcome_to_confirmation(outcome: &RequestPermissionOutcome) -> PermissionConf
     }
 }
 
+/// The client terminal a developer shell command ran in, so the tool call can show it
+fn tool_terminal_id(tool_result: &ToolResult<CallToolResult>) -> Option<String> {
+    let meta = tool_result.as_ref().ok()?.meta.as_ref()?;
+    meta.0
+        .get(TERMINAL_ID_META)
+        .and_then(|id| id.as_str())
+        .map(String::from)
+}
+
 fn build_tool_call_content(tool_result: &ToolResult<CallToolResult>) -> Vec<ToolCallContent> {
     match tool_result {
         Ok(result) => result
@@ -662,6 +792,7 @@ impl GooseAcpAgent {
         args: InitializeRequest,
     ) -> Result<InitializeResponse, sacp::Error> {
         debug!(?args, "initialize request");
+        *self.client_capabilities.lock().await = args.client_capabilities.clone();
 
         let capabilities = AgentCapabilities::new()
             .load_session(true)
@@ -687,6 +818,7 @@ impl GooseAcpAgent {
     async fn on_new_session(
         &self,
         args: NewSessionRequest,
+        cx: &JrConnectionCx<AgentToClient>,
     ) -> Result<NewSessionResponse, sacp::Error> {
         debug!(?args, "new session request");
 
@@ -702,7 +834,7 @@ impl GooseAcpAgent {
                 sacp::Error::internal_error().data(format!("Failed to create session: {}", e))
             })?;
 
-        let agent = self.create_agent_for_session().await;
+        let agent = self.create_agent_for_session(cx).await;
         let provider = self
             .init_provider(&agent, &goose_session)
             .await
@@ -724,6 +856,7 @@ impl GooseAcpAgent {
             }
         }
 
+        let mode_state = build_mode_state(agent.goose_mode().await);
         let session = GooseAcpSession {
             agent,
             messages: Conversation::new_unvalidated(Vec::new()),
@@ -743,7 +876,9 @@ impl GooseAcpAgent {
         let model_state =
             build_model_state(&*provider, &provider.get_model_config().model_name).await;
 
-        Ok(NewSessionResponse::new(SessionId::new(goose_session.id)).models(model_state))
+        Ok(NewSessionResponse::new(SessionId::new(goose_session.id))
+            .models(model_state)
+            .modes(mode_state))
     }
 
     async fn init_provider(&self, agent: &Agent, session: &Session) -> Result<Arc<dyn Provider>> {
@@ -758,6 +893,7 @@ impl GooseAcpAgent {
             }
         };
         let provider = (self.provider_factory)(model_config, Vec::new()).await?;
+        let provider = CachingProvider::wrap_for_recipe(provider, session.recipe.as_ref());
         agent.update_provider(provider.clone(), &session.id).await?;
         Ok(provider)
     }
@@ -780,7 +916,7 @@ impl GooseAcpAgent {
                     .data(format!("Failed to load session {}: {}", session_id, e))
             })?;
 
-        let agent = self.create_agent_for_session().await;
+        let agent = self.create_agent_for_session(cx).await;
         let provider = self
             .init_provider(&agent, &goose_session)
             .await
@@ -803,6 +939,7 @@ impl GooseAcpAgent {
                     .data(format!("Failed to update session working directory: {}", e))
             })?;
 
+        let mode_state = build_mode_state(agent.goose_mode().await);
         let mut session = GooseAcpSession {
             agent,
             messages: conversation.clone(),
@@ -856,6 +993,14 @@ impl GooseAcpAgent {
             }
         }
 
+        let todo_list = TodoList::load(&goose_session.extension_data);
+        if !todo_list.items.is_empty() {
+            cx.send_notification(SessionNotification::new(
+                args.session_id.clone(),
+                SessionUpdate::Plan(todo_list_to_plan(&todo_list)),
+            ))?;
+        }
+
         let mut sessions = self.sessions.lock().await;
         sessions.insert(session_id.clone(), session);
 
@@ -868,7 +1013,9 @@ impl GooseAcpAgent {
         let model_state =
             build_model_state(&*provider, &provider.get_model_config().model_name).await;
 
-        Ok(LoadSessionResponse::new().models(model_state))
+        Ok(LoadSessionResponse::new()
+            .models(model_state)
+            .modes(mode_state))
     }
 
     async fn on_prompt(
@@ -989,6 +1136,13 @@ impl GooseAcpAgent {
             .map_err(|e| {
                 sacp::Error::internal_error().data(format!("Failed to create provider: {}", e))
             })?;
+        let recipe = self
+            .session_manager
+            .get_session(session_id, false)
+            .await
+            .ok()
+            .and_then(|session| session.recipe);
+        let provider = CachingProvider::wrap_for_recipe(provider, recipe.as_ref());
 
         let agent = {
             let sessions = self.sessions.lock().await;
@@ -1007,6 +1161,28 @@ impl GooseAcpAgent {
         info!(session_id = %session_id, model_id = %model_id, "Model switched");
         Ok(SetSessionModelResponse::new())
     }
+
+    async fn on_set_mode(
+        &self,
+        args: SetSessionModeRequest,
+        cx: &JrConnectionCx<AgentToClient>,
+    ) -> Result<SetSessionModeResponse, sacp::Error> {
+        let mode: GooseMode = args
+            .mode_id
+            .0
+            .parse()
+            .map_err(|e: String| sacp::Error::invalid_params().data(e))?;
+        let agent = self.get_agent_for_session(&args.session_id.0).await?;
+        agent.set_goose_mode(mode).await;
+
+        cx.send_notification(SessionNotification::new(
+            args.session_id.clone(),
+            SessionUpdate::CurrentModeUpdate(CurrentModeUpdate::new(mode_id(mode))),
+        ))?;
+
+        info!(session_id = %args.session_id.0, mode = %args.mode_id, "Mode switched");
+        Ok(SetSessionModeResponse::new())
+    }
 }
 
 #[custom_methods]
@@ -1085,10 +1261,14 @@ impl GooseAcpAgent {
         }
         self.session_manager
             .update(&req.session_id)
-            .working_dir(path)
+            .working_dir(path.clone())
             .apply()
             .await
             .map_err(|e| sacp::Error::internal_error().data(e.to_string()))?;
+        // Running extensions keep going in the old directory, so move their roots along
+        if let Ok(agent) = self.get_agent_for_session(&req.session_id).await {
+            agent.extension_manager.update_working_dir(&path).await;
+        }
         Ok(EmptyResponse {})
     }
 
@@ -1235,7 +1415,7 @@ impl JrMessageHandler for GooseAcpHandler {
                 .await
                 .if_request(
                     |req: NewSessionRequest, req_cx: JrRequestCx<NewSessionResponse>| async {
-                        req_cx.respond(agent.on_new_session(req).await?)
+                        req_cx.respond(agent.on_new_session(req, &cx).await?)
                     },
                 )
                 .await
@@ -1245,6 +1425,12 @@ impl JrMessageHandler for GooseAcpHandler {
                     },
                 )
                 .await
+                .if_request(
+                    |req: SetSessionModeRequest, req_cx: JrRequestCx<SetSessionModeResponse>| async {
+                        req_cx.respond(agent.on_set_mode(req, &cx).await?)
+                    },
+                )
+                .await
                 .if_request(
                     |req: PromptRequest, req_cx: JrRequestCx<PromptResponse>| async {
                         let agent = agent.clone();
@@ -1338,6 +1524,9 @@ where
 
 pub async fn run(builtins: Vec<String>) -> Result<()> {
     register_builtin_extensions(goose_mcp::BUILTIN_EXTENSIONS.clone());
+    if let Some(embedder) = goose::providers::embedding::memory_embedder() {
+        goose_mcp::set_memory_embedder(embedder);
+    }
     info!("listening on stdio");
 
     let outgoing = tokio::io::stdout().compat_write();
@@ -1459,7 +1648,7 @@ print(\"hello, world\")
             format_tool_name("platform__manage_extensions"),
             "Platform: Manage Extensions"
         );
-        assert_eq!(format_tool_name("todo__write"), "Todo: Write");
+        assert_eq!(format_tool_name("todo__todo_add"), "Todo: Todo Add");
     }
 
     #[test]
@@ -1574,4 +1763,47 @@ print(\"hello, world\")
         let provider = MockModelProvider { models };
         build_model_state(&provider, current_model).await
     }
+
+    #[test_case(GooseMode::Auto; "auto")]
+    #[test_case(GooseMode::Approve; "approve")]
+    #[test_case(GooseMode::SmartApprove; "smart approve")]
+    #[test_case(GooseMode::Chat; "chat")]
+    fn test_build_mode_state(current: GooseMode) {
+        let state = build_mode_state(current);
+        assert_eq!(state.available_modes.len(), 4);
+        let parsed: GooseMode = state.current_mode_id.0.parse().unwrap();
+        assert_eq!(parsed, current);
+        for mode in &state.available_modes {
+            assert!(mode.id.0.parse::<GooseMode>().is_ok(), "{}", mode.id);
+        }
+    }
+
+    #[test]
+    fn test_todo_list_to_plan() {
+        let mut list = TodoList::default();
+        let release = list
+            .add("Release".into(), TodoStatus::InProgress, None, None)
+            .unwrap();
+        list.add("Write docs".into(), TodoStatus::Pending, None, None)
+            .unwrap();
+        list.add("Tag".into(), TodoStatus::Done, Some(release), None)
+            .unwrap();
+        list.add("Publish".into(), TodoStatus::Blocked, Some(release), None)
+            .unwrap();
+
+        let entries: Vec<_> = todo_list_to_plan(&list)
+            .entries
+            .into_iter()
+            .map(|e| (e.content, e.status))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("Release".to_string(), PlanEntryStatus::InProgress),
+                ("Tag".to_string(), PlanEntryStatus::Completed),
+                ("Publish (blocked)".to_string(), PlanEntryStatus::Pending),
+                ("Write docs".to_string(), PlanEntryStatus::Pending),
+            ]
+        );
+    }
 }
@@ -5,7 +5,8 @@
 #[path = "../fixtures/mod.rs"]
 pub mod fixtures;
 use fixtures::{
-    initialize_agent, Connection, OpenAiFixture, PermissionDecision, Session, TestConnectionConfig,
+    initialize_agent, Connection, FakeEditor, OpenAiFixture, PermissionDecision, Session,
+    TestConnectionConfig,
 };
 use fs_err as fs;
 use goose::config::base::CONFIG_YAML_NAME;
@@ -13,8 +14,11 @@ use goose::config::GooseMode;
 use goose::providers::provider_registry::ProviderConstructor;
 use goose_acp::server::GooseAcpAgent;
 use goose_test_support::{ExpectedSessionId, McpFixture, FAKE_CODE, TEST_MODEL};
-use sacp::schema::{McpServer, McpServerHttp, ModelId, ToolCallStatus};
-use std::sync::Arc;
+use sacp::schema::{
+    ClientCapabilities, FileSystemCapability, McpServer, McpServerHttp, ModelId, ToolCallStatus,
+};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 pub async fn run_config_mcp<C: Connection>() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -297,6 +301,74 @@ pub async fn run_prompt_codemode<C: Connection>() {
     expected_session_id.assert_matches(&session.session_id().0);
 }
 
+pub async fn run_prompt_client_delegation<C: Connection>() {
+    let expected_session_id = ExpectedSessionId::default();
+    let path = "/tmp/goose-acp-client-write.txt";
+    let prompt = "Write 'hello from the editor' to /tmp/goose-acp-client-write.txt, then cat it.";
+    let openai = OpenAiFixture::new(
+        vec![
+            (
+                prompt.to_string(),
+                include_str!("../test_data/openai_client_write.txt"),
+            ),
+            (
+                format!("Created {path}"),
+                include_str!("../test_data/openai_client_shell.txt"),
+            ),
+            (
+                "hello from the editor terminal".into(),
+                include_str!("../test_data/openai_client_final.txt"),
+            ),
+        ],
+        expected_session_id.clone(),
+    )
+    .await;
+
+    let editor = Arc::new(Mutex::new(FakeEditor {
+        terminal_output: "hello from the editor terminal\n".to_string(),
+        ..Default::default()
+    }));
+    let config = TestConnectionConfig {
+        builtins: vec!["developer".to_string()],
+        client_capabilities: ClientCapabilities::new()
+            .fs(FileSystemCapability::new()
+                .read_text_file(true)
+                .write_text_file(true))
+            .terminal(true),
+        editor: editor.clone(),
+        ..Default::default()
+    };
+
+    let _ = fs::remove_file(path);
+
+    let mut conn = C::new(config, openai).await;
+    let (mut session, _) = conn.new_session().await;
+    expected_session_id.set(session.session_id().0.to_string());
+
+    let output = session.prompt(prompt, PermissionDecision::Cancel).await;
+    if matches!(output.tool_status, Some(ToolCallStatus::Failed)) {
+        panic!("{}", output.text);
+    }
+    assert_eq!(
+        output.text,
+        "The editor terminal printed the file: hello from the editor"
+    );
+
+    // Both tools went through the client, so nothing touched the local filesystem
+    let editor = editor.lock().unwrap();
+    assert_eq!(
+        editor.files.get(Path::new(path)).map(String::as_str),
+        Some("hello from the editor\n")
+    );
+    assert!(!Path::new(path).exists());
+    assert_eq!(editor.commands.len(), 1);
+    assert_eq!(
+        editor.commands[0].last().map(String::as_str),
+        Some("cat /tmp/goose-acp-client-write.txt")
+    );
+    expected_session_id.assert_matches(&session.session_id().0);
+}
+
 pub async fn run_prompt_image<C: Connection>() {
     let expected_session_id = ExpectedSessionId::default();
     let mcp = McpFixture::new(Some(expected_session_id.clone())).await;
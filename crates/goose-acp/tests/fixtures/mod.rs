@@ -13,10 +13,11 @@ use goose::session_context::SESSION_ID_HEADER;
 use goose_acp::server::{serve, GooseAcpAgent};
 use goose_test_support::{ExpectedSessionId, TEST_MODEL};
 use sacp::schema::{
-    McpServer, PermissionOptionKind, RequestPermissionOutcome, RequestPermissionRequest,
-    RequestPermissionResponse, SelectedPermissionOutcome, SessionModelState, ToolCallStatus,
+    ClientCapabilities, McpServer, PermissionOptionKind, RequestPermissionOutcome,
+    RequestPermissionRequest, RequestPermissionResponse, SelectedPermissionOutcome,
+    SessionModelState, ToolCallStatus,
 };
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::path::Path;
 use std::path::PathBuf;
@@ -243,12 +244,24 @@ pub struct TestOutput {
     pub tool_status: Option<ToolCallStatus>,
 }
 
+/// The editor side of `fs/*` and `terminal/*` requests, for clients that advertise them
+#[derive(Default)]
+pub struct FakeEditor {
+    /// Open buffers by absolute path
+    pub files: HashMap<PathBuf, String>,
+    /// Each terminal command as program followed by its arguments
+    pub commands: Vec<Vec<String>>,
+    pub terminal_output: String,
+}
+
 pub struct TestConnectionConfig {
     pub mcp_servers: Vec<McpServer>,
     pub builtins: Vec<String>,
     pub goose_mode: GooseMode,
     pub data_root: PathBuf,
     pub provider_factory: Option<ProviderConstructor>,
+    pub client_capabilities: ClientCapabilities,
+    pub editor: Arc<Mutex<FakeEditor>>,
 }
 
 impl Default for TestConnectionConfig {
@@ -259,6 +272,8 @@ impl Default for TestConnectionConfig {
             goose_mode: GooseMode::Auto,
             data_root: PathBuf::new(),
             provider_factory: None,
+            client_capabilities: ClientCapabilities::default(),
+            editor: Arc::default(),
         }
     }
 }
@@ -5,9 +5,13 @@ use super::{
 use async_trait::async_trait;
 use goose::config::PermissionManager;
 use sacp::schema::{
-    ContentBlock, InitializeRequest, LoadSessionRequest, McpServer, NewSessionRequest,
-    PromptRequest, ProtocolVersion, RequestPermissionRequest, SessionModelState,
-    SessionNotification, SessionUpdate, StopReason, TextContent, ToolCallStatus,
+    ContentBlock, CreateTerminalRequest, CreateTerminalResponse, InitializeRequest,
+    LoadSessionRequest, McpServer, NewSessionRequest, PromptRequest, ProtocolVersion,
+    ReadTextFileRequest, ReadTextFileResponse, ReleaseTerminalRequest, ReleaseTerminalResponse,
+    RequestPermissionRequest, SessionModelState, SessionNotification, SessionUpdate, StopReason,
+    TerminalExitStatus, TerminalOutputRequest, TerminalOutputResponse, TextContent, ToolCallStatus,
+    WaitForTerminalExitRequest, WaitForTerminalExitResponse, WriteTextFileRequest,
+    WriteTextFileResponse,
 };
 use sacp::{ClientToAgent, JrConnectionCx};
 use std::sync::{Arc, Mutex};
@@ -71,6 +75,8 @@ impl Connection for ClientToAgentConnection {
             let updates_clone = updates.clone();
             let notify_clone = notify.clone();
             let permission_clone = permission.clone();
+            let editor = config.editor.clone();
+            let client_capabilities = config.client_capabilities.clone();
 
             let cx_holder: Arc<Mutex<Option<JrConnectionCx<ClientToAgent>>>> =
                 Arc::new(Mutex::new(None));
@@ -108,15 +114,85 @@ impl Connection for ClientToAgentConnection {
                         },
                         sacp::on_receive_request!(),
                     )
+                    .on_receive_request(
+                        {
+                            let editor = editor.clone();
+                            async move |req: ReadTextFileRequest, request_cx, _connection_cx| {
+                                let content = editor.lock().unwrap().files.get(&req.path).cloned();
+                                match content {
+                                    Some(content) => {
+                                        request_cx.respond(ReadTextFileResponse::new(content))
+                                    }
+                                    None => request_cx.respond_with_error(
+                                        sacp::Error::resource_not_found(Some(
+                                            req.path.display().to_string(),
+                                        )),
+                                    ),
+                                }
+                            }
+                        },
+                        sacp::on_receive_request!(),
+                    )
+                    .on_receive_request(
+                        {
+                            let editor = editor.clone();
+                            async move |req: WriteTextFileRequest, request_cx, _connection_cx| {
+                                editor.lock().unwrap().files.insert(req.path, req.content);
+                                request_cx.respond(WriteTextFileResponse::new())
+                            }
+                        },
+                        sacp::on_receive_request!(),
+                    )
+                    .on_receive_request(
+                        {
+                            let editor = editor.clone();
+                            async move |req: CreateTerminalRequest, request_cx, _connection_cx| {
+                                let mut command = vec![req.command];
+                                command.extend(req.args);
+                                editor.lock().unwrap().commands.push(command);
+                                request_cx.respond(CreateTerminalResponse::new("term-1"))
+                            }
+                        },
+                        sacp::on_receive_request!(),
+                    )
+                    .on_receive_request(
+                        async move |_req: WaitForTerminalExitRequest,
+                                    request_cx,
+                                    _connection_cx| {
+                            request_cx.respond(WaitForTerminalExitResponse::new(
+                                TerminalExitStatus::new().exit_code(0),
+                            ))
+                        },
+                        sacp::on_receive_request!(),
+                    )
+                    .on_receive_request(
+                        {
+                            let editor = editor.clone();
+                            async move |_req: TerminalOutputRequest, request_cx, _connection_cx| {
+                                let output = editor.lock().unwrap().terminal_output.clone();
+                                request_cx.respond(TerminalOutputResponse::new(output, false))
+                            }
+                        },
+                        sacp::on_receive_request!(),
+                    )
+                    .on_receive_request(
+                        async move |_req: ReleaseTerminalRequest, request_cx, _connection_cx| {
+                            request_cx.respond(ReleaseTerminalResponse::new())
+                        },
+                        sacp::on_receive_request!(),
+                    )
                     .connect_to(transport)
                     .unwrap()
                     .run_until({
                         let cx_holder = cx_holder_clone;
                         move |cx: JrConnectionCx<ClientToAgent>| async move {
-                            cx.send_request(InitializeRequest::new(ProtocolVersion::LATEST))
-                                .block_task()
-                                .await
-                                .unwrap();
+                            cx.send_request(
+                                InitializeRequest::new(ProtocolVersion::LATEST)
+                                    .client_capabilities(client_capabilities),
+                            )
+                            .block_task()
+                            .await
+                            .unwrap();
 
                             *cx_holder.lock().unwrap() = Some(cx.clone());
                             let _ = ready_tx.send(());
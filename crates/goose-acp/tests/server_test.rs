@@ -3,8 +3,8 @@ use common_tests::fixtures::run_test;
 use common_tests::fixtures::server::ClientToAgentConnection;
 use common_tests::{
     run_config_mcp, run_initialize_without_provider, run_load_model, run_model_list, run_model_set,
-    run_permission_persistence, run_prompt_basic, run_prompt_codemode, run_prompt_image,
-    run_prompt_mcp,
+    run_permission_persistence, run_prompt_basic, run_prompt_client_delegation,
+    run_prompt_codemode, run_prompt_image, run_prompt_mcp,
 };
 
 #[test]
@@ -42,6 +42,11 @@ fn test_prompt_basic() {
     run_test(async { run_prompt_basic::<ClientToAgentConnection>().await });
 }
 
+#[test]
+fn test_prompt_client_delegation() {
+    run_test(async { run_prompt_client_delegation::<ClientToAgentConnection>().await });
+}
+
 #[test]
 fn test_prompt_codemode() {
     run_test(async { run_prompt_codemode::<ClientToAgentConnection>().await });
@@ -1,30 +1,38 @@
 use anyhow::Result;
 use clap::{Args, CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell as ClapShell};
+use goose::audit::{AuditFilter, AuditKind};
 use goose::builtin_extension::register_builtin_extensions;
-use goose::config::Config;
+use goose::config::{Config, TokenScope};
 use goose::posthog::get_telemetry_choice;
 use goose::recipe::Recipe;
 use goose_mcp::mcp_server_runner::{serve, McpCommand};
 use goose_mcp::{AutoVisualiserRouter, ComputerControllerServer, MemoryServer, TutorialServer};
 
+use crate::commands::audit::handle_audit;
 use crate::commands::configure::{configure_telemetry_consent_dialog, handle_configure};
 use crate::commands::info::handle_info;
+use crate::commands::permissions::handle_permissions_explain;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
 use crate::commands::recipe::{handle_deeplink, handle_list, handle_open, handle_validate};
 use crate::commands::term::{
     handle_term_info, handle_term_init, handle_term_log, handle_term_run, Shell,
 };
+use crate::commands::token::{handle_token_create, handle_token_list, handle_token_revoke};
 
 use crate::commands::schedule::{
     handle_schedule_add, handle_schedule_cron_help, handle_schedule_list, handle_schedule_remove,
     handle_schedule_run_now, handle_schedule_services_status, handle_schedule_services_stop,
     handle_schedule_sessions,
 };
-use crate::commands::session::{handle_session_list, handle_session_remove};
+use crate::commands::session::{
+    handle_session_list, handle_session_remove, parse_age, parse_date, SessionPruneArgs,
+    SessionSearchArgs,
+};
 use crate::recipes::extract_from_cli::extract_recipe_info_from_cli;
 use crate::recipes::recipe::{explain_recipe, render_recipe_as_yaml};
 use crate::session::{build_session, SessionBuilderConfig};
+use goose::agents::container::{configured_runtime, project_container_name, ContainerMount};
 use goose::agents::Container;
 use goose::session::session_manager::SessionType;
 use goose::session::SessionManager;
@@ -103,6 +111,29 @@ pub struct SessionOptions {
         long_help = "Run extensions (stdio and built-in) inside the specified container. The extension must exist in the container. For built-in extensions, goose must be installed inside the container."
     )]
     pub container: Option<String>,
+
+    #[arg(
+        long = "container-image",
+        value_name = "IMAGE",
+        conflicts_with = "container",
+        help = "Run the developer tools in a container started from this image",
+        long_help = "Start a container from IMAGE with the working directory mounted at /workspace, and run extensions and the developer tools inside it. The container is named after the working directory and reused by later sessions there; stop it with `docker stop` when done. Set GOOSE_CONTAINER_RUNTIME to use podman or another docker compatible CLI."
+    )]
+    pub container_image: Option<String>,
+
+    #[arg(
+        long = "response-cache",
+        help = "Replay identical provider requests from the response cache",
+        long_help = "Answer provider requests that exactly match an earlier one (same system prompt, messages, tools and model) from the on-disk response cache instead of calling the provider. Useful when re-running recipes in CI or iterating on prompts."
+    )]
+    pub response_cache: bool,
+
+    #[arg(
+        long = "sandbox",
+        help = "Run developer shell commands in a sandbox",
+        long_help = "Run developer shell commands under bubblewrap (Linux only), with writes limited to the working directory. Limits and network access come from the GOOSE_SANDBOX config when set. The choice is saved with the session."
+    )]
+    pub sandbox: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -519,6 +550,198 @@ enum SessionCommand {
         )]
         format: String,
     },
+    #[command(
+        about = "Show what sessions have spent",
+        long_about = "Show spend computed from token usage and model pricing. With a session identifier, breaks that session down by model; otherwise lists the sessions with spend in the last --days days."
+    )]
+    Cost {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        #[arg(
+            long,
+            help = "Number of days to cover, counting today",
+            default_value = "1"
+        )]
+        days: u32,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(
+        about = "Branch a session from one of its messages",
+        long_about = "Start a new session that shares the history of an existing one up to and including the given message. Resume the branch with `goose session -r --session-id <id>`."
+    )]
+    Branch {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        #[arg(
+            long = "message-id",
+            value_name = "MESSAGE_ID",
+            help = "Last message the branch shares (default: the session's last message)"
+        )]
+        message_id: Option<String>,
+
+        #[arg(
+            long = "branch-name",
+            value_name = "NAME",
+            help = "Name for the new branch"
+        )]
+        branch_name: Option<String>,
+    },
+    #[command(about = "Show the branch tree a session belongs to")]
+    Branches {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(
+        about = "Search the messages of past sessions",
+        long_about = "Full-text search over past sessions. Words are ranked by relevance; use \"quotes\" for phrases, prefix* for prefixes and -word to exclude."
+    )]
+    Search {
+        /// Text to search for
+        query: String,
+
+        #[arg(
+            short = 'l',
+            long = "limit",
+            help = "Maximum number of sessions to show",
+            default_value = "10"
+        )]
+        limit: usize,
+
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = parse_date,
+            help = "Only messages after this date (YYYY-MM-DD, RFC 3339 or an age like 7d)"
+        )]
+        after: Option<chrono::DateTime<chrono::Utc>>,
+
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = parse_date,
+            help = "Only messages before this date (YYYY-MM-DD, RFC 3339 or an age like 7d)"
+        )]
+        before: Option<chrono::DateTime<chrono::Utc>>,
+
+        #[arg(
+            short = 'w',
+            long = "working_dir",
+            help = "Only sessions whose working directory contains this path"
+        )]
+        working_dir: Option<PathBuf>,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(about = "Rename a session")]
+    Rename {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        /// New name for the session
+        new_name: String,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(about = "Import a session exported with `goose session export` as JSON or YAML")]
+    Import {
+        /// Path to the exported session
+        file: PathBuf,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(
+        about = "Remove sessions that have not been updated for a while",
+        long_about = "Remove sessions last updated longer ago than --older-than. Only user and scheduled sessions are considered unless --type is given."
+    )]
+    Prune {
+        #[arg(
+            long = "older-than",
+            value_name = "AGE",
+            value_parser = parse_age,
+            help = "Minimum age since the last update, e.g. 30d, 12h or 2w"
+        )]
+        older_than: chrono::Duration,
+
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            value_parser = |s: &str| s.parse::<SessionType>().map_err(|e| e.to_string()),
+            help = "Session type to prune (user, scheduled, sub_agent, hidden, terminal, gateway); repeatable"
+        )]
+        types: Vec<SessionType>,
+
+        #[arg(long = "dry-run", help = "List the sessions without removing them")]
+        dry_run: bool,
+
+        #[arg(short = 'y', long, help = "Remove without asking for confirmation")]
+        yes: bool,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(
+        about = "Show token, cost and tool usage",
+        long_about = "Show token, cost and tool usage for a session, or across all sessions over the last --days days."
+    )]
+    Stats {
+        #[command(flatten)]
+        identifier: Option<Identifier>,
+
+        #[arg(
+            long,
+            help = "Number of days to cover without a session, counting today",
+            default_value = "30"
+        )]
+        days: u32,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
     #[command(name = "diagnostics")]
     Diagnostics {
         /// Session identifier for generating diagnostics
@@ -597,15 +820,32 @@ enum GatewayCommand {
 
     #[command(about = "Start a gateway")]
     Start {
-        #[arg(help = "Gateway type (e.g., 'telegram')")]
+        #[arg(help = "Gateway type ('telegram', 'slack', 'discord', 'matrix' or 'webhook')")]
         gateway_type: String,
 
         #[arg(
             long = "bot-token",
             help = "Bot token for the gateway platform",
-            long_help = "Authentication token for the gateway platform (e.g., Telegram bot token)"
+            long_help = "Authentication token for the gateway platform (e.g., Telegram bot token, Slack xoxb- bot token)"
         )]
-        bot_token: String,
+        bot_token: Option<String>,
+
+        #[arg(
+            long = "app-token",
+            help = "App-level token for Slack Socket Mode (xapp-...)"
+        )]
+        app_token: Option<String>,
+
+        #[arg(
+            long = "option",
+            short = 'o',
+            value_name = "KEY=VALUE",
+            help = "Additional platform setting, can be repeated",
+            long_help = "Additional platform setting, e.g. -o homeserver=https://matrix.example.org -o access_token=... for Matrix, or -o callback_url=... -o secret=... -o listen=127.0.0.1:8787 for webhooks",
+            action = clap::ArgAction::Append,
+            value_parser = parse_key_val,
+        )]
+        options: Vec<(String, String)>,
     },
 
     #[command(about = "Stop a running gateway")]
@@ -621,6 +861,85 @@ enum GatewayCommand {
     },
 }
 
+#[derive(Subcommand)]
+enum PermissionsCommand {
+    /// Show which permission rule would decide a tool call
+    #[command(
+        about = "Explain which permission rule applies to a tool call",
+        long_about = "Evaluates a tool call against permission_rules.yaml and the .goosepermissions\n\
+                      files for the working directory without running it, and prints the rule that fired.\n\n\
+                      Example:\n  \
+                        goose permissions explain developer__shell --args '{\"command\": \"git status\"}'"
+    )]
+    Explain {
+        #[arg(help = "Tool name, e.g. developer__shell")]
+        tool: String,
+
+        #[arg(long, value_name = "JSON", help = "Tool arguments as a JSON object")]
+        args: Option<String>,
+
+        #[arg(
+            short = 'w',
+            long = "working-dir",
+            value_name = "DIR",
+            help = "Working directory to evaluate project rules for (default: current directory)"
+        )]
+        working_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Create an API token for goose-server
+    #[command(
+        about = "Create a scoped API token for goose-server",
+        long_about = "Mints a named token that clients can send to goose-server as X-Secret-Key or\n\
+                      Authorization: Bearer. The token is printed once and only its hash is stored.\n\n\
+                      Example:\n  \
+                        goose token create ci-bot --scope read-sessions --scope reply"
+    )]
+    Create {
+        #[arg(help = "Unique name for the token")]
+        name: String,
+
+        #[arg(
+            long = "scope",
+            value_name = "SCOPE",
+            required = true,
+            value_parser = |s: &str| s.parse::<TokenScope>().map_err(|e| e.to_string()),
+            help = "Scope to grant; repeat for several (read-sessions, reply, manage-config, manage-extensions, schedule)"
+        )]
+        scopes: Vec<TokenScope>,
+
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+
+    /// List API tokens
+    #[command(about = "List API tokens, without their secrets")]
+    List {
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+
+    /// Revoke an API token
+    #[command(about = "Revoke an API token by id or name")]
+    Revoke {
+        #[arg(help = "Id or name of the token")]
+        id_or_name: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum RecipeCommand {
     /// Validate a recipe file
@@ -814,7 +1133,7 @@ enum Command {
         command: SchedulerCommand,
     },
 
-    /// Manage gateways for external platform integrations (e.g., Telegram)
+    /// Manage gateways for external platform integrations (Telegram, Slack, Discord, Matrix, webhooks)
     #[command(
         about = "Manage gateways for external platform integrations",
         visible_alias = "gw"
@@ -824,6 +1143,81 @@ enum Command {
         command: GatewayCommand,
     },
 
+    /// Inspect tool permission rules
+    #[command(about = "Inspect tool permission rules")]
+    Permissions {
+        #[command(subcommand)]
+        command: PermissionsCommand,
+    },
+
+    /// Show the audit log of tool calls and config changes
+    #[command(
+        about = "Show the audit log of tool permission decisions and config changes",
+        long_about = "Shows entries from the append-only audit log, most recent first. Tool calls\n\
+                      record the permission decision and what made it; config changes record the\n\
+                      key and who changed it.\n\n\
+                      Example:\n  \
+                        goose audit --since 1d --tool developer__shell"
+    )]
+    Audit {
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = parse_date,
+            help = "Only entries after this date (YYYY-MM-DD, RFC 3339 or an age like 7d)"
+        )]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = parse_date,
+            help = "Only entries before this date (YYYY-MM-DD, RFC 3339 or an age like 7d)"
+        )]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+
+        #[arg(
+            long = "session-id",
+            value_name = "ID",
+            help = "Only tool calls in this session"
+        )]
+        session_id: Option<String>,
+
+        #[arg(long, value_name = "TOOL", help = "Only calls to this tool")]
+        tool: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "KIND",
+            value_parser = |s: &str| s.parse::<AuditKind>().map_err(|e| e.to_string()),
+            help = "Only entries of this kind (tool_call, config_change)"
+        )]
+        kind: Option<AuditKind>,
+
+        #[arg(
+            short = 'l',
+            long = "limit",
+            help = "Maximum number of entries to show",
+            default_value = "50"
+        )]
+        limit: usize,
+
+        #[arg(
+            long = "format",
+            value_name = "FORMAT",
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+
+    /// Manage API tokens for goose-server
+    #[command(about = "Manage scoped API tokens for goose-server")]
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+
     /// Update the goose CLI version
     #[command(about = "Update the goose CLI version")]
     Update {
@@ -1003,6 +1397,9 @@ fn get_command_name(command: &Option<Command>) -> &'static str {
         Some(Command::Run { .. }) => "run",
         Some(Command::Gateway { .. }) => "gateway",
         Some(Command::Schedule { .. }) => "schedule",
+        Some(Command::Permissions { .. }) => "permissions",
+        Some(Command::Audit { .. }) => "audit",
+        Some(Command::Token { .. }) => "token",
         Some(Command::Update { .. }) => "update",
         Some(Command::Recipe { .. }) => "recipe",
         Some(Command::Term { .. }) => "term",
@@ -1067,6 +1464,110 @@ async fn handle_session_subcommand(command: SessionCommand) -> Result<()> {
             crate::commands::session::handle_session_export(session_identifier, output, format)
                 .await?;
         }
+        SessionCommand::Cost {
+            identifier,
+            days,
+            format,
+        } => {
+            let session_id = match identifier {
+                Some(id) => Some(lookup_session_id(id).await?),
+                None => None,
+            };
+            crate::commands::session::handle_session_cost(session_id, days, format).await?;
+        }
+        SessionCommand::Branch {
+            identifier,
+            message_id,
+            branch_name,
+        } => {
+            let session_id = match identifier {
+                Some(id) => lookup_session_id(id).await?,
+                None => {
+                    crate::commands::session::prompt_interactive_session_selection(
+                        &SessionManager::instance(),
+                    )
+                    .await?
+                }
+            };
+            crate::commands::session::handle_session_branch(&session_id, message_id, branch_name)
+                .await?;
+        }
+        SessionCommand::Branches { identifier, format } => {
+            let session_id = match identifier {
+                Some(id) => lookup_session_id(id).await?,
+                None => {
+                    crate::commands::session::prompt_interactive_session_selection(
+                        &SessionManager::instance(),
+                    )
+                    .await?
+                }
+            };
+            crate::commands::session::handle_session_branches(&session_id, format).await?;
+        }
+        SessionCommand::Search {
+            query,
+            limit,
+            after,
+            before,
+            working_dir,
+            format,
+        } => {
+            crate::commands::session::handle_session_search(SessionSearchArgs {
+                query,
+                limit,
+                after,
+                before,
+                working_dir,
+                format,
+            })
+            .await?;
+        }
+        SessionCommand::Rename {
+            identifier,
+            new_name,
+            format,
+        } => {
+            let session_id = match identifier {
+                Some(id) => lookup_session_id(id).await?,
+                None => {
+                    crate::commands::session::prompt_interactive_session_selection(
+                        &SessionManager::instance(),
+                    )
+                    .await?
+                }
+            };
+            crate::commands::session::handle_session_rename(&session_id, new_name, format).await?;
+        }
+        SessionCommand::Import { file, format } => {
+            crate::commands::session::handle_session_import(&file, format).await?;
+        }
+        SessionCommand::Prune {
+            older_than,
+            types,
+            dry_run,
+            yes,
+            format,
+        } => {
+            crate::commands::session::handle_session_prune(SessionPruneArgs {
+                older_than,
+                types,
+                dry_run,
+                yes,
+                format,
+            })
+            .await?;
+        }
+        SessionCommand::Stats {
+            identifier,
+            days,
+            format,
+        } => {
+            let session_id = match identifier {
+                Some(id) => Some(lookup_session_id(id).await?),
+                None => None,
+            };
+            crate::commands::session::handle_session_stats(session_id, days, format).await?;
+        }
         SessionCommand::Diagnostics { identifier, output } => {
             let session_manager = SessionManager::instance();
             let session_id = if let Some(id) = identifier {
@@ -1160,7 +1661,9 @@ async fn handle_interactive_session(
         interactive: true,
         quiet: false,
         output_format: "text".to_string(),
-        container: session_opts.container.map(Container::new),
+        container: session_container(&session_opts).await?,
+        response_cache: session_opts.response_cache,
+        sandbox: session_opts.sandbox,
     })
     .await;
 
@@ -1213,6 +1716,27 @@ async fn log_session_completion(
     }
 }
 
+/// Where the session runs: an existing container from `--container`, or one started from
+/// `--container-image` with the working directory mounted at `/workspace`.
+async fn session_container(session_opts: &SessionOptions) -> Result<Option<Container>> {
+    if let Some(id) = &session_opts.container {
+        return Ok(Some(Container::new(id)));
+    }
+    let Some(image) = &session_opts.container_image else {
+        return Ok(None);
+    };
+    let cwd = std::env::current_dir()?;
+    let name = project_container_name(&cwd);
+    let mount = ContainerMount {
+        host: cwd,
+        container: PathBuf::from("/workspace"),
+    };
+    let container =
+        Container::reuse_or_create(&configured_runtime(), &name, image, Some(mount)).await?;
+    eprintln!("Running in container {} ({})", container.id(), image);
+    Ok(Some(container))
+}
+
 fn parse_run_input(
     input_opts: &InputOptions,
     quiet: bool,
@@ -1365,7 +1889,9 @@ async fn handle_run_command(
         interactive: run_behavior.interactive,
         quiet: output_opts.quiet,
         output_format: output_opts.output_format,
-        container: session_opts.container.map(Container::new),
+        container: session_container(&session_opts).await?,
+        response_cache: session_opts.response_cache,
+        sandbox: session_opts.sandbox,
     })
     .await;
 
@@ -1400,8 +1926,20 @@ async fn handle_gateway_command(command: GatewayCommand) -> Result<()> {
         GatewayCommand::Start {
             gateway_type,
             bot_token,
+            app_token,
+            options,
         } => {
-            let platform_config = serde_json::json!({ "bot_token": bot_token });
+            let mut platform_config = serde_json::Map::new();
+            let tokens = [("bot_token", bot_token), ("app_token", app_token)];
+            for (key, value) in tokens {
+                if let Some(value) = value {
+                    platform_config.insert(key.to_string(), value.into());
+                }
+            }
+            for (key, value) in options {
+                platform_config.insert(key, value.into());
+            }
+            let platform_config = serde_json::Value::Object(platform_config);
             gateway::handle_gateway_start(gateway_type, platform_config).await
         }
         GatewayCommand::Stop { gateway_type } => gateway::handle_gateway_stop(gateway_type).await,
@@ -1652,6 +2190,8 @@ async fn handle_default_session() -> Result<()> {
         quiet: false,
         output_format: "text".to_string(),
         container: None,
+        response_cache: false,
+        sandbox: false,
     })
     .await;
     session.interactive(None).await
@@ -1659,6 +2199,9 @@ async fn handle_default_session() -> Result<()> {
 
 pub async fn cli() -> anyhow::Result<()> {
     register_builtin_extensions(goose_mcp::BUILTIN_EXTENSIONS.clone());
+    if let Some(embedder) = goose::providers::embedding::memory_embedder() {
+        goose_mcp::set_memory_embedder(embedder);
+    }
 
     let cli = Cli::parse();
 
@@ -1735,6 +2278,41 @@ pub async fn cli() -> anyhow::Result<()> {
         }
         Some(Command::Gateway { command }) => handle_gateway_command(command).await,
         Some(Command::Schedule { command }) => handle_schedule_command(command).await,
+        Some(Command::Permissions { command }) => match command {
+            PermissionsCommand::Explain {
+                tool,
+                args,
+                working_dir,
+            } => handle_permissions_explain(tool, args, working_dir),
+        },
+        Some(Command::Audit {
+            since,
+            until,
+            session_id,
+            tool,
+            kind,
+            limit,
+            format,
+        }) => handle_audit(
+            AuditFilter {
+                since,
+                until,
+                session_id,
+                tool,
+                kind,
+                limit: Some(limit),
+            },
+            format,
+        ),
+        Some(Command::Token { command }) => match command {
+            TokenCommand::Create {
+                name,
+                scopes,
+                format,
+            } => handle_token_create(name, scopes, format),
+            TokenCommand::List { format } => handle_token_list(format),
+            TokenCommand::Revoke { id_or_name } => handle_token_revoke(id_or_name),
+        },
         Some(Command::Update {
             canary,
             reconfigure,
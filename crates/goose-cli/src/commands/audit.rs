@@ -0,0 +1,67 @@
+use anyhow::Result;
+use console::style;
+use goose::audit::{AuditDecision, AuditEntry, AuditFilter, AuditKind, AuditLog};
+
+fn print_entry(entry: &AuditEntry) {
+    let timestamp = entry
+        .timestamp
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%d %H:%M:%S");
+
+    match entry.kind {
+        AuditKind::ToolCall => {
+            let decision = match entry.decision {
+                Some(AuditDecision::Allowed) => style("allowed").green(),
+                Some(AuditDecision::Denied) => style("denied").red(),
+                None => style("-").dim(),
+            };
+            println!(
+                "{} {} {} {}",
+                style(timestamp).dim(),
+                decision,
+                style(entry.tool.as_deref().unwrap_or("-")).cyan(),
+                entry.summary
+            );
+            let mut source = entry.source.clone().unwrap_or_default();
+            if let Some(reason) = entry.reason.as_deref().filter(|r| !r.is_empty()) {
+                source = format!("{}: {}", source, reason);
+            }
+            println!(
+                "    {} session {}",
+                style(format!("[{}]", source)).dim(),
+                entry.session_id.as_deref().unwrap_or("-")
+            );
+        }
+        AuditKind::ConfigChange => {
+            println!(
+                "{} {} {} {}",
+                style(timestamp).dim(),
+                style("config").yellow(),
+                style(entry.key.as_deref().unwrap_or("-")).cyan(),
+                entry.summary
+            );
+            if let Some(actor) = &entry.actor {
+                println!("    {}", style(format!("[by {}]", actor)).dim());
+            }
+        }
+    }
+}
+
+/// Show audit log entries, most recent first.
+pub fn handle_audit(filter: AuditFilter, format: String) -> Result<()> {
+    let entries = AuditLog::instance().query(&filter);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No matching audit entries.");
+        return Ok(());
+    }
+    for entry in &entries {
+        print_entry(entry);
+    }
+    Ok(())
+}
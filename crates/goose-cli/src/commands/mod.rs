@@ -1,9 +1,12 @@
+pub mod audit;
 pub mod configure;
 pub mod gateway;
 pub mod info;
+pub mod permissions;
 pub mod project;
 pub mod recipe;
 pub mod schedule;
 pub mod session;
 pub mod term;
+pub mod token;
 pub mod update;
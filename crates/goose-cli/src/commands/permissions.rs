@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use console::style;
+use goose::config::paths::Paths;
+use goose::config::permission::PermissionLevel;
+use goose::config::PermissionManager;
+use goose::permission::PermissionRules;
+use rmcp::model::JsonObject;
+use std::path::PathBuf;
+
+fn level_label(level: &PermissionLevel) -> &'static str {
+    match level {
+        PermissionLevel::AlwaysAllow => "always_allow",
+        PermissionLevel::AskBefore => "ask_before",
+        PermissionLevel::NeverAllow => "never_allow",
+    }
+}
+
+/// Dry-run a tool call against the permission rules and report which rule would decide it.
+pub fn handle_permissions_explain(
+    tool: String,
+    args: Option<String>,
+    working_dir: Option<PathBuf>,
+) -> Result<()> {
+    let arguments: Option<JsonObject> = args
+        .map(|raw| serde_json::from_str(&raw).context("--args must be a JSON object"))
+        .transpose()?;
+    let working_dir = match working_dir {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+
+    let rules = PermissionRules::load(&Paths::config_dir(), &working_dir);
+
+    println!("{}", style("Rule files:").cyan().bold());
+    if rules.files().is_empty() {
+        println!("  (none found)");
+    }
+    for file in rules.files() {
+        println!("  {}", file.display());
+    }
+    for error in rules.errors() {
+        println!("  {} {}", style("ignored:").yellow(), error);
+    }
+    println!();
+
+    if let Some(rule_match) = rules.evaluate(&tool, arguments.as_ref(), &working_dir) {
+        println!(
+            "{} {}",
+            style("Decision:").cyan().bold(),
+            level_label(&rule_match.action)
+        );
+        println!("  {}", rule_match.explain());
+        return Ok(());
+    }
+
+    match PermissionManager::instance().get_user_permission(&tool) {
+        Some(level) => {
+            println!(
+                "{} {}",
+                style("Decision:").cyan().bold(),
+                level_label(&level)
+            );
+            println!("  No rule matched; using the tool permission from permission.yaml");
+        }
+        None => {
+            println!("{} depends on mode", style("Decision:").cyan().bold());
+            println!("  No rule or tool permission matched; the current goose mode decides");
+        }
+    }
+    Ok(())
+}
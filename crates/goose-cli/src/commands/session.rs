@@ -1,9 +1,14 @@
 use crate::session::message_to_markdown;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
 
 use cliclack::{confirm, multiselect, select};
 use etcetera::home_dir;
-use goose::session::{generate_diagnostics, Session, SessionManager};
+use goose::session::budget::start_of_today;
+use goose::session::{
+    generate_diagnostics, BudgetLimits, ChatHistorySearchOptions, ModelCost, Session,
+    SessionManager, SessionType,
+};
 use goose::utils::safe_truncate;
 use regex::Regex;
 use std::fs;
@@ -237,6 +242,545 @@ pub async fn handle_session_export(
     Ok(())
 }
 
+fn format_model_cost(model: &ModelCost) -> String {
+    let cost = if model.unpriced_requests == model.requests {
+        "no pricing".to_string()
+    } else {
+        format!("${:.4}", model.cost)
+    };
+    format!(
+        "{}/{}: {} requests, {} in / {} out tokens, {}",
+        model.provider, model.model, model.requests, model.input_tokens, model.output_tokens, cost
+    )
+}
+
+fn format_budget(spend: &[(&str, Option<f64>, f64)]) -> Option<String> {
+    let parts: Vec<String> = spend
+        .iter()
+        .filter_map(|(scope, limit, spent)| {
+            limit.map(|limit| format!("{} ${:.2} of ${:.2}", scope, spent, limit))
+        })
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(format!("Budget: {}", parts.join(", ")))
+    }
+}
+
+pub async fn handle_session_cost(
+    session_id: Option<String>,
+    days: u32,
+    format: String,
+) -> Result<()> {
+    let session_manager = SessionManager::instance();
+
+    if let Some(session_id) = session_id {
+        let session = session_manager
+            .get_session(&session_id, false)
+            .await
+            .with_context(|| format!("Session '{}' not found", session_id))?;
+        let models = session_manager.get_session_costs(&session_id).await?;
+        let limits = BudgetLimits::from_config(session.recipe.as_ref());
+        let spend = session_manager.get_budget_spend(&session).await?;
+        let total: f64 = models.iter().fold(0.0, |total, m| total + m.cost);
+
+        if format == "json" {
+            let report = serde_json::json!({
+                "sessionId": session.id,
+                "name": session.name,
+                "totalCost": total,
+                "models": models,
+                "limits": limits,
+                "spend": spend,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+
+        println!("Session {} ({})", session.id, session.name);
+        if models.is_empty() {
+            println!("  No usage recorded");
+        }
+        for model in &models {
+            println!("  {}", format_model_cost(model));
+        }
+        println!("Total: ${:.4}", total);
+        if let Some(budget) = format_budget(&[
+            ("session", limits.session, spend.session),
+            ("daily", limits.daily, spend.daily),
+            ("recipe", limits.recipe, spend.recipe),
+        ]) {
+            println!("{}", budget);
+        }
+        return Ok(());
+    }
+
+    let since = start_of_today() - chrono::Duration::days(i64::from(days.max(1)) - 1);
+    let sessions = session_manager.list_session_costs(since).await?;
+    let total: f64 = sessions.iter().fold(0.0, |total, s| total + s.cost);
+    let limits = BudgetLimits::from_config(None);
+    let today: f64 = session_manager
+        .list_session_costs(start_of_today())
+        .await?
+        .iter()
+        .fold(0.0, |total, s| total + s.cost);
+
+    if format == "json" {
+        let report = serde_json::json!({
+            "since": since,
+            "totalCost": total,
+            "todayCost": today,
+            "sessions": sessions,
+            "limits": limits,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("Spend since {}:", since.with_timezone(&Local).date_naive());
+    if sessions.is_empty() {
+        println!("  No usage recorded");
+    }
+    for session in &sessions {
+        let name = if session.name.is_empty() {
+            "(deleted)"
+        } else {
+            &session.name
+        };
+        let recipe = session
+            .recipe
+            .as_ref()
+            .map(|recipe| format!(" [recipe: {}]", recipe))
+            .unwrap_or_default();
+        println!(
+            "  {} - ${:.4} - {}{}",
+            session.session_id,
+            session.cost,
+            safe_truncate(name, TRUNCATED_DESC_LENGTH),
+            recipe
+        );
+    }
+    println!("Total: ${:.4}", total);
+    if let Some(budget) = format_budget(&[("daily", limits.daily, today)]) {
+        println!("{}", budget);
+    }
+    Ok(())
+}
+
+pub async fn handle_session_branch(
+    session_id: &str,
+    message_id: Option<String>,
+    name: Option<String>,
+) -> Result<()> {
+    let session_manager = SessionManager::instance();
+    let message_id = match message_id {
+        Some(id) => id,
+        None => {
+            let session = session_manager
+                .get_session(session_id, true)
+                .await
+                .with_context(|| format!("Session '{}' not found", session_id))?;
+            session
+                .conversation
+                .as_ref()
+                .and_then(|conversation| conversation.messages().last())
+                .and_then(|message| message.id.clone())
+                .with_context(|| {
+                    format!("Session '{}' has no messages to branch from", session_id)
+                })?
+        }
+    };
+
+    let branch = session_manager
+        .branch_session(session_id, &message_id, name)
+        .await?;
+    println!("Created branch {} ({})", branch.id, branch.name);
+    println!(
+        "Resume it with: goose session -r --session-id {}",
+        branch.id
+    );
+    Ok(())
+}
+
+pub async fn handle_session_branches(session_id: &str, format: String) -> Result<()> {
+    let tree = SessionManager::instance()
+        .get_branch_tree(session_id)
+        .await
+        .with_context(|| format!("Session '{}' not found", session_id))?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&tree)?);
+        return Ok(());
+    }
+
+    for (depth, branch) in tree.flatten() {
+        let marker = if branch.session_id == session_id {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "{} {}{} - {} - {} messages",
+            marker,
+            "  ".repeat(depth),
+            branch.session_id,
+            safe_truncate(&branch.name, TRUNCATED_DESC_LENGTH),
+            branch.message_count
+        );
+    }
+    Ok(())
+}
+
+/// Parses an age such as `30d`, `12h`, `2w` or `45m`.
+pub fn parse_age(value: &str) -> std::result::Result<chrono::Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid age '{}': expected e.g. 30d, 12h or 2w", value))?;
+    match unit {
+        "m" => Ok(chrono::Duration::minutes(amount)),
+        "h" => Ok(chrono::Duration::hours(amount)),
+        "d" | "" => Ok(chrono::Duration::days(amount)),
+        "w" => Ok(chrono::Duration::weeks(amount)),
+        _ => Err(format!("invalid age unit '{}': use m, h, d or w", unit)),
+    }
+}
+
+/// Parses a point in time given as RFC 3339, a local `YYYY-MM-DD` date (midnight) or an age
+/// relative to now such as `7d`.
+pub fn parse_date(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+            .map(|midnight| midnight.with_timezone(&Utc))
+            .ok_or_else(|| format!("invalid date '{}'", value));
+    }
+    parse_age(value).map(|age| Utc::now() - age).map_err(|_| {
+        format!(
+            "invalid date '{}': use YYYY-MM-DD, RFC 3339 or an age like 7d",
+            value
+        )
+    })
+}
+
+pub struct SessionSearchArgs {
+    pub query: String,
+    pub limit: usize,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub working_dir: Option<PathBuf>,
+    pub format: String,
+}
+
+pub async fn handle_session_search(args: SessionSearchArgs) -> Result<()> {
+    let options = ChatHistorySearchOptions {
+        limit: Some(args.limit),
+        after_date: args.after,
+        before_date: args.before,
+        working_dir: args
+            .working_dir
+            .map(|dir| dir.to_string_lossy().to_string()),
+        session_types: vec![SessionType::User, SessionType::Scheduled],
+        ..Default::default()
+    };
+    let results = SessionManager::instance()
+        .search_chat_history(&args.query, options)
+        .await?;
+
+    if args.format == "json" {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
+    if results.results.is_empty() {
+        println!("No matches for '{}'", args.query);
+        return Ok(());
+    }
+
+    for result in &results.results {
+        println!(
+            "{} - {} - {} - {}",
+            result.session_id,
+            safe_truncate(&result.session_description, TRUNCATED_DESC_LENGTH),
+            result
+                .last_activity
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M"),
+            display_path_with_tilde(Path::new(&result.session_working_dir))
+        );
+        for message in &result.messages {
+            println!(
+                "    [{}] {}",
+                message.role,
+                message.snippet.replace('\n', " ")
+            );
+        }
+    }
+    println!(
+        "{} matching messages in {} sessions",
+        results.total_matches,
+        results.results.len()
+    );
+    Ok(())
+}
+
+pub async fn handle_session_rename(session_id: &str, name: String, format: String) -> Result<()> {
+    let session_manager = SessionManager::instance();
+    session_manager
+        .get_session(session_id, false)
+        .await
+        .with_context(|| format!("Session '{}' not found", session_id))?;
+    session_manager
+        .update(session_id)
+        .user_provided_name(name.clone())
+        .apply()
+        .await?;
+
+    if format == "json" {
+        let report = serde_json::json!({ "sessionId": session_id, "name": name });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Session {} renamed to '{}'", session_id, name);
+    }
+    Ok(())
+}
+
+pub async fn handle_session_import(path: &Path, format: String) -> Result<()> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_yaml = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml" | "yml")
+    );
+    let json = if is_yaml {
+        let value: serde_json::Value = serde_yaml::from_str(&contents)
+            .with_context(|| format!("{} is not valid YAML", path.display()))?;
+        serde_json::to_string(&value)?
+    } else if serde_json::from_str::<serde_json::Value>(&contents).is_ok() {
+        contents
+    } else {
+        let value: serde_json::Value = serde_yaml::from_str(&contents)
+            .with_context(|| format!("{} is neither a JSON nor a YAML export", path.display()))?;
+        serde_json::to_string(&value)?
+    };
+
+    let session = SessionManager::instance()
+        .import_session(&json)
+        .await
+        .with_context(|| format!("Failed to import session from {}", path.display()))?;
+
+    if format == "json" {
+        let report = serde_json::json!({
+            "sessionId": session.id,
+            "name": session.name,
+            "messageCount": session.message_count,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "Imported session {} ({}) with {} messages",
+            session.id, session.name, session.message_count
+        );
+    }
+    Ok(())
+}
+
+pub struct SessionPruneArgs {
+    pub older_than: chrono::Duration,
+    pub types: Vec<SessionType>,
+    pub dry_run: bool,
+    pub yes: bool,
+    pub format: String,
+}
+
+pub async fn handle_session_prune(args: SessionPruneArgs) -> Result<()> {
+    let session_manager = SessionManager::instance();
+    let types = if args.types.is_empty() {
+        vec![SessionType::User, SessionType::Scheduled]
+    } else {
+        args.types
+    };
+    let cutoff = Utc::now() - args.older_than;
+    let mut sessions = session_manager.list_sessions_by_types(&types).await?;
+    sessions.retain(|session| session.updated_at < cutoff);
+    sessions.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+
+    let json = args.format == "json";
+    if !json {
+        if sessions.is_empty() {
+            println!(
+                "No sessions last updated before {}",
+                cutoff.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            );
+            return Ok(());
+        }
+        let verb = if args.dry_run {
+            "Would remove"
+        } else {
+            "Removing"
+        };
+        println!("{} {} sessions:", verb, sessions.len());
+        for session in &sessions {
+            println!(
+                "- {} - {} - {} - {}",
+                session.id,
+                safe_truncate(&session.name, TRUNCATED_DESC_LENGTH),
+                session.session_type,
+                session.updated_at.with_timezone(&Local).format("%Y-%m-%d")
+            );
+        }
+    }
+
+    let removed = if args.dry_run || sessions.is_empty() {
+        false
+    } else if args.yes {
+        true
+    } else if json {
+        return Err(anyhow::anyhow!(
+            "Pass --yes or --dry-run to prune with --format json"
+        ));
+    } else {
+        confirm("Remove these sessions?")
+            .initial_value(false)
+            .interact()?
+    };
+
+    if removed {
+        for session in &sessions {
+            session_manager.delete_session(&session.id).await?;
+        }
+    }
+
+    if json {
+        let report = serde_json::json!({
+            "cutoff": cutoff,
+            "removed": removed,
+            "sessions": sessions
+                .iter()
+                .map(|session| serde_json::json!({
+                    "sessionId": session.id,
+                    "name": session.name,
+                    "sessionType": session.session_type,
+                    "updatedAt": session.updated_at,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if removed {
+        println!("Removed {} sessions", sessions.len());
+    } else if !args.dry_run {
+        println!("Skipping removal of the sessions.");
+    }
+    Ok(())
+}
+
+pub async fn handle_session_stats(
+    session_id: Option<String>,
+    days: u32,
+    format: String,
+) -> Result<()> {
+    let session_manager = SessionManager::instance();
+
+    if let Some(session_id) = session_id {
+        let session = session_manager
+            .get_session(&session_id, false)
+            .await
+            .with_context(|| format!("Session '{}' not found", session_id))?;
+        let models = session_manager.get_session_costs(&session_id).await?;
+        let tools = session_manager
+            .get_tool_usage(Some(&session_id), None)
+            .await?;
+        let total_cost: f64 = models.iter().fold(0.0, |total, m| total + m.cost);
+
+        if format == "json" {
+            let report = serde_json::json!({
+                "sessionId": session.id,
+                "name": session.name,
+                "messageCount": session.message_count,
+                "inputTokens": session.accumulated_input_tokens,
+                "outputTokens": session.accumulated_output_tokens,
+                "totalTokens": session.accumulated_total_tokens,
+                "totalCost": total_cost,
+                "models": models,
+                "tools": tools,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+
+        println!("Session {} ({})", session.id, session.name);
+        println!("  Messages: {}", session.message_count);
+        println!(
+            "  Tokens: {} in / {} out / {} total",
+            session.accumulated_input_tokens.unwrap_or(0),
+            session.accumulated_output_tokens.unwrap_or(0),
+            session.accumulated_total_tokens.unwrap_or(0)
+        );
+        println!("  Cost: ${:.4}", total_cost);
+        print_usage_breakdown(&models, &tools);
+        return Ok(());
+    }
+
+    let since = start_of_today() - chrono::Duration::days(i64::from(days.max(1)) - 1);
+    let insights = session_manager.get_insights().await?;
+    let models = session_manager.get_model_costs(since).await?;
+    let tools = session_manager.get_tool_usage(None, Some(since)).await?;
+
+    if format == "json" {
+        let report = serde_json::json!({
+            "since": since,
+            "totalSessions": insights.total_sessions,
+            "totalTokens": insights.total_tokens,
+            "totalCost": insights.total_cost,
+            "todayCost": insights.today_cost,
+            "models": models,
+            "tools": tools,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!("All sessions:");
+    println!("  Sessions: {}", insights.total_sessions);
+    println!("  Tokens: {}", insights.total_tokens);
+    println!(
+        "  Cost: ${:.4} (today ${:.4})",
+        insights.total_cost, insights.today_cost
+    );
+    println!("Since {}:", since.with_timezone(&Local).date_naive());
+    print_usage_breakdown(&models, &tools);
+    Ok(())
+}
+
+fn print_usage_breakdown(models: &[ModelCost], tools: &[goose::session::ToolUsage]) {
+    println!("Models:");
+    if models.is_empty() {
+        println!("  No usage recorded");
+    }
+    for model in models {
+        println!("  {}", format_model_cost(model));
+    }
+    println!("Tools:");
+    if tools.is_empty() {
+        println!("  No tool calls");
+    }
+    for tool in tools {
+        println!(
+            "  {}: {} calls, {} errors",
+            tool.tool, tool.calls, tool.errors
+        );
+    }
+}
+
 pub async fn handle_diagnostics(session_id: &str, output_path: Option<PathBuf>) -> Result<()> {
     println!(
         "Generating diagnostics bundle for session '{}'...",
@@ -394,3 +938,36 @@ pub async fn prompt_interactive_session_selection(
         Err(anyhow::anyhow!("Invalid selection"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("30d").unwrap(), chrono::Duration::days(30));
+        assert_eq!(parse_age("12h").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_age("2w").unwrap(), chrono::Duration::weeks(2));
+        assert_eq!(parse_age("7").unwrap(), chrono::Duration::days(7));
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3y").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let rfc = parse_date("2025-03-01T12:00:00Z").unwrap();
+        assert_eq!(rfc.to_rfc3339(), "2025-03-01T12:00:00+00:00");
+
+        let day = parse_date("2025-03-01").unwrap().with_timezone(&Local);
+        assert_eq!(
+            day.date_naive(),
+            NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()
+        );
+
+        let week_ago = parse_date("7d").unwrap();
+        let expected = Utc::now() - chrono::Duration::days(7);
+        assert!((week_ago - expected).num_seconds().abs() < 5);
+
+        assert!(parse_date("yesterday").is_err());
+    }
+}
@@ -229,7 +229,7 @@ pub async fn handle_term_run(prompt: Vec<String>) -> Result<()> {
 
     if let Some(oldest_user) = user_messages_after_last_assistant.last() {
         session_manager
-            .truncate_conversation(&session_id, oldest_user.created)
+            .truncate_conversation(&session_id, oldest_user.created, false)
             .await?;
     }
 
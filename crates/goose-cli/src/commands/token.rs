@@ -0,0 +1,70 @@
+use anyhow::{bail, Result};
+use console::style;
+use goose::config::{ApiTokenStore, TokenScope};
+
+/// Mint a goose-server API token and print its secret once.
+pub fn handle_token_create(name: String, scopes: Vec<TokenScope>, format: String) -> Result<()> {
+    let (token, secret) = ApiTokenStore::instance().create(&name, scopes)?;
+
+    if format == "json" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "token": token,
+                "secret": secret,
+            }))?
+        );
+        return Ok(());
+    }
+
+    let scopes: Vec<&str> = token.scopes.iter().map(|s| s.as_str()).collect();
+    println!(
+        "Created token '{}' ({}) with scopes: {}",
+        token.name,
+        token.id,
+        scopes.join(", ")
+    );
+    println!();
+    println!("{}", secret);
+    println!();
+    println!(
+        "{}",
+        style("Store this token now; it cannot be shown again.").yellow()
+    );
+    Ok(())
+}
+
+pub fn handle_token_list(format: String) -> Result<()> {
+    let tokens = ApiTokenStore::instance().list()?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&tokens)?);
+        return Ok(());
+    }
+
+    if tokens.is_empty() {
+        println!("No API tokens. Create one with `goose token create <name> --scope <scope>`.");
+        return Ok(());
+    }
+
+    println!("{}", style("API tokens:").cyan().bold());
+    for token in tokens {
+        let scopes: Vec<&str> = token.scopes.iter().map(|s| s.as_str()).collect();
+        println!(
+            "  {} {} - {} (created {})",
+            style(&token.id).dim(),
+            token.name,
+            scopes.join(", "),
+            token.created_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_token_revoke(id_or_name: String) -> Result<()> {
+    if !ApiTokenStore::instance().revoke(&id_or_name)? {
+        bail!("No API token with id or name '{}'", id_or_name);
+    }
+    println!("Revoked token '{}'", id_or_name);
+    Ok(())
+}
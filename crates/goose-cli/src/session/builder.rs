@@ -3,13 +3,15 @@ use crate::cli::StreamableHttpOptions;
 use super::output;
 use super::CliSession;
 use console::style;
+use goose::agents::platform_extensions::developer::sandbox::SandboxPolicy;
 use goose::agents::{Agent, Container, ExtensionError};
 use goose::config::resolve_extensions_for_new_session;
 use goose::config::{get_all_extensions, Config, ExtensionConfig};
 use goose::providers::create;
+use goose::providers::response_cache::{CachingProvider, ResponseCacheConfig};
 use goose::recipe::Recipe;
 use goose::session::session_manager::SessionType;
-use goose::session::EnabledExtensionsState;
+use goose::session::{EnabledExtensionsState, ExtensionState, SessionManager};
 use rustyline::EditMode;
 use std::collections::BTreeSet;
 use std::process;
@@ -116,6 +118,10 @@ pub struct SessionBuilderConfig {
     pub output_format: String,
     /// Docker container to run stdio extensions inside
     pub container: Option<Container>,
+    /// Replay identical provider requests from the response cache
+    pub response_cache: bool,
+    /// Sandbox developer shell commands for this session
+    pub sandbox: bool,
 }
 
 /// Manual implementation of Default to ensure proper initialization of output_format
@@ -143,6 +149,8 @@ impl Default for SessionBuilderConfig {
             quiet: false,
             output_format: "text".to_string(),
             container: None,
+            response_cache: false,
+            sandbox: false,
         }
     }
 }
@@ -572,6 +580,34 @@ async fn configure_session_prompts(
     }
 }
 
+/// Saves a sandbox policy on the session, based on `GOOSE_SANDBOX` when that is configured.
+async fn enable_session_sandbox(
+    session_manager: &SessionManager,
+    session_id: &str,
+    config: &Config,
+) {
+    let result = async {
+        let session = session_manager.get_session(session_id, false).await?;
+        let mut extension_data = session.extension_data;
+        let policy = SandboxPolicy {
+            enabled: true,
+            modes: Vec::new(),
+            ..SandboxPolicy::from_config(config).unwrap_or_default()
+        };
+        policy.to_extension_data(&mut extension_data)?;
+        session_manager
+            .update(session_id)
+            .extension_data(extension_data)
+            .apply()
+            .await
+    }
+    .await;
+    if let Err(e) = result {
+        output::render_error(&format!("Failed to enable the sandbox: {}", e));
+        process::exit(1);
+    }
+}
+
 pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
     goose::posthog::set_session_context("cli", session_config.resume);
 
@@ -640,6 +676,13 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
             process::exit(1);
         }
     };
+    // `create` already wraps the provider when the cache is enabled globally
+    let new_provider = if session_config.response_cache && !ResponseCacheConfig::enabled_in_config()
+    {
+        CachingProvider::wrap(new_provider)
+    } else {
+        CachingProvider::wrap_for_recipe(new_provider, recipe)
+    };
     let provider_for_display = Arc::clone(&new_provider);
 
     if let Some(lead_worker) = new_provider.as_lead_worker() {
@@ -672,6 +715,10 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> CliSession {
         }
     }
 
+    if session_config.sandbox {
+        enable_session_sandbox(&session_manager, &session_id, config).await;
+    }
+
     // Extensions are loaded after session creation because we may change directory when resuming
     let agent_ptr = resolve_and_load_extensions(
         agent,
@@ -752,6 +799,8 @@ mod tests {
             quiet: false,
             output_format: "text".to_string(),
             container: None,
+            response_cache: false,
+            sandbox: false,
         };
 
         assert_eq!(config.extensions.len(), 1);
@@ -25,6 +25,9 @@ pub enum InputResult {
     Recipe(Option<String>),
     Compact,
     ToggleFullToolOutput,
+    Undo,
+    Checkpoints,
+    ExtensionStatus,
 }
 
 #[derive(Debug)]
@@ -240,6 +243,9 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_RECIPE: &str = "/recipe";
     const CMD_COMPACT: &str = "/compact";
     const CMD_SUMMARIZE_DEPRECATED: &str = "/summarize";
+    const CMD_UNDO: &str = "/undo";
+    const CMD_CHECKPOINTS: &str = "/checkpoints";
+    const CMD_EXTENSIONS: &str = "/extensions";
 
     match input {
         "/exit" | "/quit" => Some(InputResult::Exit),
@@ -308,6 +314,9 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
             Some(InputResult::Compact)
         }
         "/r" => Some(InputResult::ToggleFullToolOutput),
+        s if s == CMD_UNDO => Some(InputResult::Undo),
+        s if s == CMD_CHECKPOINTS => Some(InputResult::Checkpoints),
+        s if s == CMD_EXTENSIONS => Some(InputResult::ExtensionStatus),
         _ => None,
     }
 }
@@ -431,6 +440,9 @@ fn print_help() {
 /compact - Compact the current conversation to reduce context length while preserving key information.
 /? or /help - Display this help message
 /clear - Clears the current chat history
+/undo - Revert the most recent file write or edit made in this session
+/checkpoints - List the file changes in this session that can be undone
+/extensions - Show whether each extension is running, restarted or stopped
 
 Navigation:
 Ctrl+C - Clear current line if text is entered, otherwise exit the session
@@ -498,6 +510,20 @@ mod tests {
             Some(InputResult::ToggleFullToolOutput)
         ));
 
+        // Test checkpoint commands
+        assert!(matches!(
+            handle_slash_command("/undo"),
+            Some(InputResult::Undo)
+        ));
+        assert!(matches!(
+            handle_slash_command("/checkpoints"),
+            Some(InputResult::Checkpoints)
+        ));
+        assert!(matches!(
+            handle_slash_command("/extensions"),
+            Some(InputResult::ExtensionStatus)
+        ));
+
         // Test extension command
         if let Some(InputResult::AddExtension(cmd)) = handle_slash_command("/extension foo bar") {
             assert_eq!(cmd, "foo bar");
@@ -27,11 +27,13 @@ use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::Permission;
 use goose::permission::PermissionConfirmation;
 use goose::providers::base::Provider;
+use goose::session::TodoList;
 use goose::utils::safe_truncate;
 
 use anyhow::{Context, Result};
 use completion::GooseCompleter;
 use goose::agents::extension::{Envs, ExtensionConfig, PLATFORM_EXTENSIONS};
+use goose::agents::extension_supervisor::ExtensionHealth;
 use goose::agents::types::RetryConfig;
 use goose::agents::{Agent, SessionConfig, COMPACT_TRIGGERS};
 use goose::config::{Config, GooseMode};
@@ -80,6 +82,8 @@ enum StreamEvent {
     ModelChange {
         model: String,
         mode: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
     },
     Error {
         error: String,
@@ -603,6 +607,18 @@ impl CliSession {
                 history.save(editor);
                 self.handle_compact().await?;
             }
+            InputResult::Undo => {
+                history.save(editor);
+                self.handle_undo().await;
+            }
+            InputResult::Checkpoints => {
+                history.save(editor);
+                self.handle_checkpoints().await;
+            }
+            InputResult::ExtensionStatus => {
+                history.save(editor);
+                self.handle_extension_status().await;
+            }
         }
         Ok(())
     }
@@ -779,6 +795,104 @@ impl CliSession {
         Ok(())
     }
 
+    async fn handle_undo(&self) {
+        match self
+            .agent
+            .config
+            .session_manager
+            .undo_file_change(&self.session_id, None)
+            .await
+        {
+            Ok(Some(checkpoint)) if checkpoint.existed => {
+                println!(
+                    "{}",
+                    console::style(format!("Restored {}", checkpoint.path)).green()
+                );
+            }
+            Ok(Some(checkpoint)) => {
+                println!(
+                    "{}",
+                    console::style(format!("Removed {}", checkpoint.path)).green()
+                );
+            }
+            Ok(None) => println!("No file changes to undo."),
+            Err(e) => output::render_error(&format!("Failed to undo: {}", e)),
+        }
+    }
+
+    async fn handle_checkpoints(&self) {
+        let checkpoints = match self
+            .agent
+            .config
+            .session_manager
+            .list_file_checkpoints(&self.session_id)
+            .await
+        {
+            Ok(checkpoints) => checkpoints,
+            Err(e) => {
+                output::render_error(&format!("Failed to list checkpoints: {}", e));
+                return;
+            }
+        };
+
+        if checkpoints.is_empty() {
+            println!("No file changes in this session.");
+            return;
+        }
+        // Most recent first, matching the order /undo walks back through them
+        for checkpoint in checkpoints.iter().rev() {
+            let when = chrono::DateTime::from_timestamp(checkpoint.created_timestamp, 0)
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let action = if checkpoint.existed {
+                checkpoint.tool_name.as_str()
+            } else {
+                "create"
+            };
+            println!(
+                "  {} {:<6} {}",
+                console::style(when).dim(),
+                action,
+                checkpoint.path
+            );
+        }
+    }
+
+    async fn handle_extension_status(&self) {
+        let statuses = self.agent.extension_manager.get_extension_statuses().await;
+        if statuses.is_empty() {
+            println!("No extensions enabled.");
+            return;
+        }
+        for status in statuses {
+            let health = match status.health {
+                ExtensionHealth::Running => console::style("running").green(),
+                ExtensionHealth::Stopped => console::style("stopped").yellow(),
+                ExtensionHealth::Restarting => console::style("restarting").yellow(),
+                ExtensionHealth::Failed => console::style("failed").red(),
+                ExtensionHealth::CrashLooping => console::style("crash looping").red(),
+            };
+            let restarts = match status.restarts {
+                0 => String::new(),
+                1 => "restarted once".to_string(),
+                n => format!("restarted {} times", n),
+            };
+            println!(
+                "  {:<20} {:<14} {}",
+                status.name,
+                health,
+                console::style(restarts).dim()
+            );
+            if let Some(error) = status.last_error {
+                println!("    {}", console::style(error).dim());
+            }
+        }
+    }
+
     async fn handle_recipe(&mut self, filepath_opt: Option<String>) {
         println!("{}", console::style("Generating Recipe").green());
 
@@ -1068,11 +1182,14 @@ impl CliSession {
                         Some(Ok(AgentEvent::HistoryReplaced(updated_conversation))) => {
                             self.messages = updated_conversation;
                         }
-                        Some(Ok(AgentEvent::ModelChange { model, mode })) => {
+                        Some(Ok(AgentEvent::ModelChange { model, mode, provider })) => {
                             if is_stream_json_mode {
-                                emit_stream_event(&StreamEvent::ModelChange { model: model.clone(), mode: mode.clone() });
+                                emit_stream_event(&StreamEvent::ModelChange { model: model.clone(), mode: mode.clone(), provider: provider.clone() });
                             } else if self.debug {
-                                eprintln!("Model changed to {} in {} mode", model, mode);
+                                match provider {
+                                    Some(provider) => eprintln!("Model changed to {}/{} in {} mode", provider, model, mode),
+                                    None => eprintln!("Model changed to {} in {} mode", model, mode),
+                                }
                             }
                         }
                         Some(Err(e)) => {
@@ -1320,6 +1437,7 @@ impl CliSession {
                 let total_tokens = metadata.total_tokens.unwrap_or(0) as usize;
 
                 output::display_context_usage(total_tokens, context_limit);
+                output::display_todo_progress(&TodoList::load(&metadata.extension_data));
 
                 if show_cost {
                     let input_tokens = metadata.input_tokens.unwrap_or(0) as usize;
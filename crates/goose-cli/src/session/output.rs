@@ -7,6 +7,7 @@ use goose::conversation::message::{
     ToolRequest, ToolResponse,
 };
 use goose::providers::canonical::maybe_get_canonical_model;
+use goose::session::TodoList;
 #[cfg(target_os = "windows")]
 use goose::subprocess::SubprocessExt;
 use goose::utils::safe_truncate;
@@ -490,7 +491,7 @@ fn render_tool_request(req: &ToolRequest, theme: Theme, debug: bool) {
             "execute" | "execute_code" => render_execute_code_request(call, debug),
             "delegate" => render_delegate_request(call, debug),
             "subagent" => render_delegate_request(call, debug),
-            "todo__write" => render_todo_request(call, debug),
+            "todo__todo_add" | "todo__todo_update" => render_todo_request(call, debug),
             "load" => {}
             _ => render_default_request(call, debug),
         },
@@ -781,10 +782,28 @@ fn render_delegate_request(call: &CallToolRequestParams, debug: bool) {
 fn render_todo_request(call: &CallToolRequestParams, _debug: bool) {
     print_tool_header(call);
 
-    if let Some(args) = &call.arguments {
-        if let Some(Value::String(content)) = args.get("content") {
-            println!("    {} {}", style("content").dim(), style(content).dim());
-        }
+    let args = call.arguments.as_ref();
+    let entries = args
+        .and_then(|a| a.get("items").or_else(|| a.get("updates")))
+        .and_then(Value::as_array);
+    for entry in entries.into_iter().flatten() {
+        let field = |key: &str| entry.get(key).and_then(Value::as_str);
+        let line = match (entry.get("id"), field("title")) {
+            (Some(id), title) => {
+                let change = if entry.get("remove").and_then(Value::as_bool) == Some(true) {
+                    "removed".to_string()
+                } else {
+                    field("status")
+                        .map(str::to_string)
+                        .or_else(|| title.map(|t| format!("\"{t}\"")))
+                        .unwrap_or_else(|| "notes".to_string())
+                };
+                format!("#{} → {}", id, change)
+            }
+            (None, Some(title)) => format!("+ {}", title),
+            (None, None) => continue,
+        };
+        println!("    {}", style(line).dim());
     }
     println!();
 }
@@ -1386,6 +1405,21 @@ pub fn display_context_usage(total_tokens: usize, context_limit: usize) {
     );
 }
 
+pub fn display_todo_progress(list: &TodoList) {
+    let (done, total) = list.progress();
+    if total == 0 {
+        return;
+    }
+    let current = list
+        .current()
+        .map(|item| format!(" · {}", item.title))
+        .unwrap_or_default();
+    println!(
+        "  {}",
+        style(format!("todo {}/{} done{}", done, total, current)).dim()
+    );
+}
+
 fn estimate_cost_usd(
     provider: &str,
     model: &str,
@@ -20,7 +20,7 @@ pub mod tutorial;
 
 pub use autovisualiser::AutoVisualiserRouter;
 pub use computercontroller::ComputerControllerServer;
-pub use memory::MemoryServer;
+pub use memory::{set_memory_embedder, MemoryEmbedder, MemoryServer};
 pub use tutorial::TutorialServer;
 
 /// Type definition for a function that spawns and serves a builtin extension server
@@ -1,10 +1,15 @@
+mod recall;
+
+pub use recall::{set_memory_embedder, MemoryEmbedder};
+
 use etcetera::{choose_app_strategy, AppStrategy};
 use indoc::formatdoc;
+use recall::{MemoryEntry, Scope};
 use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
     model::{
-        CallToolResult, Content, ErrorCode, ErrorData, Implementation, InitializeResult, Meta,
-        ServerCapabilities, ServerInfo,
+        CallToolResult, Content, ErrorCode, ErrorData, Implementation, InitializeRequestParams,
+        InitializeResult, Meta, ServerCapabilities, ServerInfo,
     },
     schemars::JsonSchema,
     service::RequestContext,
@@ -20,6 +25,13 @@ use std::{
 
 const WORKING_DIR_HEADER: &str = "agent-working-dir";
 
+/// Global memories beyond this many are not all listed in the instructions when semantic recall
+/// is available; only the ones most relevant to the project are.
+const MAX_INSTRUCTION_MEMORIES: usize = 15;
+const DEFAULT_SEARCH_LIMIT: usize = 5;
+/// Embedding similarity at which a new memory is treated as restating an existing one
+const DUPLICATE_SIMILARITY: f32 = 0.92;
+
 fn extract_working_dir_from_meta(meta: &Meta) -> Option<PathBuf> {
     meta.0
         .get(WORKING_DIR_HEADER)
@@ -51,6 +63,19 @@ pub struct RetrieveMemoriesParams {
     pub is_global: bool,
 }
 
+/// Parameters for the search_memories tool
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SearchMemoriesParams {
+    /// What to look for, in natural language
+    pub query: String,
+    /// Maximum number of memories to return (defaults to 5)
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Search only global (true) or only local (false) memories; both when omitted
+    #[serde(default)]
+    pub is_global: Option<bool>,
+}
+
 /// Parameters for the remove_memory_category tool
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct RemoveMemoryCategoryParams {
@@ -77,6 +102,9 @@ pub struct MemoryServer {
     tool_router: ToolRouter<Self>,
     instructions: String,
     global_memory_dir: PathBuf,
+    embedder: Option<MemoryEmbedder>,
+    /// Set when there are too many global memories to list them all in the instructions
+    relevant_memories_only: bool,
 }
 
 impl Default for MemoryServer {
@@ -164,6 +192,10 @@ impl MemoryServer {
              - **Filter by Tags**:
                - Enables targeted retrieval based on specific tags.
                - Use: Provide tag filters to refine search.
+             - **Search by Meaning**:
+               - Finds the most relevant memories across every category, local and global.
+               - Use: `search_memories(query="how do we format code?")`
+               - Prefer this over retrieving whole categories when there are many memories.
             To remove a memory, use the following protocol:
             - **Remove by Category**:
               - Removes all memories within the specified category.
@@ -199,9 +231,15 @@ impl MemoryServer {
             tool_router: Self::tool_router(),
             instructions: instructions.clone(),
             global_memory_dir,
+            embedder: recall::registered_embedder(),
+            relevant_memories_only: false,
         };
 
         let retrieved_global_memories = memory_router.retrieve_all(true, None);
+        let global_memory_count = memory_router
+            .entries(true, None)
+            .map(|entries| entries.len())
+            .unwrap_or_default();
 
         let mut updated_instructions = instructions;
 
@@ -216,7 +254,10 @@ impl MemoryServer {
         updated_instructions.push_str("\n\n");
         updated_instructions.push_str(&memories_follow_up_instructions);
 
-        if let Ok(global_memories) = retrieved_global_memories {
+        if memory_router.embedder.is_some() && global_memory_count > MAX_INSTRUCTION_MEMORIES {
+            // Listed when a client connects, once the relevant ones have been found
+            memory_router.relevant_memories_only = true;
+        } else if let Ok(global_memories) = retrieved_global_memories {
             if !global_memories.is_empty() {
                 updated_instructions.push_str("\n\nGlobal Memories:\n");
                 for (category, memories) in global_memories {
@@ -248,7 +289,12 @@ impl MemoryServer {
         is_global: bool,
         working_dir: Option<&PathBuf>,
     ) -> PathBuf {
-        let base_dir = if is_global {
+        self.memory_dir(is_global, working_dir)
+            .join(format!("{}.txt", category))
+    }
+
+    fn memory_dir(&self, is_global: bool, working_dir: Option<&PathBuf>) -> PathBuf {
+        if is_global {
             self.global_memory_dir.clone()
         } else {
             let local_base = working_dir
@@ -256,8 +302,157 @@ impl MemoryServer {
                 .or_else(|| std::env::current_dir().ok())
                 .unwrap_or_else(|| PathBuf::from("."));
             local_base.join(".goose").join("memory")
+        }
+    }
+
+    /// Every stored memory in global or local storage, one per entry rather than per line.
+    pub fn entries(
+        &self,
+        is_global: bool,
+        working_dir: Option<&PathBuf>,
+    ) -> io::Result<Vec<MemoryEntry>> {
+        let base_dir = self.memory_dir(is_global, working_dir);
+        let mut entries = Vec::new();
+        if base_dir.exists() {
+            for entry in fs::read_dir(&base_dir)? {
+                let entry = entry?;
+                if entry.file_type()?.is_file() {
+                    let category = entry.file_name().to_string_lossy().replace(".txt", "");
+                    let content = fs::read_to_string(entry.path())?;
+                    entries.extend(recall::parse_entries(&category, &content, is_global));
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.category.cmp(&b.category));
+        Ok(entries)
+    }
+
+    /// An existing memory that says the same thing as `data`: the same text in the category,
+    /// or, with semantic recall, a close match anywhere in the same storage.
+    async fn find_duplicate(
+        &self,
+        category: &str,
+        data: &str,
+        is_global: bool,
+        working_dir: Option<&PathBuf>,
+    ) -> Option<MemoryEntry> {
+        let entries = self.entries(is_global, working_dir).ok()?;
+        let normalized = recall::normalize(data);
+        if let Some(entry) = entries
+            .iter()
+            .find(|e| e.category == category && recall::normalize(&e.text) == normalized)
+        {
+            return Some(entry.clone());
+        }
+
+        let embedder = self.embedder.as_ref()?;
+        let memory_dir = self.memory_dir(is_global, working_dir);
+        let scope = Scope {
+            memory_dir: &memory_dir,
+            entries,
         };
-        base_dir.join(format!("{}.txt", category))
+        match recall::search(Some(embedder), data, vec![scope], 1).await {
+            Ok(results) => results
+                .into_iter()
+                .find(|(score, _)| *score >= DUPLICATE_SIMILARITY)
+                .map(|(_, entry)| entry),
+            Err(e) => {
+                tracing::warn!("Could not check for similar memories: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Ranks the memories in each `(memory_dir, entries)` group against `query`, falling back to
+    /// matching words when the embedder is unavailable so a flaky embedding service doesn't
+    /// make memories unsearchable.
+    async fn rank_memories(
+        &self,
+        query: &str,
+        searched: &[(PathBuf, Vec<MemoryEntry>)],
+        limit: usize,
+    ) -> anyhow::Result<Vec<(f32, MemoryEntry)>> {
+        let scopes = || {
+            searched
+                .iter()
+                .map(|(memory_dir, entries)| Scope {
+                    memory_dir,
+                    entries: entries.clone(),
+                })
+                .collect()
+        };
+        match recall::search(self.embedder.as_ref(), query, scopes(), limit).await {
+            Ok(results) => Ok(results),
+            Err(e) if self.embedder.is_some() => {
+                tracing::warn!(
+                    "Semantic memory search failed, matching words instead: {}",
+                    e
+                );
+                recall::search(None, query, scopes(), limit).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The instructions with the global memories most relevant to the project goose was
+    /// started in, used instead of listing every one once there are many.
+    async fn instructions_with_relevant_memories(&self) -> String {
+        let mut instructions = self.instructions.clone();
+        let Ok(entries) = self.entries(true, None) else {
+            return instructions;
+        };
+        let total = entries.len();
+        let scope = Scope {
+            memory_dir: &self.global_memory_dir,
+            entries,
+        };
+        match recall::search(
+            self.embedder.as_ref(),
+            &self.project_query(),
+            vec![scope],
+            MAX_INSTRUCTION_MEMORIES,
+        )
+        .await
+        {
+            Ok(relevant) => {
+                instructions.push_str(&format!(
+                    "\n\nGlobal Memories (the {} most relevant of {}; use search_memories to find others):\n",
+                    relevant.len(),
+                    total
+                ));
+                for (_, entry) in relevant {
+                    instructions.push_str(&format!("- [{}] {}\n", entry.category, entry.text));
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Could not rank global memories: {}", e);
+                instructions.push_str(&format!(
+                    "\n\nThere are {} global memories. Use search_memories to find the ones relevant to the task.\n",
+                    total
+                ));
+            }
+        }
+        instructions
+    }
+
+    /// What the current project is about, to rank global memories against: the directory name and
+    /// the project's own memories.
+    fn project_query(&self) -> String {
+        let working_dir = std::env::current_dir().unwrap_or_default();
+        let mut query = working_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Ok(local) = self.entries(false, Some(&working_dir)) {
+            for entry in local {
+                query.push('\n');
+                query.push_str(&entry.text);
+            }
+        }
+        if query.trim().is_empty() {
+            return "user preferences and workflow".to_string();
+        }
+        query.chars().take(4000).collect()
     }
 
     pub fn retrieve_all(
@@ -265,15 +460,7 @@ impl MemoryServer {
         is_global: bool,
         working_dir: Option<&PathBuf>,
     ) -> io::Result<HashMap<String, Vec<String>>> {
-        let base_dir = if is_global {
-            self.global_memory_dir.clone()
-        } else {
-            let local_base = working_dir
-                .cloned()
-                .or_else(|| std::env::current_dir().ok())
-                .unwrap_or_else(|| PathBuf::from("."));
-            local_base.join(".goose").join("memory")
-        };
+        let base_dir = self.memory_dir(is_global, working_dir);
         let mut memories = HashMap::new();
         if base_dir.exists() {
             for entry in fs::read_dir(&base_dir)? {
@@ -405,15 +592,7 @@ impl MemoryServer {
         is_global: bool,
         working_dir: Option<&PathBuf>,
     ) -> io::Result<()> {
-        let base_dir = if is_global {
-            self.global_memory_dir.clone()
-        } else {
-            let local_base = working_dir
-                .cloned()
-                .or_else(|| std::env::current_dir().ok())
-                .unwrap_or_else(|| PathBuf::from("."));
-            local_base.join(".goose").join("memory")
-        };
+        let base_dir = self.memory_dir(is_global, working_dir);
         if base_dir.exists() {
             fs::remove_dir_all(&base_dir)?;
         }
@@ -441,6 +620,21 @@ impl MemoryServer {
             ));
         }
 
+        if let Some(existing) = self
+            .find_duplicate(
+                &params.category,
+                &params.data,
+                params.is_global,
+                working_dir.as_ref(),
+            )
+            .await
+        {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "A matching memory is already stored in category '{}', so nothing new was stored: {}",
+                existing.category, existing.text
+            ))]));
+        }
+
         let tags: Vec<&str> = params.tags.iter().map(|s| s.as_str()).collect();
         self.remember(
             "context",
@@ -484,6 +678,59 @@ impl MemoryServer {
         ))]))
     }
 
+    /// Finds the memories most relevant to a query
+    #[tool(
+        name = "search_memories",
+        description = "Finds the stored memories most relevant to a query, across all categories"
+    )]
+    pub async fn search_memories(
+        &self,
+        params: Parameters<SearchMemoriesParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let params = params.0;
+        let working_dir = extract_working_dir_from_meta(&context.meta);
+        let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(1);
+        let scopes = match params.is_global {
+            Some(is_global) => vec![is_global],
+            None => vec![false, true],
+        };
+
+        let mut searched = Vec::new();
+        for is_global in scopes {
+            let entries = self
+                .entries(is_global, working_dir.as_ref())
+                .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            searched.push((self.memory_dir(is_global, working_dir.as_ref()), entries));
+        }
+
+        let results = self
+            .rank_memories(&params.query, &searched, limit)
+            .await
+            .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        if results.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No matching memories found.",
+            )]));
+        }
+
+        let lines: Vec<String> = results
+            .into_iter()
+            .map(|(_, entry)| {
+                let scope = if entry.is_global { "global" } else { "local" };
+                let tags = if entry.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" #{}", entry.tags.join(" #"))
+                };
+                format!("- [{} / {}] {}{}", scope, entry.category, entry.text, tags)
+            })
+            .collect();
+        Ok(CallToolResult::success(vec![Content::text(
+            lines.join("\n"),
+        )]))
+    }
+
     /// Removes all memories within a specified category
     #[tool(
         name = "remove_memory_category",
@@ -551,6 +798,21 @@ impl ServerHandler for MemoryServer {
             ))
             .with_instructions(self.instructions.clone())
     }
+
+    async fn initialize(
+        &self,
+        request: InitializeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<InitializeResult, ErrorData> {
+        if context.peer.peer_info().is_none() {
+            context.peer.set_peer_info(request);
+        }
+        let mut info = self.get_info();
+        if self.relevant_memories_only {
+            info.instructions = Some(self.instructions_with_relevant_memories().await);
+        }
+        Ok(info)
+    }
 }
 
 // Remove the old MemoryArgs struct since we're using the new parameter structs
@@ -570,6 +832,8 @@ mod tests {
             tool_router: ToolRouter::new(),
             instructions: String::new(),
             global_memory_dir: memory_base.join("global"),
+            embedder: None,
+            relevant_memories_only: false,
         };
 
         let local_memory_dir = working_dir.join(".goose").join("memory");
@@ -615,6 +879,8 @@ mod tests {
             tool_router: ToolRouter::new(),
             instructions: String::new(),
             global_memory_dir: memory_base.join("global"),
+            embedder: None,
+            relevant_memories_only: false,
         };
 
         assert!(router
@@ -635,6 +901,8 @@ mod tests {
             tool_router: ToolRouter::new(),
             instructions: String::new(),
             global_memory_dir: memory_base.join("global"),
+            embedder: None,
+            relevant_memories_only: false,
         };
 
         router
@@ -679,6 +947,8 @@ mod tests {
             tool_router: ToolRouter::new(),
             instructions: String::new(),
             global_memory_dir: memory_base.join("global"),
+            embedder: None,
+            relevant_memories_only: false,
         };
 
         let local_memory_dir = working_dir.join(".goose").join("memory");
@@ -709,6 +979,8 @@ mod tests {
             tool_router: ToolRouter::new(),
             instructions: String::new(),
             global_memory_dir: memory_base.join("global"),
+            embedder: None,
+            relevant_memories_only: false,
         };
 
         router
@@ -754,4 +1026,80 @@ mod tests {
             .any(|v| v.iter().any(|content| content.contains("keep_this")));
         assert!(has_kept);
     }
+
+    #[tokio::test]
+    async fn test_find_duplicate_memory() {
+        let temp_dir = tempdir().unwrap();
+        let working_dir = temp_dir.path().join("working");
+
+        let router = MemoryServer {
+            tool_router: ToolRouter::new(),
+            instructions: String::new(),
+            global_memory_dir: temp_dir.path().join("global"),
+            embedder: None,
+            relevant_memories_only: false,
+        };
+        router
+            .remember(
+                "context",
+                "development",
+                "Use black  for formatting",
+                &["tools"],
+                false,
+                Some(&working_dir),
+            )
+            .unwrap();
+
+        let duplicate = router
+            .find_duplicate(
+                "development",
+                "use black for formatting",
+                false,
+                Some(&working_dir),
+            )
+            .await
+            .unwrap();
+        assert_eq!(duplicate.text, "Use black  for formatting");
+        assert_eq!(duplicate.tags, vec!["tools"]);
+
+        assert!(router
+            .find_duplicate(
+                "development",
+                "use ruff for linting",
+                false,
+                Some(&working_dir)
+            )
+            .await
+            .is_none());
+        assert!(router
+            .find_duplicate("development", "use black for formatting", true, None)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_falls_back_to_words_when_embedding_fails() {
+        let temp_dir = tempdir().unwrap();
+        let embedder: MemoryEmbedder = std::sync::Arc::new(|_texts: Vec<String>| {
+            Box::pin(async { Err(anyhow::anyhow!("embedding service unavailable")) })
+        });
+        let router = MemoryServer {
+            tool_router: ToolRouter::new(),
+            instructions: String::new(),
+            global_memory_dir: temp_dir.path().join("global"),
+            embedder: Some(embedder),
+            relevant_memories_only: false,
+        };
+        let searched = vec![(
+            temp_dir.path().join("global"),
+            recall::parse_entries("dev", "use black for python\n\nprefer tabs", true),
+        )];
+
+        let results = router
+            .rank_memories("How do we format Python?", &searched, 5)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.text, "use black for python");
+    }
 }
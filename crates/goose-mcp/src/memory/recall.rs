@@ -0,0 +1,362 @@
+//! Similarity search over stored memories.
+//!
+//! The category files stay the source of truth. When goose registers an embedder, each memory's
+//! vector is cached in an index file next to them and refreshed for entries it has not seen;
+//! without one, memories are ranked by how many of the query's words they contain, counting
+//! their category and tags.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::{fs, io};
+
+/// Turns texts into embedding vectors, one per text.
+pub type MemoryEmbedder = Arc<
+    dyn Fn(Vec<String>) -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<Vec<f32>>>> + Send>>
+        + Send
+        + Sync,
+>;
+
+static EMBEDDER: OnceLock<MemoryEmbedder> = OnceLock::new();
+
+/// Enables semantic recall for memory servers created after this call.
+pub fn set_memory_embedder(embedder: MemoryEmbedder) {
+    let _ = EMBEDDER.set(embedder);
+}
+
+pub(crate) fn registered_embedder() -> Option<MemoryEmbedder> {
+    EMBEDDER.get().cloned()
+}
+
+const INDEX_DIR: &str = ".index";
+const INDEX_FILE: &str = "embeddings.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryEntry {
+    pub category: String,
+    pub tags: Vec<String>,
+    pub text: String,
+    pub is_global: bool,
+}
+
+impl MemoryEntry {
+    fn searchable_text(&self) -> String {
+        if self.tags.is_empty() {
+            format!("{}: {}", self.category, self.text)
+        } else {
+            format!("{}: {} ({})", self.category, self.text, self.tags.join(" "))
+        }
+    }
+}
+
+/// Splits a category file into its entries, which are separated by blank lines and may start
+/// with a `# tag tag` line.
+pub fn parse_entries(category: &str, content: &str, is_global: bool) -> Vec<MemoryEntry> {
+    content
+        .split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines().peekable();
+            let tags = match lines.peek() {
+                Some(first) if first.starts_with('#') => {
+                    let tags = first
+                        .trim_start_matches('#')
+                        .split_whitespace()
+                        .map(String::from)
+                        .collect();
+                    lines.next();
+                    tags
+                }
+                _ => Vec::new(),
+            };
+            let text = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+            (!text.is_empty()).then(|| MemoryEntry {
+                category: category.to_string(),
+                tags,
+                text,
+                is_global,
+            })
+        })
+        .collect()
+}
+
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexFile {
+    entries: Vec<IndexedEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexedEntry {
+    category: String,
+    text: String,
+    vector: Vec<f32>,
+}
+
+fn index_path(memory_dir: &Path) -> std::path::PathBuf {
+    memory_dir.join(INDEX_DIR).join(INDEX_FILE)
+}
+
+fn load_index(memory_dir: &Path) -> IndexFile {
+    fs::read_to_string(index_path(memory_dir))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(memory_dir: &Path, index: &IndexFile) -> io::Result<()> {
+    let path = index_path(memory_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(index)?)
+}
+
+/// Returns a vector for each entry, embedding only those missing from the index (or stored
+/// with a different dimension, after switching embedding models) and saving the result.
+async fn vectors_for(
+    embedder: &MemoryEmbedder,
+    memory_dir: &Path,
+    entries: &[MemoryEntry],
+    dimensions: usize,
+) -> anyhow::Result<Vec<Vec<f32>>> {
+    let mut cached: HashMap<(String, String), Vec<f32>> = load_index(memory_dir)
+        .entries
+        .into_iter()
+        .filter(|e| e.vector.len() == dimensions)
+        .map(|e| ((e.category, e.text), e.vector))
+        .collect();
+
+    let missing: Vec<&MemoryEntry> = entries
+        .iter()
+        .filter(|e| !cached.contains_key(&(e.category.clone(), e.text.clone())))
+        .collect();
+    let stale = cached.len() + missing.len() != entries.len();
+    if !missing.is_empty() {
+        let vectors = embedder(missing.iter().map(|e| e.text.clone()).collect()).await?;
+        if vectors.len() != missing.len() {
+            anyhow::bail!(
+                "expected {} embeddings, got {}",
+                missing.len(),
+                vectors.len()
+            );
+        }
+        for (entry, vector) in missing.iter().zip(vectors) {
+            cached.insert((entry.category.clone(), entry.text.clone()), vector);
+        }
+    }
+
+    let vectors: Vec<Vec<f32>> = entries
+        .iter()
+        .map(|e| {
+            cached
+                .get(&(e.category.clone(), e.text.clone()))
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect();
+    if !missing.is_empty() || stale {
+        let index = IndexFile {
+            entries: entries
+                .iter()
+                .zip(&vectors)
+                .map(|(e, vector)| IndexedEntry {
+                    category: e.category.clone(),
+                    text: e.text.clone(),
+                    vector: vector.clone(),
+                })
+                .collect(),
+        };
+        if let Err(e) = save_index(memory_dir, &index) {
+            tracing::warn!("Failed to save memory index: {}", e);
+        }
+    }
+    Ok(vectors)
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn keyword_score(query_terms: &HashSet<String>, entry: &MemoryEntry) -> f32 {
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let text = normalize(&entry.searchable_text());
+    let matched = query_terms
+        .iter()
+        .filter(|term| text.contains(term.as_str()))
+        .count();
+    matched as f32 / query_terms.len() as f32
+}
+
+fn query_terms(query: &str) -> HashSet<String> {
+    normalize(query)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.len() > 2)
+        .map(String::from)
+        .collect()
+}
+
+/// One group of entries to search, with the directory their index lives in.
+pub struct Scope<'a> {
+    pub memory_dir: &'a Path,
+    pub entries: Vec<MemoryEntry>,
+}
+
+/// Ranks entries by similarity to `query`, best first, keeping at most `limit`.
+pub async fn search(
+    embedder: Option<&MemoryEmbedder>,
+    query: &str,
+    scopes: Vec<Scope<'_>>,
+    limit: usize,
+) -> anyhow::Result<Vec<(f32, MemoryEntry)>> {
+    let mut scored = Vec::new();
+    match embedder {
+        Some(embedder) => {
+            let query_vector = embedder(vec![query.to_string()])
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("no embedding returned for the query"))?;
+            for scope in scopes {
+                let vectors = vectors_for(
+                    embedder,
+                    scope.memory_dir,
+                    &scope.entries,
+                    query_vector.len(),
+                )
+                .await?;
+                scored.extend(
+                    scope
+                        .entries
+                        .into_iter()
+                        .zip(vectors)
+                        .map(|(entry, vector)| (cosine_similarity(&query_vector, &vector), entry)),
+                );
+            }
+        }
+        None => {
+            let terms = query_terms(query);
+            for scope in scopes {
+                scored.extend(
+                    scope
+                        .entries
+                        .into_iter()
+                        .map(|entry| (keyword_score(&terms, &entry), entry))
+                        .filter(|(score, _)| *score > 0.0),
+                );
+            }
+        }
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(limit);
+    Ok(scored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Embeds by counting a few fixed words, so related texts point the same way.
+    fn word_count_embedder(calls: Arc<std::sync::Mutex<usize>>) -> MemoryEmbedder {
+        Arc::new(move |texts: Vec<String>| {
+            *calls.lock().unwrap() += texts.len();
+            let vectors = texts
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    ["python", "format", "coffee", "rust"]
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect();
+            Box::pin(async move { Ok(vectors) })
+        })
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let content = "# tools formatting\nuse black\n\nplain entry\nsecond line\n\n";
+        let entries = parse_entries("dev", content, false);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tags, vec!["tools", "formatting"]);
+        assert_eq!(entries[0].text, "use black");
+        assert!(entries[1].tags.is_empty());
+        assert_eq!(entries[1].text, "plain entry\nsecond line");
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_by_embedding_and_caches_vectors() {
+        let dir = tempdir().unwrap();
+        let calls = Arc::new(std::sync::Mutex::new(0));
+        let embedder = word_count_embedder(calls.clone());
+        let entries = vec![
+            MemoryEntry {
+                category: "personal".to_string(),
+                tags: vec![],
+                text: "Takes coffee black".to_string(),
+                is_global: true,
+            },
+            MemoryEntry {
+                category: "development".to_string(),
+                tags: vec!["python".to_string()],
+                text: "Format Python with black".to_string(),
+                is_global: true,
+            },
+        ];
+
+        let scope = || Scope {
+            memory_dir: dir.path(),
+            entries: entries.clone(),
+        };
+        let results = search(Some(&embedder), "python formatting", vec![scope()], 1)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.category, "development");
+        assert_eq!(*calls.lock().unwrap(), 3);
+
+        // Stored vectors are reused; only the query is embedded again
+        search(Some(&embedder), "coffee", vec![scope()], 1)
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_search_without_embedder_matches_words() {
+        let dir = tempdir().unwrap();
+        let entries = parse_entries("dev", "use black for python\n\nprefer tabs", false);
+        let results = search(
+            None,
+            "How do we format Python?",
+            vec![Scope {
+                memory_dir: dir.path(),
+                entries,
+            }],
+            5,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.text, "use black for python");
+    }
+}
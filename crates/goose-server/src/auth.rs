@@ -1,13 +1,277 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::Extension;
 use axum::{
+    body::Body,
     extract::{Request, State},
-    http::StatusCode,
+    http::{header, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
+use goose::config::{secrets_match, ApiToken, TokenScope};
+use goose::session::SessionManager;
 
-pub async fn check_token(
-    State(state): State<String>,
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+
+/// Largest request body inspected for a session id when a request is made with an API token.
+/// Matches the limit of `/reply`, the largest body the server accepts.
+const MAX_INSPECTED_BODY_BYTES: usize = 50 * 1024 * 1024;
+
+/// Who a request was authenticated as.
+#[derive(Debug, Clone)]
+pub enum Principal {
+    /// The holder of the server's secret key, with full access.
+    Owner,
+    /// A named API token, limited to its scopes and to the sessions it created.
+    Token(ApiToken),
+}
+
+impl Principal {
+    /// The id of the API token, or `None` for the owner.
+    pub fn token_id(&self) -> Option<&str> {
+        match self {
+            Principal::Owner => None,
+            Principal::Token(token) => Some(&token.id),
+        }
+    }
+
+    /// How this principal is named in the audit log.
+    pub fn actor(&self) -> String {
+        match self {
+            Principal::Owner => "owner".to_string(),
+            Principal::Token(token) => format!("token:{}", token.name),
+        }
+    }
+
+    /// Makes a session this principal just created its own. Sessions created with the secret
+    /// key stay unowned, which keeps them out of reach of every API token.
+    pub async fn claim_session(
+        &self,
+        session_manager: &SessionManager,
+        session_id: &str,
+    ) -> anyhow::Result<()> {
+        match self.token_id() {
+            Some(token_id) => {
+                session_manager
+                    .set_session_owner(session_id, token_id)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Fails with `NOT_FOUND` unless this principal may act on the session. Sessions of other
+    /// principals are reported as missing rather than forbidden.
+    pub async fn authorize_session(
+        &self,
+        session_manager: &SessionManager,
+        session_id: &str,
+    ) -> Result<(), StatusCode> {
+        let Some(token_id) = self.token_id() else {
+            return Ok(());
+        };
+        let owner = session_manager
+            .get_session_owner(session_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if owner.as_deref() == Some(token_id) {
+            Ok(())
+        } else {
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+
+    /// Ids of the sessions this principal may see, or `None` when it may see all of them.
+    pub async fn visible_sessions(
+        &self,
+        session_manager: &SessionManager,
+    ) -> anyhow::Result<Option<HashSet<String>>> {
+        match self.token_id() {
+            Some(token_id) => Ok(Some(
+                session_manager
+                    .list_owned_session_ids(token_id)
+                    .await?
+                    .into_iter()
+                    .collect(),
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Checks the session a handler is about to act on against the principal of the request.
+/// Requests that did not pass through `check_token` carry no principal and are let through.
+pub async fn authorize_session(
+    principal: &Option<Extension<Principal>>,
+    session_manager: &SessionManager,
+    session_id: &str,
+) -> Result<(), ErrorResponse> {
+    match principal {
+        Some(Extension(principal)) => principal
+            .authorize_session(session_manager, session_id)
+            .await
+            .map_err(|status| ErrorResponse {
+                message: format!("Session {} not found", session_id),
+                status,
+            }),
+        None => Ok(()),
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthState {
+    secret_key: String,
+    app_state: Arc<AppState>,
+}
+
+impl AuthState {
+    pub fn new(secret_key: String, app_state: Arc<AppState>) -> Self {
+        Self {
+            secret_key,
+            app_state,
+        }
+    }
+}
+
+/// Scope an API token needs for a route, or `None` for routes only the owner may use.
+pub fn required_scope(method: &Method, path: &str) -> Option<TokenScope> {
+    let under = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+
+    if path == "/agent/add_extension"
+        || path == "/agent/remove_extension"
+        || under("/config/extensions")
+    {
+        Some(TokenScope::ManageExtensions)
+    } else if under("/schedule") || path == "/recipes/schedule" {
+        Some(TokenScope::Schedule)
+    } else if matches!(
+        path,
+        "/recipes/save" | "/recipes/delete" | "/recipes/slash-command"
+    ) {
+        // These change the owner's recipe files and commands, which schedules may run
+        Some(TokenScope::ManageConfig)
+    } else if path == "/sessions/insights" {
+        None
+    } else if under("/sessions") || under("/diagnostics") {
+        if method == Method::GET {
+            Some(TokenScope::ReadSessions)
+        } else {
+            Some(TokenScope::Reply)
+        }
+    } else if path == "/reply"
+        || path == "/dictation/transcribe"
+        || under("/agent")
+        || under("/action-required")
+        || under("/recipes")
+    {
+        Some(TokenScope::Reply)
+    } else if under("/config")
+        || under("/local-inference")
+        || under("/dictation")
+        || path == "/handle_openrouter"
+        || path == "/handle_tetrate"
+    {
+        Some(TokenScope::ManageConfig)
+    } else {
+        None
+    }
+}
+
+/// Whether a route acts on an existing session, so a token request must name one the
+/// middleware can check.
+fn requires_session(path: &str) -> bool {
+    path == "/reply"
+        || path.starts_with("/action-required/")
+        || (path.starts_with("/agent/")
+            && !matches!(
+                path,
+                "/agent/start" | "/agent/list_apps" | "/agent/import_app"
+            )
+            && !path.starts_with("/agent/export_app/"))
+}
+
+/// Session named in the path of a `/sessions/{session_id}/...` or `/diagnostics/{session_id}`
+/// route.
+fn path_session_id(path: &str) -> Option<&str> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    match (segments.next(), segments.next()) {
+        (Some("sessions"), Some(id)) if !matches!(id, "search" | "insights" | "import") => Some(id),
+        (Some("diagnostics"), Some(id)) => Some(id),
+        _ => None,
+    }
+}
+
+fn query_session_id(request: &Request) -> Option<String> {
+    request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "session_id" || key == "sessionId")
+            .map(|(_, value)| value.into_owned())
+    })
+}
+
+/// Reads the body of a JSON request to find a top-level `session_id`, or `sessionId` for the
+/// camelCase request types, handing back a request with the same body.
+async fn body_session_id(request: Request) -> Result<(Request, Option<String>), StatusCode> {
+    let is_json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_json {
+        return Ok((request, None));
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_INSPECTED_BODY_BYTES)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let session_id = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|value| {
+            ["session_id", "sessionId"]
+                .iter()
+                .find_map(|key| value.get(*key).and_then(|id| id.as_str()))
+                .map(str::to_string)
+        });
+    Ok((Request::from_parts(parts, Body::from(bytes)), session_id))
+}
+
+async fn check_session_access(
+    app_state: &AppState,
+    token: &ApiToken,
     request: Request,
+) -> Result<Request, StatusCode> {
+    let mut session_ids: Vec<String> = path_session_id(request.uri().path())
+        .map(str::to_string)
+        .into_iter()
+        .chain(query_session_id(&request))
+        .collect();
+    let (request, body_id) = body_session_id(request).await?;
+    session_ids.extend(body_id);
+
+    if session_ids.is_empty() && requires_session(request.uri().path()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let principal = Principal::Token(token.clone());
+    for session_id in session_ids {
+        principal
+            .authorize_session(app_state.session_manager(), &session_id)
+            .await?;
+    }
+    Ok(request)
+}
+
+pub async fn check_token(
+    State(auth): State<AuthState>,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     if request.uri().path() == "/status"
@@ -17,13 +281,128 @@ pub async fn check_token(
     {
         return Ok(next.run(request).await);
     }
-    let secret_key = request
+    let presented = request
         .headers()
         .get("X-Secret-Key")
-        .and_then(|value| value.to_str().ok());
+        .or_else(|| request.headers().get(header::AUTHORIZATION))
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.strip_prefix("Bearer ").unwrap_or(value).to_string());
+
+    let Some(presented) = presented else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    if secrets_match(&presented, &auth.secret_key) {
+        request.extensions_mut().insert(Principal::Owner);
+        return Ok(next.run(request).await);
+    }
+
+    let Some(token) = auth.app_state.api_tokens.authenticate(&presented) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    match required_scope(request.method(), request.uri().path()) {
+        Some(scope) if token.has_scope(scope) => {}
+        _ => return Err(StatusCode::FORBIDDEN),
+    }
+
+    let mut request = check_session_access(&auth.app_state, &token, request).await?;
+    request.extensions_mut().insert(Principal::Token(token));
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        let cases = [
+            (Method::GET, "/sessions", Some(TokenScope::ReadSessions)),
+            (
+                Method::GET,
+                "/sessions/20250101_1",
+                Some(TokenScope::ReadSessions),
+            ),
+            (
+                Method::DELETE,
+                "/sessions/20250101_1",
+                Some(TokenScope::Reply),
+            ),
+            (Method::GET, "/sessions/insights", None),
+            (Method::POST, "/reply", Some(TokenScope::Reply)),
+            (Method::POST, "/agent/start", Some(TokenScope::Reply)),
+            (
+                Method::POST,
+                "/agent/add_extension",
+                Some(TokenScope::ManageExtensions),
+            ),
+            (
+                Method::DELETE,
+                "/config/extensions/developer",
+                Some(TokenScope::ManageExtensions),
+            ),
+            (
+                Method::POST,
+                "/config/upsert",
+                Some(TokenScope::ManageConfig),
+            ),
+            (
+                Method::POST,
+                "/recipes/schedule",
+                Some(TokenScope::Schedule),
+            ),
+            (Method::GET, "/schedule/list", Some(TokenScope::Schedule)),
+            (
+                Method::POST,
+                "/recipes/save",
+                Some(TokenScope::ManageConfig),
+            ),
+            (
+                Method::POST,
+                "/recipes/delete",
+                Some(TokenScope::ManageConfig),
+            ),
+            (
+                Method::POST,
+                "/recipes/slash-command",
+                Some(TokenScope::ManageConfig),
+            ),
+            (Method::GET, "/recipes/list", Some(TokenScope::Reply)),
+            (Method::POST, "/recipes/parse", Some(TokenScope::Reply)),
+            (Method::POST, "/recipes/encode", Some(TokenScope::Reply)),
+            (Method::GET, "/scheduled", None),
+            (Method::GET, "/auth/tokens", None),
+            (Method::GET, "/audit", None),
+            (Method::POST, "/tunnel/start", None),
+        ];
+        for (method, path, expected) in cases {
+            assert_eq!(
+                required_scope(&method, path),
+                expected,
+                "{} {}",
+                method,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_requires_session() {
+        assert!(requires_session("/reply"));
+        assert!(requires_session("/action-required/tool-confirmation"));
+        assert!(requires_session("/agent/call_tool"));
+        assert!(!requires_session("/agent/start"));
+        assert!(!requires_session("/agent/export_app/todo"));
+        assert!(!requires_session("/sessions"));
+    }
 
-    match secret_key {
-        Some(key) if key == state => Ok(next.run(request).await),
-        _ => Err(StatusCode::UNAUTHORIZED),
+    #[test]
+    fn test_path_session_id() {
+        assert_eq!(path_session_id("/sessions/abc/fork"), Some("abc"));
+        assert_eq!(path_session_id("/diagnostics/abc"), Some("abc"));
+        assert_eq!(path_session_id("/sessions/search"), None);
+        assert_eq!(path_session_id("/sessions"), None);
+        assert_eq!(path_session_id("/reply"), None);
     }
 }
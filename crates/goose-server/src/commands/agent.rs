@@ -1,9 +1,9 @@
+use crate::auth::{check_token, AuthState};
 use crate::configuration;
 use crate::state;
 use anyhow::Result;
 use axum::middleware;
 use axum_server::Handle;
-use goose_server::auth::check_token;
 use goose_server::tls::self_signed_config;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
@@ -49,7 +49,7 @@ pub async fn run() -> Result<()> {
 
     let app = crate::routes::configure(app_state.clone(), secret_key.clone())
         .layer(middleware::from_fn_with_state(
-            secret_key.clone(),
+            AuthState::new(secret_key.clone(), app_state.clone()),
             check_token,
         ))
         .layer(cors);
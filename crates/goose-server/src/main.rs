@@ -1,3 +1,4 @@
+mod auth;
 mod commands;
 mod configuration;
 mod error;
@@ -54,7 +55,12 @@ async fn main() -> anyhow::Result<()> {
             match server {
                 McpCommand::AutoVisualiser => serve(AutoVisualiserRouter::new()).await?,
                 McpCommand::ComputerController => serve(ComputerControllerServer::new()).await?,
-                McpCommand::Memory => serve(MemoryServer::new()).await?,
+                McpCommand::Memory => {
+                    if let Some(embedder) = goose::providers::embedding::memory_embedder() {
+                        goose_mcp::set_memory_embedder(embedder);
+                    }
+                    serve(MemoryServer::new()).await?
+                }
                 McpCommand::Tutorial => serve(TutorialServer::new()).await?,
             }
         }
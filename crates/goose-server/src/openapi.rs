@@ -8,7 +8,10 @@ use goose::download_manager::{DownloadProgress, DownloadStatus};
 use goose::model::ModelConfig;
 use goose::permission::permission_confirmation::{Permission, PrincipalType};
 use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata, ProviderType};
-use goose::session::{Session, SessionInsights, SessionType, SystemInfo};
+use goose::session::{
+    FileCheckpoint, Session, SessionBranch, SessionInsights, SessionType, SystemInfo, TodoItem,
+    TodoList, TodoStatus,
+};
 use rmcp::model::{
     Annotations, Content, EmbeddedResource, Icon, ImageContent, JsonObject, RawAudioContent,
     RawEmbeddedResource, RawImageContent, RawResource, RawTextContent, ResourceContents, Role,
@@ -370,7 +373,10 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::agent::restart_agent,
         super::routes::agent::update_working_dir,
         super::routes::agent::get_tools,
+        super::routes::agent::get_extension_status,
         super::routes::agent::read_resource,
+        super::routes::agent::subscribe_resource,
+        super::routes::agent::unsubscribe_resource,
         super::routes::agent::call_tool,
         super::routes::agent::list_apps,
         super::routes::agent::export_app,
@@ -380,6 +386,10 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::agent::agent_remove_extension,
         super::routes::agent::update_agent_provider,
         super::routes::action_required::confirm_tool_action,
+        super::routes::api_tokens::list_api_tokens,
+        super::routes::api_tokens::create_api_token,
+        super::routes::api_tokens::revoke_api_token,
+        super::routes::audit::query_audit_log,
         super::routes::reply::reply,
         super::routes::session::list_sessions,
         super::routes::session::search_sessions,
@@ -391,7 +401,12 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::session::import_session,
         super::routes::session::update_session_user_recipe_values,
         super::routes::session::fork_session,
+        super::routes::session::branch_session,
+        super::routes::session::get_session_branches,
         super::routes::session::get_session_extensions,
+        super::routes::session::list_session_checkpoints,
+        super::routes::session::restore_session_checkpoints,
+        super::routes::session::get_session_todos,
         super::routes::schedule::create_schedule,
         super::routes::schedule::list_schedules,
         super::routes::schedule::delete_schedule,
@@ -466,6 +481,13 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::prompts::SavePromptRequest,
         goose::prompt_template::Template,
         super::routes::action_required::ConfirmToolActionRequest,
+        super::routes::api_tokens::CreateApiTokenRequest,
+        super::routes::api_tokens::CreateApiTokenResponse,
+        goose::config::ApiToken,
+        goose::config::TokenScope,
+        goose::audit::AuditEntry,
+        goose::audit::AuditKind,
+        goose::audit::AuditDecision,
         super::routes::reply::ChatRequest,
         super::routes::session::ImportSessionRequest,
         super::routes::session::SessionListResponse,
@@ -473,8 +495,17 @@ derive_utoipa!(Icon as IconSchema);
         super::routes::session::UpdateSessionUserRecipeValuesRequest,
         super::routes::session::UpdateSessionUserRecipeValuesResponse,
         super::routes::session::ForkRequest,
+        super::routes::session::BranchSessionRequest,
         super::routes::session::ForkResponse,
         super::routes::session::SessionExtensionsResponse,
+        super::routes::session::MessageCheckpoints,
+        super::routes::session::SessionCheckpointsResponse,
+        super::routes::session::RestoreCheckpointsRequest,
+        super::routes::session::RestoreCheckpointsResponse,
+        FileCheckpoint,
+        TodoList,
+        TodoItem,
+        TodoStatus,
         Message,
         MessageContent,
         MessageMetadata,
@@ -525,6 +556,7 @@ derive_utoipa!(Icon as IconSchema);
         ModelInfo,
         ModelConfig,
         Session,
+        SessionBranch,
         SessionInsights,
         SessionType,
         SystemInfo,
@@ -563,6 +595,7 @@ derive_utoipa!(Icon as IconSchema);
         goose::recipe::Recipe,
         goose::recipe::Author,
         goose::recipe::Settings,
+        goose::agents::platform_extensions::developer::sandbox::SandboxPolicy,
         goose::recipe::RecipeParameter,
         goose::recipe::RecipeParameterInputType,
         goose::recipe::RecipeParameterRequirement,
@@ -572,8 +605,12 @@ derive_utoipa!(Icon as IconSchema);
         goose::agents::types::SuccessCheck,
         super::routes::agent::UpdateProviderRequest,
         super::routes::agent::GetToolsQuery,
+        super::routes::agent::ExtensionStatusQuery,
+        goose::agents::extension_supervisor::ExtensionStatus,
+        goose::agents::extension_supervisor::ExtensionHealth,
         super::routes::agent::ReadResourceRequest,
         super::routes::agent::ReadResourceResponse,
+        super::routes::agent::ResourceSubscriptionRequest,
         super::routes::agent::CallToolRequest,
         super::routes::agent::CallToolResponse,
         super::routes::agent::ListAppsRequest,
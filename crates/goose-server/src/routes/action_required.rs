@@ -1,6 +1,7 @@
+use crate::auth::{authorize_session, Principal};
 use crate::routes::errors::ErrorResponse;
 use crate::state::AppState;
-use axum::{extract::State, routing::post, Json, Router};
+use axum::{extract::State, routing::post, Extension, Json, Router};
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::{Permission, PermissionConfirmation};
 use serde::{Deserialize, Serialize};
@@ -29,13 +30,16 @@ fn default_principal_type() -> PrincipalType {
     responses(
         (status = 200, description = "Tool confirmation action is confirmed", body = Value),
         (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "Session not found"),
         (status = 500, description = "Internal server error")
     )
 )]
 pub async fn confirm_tool_action(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<ConfirmToolActionRequest>,
 ) -> Result<Json<Value>, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &request.session_id).await?;
     let agent = state.get_agent_for_route(request.session_id).await?;
 
     agent
@@ -96,5 +100,93 @@ mod tests {
 
             assert_eq!(response.status(), StatusCode::OK);
         }
+
+        #[tokio::test(flavor = "multi_thread")]
+        async fn test_tool_confirmation_rejects_other_sessions() {
+            use crate::auth::{check_token, AuthState};
+            use goose::config::{ApiTokenStore, TokenScope};
+            use goose::session::session_manager::SessionType;
+
+            let tokens_dir = tempfile::tempdir().unwrap();
+            let mut state = (*AppState::new(true).await.unwrap()).clone();
+            state.api_tokens = Arc::new(ApiTokenStore::new(tokens_dir.path().to_path_buf()));
+            let state = Arc::new(state);
+
+            let (caller_token, caller) = state
+                .api_tokens
+                .create("caller", vec![TokenScope::Reply])
+                .unwrap();
+            let (other, _) = state
+                .api_tokens
+                .create("other", vec![TokenScope::Reply])
+                .unwrap();
+            let session = state
+                .session_manager()
+                .create_session(
+                    tokens_dir.path().to_path_buf(),
+                    "owned by other".to_string(),
+                    SessionType::User,
+                )
+                .await
+                .unwrap();
+            state
+                .session_manager()
+                .set_session_owner(&session.id, &other.id)
+                .await
+                .unwrap();
+
+            // The handler checks the session it acts on even without the middleware in front
+            let request = Request::builder()
+                .uri("/action-required/tool-confirmation")
+                .method("POST")
+                .header("content-type", "application/json")
+                .extension(Principal::Token(caller_token))
+                .body(Body::from(
+                    serde_json::to_string(&ConfirmToolActionRequest {
+                        id: "test-id".to_string(),
+                        principal_type: PrincipalType::Tool,
+                        action: Permission::AllowOnce,
+                        session_id: session.id.clone(),
+                    })
+                    .unwrap(),
+                ))
+                .unwrap();
+            let response = routes(state.clone()).oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let app = routes(state.clone()).layer(axum::middleware::from_fn_with_state(
+                AuthState::new("test-secret".to_string(), state.clone()),
+                check_token,
+            ));
+            let confirm = |session_id: &str| {
+                Request::builder()
+                    .uri("/action-required/tool-confirmation")
+                    .method("POST")
+                    .header("content-type", "application/json")
+                    .header("authorization", format!("Bearer {caller}"))
+                    .body(Body::from(
+                        serde_json::to_string(&ConfirmToolActionRequest {
+                            id: "test-id".to_string(),
+                            principal_type: PrincipalType::Tool,
+                            action: Permission::AllowOnce,
+                            session_id: session_id.to_string(),
+                        })
+                        .unwrap(),
+                    ))
+                    .unwrap()
+            };
+
+            // Another token's session, then one started with the secret key
+            let response = app.clone().oneshot(confirm(&session.id)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            let response = app.oneshot(confirm("test-session")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            state
+                .session_manager()
+                .delete_session(&session.id)
+                .await
+                .unwrap();
+        }
     }
 }
@@ -1,3 +1,4 @@
+use crate::auth::{authorize_session, Principal};
 use crate::routes::errors::ErrorResponse;
 use crate::routes::recipe_utils::{
     apply_recipe_to_agent, build_recipe_with_parameter_values, load_recipe_by_id, validate_recipe,
@@ -8,8 +9,10 @@ use axum::{
     extract::{Query, State},
     http::StatusCode,
     routing::{get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use goose::agents::container::configured_runtime;
+use goose::agents::extension_supervisor::ExtensionStatus;
 use goose::agents::{Container, ExtensionLoadResult};
 use goose::goose_apps::{fetch_mcp_apps, GooseApp, McpAppCache};
 
@@ -18,7 +21,7 @@ use goose::agents::ExtensionConfig;
 use goose::config::resolve_extensions_for_new_session;
 use goose::config::{Config, GooseMode};
 use goose::model::ModelConfig;
-use goose::providers::create;
+use goose::providers::create_for_recipe;
 use goose::recipe::Recipe;
 use goose::recipe_deeplink;
 use goose::session::session_manager::SessionType;
@@ -27,7 +30,7 @@ use goose::{
     agents::{extension::ToolInfo, extension_manager::get_parameter_names},
     config::permission::PermissionLevel,
 };
-use rmcp::model::{CallToolRequestParams, Content};
+use rmcp::model::{CallToolRequestParams, Content, ErrorCode, ErrorData};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashSet;
@@ -56,6 +59,11 @@ pub struct GetToolsQuery {
     session_id: String,
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ExtensionStatusQuery {
+    session_id: String,
+}
+
 #[derive(Deserialize, utoipa::ToSchema)]
 pub struct StartAgentRequest {
     working_dir: String,
@@ -116,6 +124,13 @@ pub struct ReadResourceRequest {
     uri: String,
 }
 
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct ResourceSubscriptionRequest {
+    session_id: String,
+    extension_name: String,
+    uri: String,
+}
+
 #[derive(Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ReadResourceResponse {
@@ -170,6 +185,7 @@ pub struct RestartAgentResponse {
 #[allow(clippy::too_many_lines)]
 async fn start_agent(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<StartAgentRequest>,
 ) -> Result<Json<Session>, ErrorResponse> {
     goose::posthog::set_session_context("desktop", false);
@@ -228,6 +244,13 @@ async fn start_agent(
             }
         })?;
 
+    if let Some(Extension(principal)) = principal {
+        principal
+            .claim_session(manager, &session.id)
+            .await
+            .map_err(|err| ErrorResponse::internal(format!("Failed to create session: {}", err)))?;
+    }
+
     let recipe_extensions = original_recipe
         .as_ref()
         .and_then(|r| r.extensions.as_deref());
@@ -335,8 +358,11 @@ async fn start_agent(
 )]
 async fn resume_agent(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<ResumeAgentRequest>,
 ) -> Result<Json<ResumeAgentResponse>, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id).await?;
+
     goose::posthog::set_session_context("desktop", true);
 
     let session = state
@@ -410,8 +436,11 @@ async fn resume_agent(
 )]
 async fn update_from_session(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<UpdateFromSessionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id).await?;
+
     let agent = state
         .get_agent_for_route(payload.session_id.clone())
         .await
@@ -472,8 +501,13 @@ async fn update_from_session(
 )]
 async fn get_tools(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Query(query): Query<GetToolsQuery>,
 ) -> Result<Json<Vec<ToolInfo>>, StatusCode> {
+    authorize_session(&principal, state.session_manager(), &query.session_id)
+        .await
+        .map_err(|e| e.status)?;
+
     let config = Config::global();
     let goose_mode = config.get_goose_mode().unwrap_or(GooseMode::Auto);
     let session_id = query.session_id;
@@ -527,8 +561,13 @@ async fn get_tools(
 )]
 async fn update_agent_provider(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<UpdateProviderRequest>,
 ) -> Result<(), impl IntoResponse> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id)
+        .await
+        .map_err(|e| (e.status, e.message))?;
+
     let agent = state
         .get_agent_for_route(payload.session_id.clone())
         .await
@@ -557,14 +596,21 @@ async fn update_agent_provider(
         EnabledExtensionsState::for_session(state.session_manager(), &payload.session_id, config)
             .await;
 
-    let new_provider = create(&payload.provider, model_config, extensions)
+    let recipe = state
+        .session_manager()
+        .get_session(&payload.session_id, false)
         .await
-        .map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Failed to create {} provider: {}", &payload.provider, e),
-            )
-        })?;
+        .ok()
+        .and_then(|session| session.recipe);
+    let new_provider =
+        create_for_recipe(&payload.provider, model_config, extensions, recipe.as_ref())
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to create {} provider: {}", &payload.provider, e),
+                )
+            })?;
 
     agent
         .update_provider(new_provider, &payload.session_id)
@@ -592,8 +638,11 @@ async fn update_agent_provider(
 )]
 async fn agent_add_extension(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<AddExtensionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &request.session_id).await?;
+
     let extension_name = request.config.name();
     let agent = state.get_agent(request.session_id.clone()).await?;
 
@@ -624,8 +673,11 @@ async fn agent_add_extension(
 )]
 async fn agent_remove_extension(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<RemoveExtensionRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &request.session_id).await?;
+
     let agent = state.get_agent(request.session_id.clone()).await?;
 
     agent
@@ -648,6 +700,7 @@ async fn agent_remove_extension(
     request_body = SetContainerRequest,
     responses(
         (status = 200, description = "Container set successfully"),
+        (status = 400, description = "Container does not mount the session's working directory"),
         (status = 401, description = "Unauthorized - invalid secret key"),
         (status = 424, description = "Agent not initialized"),
         (status = 500, description = "Internal server error")
@@ -655,11 +708,34 @@ async fn agent_remove_extension(
 )]
 async fn set_container(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<SetContainerRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &request.session_id).await?;
+
     let agent = state.get_agent(request.session_id.clone()).await?;
 
-    let container = request.container_id.map(Container::new);
+    // File tools translate paths through the mount holding the session's working directory
+    let container = match request.container_id {
+        Some(id) => {
+            let session = state
+                .session_manager()
+                .get_session(&request.session_id, false)
+                .await
+                .map_err(|e| ErrorResponse {
+                    message: format!("Failed to get session: {}", e),
+                    status: StatusCode::NOT_FOUND,
+                })?;
+            let container = Container::attach(&configured_runtime(), &id, &session.working_dir)
+                .await
+                .map_err(|e| ErrorResponse {
+                    message: e.to_string(),
+                    status: StatusCode::BAD_REQUEST,
+                })?;
+            Some(container)
+        }
+        None => None,
+    };
     agent.set_container(container).await;
 
     Ok(StatusCode::OK)
@@ -678,8 +754,11 @@ async fn set_container(
 )]
 async fn stop_agent(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<StopAgentRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id).await?;
+
     let session_id = payload.session_id;
     state
         .agent_manager
@@ -760,8 +839,11 @@ async fn restart_agent_internal(
 )]
 async fn restart_agent(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<RestartAgentRequest>,
 ) -> Result<Json<RestartAgentResponse>, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id).await?;
+
     let session_id = payload.session_id.clone();
 
     let session = state
@@ -795,8 +877,11 @@ async fn restart_agent(
 )]
 async fn update_working_dir(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<UpdateWorkingDirRequest>,
 ) -> Result<StatusCode, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id).await?;
+
     let session_id = payload.session_id.clone();
     let working_dir = payload.working_dir.trim();
 
@@ -858,6 +943,31 @@ async fn ensure_extensions_loaded(state: &AppState, session_id: &str) {
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/agent/extension_status",
+    params(
+        ("session_id" = String, Query, description = "Session whose extensions to report on")
+    ),
+    responses(
+        (status = 200, description = "Health of each extension", body = Vec<ExtensionStatus>),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_extension_status(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Query(query): Query<ExtensionStatusQuery>,
+) -> Result<Json<Vec<ExtensionStatus>>, StatusCode> {
+    authorize_session(&principal, state.session_manager(), &query.session_id)
+        .await
+        .map_err(|e| e.status)?;
+
+    let agent = state.get_agent_for_route(query.session_id).await?;
+    Ok(Json(agent.extension_manager.get_extension_statuses().await))
+}
+
 #[utoipa::path(
     post,
     path = "/agent/read_resource",
@@ -872,8 +982,13 @@ async fn ensure_extensions_loaded(state: &AppState, session_id: &str) {
 )]
 async fn read_resource(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<ReadResourceRequest>,
 ) -> Result<Json<ReadResourceResponse>, StatusCode> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id)
+        .await
+        .map_err(|e| e.status)?;
+
     use rmcp::model::ResourceContents;
 
     ensure_extensions_loaded(&state, &payload.session_id).await;
@@ -941,6 +1056,91 @@ async fn read_resource(
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/agent/subscribe_resource",
+    request_body = ResourceSubscriptionRequest,
+    responses(
+        (status = 200, description = "Subscribed; updates arrive as notifications in the reply stream"),
+        (status = 400, description = "Extension not found or does not support subscriptions"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn subscribe_resource(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ResourceSubscriptionRequest>,
+) -> Result<StatusCode, StatusCode> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id)
+        .await
+        .map_err(|e| e.status)?;
+
+    ensure_extensions_loaded(&state, &payload.session_id).await;
+
+    let agent = state
+        .get_agent_for_route(payload.session_id.clone())
+        .await?;
+    agent
+        .extension_manager
+        .subscribe_resource(
+            &payload.session_id,
+            &payload.uri,
+            &payload.extension_name,
+            CancellationToken::default(),
+        )
+        .await
+        .map_err(subscription_error_status)?;
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/agent/unsubscribe_resource",
+    request_body = ResourceSubscriptionRequest,
+    responses(
+        (status = 200, description = "Unsubscribed"),
+        (status = 400, description = "Extension not found or does not support subscriptions"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn unsubscribe_resource(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Json(payload): Json<ResourceSubscriptionRequest>,
+) -> Result<StatusCode, StatusCode> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id)
+        .await
+        .map_err(|e| e.status)?;
+
+    let agent = state
+        .get_agent_for_route(payload.session_id.clone())
+        .await?;
+    agent
+        .extension_manager
+        .unsubscribe_resource(
+            &payload.session_id,
+            &payload.uri,
+            &payload.extension_name,
+            CancellationToken::default(),
+        )
+        .await
+        .map_err(subscription_error_status)?;
+    Ok(StatusCode::OK)
+}
+
+fn subscription_error_status(e: ErrorData) -> StatusCode {
+    error!("resource subscription failed: {}", e.message);
+    if e.code == ErrorCode::INVALID_PARAMS {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
 #[utoipa::path(
     post,
     path = "/agent/call_tool",
@@ -955,8 +1155,13 @@ async fn read_resource(
 )]
 async fn call_tool(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(payload): Json<CallToolRequest>,
 ) -> Result<Json<CallToolResponse>, StatusCode> {
+    authorize_session(&principal, state.session_manager(), &payload.session_id)
+        .await
+        .map_err(|e| e.status)?;
+
     ensure_extensions_loaded(&state, &payload.session_id).await;
 
     let agent = state
@@ -1029,6 +1234,7 @@ pub struct ListAppsResponse {
 )]
 async fn list_apps(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Query(params): Query<ListAppsRequest>,
 ) -> Result<Json<ListAppsResponse>, ErrorResponse> {
     let cache = McpAppCache::new().ok();
@@ -1040,6 +1246,7 @@ async fn list_apps(
             .unwrap_or_default();
         return Ok(Json(ListAppsResponse { apps }));
     };
+    authorize_session(&principal, state.session_manager(), &session_id).await?;
 
     let agent = state
         .get_agent_for_route(session_id.clone())
@@ -1204,7 +1411,10 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/agent/restart", post(restart_agent))
         .route("/agent/update_working_dir", post(update_working_dir))
         .route("/agent/tools", get(get_tools))
+        .route("/agent/extension_status", get(get_extension_status))
         .route("/agent/read_resource", post(read_resource))
+        .route("/agent/subscribe_resource", post(subscribe_resource))
+        .route("/agent/unsubscribe_resource", post(unsubscribe_resource))
         .route("/agent/call_tool", post(call_tool))
         .route("/agent/list_apps", get(list_apps))
         .route("/agent/export_app/{name}", get(export_app))
@@ -0,0 +1,121 @@
+use crate::routes::errors::ErrorResponse;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use goose::config::{ApiToken, TokenScope};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenRequest {
+    /// Unique name to recognise the token by
+    name: String,
+    scopes: Vec<TokenScope>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenResponse {
+    token: ApiToken,
+    /// The token to send as `X-Secret-Key` or `Authorization: Bearer`. It is only shown once.
+    secret: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/tokens",
+    responses(
+        (status = 200, description = "API tokens, without their secrets", body = Vec<ApiToken>),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 403, description = "Forbidden - Only the server's secret key can manage tokens"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "API Tokens"
+)]
+async fn list_api_tokens(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ApiToken>>, ErrorResponse> {
+    let tokens = state
+        .api_tokens
+        .list()
+        .map_err(|e| ErrorResponse::internal(format!("Failed to read API tokens: {}", e)))?;
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/tokens",
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 200, description = "Token created", body = CreateApiTokenResponse),
+        (status = 400, description = "Bad request - Missing name or scopes, or name already taken"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 403, description = "Forbidden - Only the server's secret key can manage tokens")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "API Tokens"
+)]
+async fn create_api_token(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<Json<CreateApiTokenResponse>, ErrorResponse> {
+    let (token, secret) = state
+        .api_tokens
+        .create(&request.name, request.scopes)
+        .map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    Ok(Json(CreateApiTokenResponse { token, secret }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/tokens/{id}",
+    params(
+        ("id" = String, Path, description = "Id or name of the token")
+    ),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 403, description = "Forbidden - Only the server's secret key can manage tokens"),
+        (status = 404, description = "Token not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "API Tokens"
+)]
+async fn revoke_api_token(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ErrorResponse> {
+    let revoked = state
+        .api_tokens
+        .revoke(&id)
+        .map_err(|e| ErrorResponse::internal(format!("Failed to revoke API token: {}", e)))?;
+    if revoked {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ErrorResponse::not_found(format!(
+            "Token '{}' not found",
+            id
+        )))
+    }
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/auth/tokens", get(list_api_tokens).post(create_api_token))
+        .route("/auth/tokens/{id}", delete(revoke_api_token))
+        .with_state(state)
+}
@@ -0,0 +1,61 @@
+use crate::routes::errors::ErrorResponse;
+use axum::{extract::Query, routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use goose::audit::{AuditEntry, AuditFilter, AuditKind, AuditLog};
+use serde::Deserialize;
+
+const MAX_AUDIT_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditQuery {
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    session_id: Option<String>,
+    tool: Option<String>,
+    kind: Option<AuditKind>,
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    params(
+        ("since" = Option<String>, Query, description = "Only entries at or after this time (RFC 3339)"),
+        ("until" = Option<String>, Query, description = "Only entries before this time (RFC 3339)"),
+        ("sessionId" = Option<String>, Query, description = "Only tool calls in this session"),
+        ("tool" = Option<String>, Query, description = "Only calls to this tool"),
+        ("kind" = Option<AuditKind>, Query, description = "Only tool calls or only config changes"),
+        ("limit" = Option<usize>, Query, description = "Maximum entries (default: 100, max: 1000)")
+    ),
+    responses(
+        (status = 200, description = "Audit entries, most recent first", body = Vec<AuditEntry>),
+        (status = 400, description = "Bad request - Invalid filter"),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Audit"
+)]
+async fn query_audit_log(
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, ErrorResponse> {
+    let filter = AuditFilter {
+        since: query.since,
+        until: query.until,
+        session_id: query.session_id,
+        tool: query.tool,
+        kind: query.kind,
+        limit: Some(query.limit.unwrap_or(100).min(MAX_AUDIT_LIMIT)),
+    };
+    let entries = tokio::task::spawn_blocking(move || AuditLog::instance().query(&filter))
+        .await
+        .map_err(|e| ErrorResponse::internal(format!("Failed to read audit log: {}", e)))?;
+    Ok(Json(entries))
+}
+
+pub fn routes() -> Router {
+    Router::new().route("/audit", get(query_audit_log))
+}
@@ -1,3 +1,4 @@
+use crate::auth::Principal;
 use crate::routes::errors::ErrorResponse;
 use crate::routes::utils::check_provider_configured;
 use crate::state::AppState;
@@ -5,8 +6,9 @@ use axum::routing::put;
 use axum::{
     extract::Path,
     routing::{delete, get, post},
-    Json, Router,
+    Extension, Json, Router,
 };
+use goose::audit::{AuditEntry, AuditLog};
 use goose::config::declarative_providers::LoadedProvider;
 use goose::config::paths::Paths;
 use goose::config::ExtensionEntry;
@@ -169,10 +171,12 @@ pub struct DetectProviderResponse {
     )
 )]
 pub async fn upsert_config(
+    principal: Option<Extension<Principal>>,
     Json(query): Json<UpsertConfigQuery>,
 ) -> Result<Json<Value>, ErrorResponse> {
     let config = Config::global();
     config.set(&query.key, &query.value, query.is_secret)?;
+    audit_config_change(principal, &query.key, "Set config value");
     Ok(Json(Value::String(format!("Upserted key {}", query.key))))
 }
 
@@ -187,6 +191,7 @@ pub async fn upsert_config(
     )
 )]
 pub async fn remove_config(
+    principal: Option<Extension<Principal>>,
     Json(query): Json<ConfigKeyQuery>,
 ) -> Result<Json<String>, ErrorResponse> {
     let config = Config::global();
@@ -196,10 +201,21 @@ pub async fn remove_config(
     } else {
         config.delete(&query.key)?;
     }
+    audit_config_change(principal, &query.key, "Removed config value");
 
     Ok(Json(format!("Removed key {}", query.key)))
 }
 
+/// Records a configuration change in the audit log. Values are left out since they may be
+/// secrets.
+fn audit_config_change(principal: Option<Extension<Principal>>, key: &str, summary: &str) {
+    AuditLog::record(AuditEntry::config_change(
+        key,
+        summary,
+        principal.map(|Extension(principal)| principal.actor()),
+    ));
+}
+
 const SECRET_MASK_SHOW_LEN: usize = 8;
 
 fn mask_secret(secret: Value) -> String {
@@ -282,6 +298,7 @@ pub async fn get_extensions() -> Result<Json<ExtensionResponse>, ErrorResponse>
     )
 )]
 pub async fn add_extension(
+    principal: Option<Extension<Principal>>,
     Json(extension_query): Json<ExtensionQuery>,
 ) -> Result<Json<String>, ErrorResponse> {
     let extensions = goose::config::get_all_extensions();
@@ -294,11 +311,13 @@ pub async fn add_extension(
         config: extension_query.config,
     });
 
-    if is_update {
-        Ok(Json(format!("Updated extension {}", extension_query.name)))
+    let summary = if is_update {
+        format!("Updated extension {}", extension_query.name)
     } else {
-        Ok(Json(format!("Added extension {}", extension_query.name)))
-    }
+        format!("Added extension {}", extension_query.name)
+    };
+    audit_config_change(principal, &key, &summary);
+    Ok(Json(summary))
 }
 
 #[utoipa::path(
@@ -310,9 +329,13 @@ pub async fn add_extension(
         (status = 500, description = "Internal server error")
     )
 )]
-pub async fn remove_extension(Path(name): Path<String>) -> Result<Json<String>, ErrorResponse> {
+pub async fn remove_extension(
+    principal: Option<Extension<Principal>>,
+    Path(name): Path<String>,
+) -> Result<Json<String>, ErrorResponse> {
     let key = goose::config::extensions::name_to_key(&name);
     goose::config::remove_extension(&key);
+    audit_config_change(principal, &key, &format!("Removed extension {}", name));
     Ok(Json(format!("Removed extension {}", name)))
 }
 
@@ -520,6 +543,7 @@ pub async fn init_config() -> Result<Json<String>, ErrorResponse> {
     )
 )]
 pub async fn upsert_permissions(
+    principal: Option<Extension<Principal>>,
     Json(query): Json<UpsertPermissionsQuery>,
 ) -> Result<Json<String>, ErrorResponse> {
     let permission_manager = goose::config::PermissionManager::instance();
@@ -529,6 +553,11 @@ pub async fn upsert_permissions(
             &tool_permission.tool_name,
             tool_permission.permission.clone(),
         );
+        audit_config_change(
+            principal.clone(),
+            &tool_permission.tool_name,
+            &format!("Set tool permission to {:?}", tool_permission.permission),
+        );
     }
 
     Ok(Json("Permissions updated successfully".to_string()))
@@ -644,6 +673,7 @@ pub async fn validate_config() -> Result<Json<String>, ErrorResponse> {
     )
 )]
 pub async fn create_custom_provider(
+    principal: Option<Extension<Principal>>,
     Json(request): Json<UpdateCustomProviderRequest>,
 ) -> Result<Json<String>, ErrorResponse> {
     let config = goose::config::declarative_providers::create_custom_provider(
@@ -662,6 +692,7 @@ pub async fn create_custom_provider(
     )?;
 
     goose::providers::refresh_custom_providers().await?;
+    audit_config_change(principal, config.id(), "Added custom provider");
 
     Ok(Json(format!("Custom provider added - ID: {}", config.id())))
 }
@@ -695,10 +726,14 @@ pub async fn get_custom_provider(
         (status = 500, description = "Internal server error")
     )
 )]
-pub async fn remove_custom_provider(Path(id): Path<String>) -> Result<Json<String>, ErrorResponse> {
+pub async fn remove_custom_provider(
+    principal: Option<Extension<Principal>>,
+    Path(id): Path<String>,
+) -> Result<Json<String>, ErrorResponse> {
     goose::config::declarative_providers::remove_custom_provider(&id)?;
 
     goose::providers::refresh_custom_providers().await?;
+    audit_config_change(principal, &id, "Removed custom provider");
 
     Ok(Json(format!("Removed custom provider: {}", id)))
 }
@@ -714,6 +749,7 @@ pub async fn remove_custom_provider(Path(id): Path<String>) -> Result<Json<Strin
     )
 )]
 pub async fn update_custom_provider(
+    principal: Option<Extension<Principal>>,
     Path(id): Path<String>,
     Json(request): Json<UpdateCustomProviderRequest>,
 ) -> Result<Json<String>, ErrorResponse> {
@@ -734,6 +770,7 @@ pub async fn update_custom_provider(
     )?;
 
     goose::providers::refresh_custom_providers().await?;
+    audit_config_change(principal, &id, "Updated custom provider");
 
     Ok(Json(format!("Updated custom provider: {}", id)))
 }
@@ -761,6 +798,7 @@ pub async fn check_provider(
     request_body = SetProviderRequest,
 )]
 pub async fn set_config_provider(
+    principal: Option<Extension<Principal>>,
     Json(SetProviderRequest { provider, model }): Json<SetProviderRequest>,
 ) -> Result<(), ErrorResponse> {
     // Provider validation does not use extensions.
@@ -779,6 +817,11 @@ pub async fn set_config_provider(
                 provider, model, err
             ))
         })?;
+    audit_config_change(
+        principal,
+        "GOOSE_PROVIDER",
+        &format!("Set provider to {} with model {}", provider, model),
+    );
     Ok(())
 }
 
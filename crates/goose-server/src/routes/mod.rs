@@ -1,5 +1,7 @@
 pub mod action_required;
 pub mod agent;
+pub mod api_tokens;
+pub mod audit;
 pub mod config_management;
 pub mod dictation;
 pub mod errors;
@@ -30,6 +32,8 @@ pub fn configure(state: Arc<crate::state::AppState>, secret_key: String) -> Rout
         .merge(status::routes(state.clone()))
         .merge(reply::routes(state.clone()))
         .merge(action_required::routes(state.clone()))
+        .merge(api_tokens::routes(state.clone()))
+        .merge(audit::routes())
         .merge(agent::routes(state.clone()))
         .merge(dictation::routes(state.clone()))
         .merge(local_inference::routes(state.clone()))
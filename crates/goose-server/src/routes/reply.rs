@@ -1,3 +1,4 @@
+use crate::auth::{authorize_session, Principal};
 use crate::routes::errors::ErrorResponse;
 use crate::state::AppState;
 #[cfg(test)]
@@ -7,7 +8,7 @@ use axum::{
     http::{self},
     response::IntoResponse,
     routing::post,
-    Json, Router,
+    Extension, Json, Router,
 };
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
@@ -140,6 +141,8 @@ pub enum MessageEvent {
     ModelChange {
         model: String,
         mode: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provider: Option<String>,
     },
     Notification {
         request_id: String,
@@ -207,8 +210,11 @@ async fn stream_event(
 )]
 pub async fn reply(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<ChatRequest>,
 ) -> Result<SseResponse, ErrorResponse> {
+    authorize_session(&principal, state.session_manager(), &request.session_id).await?;
+
     let session_start = std::time::Instant::now();
 
     tracing::info!(
@@ -359,8 +365,8 @@ pub async fn reply(
                             stream_event(MessageEvent::UpdateConversation {conversation: new_messages}, &tx, &cancel_token).await;
 
                         }
-                        Ok(Some(Ok(AgentEvent::ModelChange { model, mode }))) => {
-                            stream_event(MessageEvent::ModelChange { model, mode }, &tx, &cancel_token).await;
+                        Ok(Some(Ok(AgentEvent::ModelChange { model, mode, provider }))) => {
+                            stream_event(MessageEvent::ModelChange { model, mode, provider }, &tx, &cancel_token).await;
                         }
                         Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
                             stream_event(MessageEvent::Notification{
@@ -1,3 +1,4 @@
+use crate::auth::Principal;
 use crate::routes::errors::ErrorResponse;
 use crate::routes::recipe_utils::{apply_recipe_to_agent, build_recipe_with_parameter_values};
 use crate::state::AppState;
@@ -7,12 +8,15 @@ use axum::{
     extract::Path,
     http::StatusCode,
     routing::{delete, get, put},
-    Json, Router,
+    Extension, Json, Router,
 };
 use goose::agents::ExtensionConfig;
 use goose::recipe::Recipe;
 use goose::session::session_manager::SessionInsights;
-use goose::session::{EnabledExtensionsState, Session};
+use goose::session::{
+    ChatHistorySearchOptions, EnabledExtensionsState, FileCheckpoint, Session, SessionBranch,
+    SessionType, TodoList,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -56,6 +60,9 @@ pub struct ForkRequest {
     timestamp: Option<i64>,
     truncate: bool,
     copy: bool,
+    /// When truncating, also roll back files changed since the timestamp
+    #[serde(default)]
+    restore_files: bool,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -64,6 +71,15 @@ pub struct ForkResponse {
     session_id: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchSessionRequest {
+    /// Last message of the session that the branch shares
+    message_id: String,
+    /// Name for the branch, defaults to the session's name with "(branch)" appended
+    name: Option<String>,
+}
+
 const MAX_NAME_LENGTH: usize = 200;
 
 #[utoipa::path(
@@ -81,13 +97,24 @@ const MAX_NAME_LENGTH: usize = 200;
 )]
 async fn list_sessions(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
 ) -> Result<Json<SessionListResponse>, StatusCode> {
-    let sessions = state
+    let mut sessions = state
         .session_manager()
         .list_sessions()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    if let Some(Extension(principal)) = principal {
+        if let Some(visible) = principal
+            .visible_sessions(state.session_manager())
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        {
+            sessions.retain(|session| visible.contains(&session.id));
+        }
+    }
+
     Ok(Json(SessionListResponse { sessions }))
 }
 
@@ -346,6 +373,7 @@ async fn export_session(
 )]
 async fn import_session(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<ImportSessionRequest>,
 ) -> Result<Json<Session>, StatusCode> {
     let session = state
@@ -354,6 +382,13 @@ async fn import_session(
         .await
         .map_err(|_| StatusCode::BAD_REQUEST)?;
 
+    if let Some(Extension(principal)) = principal {
+        principal
+            .claim_session(state.session_manager(), &session.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
     Ok(Json(session))
 }
 
@@ -379,6 +414,7 @@ async fn import_session(
 async fn fork_session(
     State(state): State<Arc<AppState>>,
     Path(session_id): Path<String>,
+    principal: Option<Extension<Principal>>,
     Json(request): Json<ForkRequest>,
 ) -> Result<Json<ForkResponse>, ErrorResponse> {
     if request.truncate && request.timestamp.is_none() {
@@ -423,6 +459,13 @@ async fn fork_session(
                 }
             })?;
 
+        if let Some(Extension(principal)) = principal {
+            principal
+                .claim_session(session_manager, &copied.id)
+                .await
+                .map_err(|e| ErrorResponse::internal(format!("Failed to copy session: {}", e)))?;
+        }
+
         copied.id
     } else {
         session_id.clone()
@@ -430,7 +473,11 @@ async fn fork_session(
 
     if request.truncate {
         session_manager
-            .truncate_conversation(&target_session_id, request.timestamp.unwrap_or(0))
+            .truncate_conversation(
+                &target_session_id,
+                request.timestamp.unwrap_or(0),
+                request.restore_files,
+            )
             .await
             .map_err(|e| {
                 tracing::error!("Failed to truncate conversation: {}", e);
@@ -447,6 +494,102 @@ async fn fork_session(
     }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/branch",
+    request_body = BranchSessionRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Branch created successfully", body = Session),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session or message not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn branch_session(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    principal: Option<Extension<Principal>>,
+    Json(request): Json<BranchSessionRequest>,
+) -> Result<Json<Session>, ErrorResponse> {
+    let session = state
+        .session_manager()
+        .branch_session(&session_id, &request.message_id, request.name)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to branch session: {}", e);
+            ErrorResponse {
+                message: format!("Failed to branch session: {}", e),
+                status: if e.to_string().contains("not found") {
+                    StatusCode::NOT_FOUND
+                } else {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                },
+            }
+        })?;
+
+    if let Some(Extension(principal)) = principal {
+        principal
+            .claim_session(state.session_manager(), &session.id)
+            .await
+            .map_err(|e| ErrorResponse::internal(format!("Failed to branch session: {}", e)))?;
+    }
+
+    Ok(Json(session))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/branches",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Branch tree containing the session, from its root. Sessions the API token does not own are left out.", body = SessionBranch),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_branches(
+    State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionBranch>, StatusCode> {
+    let tree = state
+        .session_manager()
+        .get_branch_tree(&session_id)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let visible = match principal {
+        Some(Extension(principal)) => principal
+            .visible_sessions(state.session_manager())
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        None => None,
+    };
+    let Some(visible) = visible else {
+        return Ok(Json(tree));
+    };
+
+    tree.retain(&|id| visible.contains(id))
+        .into_iter()
+        .find(|branch| branch.contains(&session_id))
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionExtensionsResponse {
@@ -488,6 +631,175 @@ async fn get_session_extensions(
     Ok(Json(SessionExtensionsResponse { extensions }))
 }
 
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageCheckpoints {
+    /// The message whose tool calls changed the files, if it is still in the conversation
+    message_id: Option<String>,
+    /// Timestamp to restore from to undo these changes and everything after them
+    timestamp: i64,
+    checkpoints: Vec<FileCheckpoint>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCheckpointsResponse {
+    messages: Vec<MessageCheckpoints>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/checkpoints",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "File checkpoints grouped by message, oldest first", body = SessionCheckpointsResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn list_session_checkpoints(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionCheckpointsResponse>, StatusCode> {
+    let session_manager = state.session_manager();
+    let session = session_manager
+        .get_session(&session_id, true)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    let checkpoints = session_manager
+        .list_file_checkpoints(&session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list checkpoints: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let messages = session
+        .conversation
+        .map(|c| c.messages().clone())
+        .unwrap_or_default();
+
+    let mut grouped: Vec<MessageCheckpoints> = Vec::new();
+    for checkpoint in checkpoints {
+        // Tool calls run after their message was created, so the latest earlier message made them.
+        let message = messages
+            .iter()
+            .rev()
+            .find(|m| m.created <= checkpoint.created_timestamp);
+        let message_id = message.and_then(|m| m.id.clone());
+        let timestamp = message.map_or(checkpoint.created_timestamp, |m| m.created);
+
+        match grouped.last_mut() {
+            Some(group) if group.message_id == message_id && group.timestamp == timestamp => {
+                group.checkpoints.push(checkpoint)
+            }
+            _ => grouped.push(MessageCheckpoints {
+                message_id,
+                timestamp,
+                checkpoints: vec![checkpoint],
+            }),
+        }
+    }
+
+    Ok(Json(SessionCheckpointsResponse { messages: grouped }))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreCheckpointsRequest {
+    /// Roll back every file change made at or after this timestamp
+    timestamp: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreCheckpointsResponse {
+    /// One entry per file that was rolled back
+    restored: Vec<FileCheckpoint>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/checkpoints/restore",
+    request_body = RestoreCheckpointsRequest,
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Files restored successfully", body = RestoreCheckpointsResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn restore_session_checkpoints(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Json(request): Json<RestoreCheckpointsRequest>,
+) -> Result<Json<RestoreCheckpointsResponse>, ErrorResponse> {
+    let session_manager = state.session_manager();
+    session_manager
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| ErrorResponse {
+            message: format!("Session {} not found", session_id),
+            status: StatusCode::NOT_FOUND,
+        })?;
+    let restored = session_manager
+        .restore_file_checkpoints(&session_id, request.timestamp)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to restore checkpoints: {}", e);
+            ErrorResponse {
+                message: format!("Failed to restore checkpoints: {}", e),
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        })?;
+
+    Ok(Json(RestoreCheckpointsResponse { restored }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/todos",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "The session's todo list", body = TodoList),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+async fn get_session_todos(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<TodoList>, StatusCode> {
+    let session = state
+        .session_manager()
+        .get_session(&session_id, false)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    Ok(Json(TodoList::load(&session.extension_data)))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/sessions", get(list_sessions))
@@ -506,10 +818,21 @@ pub fn routes(state: Arc<AppState>) -> Router {
             put(update_session_user_recipe_values),
         )
         .route("/sessions/{session_id}/fork", post(fork_session))
+        .route("/sessions/{session_id}/branch", post(branch_session))
+        .route("/sessions/{session_id}/branches", get(get_session_branches))
         .route(
             "/sessions/{session_id}/extensions",
             get(get_session_extensions),
         )
+        .route(
+            "/sessions/{session_id}/checkpoints",
+            get(list_session_checkpoints),
+        )
+        .route(
+            "/sessions/{session_id}/checkpoints/restore",
+            post(restore_session_checkpoints),
+        )
+        .route("/sessions/{session_id}/todos", get(get_session_todos))
         .with_state(state)
 }
 #[derive(Deserialize, ToSchema)]
@@ -524,6 +847,12 @@ pub struct SearchSessionsQuery {
     after_date: Option<String>,
     /// Filter results to sessions before this date (ISO 8601 format)
     before_date: Option<String>,
+    /// Only match messages from this role ("user" or "assistant")
+    role: Option<String>,
+    /// Only match sessions with this working directory
+    working_dir: Option<String>,
+    /// Comma-separated session types to search (default: "user,scheduled")
+    session_type: Option<String>,
 }
 
 fn default_limit() -> usize {
@@ -537,10 +866,13 @@ fn default_limit() -> usize {
         ("query" = String, Query, description = "Search query string"),
         ("limit" = Option<usize>, Query, description = "Maximum results (default: 10, max: 50)"),
         ("after_date" = Option<String>, Query, description = "Filter after date (ISO 8601)"),
-        ("before_date" = Option<String>, Query, description = "Filter before date (ISO 8601)")
+        ("before_date" = Option<String>, Query, description = "Filter before date (ISO 8601)"),
+        ("role" = Option<String>, Query, description = "Only match messages from this role (user or assistant)"),
+        ("workingDir" = Option<String>, Query, description = "Only match sessions with this working directory"),
+        ("sessionType" = Option<String>, Query, description = "Comma-separated session types (default: user,scheduled)")
     ),
     responses(
-        (status = 200, description = "Matching sessions", body = Vec<Session>),
+        (status = 200, description = "Matching sessions, most relevant first", body = Vec<Session>),
         (status = 400, description = "Bad request - Invalid query"),
         (status = 401, description = "Unauthorized"),
         (status = 500, description = "Internal server error")
@@ -552,6 +884,7 @@ fn default_limit() -> usize {
 )]
 async fn search_sessions(
     State(state): State<Arc<AppState>>,
+    principal: Option<Extension<Principal>>,
     axum::extract::Query(params): axum::extract::Query<SearchSessionsQuery>,
 ) -> Result<Json<Vec<Session>>, StatusCode> {
     let query = params.query.trim();
@@ -571,29 +904,53 @@ async fn search_sessions(
         .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
         .map(|dt| dt.with_timezone(&chrono::Utc));
 
-    let search_results = state
-        .session_manager()
-        .search_chat_history(query, Some(limit), after_date, before_date, None)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let role = match params.role.as_deref() {
+        None => None,
+        Some("user") => Some(rmcp::model::Role::User),
+        Some("assistant") => Some(rmcp::model::Role::Assistant),
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
 
-    // Get full Session objects for matching session IDs
-    let session_ids: Vec<String> = search_results
-        .results
-        .into_iter()
-        .map(|r| r.session_id)
-        .collect();
+    let session_types = match params.session_type.as_deref() {
+        None => vec![SessionType::User, SessionType::Scheduled],
+        Some(types) => types
+            .split(',')
+            .map(|t| t.trim().parse::<SessionType>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+    };
 
-    let all_sessions = state
+    let options = ChatHistorySearchOptions {
+        limit: Some(limit),
+        after_date,
+        before_date,
+        exclude_session_id: None,
+        role,
+        working_dir: params.working_dir,
+        session_types,
+        owner_token_id: principal
+            .as_ref()
+            .and_then(|Extension(principal)| principal.token_id().map(str::to_string)),
+    };
+
+    let search_results = state
         .session_manager()
-        .list_sessions()
+        .search_chat_history(query, options)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let matching_sessions: Vec<Session> = all_sessions
-        .into_iter()
-        .filter(|s| session_ids.contains(&s.id))
-        .collect();
+    // Get full Session objects for matching session IDs, keeping the relevance order
+    let mut matching_sessions = Vec::with_capacity(search_results.results.len());
+    for result in search_results.results {
+        match state
+            .session_manager()
+            .get_session(&result.session_id, false)
+            .await
+        {
+            Ok(session) => matching_sessions.push(session),
+            Err(e) => tracing::warn!("Skipping search result {}: {}", result.session_id, e),
+        }
+    }
 
     Ok(Json(matching_sessions))
 }
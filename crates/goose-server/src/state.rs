@@ -1,5 +1,6 @@
 use axum::http::StatusCode;
 use goose::builtin_extension::register_builtin_extensions;
+use goose::config::ApiTokenStore;
 use goose::execution::manager::AgentManager;
 use goose::scheduler_trait::SchedulerTrait;
 use goose::session::SessionManager;
@@ -26,11 +27,15 @@ pub struct AppState {
     pub gateway_manager: Arc<GatewayManager>,
     pub extension_loading_tasks: ExtensionLoadingTasks,
     pub inference_runtime: Arc<InferenceRuntime>,
+    pub api_tokens: Arc<ApiTokenStore>,
 }
 
 impl AppState {
     pub async fn new(tls: bool) -> anyhow::Result<Arc<AppState>> {
         register_builtin_extensions(goose_mcp::BUILTIN_EXTENSIONS.clone());
+        if let Some(embedder) = goose::providers::embedding::memory_embedder() {
+            goose_mcp::set_memory_embedder(embedder);
+        }
 
         let agent_manager = AgentManager::instance().await?;
         let tunnel_manager = Arc::new(TunnelManager::new(tls));
@@ -44,6 +49,7 @@ impl AppState {
             gateway_manager,
             extension_loading_tasks: Arc::new(Mutex::new(HashMap::new())),
             inference_runtime: InferenceRuntime::get_or_init(),
+            api_tokens: ApiTokenStore::instance(),
         }))
     }
 
@@ -9,14 +9,17 @@ use futures::stream::BoxStream;
 use futures::{stream, FutureExt, Stream, StreamExt, TryStreamExt};
 use uuid::Uuid;
 
+use super::client_delegate::ClientDelegate;
 use super::container::Container;
 use super::final_output_tool::FinalOutputTool;
 use super::platform_tools;
-use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
+use super::tool_execution::{
+    audit_inspector_decisions, ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE,
+};
 use crate::action_required_manager::ActionRequiredManager;
 use crate::agents::extension::{ExtensionConfig, ExtensionResult, ToolInfo};
 use crate::agents::extension_manager::{
-    get_parameter_names, ExtensionManager, ExtensionManagerCapabilities,
+    get_parameter_names, ExtensionEvent, ExtensionManager, ExtensionManagerCapabilities,
 };
 use crate::agents::final_output_tool::{FINAL_OUTPUT_CONTINUATION_MESSAGE, FINAL_OUTPUT_TOOL_NAME};
 use crate::agents::platform_extensions::MANAGE_EXTENSIONS_TOOL_NAME_COMPLETE;
@@ -42,9 +45,10 @@ use crate::providers::base::{PermissionRouting, Provider};
 use crate::providers::errors::ProviderError;
 use crate::recipe::{Author, Recipe, Response, Settings};
 use crate::scheduler_trait::SchedulerTrait;
+use crate::security::secrets::SecretRedactor;
 use crate::security::security_inspector::SecurityInspector;
 use crate::session::extension_data::{EnabledExtensionsState, ExtensionState};
-use crate::session::{Session, SessionManager};
+use crate::session::{BudgetStatus, Session, SessionManager};
 use crate::tool_inspection::ToolInspectionManager;
 use crate::tool_monitor::RepetitionInspector;
 use crate::utils::is_token_cancelled;
@@ -109,6 +113,8 @@ pub struct AgentConfig {
     pub goose_mode: GooseMode,
     pub disable_session_naming: bool,
     pub goose_platform: GoosePlatform,
+    /// Lets the developer tools work through the client's files and terminal
+    pub client_delegate: Option<Arc<dyn ClientDelegate>>,
 }
 
 impl AgentConfig {
@@ -127,8 +133,14 @@ impl AgentConfig {
             goose_mode,
             disable_session_naming,
             goose_platform,
+            client_delegate: None,
         }
     }
+
+    pub fn with_client_delegate(mut self, delegate: Arc<dyn ClientDelegate>) -> Self {
+        self.client_delegate = Some(delegate);
+        self
+    }
 }
 
 /// The main goose Agent
@@ -148,14 +160,22 @@ pub struct Agent {
 
     pub(super) retry_manager: RetryManager,
     pub(super) tool_inspection_manager: ToolInspectionManager,
+    pub(super) secret_redactor: Mutex<SecretRedactor>,
     container: Mutex<Option<Container>>,
+    goose_mode: Mutex<GooseMode>,
 }
 
 #[derive(Clone, Debug)]
 pub enum AgentEvent {
     Message(Message),
     McpNotification((String, ServerNotification)),
-    ModelChange { model: String, mode: String },
+    /// The model serving the reply changed. `provider` names the backend
+    /// provider when a router picked it; it is `None` for lead/worker swaps.
+    ModelChange {
+        model: String,
+        mode: String,
+        provider: Option<String>,
+    },
     HistoryReplaced(Conversation),
 }
 
@@ -227,15 +247,20 @@ impl Agent {
         };
         let session_manager = Arc::clone(&config.session_manager);
         let permission_manager = Arc::clone(&config.permission_manager);
+        let goose_mode = config.goose_mode;
+        let client_delegate = config.client_delegate.clone();
         Self {
             provider: provider.clone(),
             config,
-            extension_manager: Arc::new(ExtensionManager::new(
-                provider.clone(),
-                session_manager,
-                goose_platform.to_string(),
-                capabilities,
-            )),
+            extension_manager: Arc::new(
+                ExtensionManager::new(
+                    provider.clone(),
+                    session_manager.clone(),
+                    goose_platform.to_string(),
+                    capabilities,
+                )
+                .with_client_delegate(client_delegate),
+            ),
             final_output_tool: Arc::new(Mutex::new(None)),
             frontend_tools: Mutex::new(HashMap::new()),
             frontend_instructions: Mutex::new(None),
@@ -248,8 +273,11 @@ impl Agent {
             tool_inspection_manager: Self::create_tool_inspection_manager(
                 permission_manager,
                 provider.clone(),
+                session_manager,
             ),
+            secret_redactor: Mutex::new(SecretRedactor::new()),
             container: Mutex::new(None),
+            goose_mode: Mutex::new(goose_mode),
         }
     }
 
@@ -257,6 +285,7 @@ impl Agent {
     fn create_tool_inspection_manager(
         permission_manager: Arc<PermissionManager>,
         provider: SharedProvider,
+        session_manager: Arc<SessionManager>,
     ) -> ToolInspectionManager {
         let mut tool_inspection_manager = ToolInspectionManager::new();
 
@@ -267,6 +296,7 @@ impl Agent {
         tool_inspection_manager.add_inspector(Box::new(PermissionInspector::new(
             permission_manager,
             provider,
+            session_manager,
         )));
 
         // Add repetition inspector (lower priority - basic repetition checking)
@@ -290,6 +320,16 @@ impl Agent {
         self.retry_manager.get_attempts().await
     }
 
+    /// The mode used for the next reply, starting from `AgentConfig::goose_mode`
+    pub async fn goose_mode(&self) -> GooseMode {
+        *self.goose_mode.lock().await
+    }
+
+    /// Switch mode for this agent only; a reply already in progress keeps its mode
+    pub async fn set_goose_mode(&self, mode: GooseMode) {
+        *self.goose_mode.lock().await = mode;
+    }
+
     async fn handle_retry_logic(
         &self,
         messages: &mut Conversation,
@@ -313,6 +353,29 @@ impl Agent {
             | RetryResult::SuccessChecksPassed => Ok(false),
         }
     }
+    /// Change notifications that arrived since the last turn, and whether any extension's tools
+    /// changed. Falling behind the channel counts as a change, since one may have been missed.
+    fn drain_extension_events(
+        events: &mut tokio::sync::broadcast::Receiver<ExtensionEvent>,
+    ) -> (Vec<ExtensionEvent>, bool) {
+        let mut drained = Vec::new();
+        let mut tools_changed = false;
+        loop {
+            match events.try_recv() {
+                Ok(event) => {
+                    tools_changed |=
+                        matches!(event.1, ServerNotification::ToolListChangedNotification(_));
+                    drained.push(event);
+                }
+                Err(tokio::sync::broadcast::error::TryRecvError::Lagged(_)) => {
+                    tools_changed = true;
+                }
+                Err(_) => break,
+            }
+        }
+        (drained, tools_changed)
+    }
+
     async fn drain_elicitation_messages(&self, session_id: &str) -> Vec<Message> {
         let mut messages = Vec::new();
         let manager = self.config.session_manager.clone();
@@ -353,7 +416,8 @@ impl Agent {
             .prepare_tools_and_prompt(session_id, working_dir)
             .await?;
 
-        if self.config.goose_mode == GooseMode::SmartApprove {
+        let goose_mode = self.goose_mode().await;
+        if goose_mode == GooseMode::SmartApprove {
             self.tool_inspection_manager.apply_tool_annotations(&tools);
         }
 
@@ -362,7 +426,7 @@ impl Agent {
             tools,
             toolshim_tools,
             system_prompt,
-            goose_mode: self.config.goose_mode,
+            goose_mode,
             tool_call_cut_off: Config::global()
                 .get_param::<usize>("GOOSE_TOOL_CALL_CUTOFF")
                 .unwrap_or(10),
@@ -446,6 +510,39 @@ impl Agent {
         }
     }
 
+    /// Replaces secrets in a tool result or error with placeholders before it is added to the
+    /// conversation, unless `SECRET_REDACTION_ENABLED` is turned off.
+    pub(super) async fn redact_secrets(
+        &self,
+        session_id: &str,
+        request_id: &str,
+        mut output: ToolResult<CallToolResult>,
+    ) -> ToolResult<CallToolResult> {
+        let enabled = Config::global()
+            .get_param::<bool>("SECRET_REDACTION_ENABLED")
+            .unwrap_or(true);
+        if !enabled {
+            return output;
+        }
+
+        let mut redactor = self.secret_redactor.lock().await;
+        let count = match &mut output {
+            Ok(result) => redactor.redact_tool_result(result),
+            Err(error) => redactor.redact_error(error),
+        };
+        drop(redactor);
+        if count > 0 {
+            tracing::info!(
+                monotonic_counter.goose.secrets_redacted = count as u64,
+                session_id = %session_id,
+                tool_request_id = %request_id,
+                "Redacted {} secret(s) from tool result",
+                count
+            );
+        }
+        output
+    }
+
     /// Get a reference count clone to the provider
     pub async fn provider(&self) -> Result<Arc<dyn Provider>, anyhow::Error> {
         match &*self.provider.lock().await {
@@ -454,7 +551,8 @@ impl Agent {
         }
     }
 
-    /// When set, all stdio extensions will be started via `docker exec` in the specified container.
+    /// When set, stdio extensions are started in the container with `docker exec` (or the
+    /// configured runtime) and the developer tools run their commands and file changes there.
     pub async fn set_container(&self, container: Option<Container>) {
         *self.container.lock().await = container.clone();
     }
@@ -509,6 +607,14 @@ impl Agent {
         });
         tracing::Span::current().record("input", tracing::field::display(&input_summary));
 
+        let mut tool_call = tool_call;
+        if let Some(arguments) = tool_call.arguments.as_mut() {
+            self.secret_redactor
+                .lock()
+                .await
+                .restore_arguments(arguments);
+        }
+
         if tool_call.name == PLATFORM_MANAGE_SCHEDULE_TOOL_NAME {
             let arguments = tool_call
                 .arguments
@@ -1123,12 +1229,24 @@ impl Agent {
             });
             let mut compaction_attempts = 0;
             let mut last_assistant_text = String::new();
+            let mut router_backend: Option<(String, String)> = None;
+            let mut budget_warning: Option<String> = None;
+            let mut extension_events = self.extension_manager.subscribe_events();
 
             loop {
                 if is_token_cancelled(&cancel_token) {
                     break;
                 }
 
+                let (events, tools_changed) = Self::drain_extension_events(&mut extension_events);
+                for event in events {
+                    yield AgentEvent::McpNotification(event);
+                }
+                if tools_changed {
+                    (tools, toolshim_tools, system_prompt) =
+                        self.prepare_tools_and_prompt(&session_config.id, &session.working_dir).await?;
+                }
+
                 if let Some(final_output_tool) = self.final_output_tool.lock().await.as_ref() {
                     if final_output_tool.final_output.is_some() {
                         let final_event = AgentEvent::Message(
@@ -1156,12 +1274,39 @@ impl Agent {
                     tool_call_cut_off,
                 );
 
-                let conversation_with_moim = super::moim::inject_moim(
+                match self.check_budget(&session_config.id).await? {
+                    BudgetStatus::Exceeded(reason) => {
+                        Err(anyhow!(
+                            "Budget exceeded: {} Raise the limit or wait for it to reset to continue.",
+                            reason
+                        ))?;
+                    }
+                    BudgetStatus::Warning(warning) => {
+                        if budget_warning.is_none() {
+                            yield AgentEvent::Message(
+                                Message::assistant().with_system_notification(
+                                    SystemNotificationType::InlineMessage,
+                                    format!("Budget warning: {}", warning),
+                                )
+                            );
+                        }
+                        budget_warning = Some(warning);
+                    }
+                    BudgetStatus::WithinBudget => {}
+                }
+
+                let mut conversation_with_moim = super::moim::inject_moim(
                     &session_config.id,
                     conversation.clone(),
                     &self.extension_manager,
                     &working_dir,
                 ).await;
+                if let Some(warning) = &budget_warning {
+                    conversation_with_moim = super::moim::inject_text(
+                        conversation_with_moim,
+                        format!("Budget warning: {} Finish the current task as economically as possible.", warning),
+                    );
+                }
 
                 let mut stream = Self::stream_response_from_provider(
                     self.provider().await?,
@@ -1203,10 +1348,33 @@ impl Agent {
                                     yield AgentEvent::ModelChange {
                                         model: active_model,
                                         mode: mode.to_string(),
+                                        provider: None,
                                     };
                                 }
                             }
 
+                            // Record which backend a router picked so the session reflects it
+                            if let Some(router) = provider.as_router() {
+                                if usage.is_some() {
+                                    let (backend, model_config) = router.get_active_backend();
+                                    let active = (backend, model_config.model_name.clone());
+                                    if router_backend.as_ref() != Some(&active) {
+                                        self.config
+                                            .session_manager
+                                            .update(&session_config.id)
+                                            .model_config(model_config)
+                                            .apply()
+                                            .await?;
+                                        yield AgentEvent::ModelChange {
+                                            model: active.1.clone(),
+                                            mode: "router".to_string(),
+                                            provider: Some(active.0.clone()),
+                                        };
+                                        router_backend = Some(active);
+                                    }
+                                }
+                            }
+
                             if let Some(ref usage) = usage {
                                 self.update_session_metrics(&session_config.id, session_config.schedule_id.clone(), usage, false).await?;
                             }
@@ -1244,6 +1412,7 @@ impl Agent {
 
                                 for (idx, request) in frontend_requests.iter().enumerate() {
                                     let mut frontend_tool_stream = self.handle_frontend_tool_request(
+                                        &session_config.id,
                                         request,
                                         tool_response_messages[idx].clone(),
                                     );
@@ -1290,6 +1459,12 @@ impl Agent {
                                             result
                                         });
 
+                                    audit_inspector_decisions(
+                                        &session_config.id,
+                                        &permission_check_result,
+                                        &inspection_results,
+                                    );
+
                                     // Track extension requests
                                     let mut enable_extension_request_ids = vec![];
                                     for request in &remaining_requests {
@@ -1376,6 +1551,7 @@ impl Agent {
                                                                 {
                                                                     all_install_successful = false;
                                                                 }
+                                                                let output = self.redact_secrets(&session_config.id, &request_id, output).await;
                                                                 if let Some(response_msg) = request_to_response_map.get(&request_id) {
                                                                     let metadata = request_metadata.get(&request_id).and_then(|m| m.as_ref());
                                                                     let mut response = response_msg.lock().await;
@@ -1711,9 +1887,14 @@ impl Agent {
         let extensions =
             EnabledExtensionsState::extensions_or_default(Some(&session.extension_data), config);
 
-        let provider = crate::providers::create(&provider_name, model_config, extensions)
-            .await
-            .map_err(|e| anyhow!("Could not create provider: {}", e))?;
+        let provider = crate::providers::create_for_recipe(
+            &provider_name,
+            model_config,
+            extensions,
+            session.recipe.as_ref(),
+        )
+        .await
+        .map_err(|e| anyhow!("Could not create provider: {}", e))?;
 
         self.update_provider(provider, &session.id).await
     }
@@ -1985,6 +2166,9 @@ impl Agent {
             goose_model: Some(model_name.clone()),
             temperature: Some(model_config.temperature.unwrap_or(0.0)),
             max_turns: None,
+            budget: None,
+            response_cache: None,
+            sandbox: None,
         };
 
         tracing::debug!(
@@ -0,0 +1,41 @@
+//! File and terminal access offered by the client driving the agent. An editor connected over
+//! the Agent Client Protocol can read and write files through its open buffers, including
+//! unsaved changes, and run commands in its own terminal.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+
+/// A command that ran in the client's terminal
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientCommandOutput {
+    /// Combined stdout and stderr, as the terminal captured it
+    pub output: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// The client's id for the terminal, so frontends can show it alongside the tool call
+    pub terminal_id: Option<String>,
+}
+
+#[async_trait]
+pub trait ClientDelegate: Send + Sync {
+    fn can_read_files(&self) -> bool;
+
+    fn can_write_files(&self) -> bool;
+
+    fn has_terminal(&self) -> bool;
+
+    /// Paths are absolute
+    async fn read_text_file(&self, session_id: &str, path: &Path) -> Result<String>;
+
+    async fn write_text_file(&self, session_id: &str, path: &Path, content: &str) -> Result<()>;
+
+    /// Runs a shell command line and waits for it, killing it after `timeout_secs`
+    async fn run_command(
+        &self,
+        session_id: &str,
+        command_line: &str,
+        working_dir: Option<&Path>,
+        timeout_secs: Option<u64>,
+    ) -> Result<ClientCommandOutput>;
+}
@@ -1,15 +1,631 @@
+use anyhow::{anyhow, bail, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::Config;
+use crate::subprocess::SubprocessExt;
+
+pub const DEFAULT_CONTAINER_RUNTIME: &str = "docker";
+
+/// A host directory bind mounted into the container, used to translate paths between the two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerMount {
+    pub host: PathBuf,
+    pub container: PathBuf,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Container {
-    /// The Docker container ID
+    /// The container ID or name
     id: String,
+    /// Docker compatible CLI used to reach the container: `docker`, `podman` or a path
+    runtime: String,
+    mount: Option<ContainerMount>,
 }
 
 impl Container {
+    /// A running container reached through the configured runtime, `GOOSE_CONTAINER_RUNTIME`.
     pub fn new(id: impl Into<String>) -> Self {
-        Self { id: id.into() }
+        Self {
+            id: id.into(),
+            runtime: configured_runtime(),
+            mount: None,
+        }
+    }
+
+    /// An existing container reached by id, with the bind mount that holds `working_dir`
+    /// found by inspecting it. File tools need that mount to find the host side of the files
+    /// they change, so a container that does not mount the working directory is refused.
+    pub async fn attach(runtime: &str, id: &str, working_dir: &Path) -> Result<Self> {
+        let inspected = inspect(runtime, id).await?;
+        let working_dir = working_dir
+            .canonicalize()
+            .unwrap_or_else(|_| working_dir.to_path_buf());
+        let mount = inspected
+            .mounts
+            .into_iter()
+            .filter(|mount| working_dir.starts_with(&mount.host))
+            .max_by_key(|mount| mount.host.components().count())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Container {} does not mount the working directory {}. Start it with `-v {}:/workspace` so goose can reach its files.",
+                    id,
+                    working_dir.display(),
+                    working_dir.display()
+                )
+            })?;
+        Ok(Self {
+            id: id.to_string(),
+            runtime: runtime.to_string(),
+            mount: Some(mount),
+        })
+    }
+
+    pub fn with_runtime(mut self, runtime: impl Into<String>) -> Self {
+        self.runtime = runtime.into();
+        self
+    }
+
+    pub fn with_mount(mut self, host: impl Into<PathBuf>, container: impl Into<PathBuf>) -> Self {
+        self.mount = Some(ContainerMount {
+            host: host.into(),
+            container: container.into(),
+        });
+        self
     }
 
     pub fn id(&self) -> &str {
         &self.id
     }
+
+    pub fn runtime(&self) -> &str {
+        &self.runtime
+    }
+
+    pub fn mount(&self) -> Option<&ContainerMount> {
+        self.mount.as_ref()
+    }
+
+    /// Where a host path appears inside the container, if it is under the mount once `..`
+    /// is resolved.
+    pub fn to_container_path(&self, host_path: &Path) -> Option<PathBuf> {
+        let mount = self.mount.as_ref()?;
+        let host_path = normalize_lexically(host_path);
+        let relative = host_path.strip_prefix(&mount.host).ok()?;
+        Some(join_relative(&mount.container, relative))
+    }
+
+    /// Where a container path lives on the host, if it is under the mount once `..` is
+    /// resolved.
+    pub fn to_host_path(&self, container_path: &Path) -> Option<PathBuf> {
+        let mount = self.mount.as_ref()?;
+        let container_path = normalize_lexically(container_path);
+        let relative = container_path.strip_prefix(&mount.container).ok()?;
+        Some(join_relative(&mount.host, relative))
+    }
+
+    /// Whether a container path starts under the mount but climbs out of it through `..`,
+    /// like `/workspace/../../etc/passwd`.
+    pub fn leaves_mount(&self, container_path: &Path) -> bool {
+        self.mount.as_ref().is_some_and(|mount| {
+            container_path.starts_with(&mount.container)
+                && !normalize_lexically(container_path).starts_with(&mount.container)
+        })
+    }
+
+    /// `<runtime> exec -i [-w <dir>] <id>`, ready for the program and its arguments. The host
+    /// working directory is translated through the mount; without one the container's own
+    /// working directory is used.
+    pub fn exec_command(&self, working_dir: Option<&Path>) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new(&self.runtime);
+        command.arg("exec").arg("-i");
+        if let Some(dir) = working_dir.and_then(|dir| self.to_container_path(dir)) {
+            command.arg("-w").arg(dir);
+        }
+        command.arg(&self.id);
+        command.set_no_window();
+        command
+    }
+
+    /// `sh -c <command_line>` in the container, with the pid of the shell running it written
+    /// to `pid_file` inside the container for [`Container::kill_recorded`]. Killing the local
+    /// exec client does not stop what it started in the container.
+    pub fn exec_shell(
+        &self,
+        command_line: &str,
+        working_dir: Option<&Path>,
+        pid_file: &str,
+    ) -> tokio::process::Command {
+        let mut command = self.exec_command(working_dir);
+        command
+            .args([
+                "sh",
+                "-c",
+                r#"echo $$ > "$1"; sh -c "$2"; status=$?; rm -f "$1"; exit $status"#,
+                "sh",
+                pid_file,
+            ])
+            .arg(command_line);
+        command
+    }
+
+    /// Kills a command started with [`Container::exec_shell`] and everything it started:
+    /// its process group when it leads one, otherwise its process tree.
+    pub async fn kill_recorded(&self, pid_file: &str) -> Result<()> {
+        let output = self
+            .exec_command(None)
+            .args(["sh", "-c", KILL_RECORDED_SCRIPT, "sh", pid_file])
+            .stdin(Stdio::null())
+            .output()
+            .await?;
+        if !output.status.success() {
+            bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+
+    /// Reads a file inside the container.
+    pub async fn read_file(&self, path: &Path) -> Result<String> {
+        let output = self
+            .exec_command(None)
+            .arg("cat")
+            .arg("--")
+            .arg(path)
+            .stdin(Stdio::null())
+            .output()
+            .await?;
+        if !output.status.success() {
+            bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Writes a file inside the container, creating its parent directories.
+    pub async fn write_file(&self, path: &Path, content: &str) -> Result<()> {
+        let mut child = self
+            .exec_command(None)
+            .args([
+                "sh",
+                "-c",
+                r#"mkdir -p "$(dirname "$1")" && cat > "$1""#,
+                "sh",
+            ])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(content.as_bytes()).await?;
+        }
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+
+    /// Starts a container from `image` that keeps running until it is stopped, with `mount`
+    /// bind mounted and used as its working directory. Stopping it also removes it.
+    pub async fn create(
+        runtime: &str,
+        image: &str,
+        name: Option<&str>,
+        mount: Option<ContainerMount>,
+    ) -> Result<Self> {
+        let mut args = vec!["run".to_string(), "-d".to_string(), "--rm".to_string()];
+        if let Some(name) = name {
+            args.extend(["--name".to_string(), name.to_string()]);
+        }
+        if let Some(mount) = &mount {
+            args.extend([
+                "-v".to_string(),
+                format!("{}:{}", mount.host.display(), mount.container.display()),
+                "-w".to_string(),
+                mount.container.display().to_string(),
+            ]);
+        }
+        args.extend([
+            image.to_string(),
+            "sleep".to_string(),
+            "infinity".to_string(),
+        ]);
+
+        let id = run_runtime(runtime, &args).await?;
+        if id.is_empty() {
+            bail!("{} run did not return a container id", runtime);
+        }
+        Ok(Self {
+            id,
+            runtime: runtime.to_string(),
+            mount,
+        })
+    }
+
+    /// Reuses the container called `name`, starting it again if it stopped, or creates it
+    /// from `image` when there is none. An existing container built from another image, or
+    /// without `mount`, is an error rather than being used in place of the one asked for.
+    pub async fn reuse_or_create(
+        runtime: &str,
+        name: &str,
+        image: &str,
+        mount: Option<ContainerMount>,
+    ) -> Result<Self> {
+        let Ok(inspected) = inspect(runtime, name).await else {
+            return Self::create(runtime, image, Some(name), mount).await;
+        };
+        if inspected.image != image {
+            bail!(
+                "Container {} runs image {}, not {}. Remove it with `{} rm -f {}` to recreate it.",
+                name,
+                inspected.image,
+                image,
+                runtime,
+                name
+            );
+        }
+        if let Some(mount) = &mount {
+            if !inspected.mounts.contains(mount) {
+                bail!(
+                    "Container {} does not mount {} at {}. Remove it with `{} rm -f {}` to recreate it.",
+                    name,
+                    mount.host.display(),
+                    mount.container.display(),
+                    runtime,
+                    name
+                );
+            }
+        }
+        if !inspected.running {
+            run_runtime(runtime, &["start".to_string(), name.to_string()]).await?;
+        }
+        Ok(Self {
+            id: name.to_string(),
+            runtime: runtime.to_string(),
+            mount,
+        })
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        run_runtime(&self.runtime, &["stop".to_string(), self.id.clone()]).await?;
+        Ok(())
+    }
+}
+
+/// Kills the process group led by the pid in `$1`, or failing that the process and its
+/// descendants, found through `/proc`.
+const KILL_RECORDED_SCRIPT: &str = r#"pid=$(cat "$1" 2>/dev/null) || exit 0
+kill_tree() {
+  kill -STOP "$1" 2>/dev/null
+  for task in /proc/$1/task/*; do
+    for child in $(cat "$task/children" 2>/dev/null); do kill_tree "$child"; done
+  done
+  kill -9 "$1" 2>/dev/null
+}
+kill -9 -- "-$pid" 2>/dev/null
+kill_tree "$pid"
+rm -f "$1"
+"#;
+
+struct Inspected {
+    running: bool,
+    image: String,
+    mounts: Vec<ContainerMount>,
+}
+
+async fn inspect(runtime: &str, name: &str) -> Result<Inspected> {
+    let inspected = run_runtime(
+        runtime,
+        &[
+            "inspect",
+            "--format",
+            "{{.State.Running}}|{{.Config.Image}}|{{range .Mounts}}{{.Source}}:{{.Destination}};{{end}}",
+            name,
+        ]
+        .map(String::from),
+    )
+    .await?;
+    let mut fields = inspected.splitn(3, '|');
+    let running = fields.next().unwrap_or_default() == "true";
+    let image = fields.next().unwrap_or_default().to_string();
+    let mounts = fields
+        .next()
+        .unwrap_or_default()
+        .split(';')
+        .filter_map(|mount| mount.rsplit_once(':'))
+        .map(|(host, container)| ContainerMount {
+            host: PathBuf::from(host),
+            container: PathBuf::from(container),
+        })
+        .collect();
+    Ok(Inspected {
+        running,
+        image,
+        mounts,
+    })
+}
+
+/// A container name for a project directory: its name for readability, and a hash of the
+/// canonical path so projects in different places that share a name get their own container.
+pub fn project_container_name(project_dir: &Path) -> String {
+    let canonical = project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf());
+    let dir_name: String = canonical
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let hash = Sha256::digest(canonical.to_string_lossy().as_bytes());
+    let hash: String = hash[..6].iter().map(|b| format!("{b:02x}")).collect();
+    match dir_name.trim_matches('-') {
+        "" => format!("goose-{hash}"),
+        dir_name => format!("goose-{dir_name}-{hash}"),
+    }
+}
+
+/// The runtime set with `GOOSE_CONTAINER_RUNTIME`, `docker` by default.
+pub fn configured_runtime() -> String {
+    Config::global()
+        .get_param::<String>("GOOSE_CONTAINER_RUNTIME")
+        .unwrap_or_else(|_| DEFAULT_CONTAINER_RUNTIME.to_string())
+}
+
+/// Resolves `.` and `..` without touching the filesystem, which belongs to the container for
+/// half of the paths translated here. `..` at the root stays at the root.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() && !path.has_root() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn join_relative(base: &Path, relative: &Path) -> PathBuf {
+    if relative.as_os_str().is_empty() {
+        base.to_path_buf()
+    } else {
+        base.join(relative)
+    }
+}
+
+/// Runs a runtime subcommand and returns its trimmed stdout.
+async fn run_runtime(runtime: &str, args: &[String]) -> Result<String> {
+    let mut command = tokio::process::Command::new(runtime);
+    command.args(args).stdin(Stdio::null());
+    command.set_no_window();
+    let output = command
+        .output()
+        .await
+        .map_err(|e| anyhow!("Failed to run {}: {}", runtime, e))?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            runtime,
+            args.first().map(String::as_str).unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A stand-in for `docker` that runs `exec` commands directly on the host and logs every
+/// invocation, so tests can exercise containers without a container runtime.
+#[cfg(all(test, unix))]
+pub(crate) mod fake_runtime {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::{Path, PathBuf};
+
+    const SCRIPT: &str = r#"#!/bin/sh
+echo "$*" >> "$(dirname "$0")/calls.log"
+case "$1" in
+  exec)
+    shift
+    while [ $# -gt 0 ]; do
+      case "$1" in
+        -i) shift ;;
+        -w) cd "$2" || exit 125; shift 2 ;;
+        -e) export "$2"; shift 2 ;;
+        *) break ;;
+      esac
+    done
+    shift
+    exec "$@"
+    ;;
+  run) echo "fake123" ;;
+  inspect)
+    dir="$(dirname "$0")"
+    if [ -f "$dir/stopped" ]; then state=false
+    elif [ -f "$dir/running" ]; then state=true
+    else echo "Error: No such object" >&2; exit 1; fi
+    echo "$state|$(cat "$dir/image" 2>/dev/null || echo ubuntu:24.04)|/home/me/project:/workspace;"
+    ;;
+  start|stop) echo "$2" ;;
+esac
+"#;
+
+    /// Writes the fake runtime into `dir` and returns its path.
+    pub fn install(dir: &Path) -> PathBuf {
+        let path = dir.join("fake-docker");
+        std::fs::write(&path, SCRIPT).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    pub fn calls(dir: &Path) -> Vec<String> {
+        std::fs::read_to_string(dir.join("calls.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_path_mapping() {
+        let container = Container::new("c1").with_mount("/home/me/project", "/workspace");
+
+        assert_eq!(
+            container.to_container_path(Path::new("/home/me/project/src/main.rs")),
+            Some(PathBuf::from("/workspace/src/main.rs"))
+        );
+        assert_eq!(
+            container.to_container_path(Path::new("/home/me/project")),
+            Some(PathBuf::from("/workspace"))
+        );
+        assert_eq!(
+            container.to_container_path(Path::new("/home/me/other")),
+            None
+        );
+        assert_eq!(
+            container.to_host_path(Path::new("/workspace/README.md")),
+            Some(PathBuf::from("/home/me/project/README.md"))
+        );
+        assert_eq!(container.to_host_path(Path::new("/etc/hosts")), None);
+    }
+
+    #[test]
+    fn test_path_mapping_resolves_parent_dirs() {
+        let container = Container::new("c1").with_mount("/home/me/project", "/workspace");
+
+        assert_eq!(
+            container.to_host_path(Path::new("/workspace/src/../README.md")),
+            Some(PathBuf::from("/home/me/project/README.md"))
+        );
+        assert_eq!(
+            container.to_host_path(Path::new("/workspace/../../etc/x")),
+            None
+        );
+        assert_eq!(
+            container.to_container_path(Path::new("/home/me/project/../other/x")),
+            None
+        );
+        assert!(container.leaves_mount(Path::new("/workspace/../../etc/x")));
+        assert!(container.leaves_mount(Path::new("/workspace/src/../../x")));
+        assert!(!container.leaves_mount(Path::new("/workspace/src/../x")));
+        assert!(!container.leaves_mount(Path::new("/etc/x")));
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_with_fake_runtime() {
+        let dir = TempDir::new().unwrap();
+        let runtime = fake_runtime::install(dir.path());
+        let runtime = runtime.to_str().unwrap();
+        let mount = ContainerMount {
+            host: PathBuf::from("/home/me/project"),
+            container: PathBuf::from("/workspace"),
+        };
+
+        let created =
+            Container::reuse_or_create(runtime, "goose-dev", "ubuntu:24.04", Some(mount.clone()))
+                .await
+                .unwrap();
+        assert_eq!(created.id(), "fake123");
+        std::fs::write(dir.path().join("stopped"), "").unwrap();
+        let restarted =
+            Container::reuse_or_create(runtime, "goose-dev", "ubuntu:24.04", Some(mount.clone()))
+                .await
+                .unwrap();
+        assert_eq!(restarted.id(), "goose-dev");
+        restarted.stop().await.unwrap();
+
+        let inspect = "inspect --format {{.State.Running}}|{{.Config.Image}}|{{range .Mounts}}{{.Source}}:{{.Destination}};{{end}} goose-dev";
+        assert_eq!(
+            fake_runtime::calls(dir.path()),
+            vec![
+                inspect,
+                "run -d --rm --name goose-dev -v /home/me/project:/workspace -w /workspace ubuntu:24.04 sleep infinity",
+                inspect,
+                "start goose-dev",
+                "stop goose-dev",
+            ]
+        );
+
+        let other_image = Container::reuse_or_create(runtime, "goose-dev", "debian:12", None).await;
+        assert!(other_image.unwrap_err().to_string().contains("debian:12"));
+        let other_mount = ContainerMount {
+            host: PathBuf::from("/home/me/elsewhere/project"),
+            ..mount
+        };
+        let other_mount =
+            Container::reuse_or_create(runtime, "goose-dev", "ubuntu:24.04", Some(other_mount))
+                .await;
+        assert!(other_mount
+            .unwrap_err()
+            .to_string()
+            .contains("/home/me/elsewhere/project"));
+    }
+
+    #[tokio::test]
+    async fn test_attach_finds_working_dir_mount() {
+        let dir = TempDir::new().unwrap();
+        let runtime = fake_runtime::install(dir.path());
+        let runtime = runtime.to_str().unwrap();
+        std::fs::write(dir.path().join("running"), "").unwrap();
+
+        let container = Container::attach(runtime, "c1", Path::new("/home/me/project/src"))
+            .await
+            .unwrap();
+        assert_eq!(
+            container.mount(),
+            Some(&ContainerMount {
+                host: PathBuf::from("/home/me/project"),
+                container: PathBuf::from("/workspace"),
+            })
+        );
+
+        let unmounted = Container::attach(runtime, "c1", Path::new("/home/me/other")).await;
+        assert!(unmounted
+            .unwrap_err()
+            .to_string()
+            .contains("does not mount the working directory"));
+    }
+
+    #[test]
+    fn test_project_container_name() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let first = first.path().join("api");
+        let second = second.path().join("api");
+        std::fs::create_dir(&first).unwrap();
+        std::fs::create_dir(&second).unwrap();
+
+        let name = project_container_name(&first);
+        assert!(name.starts_with("goose-api-"));
+        assert_eq!(name, project_container_name(&first));
+        assert_ne!(name, project_container_name(&second));
+    }
+
+    #[tokio::test]
+    async fn test_files_through_exec() {
+        let dir = TempDir::new().unwrap();
+        let runtime = fake_runtime::install(dir.path());
+        let container = Container::new("c1").with_runtime(runtime.to_str().unwrap());
+        let path = dir.path().join("nested/file.txt");
+
+        container.write_file(&path, "hello\n").await.unwrap();
+        assert_eq!(container.read_file(&path).await.unwrap(), "hello\n");
+        assert!(container
+            .read_file(&dir.path().join("missing"))
+            .await
+            .is_err());
+        assert!(fake_runtime::calls(dir.path())[0].starts_with("exec -i c1 sh -c"));
+    }
 }
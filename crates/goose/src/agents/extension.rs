@@ -520,6 +520,8 @@ pub struct ExtensionInfo {
     pub name: String,
     pub instructions: String,
     pub has_resources: bool,
+    /// Set when the extension's process has stopped or is being restarted
+    pub status: Option<String>,
 }
 
 impl ExtensionInfo {
@@ -528,6 +530,7 @@ impl ExtensionInfo {
             name: name.to_string(),
             instructions: instructions.to_string(),
             has_resources,
+            status: None,
         }
     }
 }
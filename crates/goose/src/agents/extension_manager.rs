@@ -12,29 +12,33 @@ use rmcp::transport::{
     ConfigureCommandExt, DynamicTransportError, StreamableHttpClientTransport, TokioChildProcess,
 };
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tempfile::{tempdir, TempDir};
 use tokio::io::AsyncReadExt;
 use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, warn};
 
+use super::client_delegate::ClientDelegate;
 use super::container::Container;
 use super::extension::{
     ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, PlatformExtensionContext,
     ToolInfo, PLATFORM_EXTENSIONS,
 };
+use super::extension_supervisor::{ExtensionHealth, ExtensionStatus, Supervisor};
 use super::tool_execution::ToolCallResult;
 use super::types::SharedProvider;
 use crate::agents::extension::{Envs, ProcessExit};
 use crate::agents::extension_malware_check;
-use crate::agents::mcp_client::{GooseMcpClientCapabilities, McpClient, McpClientTrait};
+use crate::agents::mcp_client::{
+    working_dir_root, GooseMcpClientCapabilities, McpClient, McpClientTrait,
+};
 use crate::builtin_extension::get_builtin_extension;
 use crate::config::extensions::name_to_key;
 use crate::config::search_path::SearchPaths;
@@ -44,7 +48,7 @@ use crate::prompt_template;
 use crate::subprocess::configure_subprocess;
 use rmcp::model::{
     CallToolRequestParams, Content, ErrorCode, ErrorData, GetPromptResult, Prompt, Resource,
-    ResourceContents, ServerInfo, Tool,
+    ResourceContents, Root, ServerInfo, ServerNotification, Tool,
 };
 use rmcp::transport::auth::AuthClient;
 use schemars::_private::NoSerialize;
@@ -63,6 +67,13 @@ struct Extension {
 
     client: McpClientBox,
     server_info: Option<ServerInfo>,
+    /// The container the extension runs in, which decides how its roots are written
+    container: Option<Container>,
+    /// Kept so a stdio extension can be started again the way it was first added
+    working_dir: Option<PathBuf>,
+    session_id: Option<String>,
+    /// Subscribed resource URIs and the session that subscribed, replayed after a restart
+    subscriptions: HashMap<String, String>,
     _temp_dir: Option<tempfile::TempDir>,
 }
 
@@ -77,6 +88,10 @@ impl Extension {
             client,
             config,
             server_info,
+            container: None,
+            working_dir: None,
+            session_id: None,
+            subscriptions: HashMap::new(),
             _temp_dir: temp_dir,
         }
     }
@@ -97,19 +112,59 @@ impl Extension {
     fn get_client(&self) -> McpClientBox {
         self.client.clone()
     }
+
+    /// Whether the extension's process exited and it can be started again.
+    fn has_stopped(&self) -> bool {
+        is_restartable(&self.config) && self.client.is_closed()
+    }
 }
 
 pub struct ExtensionManagerCapabilities {
     pub mcpui: bool,
 }
 
+/// A notification from an extension that its tools, resources or prompts changed, or that a
+/// subscribed resource was updated, tagged with the extension's name.
+pub type ExtensionEvent = (String, ServerNotification);
+
+#[derive(Default)]
+struct ToolsCache {
+    tools: Mutex<Option<Arc<Vec<Tool>>>>,
+    version: AtomicU64,
+}
+
+impl ToolsCache {
+    async fn invalidate(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+        *self.tools.lock().await = None;
+    }
+}
+
+/// The roots offered to an extension: the working directory as the extension sees it.
+fn roots_for(working_dir: &Path, container: Option<&Container>) -> Vec<Root> {
+    let working_dir = container
+        .and_then(|container| container.to_container_path(working_dir))
+        .unwrap_or_else(|| working_dir.to_path_buf());
+    vec![working_dir_root(&working_dir)]
+}
+
+/// What to do about an extension that may have stopped.
+enum Restart {
+    Running(McpClientBox),
+    After(Duration),
+}
+
 /// Manages goose extensions / MCP clients and their interactions
 pub struct ExtensionManager {
     extensions: Mutex<HashMap<String, Extension>>,
     context: PlatformExtensionContext,
     provider: SharedProvider,
-    tools_cache: Mutex<Option<Arc<Vec<Tool>>>>,
-    tools_cache_version: AtomicU64,
+    tools_cache: Arc<ToolsCache>,
+    events: broadcast::Sender<ExtensionEvent>,
+    supervisor: Mutex<Supervisor>,
+    /// One lock per extension, held while it is being restarted so concurrent callers wait for
+    /// that restart instead of starting another
+    restart_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
     client_name: String,
     capabilities: ExtensionManagerCapabilities,
 }
@@ -209,6 +264,22 @@ fn is_unprefixed_extension(config: &ExtensionConfig) -> bool {
 
 /// Returns true if the named extension is a first-class platform extension
 /// whose tools are exposed unprefixed and remain visible during code execution mode.
+fn status_of(supervisor: &Supervisor, name: &str, stopped: bool) -> ExtensionStatus {
+    let mut status = supervisor.status(name);
+    if stopped && status.health == ExtensionHealth::Running {
+        status.health = ExtensionHealth::Stopped;
+    }
+    status
+}
+
+/// Extensions whose server is a child process goose started, and so can start again.
+fn is_restartable(config: &ExtensionConfig) -> bool {
+    matches!(
+        config,
+        ExtensionConfig::Stdio { .. } | ExtensionConfig::InlinePython { .. }
+    )
+}
+
 pub fn is_first_class_extension(name: &str) -> bool {
     PLATFORM_EXTENSIONS
         .get(name_to_key(name).as_str())
@@ -490,10 +561,14 @@ impl ExtensionManager {
                 extension_manager: None,
                 session_manager,
                 session: None,
+                container: None,
+                client_delegate: None,
             },
             provider,
-            tools_cache: Mutex::new(None),
-            tools_cache_version: AtomicU64::new(0),
+            tools_cache: Arc::new(ToolsCache::default()),
+            events: broadcast::channel(64).0,
+            supervisor: Mutex::new(Supervisor::default()),
+            restart_locks: Mutex::new(HashMap::new()),
             client_name,
             capabilities,
         }
@@ -510,6 +585,26 @@ impl ExtensionManager {
         )
     }
 
+    fn client_capabilities(
+        &self,
+        working_dir: Option<&Path>,
+        container: Option<&Container>,
+    ) -> GooseMcpClientCapabilities {
+        let working_dir = working_dir
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+        GooseMcpClientCapabilities {
+            mcpui: self.capabilities.mcpui,
+            roots: roots_for(&working_dir, container),
+        }
+    }
+
+    /// Platform extensions added from now on may route file and shell work through the client
+    pub fn with_client_delegate(mut self, delegate: Option<Arc<dyn ClientDelegate>>) -> Self {
+        self.context.client_delegate = delegate;
+        self
+    }
+
     pub fn get_context(&self) -> &PlatformExtensionContext {
         &self.context
     }
@@ -571,9 +666,7 @@ impl ExtensionManager {
                     .iter()
                     .map(|(k, v)| (k.clone(), substitute_env_vars(v, &all_envs)))
                     .collect();
-                let capability = GooseMcpClientCapabilities {
-                    mcpui: self.capabilities.mcpui,
-                };
+                let capability = self.client_capabilities(working_dir.as_deref(), None);
 
                 create_streamable_http_client(
                     uri,
@@ -599,6 +692,7 @@ impl ExtensionManager {
                     // Platform extension: create via in-process client factory
                     let mut context = self.context.clone();
                     context.extension_manager = Some(Arc::downgrade(self));
+                    context.container = container.cloned();
                     if let Some(id) = session_id {
                         if let Ok(session) =
                             self.context.session_manager.get_session(id, false).await
@@ -620,9 +714,9 @@ impl ExtensionManager {
                         tracing::info!(
                             container = %container_id,
                             builtin = %name,
-                            "Starting builtin extension inside Docker container"
+                            "Starting builtin extension inside container"
                         );
-                        let command = Command::new("docker").configure(|command| {
+                        let command = Command::new(container.runtime()).configure(|command| {
                             command
                                 .arg("exec")
                                 .arg("-i")
@@ -636,9 +730,8 @@ impl ExtensionManager {
                             .clone()
                             .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
 
-                        let capabilities = GooseMcpClientCapabilities {
-                            mcpui: self.capabilities.mcpui,
-                        };
+                        let capabilities =
+                            self.client_capabilities(Some(&effective_working_dir), Some(container));
 
                         let client = child_process_client(
                             command,
@@ -656,9 +749,7 @@ impl ExtensionManager {
                         let (client_read, server_write) = tokio::io::duplex(65536);
                         extension_fn(server_read, server_write);
 
-                        let capabilities = GooseMcpClientCapabilities {
-                            mcpui: self.capabilities.mcpui,
-                        };
+                        let capabilities = self.client_capabilities(working_dir.as_deref(), None);
 
                         Box::new(
                             McpClient::connect(
@@ -673,6 +764,50 @@ impl ExtensionManager {
                     }
                 }
             }
+            ExtensionConfig::Stdio { .. } | ExtensionConfig::InlinePython { .. } => {
+                let (client, dir) = self
+                    .spawn_child_process(&config, working_dir.as_deref(), container, session_id)
+                    .await?;
+                temp_dir = dir;
+                Box::new(client)
+            }
+            ExtensionConfig::Frontend { .. } => {
+                return Err(ExtensionError::ConfigError(
+                    "Invalid extension type: Frontend extensions cannot be added as server extensions".to_string()
+                ));
+            }
+        };
+
+        let server_info = client.get_info().cloned();
+        let client: McpClientBox = Arc::from(client);
+        self.watch_changes(sanitized_name.clone(), &client).await;
+
+        let mut extension = Extension::new(config, client, server_info, temp_dir);
+        extension.container = container.cloned();
+        extension.working_dir = working_dir;
+        extension.session_id = session_id.map(str::to_string);
+        let mut extensions = self.extensions.lock().await;
+        extensions.insert(sanitized_name.clone(), extension);
+        drop(extensions);
+        self.supervisor.lock().await.reset(&sanitized_name);
+        self.restart_locks.lock().await.remove(&sanitized_name);
+        self.invalidate_tools_cache_and_bump_version().await;
+
+        Ok(())
+    }
+
+    /// Starts the process behind a stdio or inline Python extension and connects to it. Used
+    /// both when the extension is added and when it is restarted after its process exits.
+    async fn spawn_child_process(
+        &self,
+        config: &ExtensionConfig,
+        working_dir: Option<&Path>,
+        container: Option<&Container>,
+        session_id: Option<&str>,
+    ) -> ExtensionResult<(McpClient, Option<TempDir>)> {
+        let mut temp_dir = None;
+
+        let (command, timeout) = match config {
             ExtensionConfig::Stdio {
                 cmd,
                 args,
@@ -681,9 +816,8 @@ impl ExtensionManager {
                 timeout,
                 ..
             } => {
-                let config = Config::global();
                 let mut all_envs =
-                    merge_environments(envs, env_keys, &sanitized_name, config).await?;
+                    merge_environments(envs, env_keys, &config.key(), Config::global()).await?;
 
                 if let Some(sid) = session_id {
                     all_envs.insert("AGENT_SESSION_ID".to_string(), sid.to_string());
@@ -697,9 +831,9 @@ impl ExtensionManager {
                     tracing::info!(
                         container = %container_id,
                         cmd = %cmd,
-                        "Starting stdio extension inside Docker container"
+                        "Starting stdio extension inside container"
                     );
-                    Command::new("docker").configure(|command| {
+                    Command::new(container.runtime()).configure(|command| {
                         command.arg("exec").arg("-i");
                         for (key, value) in &all_envs {
                             command.arg("-e").arg(format!("{}={}", key, value));
@@ -714,24 +848,7 @@ impl ExtensionManager {
                         command.args(args).envs(all_envs);
                     })
                 };
-
-                let effective_working_dir = working_dir
-                    .clone()
-                    .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
-                let capabilities = GooseMcpClientCapabilities {
-                    mcpui: self.capabilities.mcpui,
-                };
-                let client = child_process_client(
-                    command,
-                    timeout,
-                    self.provider.clone(),
-                    Some(&effective_working_dir),
-                    container.map(|c| c.id().to_string()),
-                    self.client_name.clone(),
-                    capabilities,
-                )
-                .await?;
-                Box::new(client)
+                (command, timeout)
             }
             ExtensionConfig::InlinePython {
                 name,
@@ -742,7 +859,6 @@ impl ExtensionManager {
             } => {
                 let dir = tempdir()?;
                 let file_path = dir.path().join(format!("{}.py", name));
-                temp_dir = Some(dir);
                 std::fs::write(&file_path, code)?;
 
                 let command = Command::new("uvx").configure(|command| {
@@ -752,47 +868,33 @@ impl ExtensionManager {
                     });
                     command.arg("python").arg(file_path.to_str().unwrap());
                 });
-
-                // Compute working_dir for InlinePython (runs as child process via uvx)
-                let effective_working_dir = working_dir
-                    .clone()
-                    .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
-
-                let capabilities = GooseMcpClientCapabilities {
-                    mcpui: self.capabilities.mcpui,
-                };
-
-                let client = child_process_client(
-                    command,
-                    timeout,
-                    self.provider.clone(),
-                    Some(&effective_working_dir),
-                    container.map(|c| c.id().to_string()),
-                    self.client_name.clone(),
-                    capabilities,
-                )
-                .await?;
-
-                Box::new(client)
+                temp_dir = Some(dir);
+                (command, timeout)
             }
-            ExtensionConfig::Frontend { .. } => {
-                return Err(ExtensionError::ConfigError(
-                    "Invalid extension type: Frontend extensions cannot be added as server extensions".to_string()
-                ));
+            _ => {
+                return Err(ExtensionError::ConfigError(format!(
+                    "Extension '{}' does not run as a child process",
+                    config.name()
+                )));
             }
         };
 
-        let server_info = client.get_info().cloned();
-
-        let mut extensions = self.extensions.lock().await;
-        extensions.insert(
-            sanitized_name,
-            Extension::new(config, Arc::from(client), server_info, temp_dir),
-        );
-        drop(extensions);
-        self.invalidate_tools_cache_and_bump_version().await;
+        let effective_working_dir = working_dir
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+        let capabilities = self.client_capabilities(Some(&effective_working_dir), container);
+        let client = child_process_client(
+            command,
+            timeout,
+            self.provider.clone(),
+            Some(&effective_working_dir),
+            container.map(|c| c.id().to_string()),
+            self.client_name.clone(),
+            capabilities,
+        )
+        .await?;
 
-        Ok(())
+        Ok((client, temp_dir))
     }
 
     pub async fn add_client(
@@ -804,6 +906,7 @@ impl ExtensionManager {
         temp_dir: Option<TempDir>,
     ) {
         let normalized = name_to_key(&name);
+        self.watch_changes(normalized.clone(), &client).await;
         self.extensions
             .lock()
             .await
@@ -814,22 +917,55 @@ impl ExtensionManager {
     /// Get extensions info for building the system prompt
     pub async fn get_extensions_info(&self, working_dir: &std::path::Path) -> Vec<ExtensionInfo> {
         let working_dir_str = working_dir.to_string_lossy();
-        self.extensions
+        let infos: Vec<_> = self
+            .extensions
             .lock()
             .await
             .iter()
             .map(|(name, ext)| {
                 let instructions = ext.get_instructions().unwrap_or_default();
                 let instructions = instructions.replace("{{WORKING_DIR}}", &working_dir_str);
-                ExtensionInfo::new(name, &instructions, ext.supports_resources())
+                (
+                    ExtensionInfo::new(name, &instructions, ext.supports_resources()),
+                    ext.has_stopped(),
+                )
+            })
+            .collect();
+
+        let supervisor = self.supervisor.lock().await;
+        infos
+            .into_iter()
+            .map(|(mut info, stopped)| {
+                info.status = status_of(&supervisor, &info.name, stopped).describe();
+                info
             })
             .collect()
     }
 
+    /// Whether each extension is running, has been restarted, or has given up after crashing.
+    pub async fn get_extension_statuses(&self) -> Vec<ExtensionStatus> {
+        let mut extensions: Vec<_> = self
+            .extensions
+            .lock()
+            .await
+            .iter()
+            .map(|(name, ext)| (name.clone(), ext.has_stopped()))
+            .collect();
+        extensions.sort();
+
+        let supervisor = self.supervisor.lock().await;
+        extensions
+            .into_iter()
+            .map(|(name, stopped)| status_of(&supervisor, &name, stopped))
+            .collect()
+    }
+
     /// Get aggregated usage statistics
     pub async fn remove_extension(&self, name: &str) -> ExtensionResult<()> {
         let sanitized_name = name_to_key(name);
         self.extensions.lock().await.remove(&sanitized_name);
+        self.supervisor.lock().await.reset(&sanitized_name);
+        self.restart_locks.lock().await.remove(&sanitized_name);
         self.invalidate_tools_cache_and_bump_version().await;
         Ok(())
     }
@@ -917,18 +1053,18 @@ impl ExtensionManager {
 
     async fn get_all_tools_cached(&self, session_id: &str) -> ExtensionResult<Arc<Vec<Tool>>> {
         {
-            let cache = self.tools_cache.lock().await;
+            let cache = self.tools_cache.tools.lock().await;
             if let Some(ref tools) = *cache {
                 return Ok(Arc::clone(tools));
             }
         }
 
-        let version_before = self.tools_cache_version.load(Ordering::SeqCst);
+        let version_before = self.tools_cache.version.load(Ordering::SeqCst);
         let tools = Arc::new(self.fetch_all_tools(session_id).await?);
 
         {
-            let mut cache = self.tools_cache.lock().await;
-            let version_after = self.tools_cache_version.load(Ordering::SeqCst);
+            let mut cache = self.tools_cache.tools.lock().await;
+            let version_after = self.tools_cache.version.load(Ordering::SeqCst);
             if version_after == version_before && cache.is_none() {
                 *cache = Some(Arc::clone(&tools));
             }
@@ -938,8 +1074,208 @@ impl ExtensionManager {
     }
 
     async fn invalidate_tools_cache_and_bump_version(&self) {
-        self.tools_cache_version.fetch_add(1, Ordering::SeqCst);
-        *self.tools_cache.lock().await = None;
+        self.tools_cache.invalidate().await;
+    }
+
+    /// Change notifications from every extension. A changed tool list has already dropped the
+    /// tools cache by the time it arrives here.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ExtensionEvent> {
+        self.events.subscribe()
+    }
+
+    async fn watch_changes(&self, name: String, client: &McpClientBox) {
+        let mut changes = client.subscribe_changes().await;
+        let tools_cache = Arc::clone(&self.tools_cache);
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            while let Some(notification) = changes.recv().await {
+                if let ServerNotification::ToolListChangedNotification(_) = notification {
+                    tools_cache.invalidate().await;
+                }
+                let _ = events.send((name.clone(), notification));
+            }
+        });
+    }
+
+    /// Points every extension's roots at a new working directory.
+    pub async fn update_working_dir(&self, working_dir: &Path) {
+        let clients: Vec<_> = self
+            .extensions
+            .lock()
+            .await
+            .iter_mut()
+            .map(|(name, ext)| {
+                ext.working_dir = Some(working_dir.to_path_buf());
+                (
+                    name.clone(),
+                    ext.get_client(),
+                    roots_for(working_dir, ext.container.as_ref()),
+                )
+            })
+            .collect();
+        for (name, client, roots) in clients {
+            if let Err(e) = client.update_roots(roots).await {
+                warn!(extension = %name, "Failed to update roots: {}", e);
+            }
+        }
+    }
+
+    /// Starts a stopped stdio extension again with the configuration, working directory and
+    /// session it was added with, waiting longer after each crash. Returns the client to use, or
+    /// why the extension could not be brought back.
+    async fn restart_if_stopped(&self, name: &str) -> Result<McpClientBox, String> {
+        let _restarting = self.restart_lock(name).await.lock_owned().await;
+        match self.begin_restart(name).await? {
+            Restart::Running(client) => Ok(client),
+            Restart::After(backoff) => {
+                tokio::time::sleep(backoff).await;
+                self.finish_restart(name).await
+            }
+        }
+    }
+
+    /// Restarts stopped extensions in the background, so their tools come back for a later turn
+    /// without holding this one up for the backoff. The tasks only keep a weak reference, so
+    /// nothing is started again for an agent that has gone away in the meantime.
+    pub async fn restart_stopped_extensions(self: &Arc<Self>) {
+        let stopped: Vec<_> = self
+            .extensions
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, ext)| ext.has_stopped())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in stopped {
+            {
+                let supervisor = self.supervisor.lock().await;
+                if supervisor.is_restarting(&name)
+                    || supervisor.status(&name).health == ExtensionHealth::CrashLooping
+                {
+                    continue;
+                }
+            }
+            let lock = self.restart_lock(&name).await;
+            let manager = Arc::downgrade(self);
+            tokio::spawn(async move {
+                let _restarting = lock.lock_owned().await;
+                let Some(this) = manager.upgrade() else {
+                    return;
+                };
+                let backoff = match this.begin_restart(&name).await {
+                    Ok(Restart::After(backoff)) => backoff,
+                    Ok(Restart::Running(_)) => return,
+                    Err(e) => {
+                        warn!(extension = %name, "{}", e);
+                        return;
+                    }
+                };
+                drop(this);
+                tokio::time::sleep(backoff).await;
+                if let Some(this) = manager.upgrade() {
+                    if let Err(e) = this.finish_restart(&name).await {
+                        warn!(extension = %name, "{}", e);
+                    }
+                }
+            });
+        }
+    }
+
+    async fn restart_lock(&self, name: &str) -> Arc<Mutex<()>> {
+        self.restart_locks
+            .lock()
+            .await
+            .entry(name.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Decides whether a stopped extension should be started again and how long to wait first.
+    /// Callers hold the extension's restart lock.
+    async fn begin_restart(&self, name: &str) -> Result<Restart, String> {
+        let (client, config) = {
+            let extensions = self.extensions.lock().await;
+            let ext = extensions
+                .get(name)
+                .ok_or_else(|| format!("Extension '{}' not found", name))?;
+            (ext.get_client(), ext.config.clone())
+        };
+        // Another caller may have restarted it while we waited for the lock
+        if !client.is_closed() || !is_restartable(&config) {
+            return Ok(Restart::Running(client));
+        }
+
+        let backoff = self
+            .supervisor
+            .lock()
+            .await
+            .begin_restart(name, Instant::now())?;
+        warn!(extension = %name, ?backoff, "Extension process exited, restarting");
+        Ok(Restart::After(backoff))
+    }
+
+    /// Spawns the extension's process again once the backoff has passed.
+    async fn finish_restart(&self, name: &str) -> Result<McpClientBox, String> {
+        let (config, working_dir, container, session_id) = {
+            let extensions = self.extensions.lock().await;
+            let ext = extensions
+                .get(name)
+                .ok_or_else(|| format!("Extension '{}' not found", name))?;
+            (
+                ext.config.clone(),
+                ext.working_dir.clone(),
+                ext.container.clone(),
+                ext.session_id.clone(),
+            )
+        };
+        let spawned = self
+            .spawn_child_process(
+                &config,
+                working_dir.as_deref(),
+                container.as_ref(),
+                session_id.as_deref(),
+            )
+            .await;
+        let (client, temp_dir) = match spawned {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                error!(extension = %name, "Failed to restart extension: {}", e);
+                self.supervisor
+                    .lock()
+                    .await
+                    .restart_failed(name, e.to_string());
+                return Err(format!(
+                    "Extension '{}' stopped and could not be restarted: {}",
+                    name, e
+                ));
+            }
+        };
+
+        let server_info = client.get_info().cloned();
+        let client: McpClientBox = Arc::new(client);
+        self.watch_changes(name.to_string(), &client).await;
+        let subscriptions = match self.extensions.lock().await.get_mut(name) {
+            Some(ext) => {
+                ext.client = client.clone();
+                ext.server_info = server_info;
+                if temp_dir.is_some() {
+                    ext._temp_dir = temp_dir;
+                }
+                ext.subscriptions.clone()
+            }
+            None => HashMap::new(),
+        };
+        for (uri, session_id) in subscriptions {
+            if let Err(e) = client
+                .subscribe_resource(&session_id, &uri, CancellationToken::default())
+                .await
+            {
+                warn!(extension = %name, %uri, "Failed to resubscribe to resource: {}", e);
+            }
+        }
+        self.supervisor.lock().await.restarted(name);
+        self.invalidate_tools_cache_and_bump_version().await;
+        Ok(client)
     }
 
     async fn fetch_all_tools(&self, session_id: &str) -> ExtensionResult<Vec<Tool>> {
@@ -1164,6 +1500,102 @@ impl ExtensionManager {
             })
     }
 
+    /// Asks an extension to send `notifications/resources/updated` when `uri` changes. The
+    /// updates arrive through [`Self::subscribe_events`].
+    pub async fn subscribe_resource(
+        &self,
+        session_id: &str,
+        uri: &str,
+        extension_name: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), ErrorData> {
+        let client = self.resource_subscription_client(extension_name).await?;
+        client
+            .subscribe_resource(session_id, uri, cancellation_token)
+            .await
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!("Could not subscribe to resource with uri {}: {}", uri, e),
+                    None,
+                )
+            })?;
+        if let Some(ext) = self
+            .extensions
+            .lock()
+            .await
+            .get_mut(&name_to_key(extension_name))
+        {
+            ext.subscriptions
+                .insert(uri.to_string(), session_id.to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe_resource(
+        &self,
+        session_id: &str,
+        uri: &str,
+        extension_name: &str,
+        cancellation_token: CancellationToken,
+    ) -> Result<(), ErrorData> {
+        let client = self.resource_subscription_client(extension_name).await?;
+        if let Some(ext) = self
+            .extensions
+            .lock()
+            .await
+            .get_mut(&name_to_key(extension_name))
+        {
+            ext.subscriptions.remove(uri);
+        }
+        client
+            .unsubscribe_resource(session_id, uri, cancellation_token)
+            .await
+            .map_err(|e| {
+                ErrorData::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!(
+                        "Could not unsubscribe from resource with uri {}: {}",
+                        uri, e
+                    ),
+                    None,
+                )
+            })
+    }
+
+    async fn resource_subscription_client(
+        &self,
+        extension_name: &str,
+    ) -> Result<McpClientBox, ErrorData> {
+        let extensions = self.extensions.lock().await;
+        let extension = extensions
+            .get(&name_to_key(extension_name))
+            .ok_or_else(|| {
+                ErrorData::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("Extension '{}' not found", extension_name),
+                    None,
+                )
+            })?;
+        let subscribable = extension
+            .server_info
+            .as_ref()
+            .and_then(|info| info.capabilities.resources.as_ref())
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false);
+        if !subscribable {
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Extension '{}' does not support resource subscriptions",
+                    extension_name
+                ),
+                None,
+            ));
+        }
+        Ok(extension.get_client())
+    }
+
     pub async fn get_ui_resources(
         &self,
         session_id: &str,
@@ -1369,7 +1801,13 @@ impl ExtensionManager {
         cancellation_token: CancellationToken,
     ) -> Result<ToolCallResult> {
         let tool_name_str = tool_call.name.to_string();
-        let resolved = self.resolve_tool(session_id, &tool_name_str).await?;
+        let mut resolved = self.resolve_tool(session_id, &tool_name_str).await?;
+        if resolved.client.is_closed() {
+            resolved.client = self
+                .restart_if_stopped(&resolved.extension_name)
+                .await
+                .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e, None))?;
+        }
 
         if let Some(extension) = self.extensions.lock().await.get(&resolved.extension_name) {
             if !extension
@@ -1393,6 +1831,7 @@ impl ExtensionManager {
         let notifications_receiver = client.subscribe().await;
         let session_id = session_id.to_string();
         let actual_tool_name = resolved.actual_tool_name;
+        let extension_name = resolved.extension_name;
         let working_dir_str = working_dir.map(|p| p.to_string_lossy().to_string());
 
         let fut = async move {
@@ -1413,6 +1852,14 @@ impl ExtensionManager {
                 .await
                 .map_err(|e| match e {
                     ServiceError::McpError(error_data) => error_data,
+                    ServiceError::TransportClosed => ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!(
+                            "Extension '{}' stopped while running this tool. It will be restarted the next time one of its tools is used.",
+                            extension_name
+                        ),
+                        None,
+                    ),
                     _ => {
                         ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), e.maybe_to_value())
                     }
@@ -2123,6 +2570,122 @@ mod tests {
         assert!(tool_names.iter().any(|n| n.starts_with("ext_b__")));
     }
 
+    /// Serves a tool list that can grow, announcing changes the way a server would.
+    #[derive(Default)]
+    struct ChangingClient {
+        tools: std::sync::Mutex<Vec<String>>,
+        changes: std::sync::Mutex<Option<mpsc::Sender<ServerNotification>>>,
+    }
+
+    impl ChangingClient {
+        async fn add_tool(&self, name: &str) {
+            self.tools.lock().unwrap().push(name.to_string());
+            let changes = self.changes.lock().unwrap().clone().unwrap();
+            changes
+                .send(ServerNotification::ToolListChangedNotification(
+                    Default::default(),
+                ))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl McpClientTrait for ChangingClient {
+        fn get_info(&self) -> Option<&InitializeResult> {
+            None
+        }
+
+        async fn list_tools(
+            &self,
+            _session_id: &str,
+            _next_cursor: Option<String>,
+            _cancellation_token: CancellationToken,
+        ) -> Result<ListToolsResult, Error> {
+            let tools = self
+                .tools
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|name| Tool::new(name.clone(), "", Arc::new(JsonObject::new())))
+                .collect();
+            Ok(ListToolsResult {
+                tools,
+                next_cursor: None,
+                meta: None,
+            })
+        }
+
+        async fn call_tool(
+            &self,
+            _session_id: &str,
+            _name: &str,
+            _arguments: Option<JsonObject>,
+            _working_dir: Option<&str>,
+            _cancellation_token: CancellationToken,
+        ) -> Result<CallToolResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn subscribe_changes(&self) -> mpsc::Receiver<ServerNotification> {
+            let (tx, rx) = mpsc::channel(4);
+            *self.changes.lock().unwrap() = Some(tx);
+            rx
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tools_cache_invalidated_on_tool_list_changed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let extension_manager =
+            ExtensionManager::new_without_provider(temp_dir.path().to_path_buf());
+        let mut events = extension_manager.subscribe_events();
+        let client = Arc::new(ChangingClient::default());
+        client.tools.lock().unwrap().push("first".to_string());
+        extension_manager
+            .add_client(
+                "watcher".to_string(),
+                ExtensionConfig::Builtin {
+                    name: "watcher".to_string(),
+                    display_name: None,
+                    description: "built-in".to_string(),
+                    timeout: None,
+                    bundled: None,
+                    available_tools: vec![],
+                },
+                client.clone(),
+                None,
+                None,
+            )
+            .await;
+
+        let tools = extension_manager
+            .get_prefixed_tools("test-session-id", None)
+            .await
+            .unwrap();
+        assert_eq!(tools.len(), 1);
+
+        client.add_tool("second").await;
+        let (extension, notification) = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(extension, "watcher");
+        assert!(matches!(
+            notification,
+            ServerNotification::ToolListChangedNotification(_)
+        ));
+
+        let names: Vec<String> = extension_manager
+            .get_prefixed_tools("test-session-id", None)
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["watcher__first", "watcher__second"]);
+    }
+
     #[tokio::test]
     async fn test_tools_cache_invalidated_on_remove_extension() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -2286,4 +2849,114 @@ mod tests {
             "old extension must be preserved when replacement client creation fails"
         );
     }
+
+    /// A stdio extension whose process has already exited.
+    struct ExitedClient;
+
+    #[async_trait::async_trait]
+    impl McpClientTrait for ExitedClient {
+        fn get_info(&self) -> Option<&InitializeResult> {
+            None
+        }
+
+        async fn list_tools(
+            &self,
+            _session_id: &str,
+            _next_cursor: Option<String>,
+            _cancellation_token: CancellationToken,
+        ) -> Result<ListToolsResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        async fn call_tool(
+            &self,
+            _session_id: &str,
+            _name: &str,
+            _arguments: Option<JsonObject>,
+            _working_dir: Option<&str>,
+            _cancellation_token: CancellationToken,
+        ) -> Result<CallToolResult, Error> {
+            Err(Error::TransportClosed)
+        }
+
+        fn is_closed(&self) -> bool {
+            true
+        }
+    }
+
+    async fn add_crashy_extension(em: &ExtensionManager) {
+        em.add_client(
+            "crashy".to_string(),
+            ExtensionConfig::Stdio {
+                name: "crashy".to_string(),
+                description: "exits".to_string(),
+                cmd: "goose-test-command-that-does-not-exist".to_string(),
+                args: vec![],
+                envs: Default::default(),
+                env_keys: vec![],
+                timeout: None,
+                bundled: None,
+                available_tools: vec![],
+            },
+            Arc::new(ExitedClient),
+            None,
+            None,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_stopped_stdio_extension_restart_attempted_on_use() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let em = ExtensionManager::new_without_provider(temp_dir.path().to_path_buf());
+        add_crashy_extension(&em).await;
+
+        let statuses = em.get_extension_statuses().await;
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].health, ExtensionHealth::Stopped);
+        let info = em.get_extensions_info(temp_dir.path()).await;
+        assert!(info[0].status.is_some());
+
+        let result = em
+            .dispatch_tool_call(
+                "test-session-id",
+                CallToolRequestParams::new("crashy__ping"),
+                None,
+                CancellationToken::default(),
+            )
+            .await;
+        let error = match result {
+            Ok(_) => panic!("restart should fail for a missing command"),
+            Err(e) => e.to_string(),
+        };
+        assert!(error.contains("could not be restarted"), "{}", error);
+
+        let status = &em.get_extension_statuses().await[0];
+        assert_eq!(status.health, ExtensionHealth::Failed);
+        assert_eq!(status.restarts, 0);
+        assert!(status.last_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stopped_extensions_restart_in_background() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let em = Arc::new(ExtensionManager::new_without_provider(
+            temp_dir.path().to_path_buf(),
+        ));
+        add_crashy_extension(&em).await;
+
+        // Returns without waiting out the backoff
+        let started = Instant::now();
+        em.restart_stopped_extensions().await;
+        assert!(started.elapsed() < Duration::from_millis(250));
+
+        let failed = async {
+            while em.get_extension_statuses().await[0].health != ExtensionHealth::Failed {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(10), failed)
+            .await
+            .expect("the background restart should fail for a missing command");
+    }
 }
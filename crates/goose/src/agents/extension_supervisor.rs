@@ -0,0 +1,246 @@
+//! Restart bookkeeping for extensions that run as child processes.
+//!
+//! When a stdio extension's process exits, [`ExtensionManager`](super::ExtensionManager) starts
+//! it again the next time the extension is used, waiting a little longer after each crash. An
+//! extension that crashes too often in a short window is left stopped until it is re-added.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Restarts allowed within `window` before the extension is considered crash looping
+    pub max_restarts: usize,
+    pub window: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            window: Duration::from_secs(300),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionHealth {
+    #[default]
+    Running,
+    /// The process exited; it is started again the next time the extension is used
+    Stopped,
+    /// The process exited and is being started again
+    Restarting,
+    /// The last restart failed; the next use tries again
+    Failed,
+    /// Restarted too often; remove and re-add the extension to try again
+    CrashLooping,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ExtensionStatus {
+    pub name: String,
+    pub health: ExtensionHealth,
+    /// Restarts since the extension was added
+    pub restarts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl ExtensionStatus {
+    /// A note for the system prompt when the extension is not running normally.
+    pub fn describe(&self) -> Option<String> {
+        match self.health {
+            ExtensionHealth::Running => None,
+            ExtensionHealth::Stopped | ExtensionHealth::Restarting => Some(
+                "This extension stopped unexpectedly and is restarted when its tools are next used."
+                    .to_string(),
+            ),
+            ExtensionHealth::Failed => Some(format!(
+                "This extension stopped and could not be restarted ({}). Its tools may fail.",
+                self.last_error.as_deref().unwrap_or("unknown error")
+            )),
+            ExtensionHealth::CrashLooping => Some(
+                "This extension keeps crashing and has been stopped. Its tools will fail until the user removes and re-adds it."
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    health: ExtensionHealth,
+    restarts: u32,
+    recent: VecDeque<Instant>,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    policy: RestartPolicy,
+    records: HashMap<String, Record>,
+}
+
+impl Supervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            records: HashMap::new(),
+        }
+    }
+
+    pub fn status(&self, name: &str) -> ExtensionStatus {
+        let record = self.records.get(name);
+        ExtensionStatus {
+            name: name.to_string(),
+            health: record.map(|r| r.health).unwrap_or_default(),
+            restarts: record.map(|r| r.restarts).unwrap_or_default(),
+            last_error: record.and_then(|r| r.last_error.clone()),
+        }
+    }
+
+    /// Records that the extension's process went away and decides how long to wait before
+    /// starting it again, or returns the reason not to.
+    pub fn begin_restart(&mut self, name: &str, now: Instant) -> Result<Duration, String> {
+        let policy = &self.policy;
+        let record = self.records.entry(name.to_string()).or_default();
+        if record.health == ExtensionHealth::CrashLooping {
+            return Err(crash_loop_message(name, record));
+        }
+        while record
+            .recent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > policy.window)
+        {
+            record.recent.pop_front();
+        }
+        if record.recent.len() >= policy.max_restarts {
+            record.health = ExtensionHealth::CrashLooping;
+            return Err(crash_loop_message(name, record));
+        }
+
+        let backoff = policy
+            .initial_backoff
+            .saturating_mul(1 << record.recent.len().min(16))
+            .min(policy.max_backoff);
+        record.recent.push_back(now);
+        record.health = ExtensionHealth::Restarting;
+        Ok(backoff)
+    }
+
+    pub fn restarted(&mut self, name: &str) {
+        let record = self.records.entry(name.to_string()).or_default();
+        record.health = ExtensionHealth::Running;
+        record.restarts += 1;
+    }
+
+    pub fn restart_failed(&mut self, name: &str, error: String) {
+        let record = self.records.entry(name.to_string()).or_default();
+        record.health = ExtensionHealth::Failed;
+        record.last_error = Some(error);
+    }
+
+    /// Whether a restart is under way, so callers can wait for it instead of starting another.
+    pub fn is_restarting(&self, name: &str) -> bool {
+        self.records
+            .get(name)
+            .is_some_and(|r| r.health == ExtensionHealth::Restarting)
+    }
+
+    /// Forgets an extension's history, when it is removed or added again.
+    pub fn reset(&mut self, name: &str) {
+        self.records.remove(name);
+    }
+}
+
+fn crash_loop_message(name: &str, record: &Record) -> String {
+    let mut message = format!(
+        "Extension '{}' keeps crashing and will not be restarted again. Remove and re-add it once the problem is fixed.",
+        name
+    );
+    if let Some(error) = &record.last_error {
+        message.push_str(&format!(" Last error: {}", error));
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RestartPolicy {
+        RestartPolicy {
+            max_restarts: 3,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_until_crash_loop() {
+        let mut supervisor = Supervisor::new(policy());
+        let now = Instant::now();
+
+        let backoffs: Vec<_> = (0..3)
+            .map(|_| {
+                let backoff = supervisor.begin_restart("ext", now).unwrap();
+                supervisor.restarted("ext");
+                backoff
+            })
+            .collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(300)
+            ]
+        );
+
+        assert!(supervisor.begin_restart("ext", now).is_err());
+        let status = supervisor.status("ext");
+        assert_eq!(status.health, ExtensionHealth::CrashLooping);
+        assert_eq!(status.restarts, 3);
+        // Stays down even once the window has passed
+        assert!(supervisor
+            .begin_restart("ext", now + Duration::from_secs(120))
+            .is_err());
+    }
+
+    #[test]
+    fn test_restarts_outside_window_are_forgotten() {
+        let mut supervisor = Supervisor::new(policy());
+        let start = Instant::now();
+        for _ in 0..3 {
+            supervisor.begin_restart("ext", start).unwrap();
+            supervisor.restarted("ext");
+        }
+
+        let later = start + Duration::from_secs(61);
+        assert_eq!(
+            supervisor.begin_restart("ext", later),
+            Ok(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn test_failed_restart_is_reported() {
+        let mut supervisor = Supervisor::new(policy());
+        supervisor.begin_restart("ext", Instant::now()).unwrap();
+        supervisor.restart_failed("ext", "command not found".to_string());
+
+        let status = supervisor.status("ext");
+        assert_eq!(status.health, ExtensionHealth::Failed);
+        assert_eq!(status.last_error.as_deref(), Some("command not found"));
+        assert_eq!(supervisor.status("other").health, ExtensionHealth::Running);
+    }
+}
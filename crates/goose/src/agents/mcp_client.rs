@@ -3,8 +3,10 @@ use crate::agents::types::SharedProvider;
 use crate::session_context::{SESSION_ID_HEADER, WORKING_DIR_HEADER};
 use rmcp::model::{
     CreateElicitationRequestParams, CreateElicitationResult, ElicitationAction, ErrorCode,
-    ExtensionCapabilities, Extensions, JsonObject, LoggingMessageNotification, Meta,
-    SamplingMessageContent,
+    ExtensionCapabilities, Extensions, JsonObject, ListRootsResult, LoggingMessageNotification,
+    Meta, PromptListChangedNotification, ResourceListChangedNotification,
+    ResourceUpdatedNotification, ResourceUpdatedNotificationParam, Root, SamplingMessageContent,
+    SubscribeRequestParams, ToolListChangedNotification, UnsubscribeRequestParams,
 };
 /// MCP client implementation for Goose
 use rmcp::{
@@ -97,13 +99,50 @@ pub trait McpClientTrait: Send + Sync {
         mpsc::channel(1).1
     }
 
+    /// Notifications that the server's tools, resources or prompts changed, including
+    /// updates to subscribed resources. Kept apart from [`Self::subscribe`] so they are not
+    /// mistaken for the output of whichever tool call happens to be running.
+    async fn subscribe_changes(&self) -> mpsc::Receiver<ServerNotification> {
+        mpsc::channel(1).1
+    }
+
+    async fn subscribe_resource(
+        &self,
+        _session_id: &str,
+        _uri: &str,
+        _cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        Err(Error::TransportClosed)
+    }
+
+    async fn unsubscribe_resource(
+        &self,
+        _session_id: &str,
+        _uri: &str,
+        _cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        Err(Error::TransportClosed)
+    }
+
+    /// Replaces the roots offered to the server and tells it they changed.
+    async fn update_roots(&self, _roots: Vec<Root>) -> Result<(), Error> {
+        Ok(())
+    }
+
     async fn get_moim(&self, _session_id: &str) -> Option<String> {
         None
     }
+
+    /// Whether the connection to the server is gone, for example because its process exited.
+    fn is_closed(&self) -> bool {
+        false
+    }
 }
 
 pub struct GooseClient {
     notification_handlers: Arc<Mutex<Vec<Sender<ServerNotification>>>>,
+    change_handlers: Arc<Mutex<Vec<Sender<ServerNotification>>>>,
+    roots: Arc<Mutex<Vec<Root>>>,
     provider: SharedProvider,
     /// Fallback session_id for server-initiated callbacks (e.g. sampling/createMessage)
     /// that don't include the session_id in their MCP extensions metadata.
@@ -116,12 +155,15 @@ pub struct GooseClient {
 impl GooseClient {
     pub fn new(
         handlers: Arc<Mutex<Vec<Sender<ServerNotification>>>>,
+        change_handlers: Arc<Mutex<Vec<Sender<ServerNotification>>>>,
         provider: SharedProvider,
         client_name: String,
         capabilities: GooseMcpClientCapabilities,
     ) -> Self {
         GooseClient {
             notification_handlers: handlers,
+            change_handlers,
+            roots: Arc::new(Mutex::new(capabilities.roots.clone())),
             provider,
             session_id: Mutex::new(None),
             client_name,
@@ -148,6 +190,16 @@ impl GooseClient {
         Self::session_id_from_extensions(extensions).or(current_session_id)
     }
 
+    async fn notify_change(&self, notification: ServerNotification) {
+        self.change_handlers
+            .lock()
+            .await
+            .retain(|handler| !handler.is_closed());
+        for handler in self.change_handlers.lock().await.iter() {
+            let _ = handler.try_send(notification.clone());
+        }
+    }
+
     fn session_id_from_extensions(extensions: &Extensions) -> Option<String> {
         let meta = extensions.get::<Meta>()?;
         meta.0
@@ -192,6 +244,53 @@ impl ClientHandler for GooseClient {
             });
     }
 
+    async fn on_tool_list_changed(&self, _context: rmcp::service::NotificationContext<RoleClient>) {
+        self.notify_change(ServerNotification::ToolListChangedNotification(
+            ToolListChangedNotification::default(),
+        ))
+        .await;
+    }
+
+    async fn on_resource_list_changed(
+        &self,
+        _context: rmcp::service::NotificationContext<RoleClient>,
+    ) {
+        self.notify_change(ServerNotification::ResourceListChangedNotification(
+            ResourceListChangedNotification::default(),
+        ))
+        .await;
+    }
+
+    async fn on_prompt_list_changed(
+        &self,
+        _context: rmcp::service::NotificationContext<RoleClient>,
+    ) {
+        self.notify_change(ServerNotification::PromptListChangedNotification(
+            PromptListChangedNotification::default(),
+        ))
+        .await;
+    }
+
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        _context: rmcp::service::NotificationContext<RoleClient>,
+    ) {
+        self.notify_change(ServerNotification::ResourceUpdatedNotification(
+            ResourceUpdatedNotification::new(params),
+        ))
+        .await;
+    }
+
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, ErrorData> {
+        let mut result = ListRootsResult::default();
+        result.roots = self.roots.lock().await.clone();
+        Ok(result)
+    }
+
     async fn create_message(
         &self,
         params: CreateMessageRequestParams,
@@ -338,6 +437,8 @@ impl ClientHandler for GooseClient {
         InitializeRequestParams::new(
             ClientCapabilities::builder()
                 .enable_extensions_with(extensions)
+                .enable_roots()
+                .enable_roots_list_changed()
                 .enable_sampling()
                 .enable_elicitation()
                 .build(),
@@ -351,15 +452,32 @@ impl ClientHandler for GooseClient {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GooseMcpClientCapabilities {
     pub mcpui: bool,
+    /// Roots offered to the server, normally the session's working directory
+    pub roots: Vec<Root>,
+}
+
+/// The `file://` root for a working directory.
+pub fn working_dir_root(working_dir: &std::path::Path) -> Root {
+    let uri = url::Url::from_directory_path(working_dir)
+        .map(String::from)
+        .unwrap_or_else(|_| format!("file://{}", working_dir.display()));
+    let name = working_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+    // Root has no constructor outside rmcp
+    serde_json::from_value(serde_json::json!({ "uri": uri, "name": name }))
+        .expect("uri and name make a valid root")
 }
 
 /// The MCP client is the interface for MCP operations.
 pub struct McpClient {
     client: Mutex<RunningService<RoleClient, GooseClient>>,
+    peer: Peer<RoleClient>,
     notification_subscribers: Arc<Mutex<Vec<mpsc::Sender<ServerNotification>>>>,
+    change_subscribers: Arc<Mutex<Vec<mpsc::Sender<ServerNotification>>>>,
     server_info: Option<InitializeResult>,
     timeout: std::time::Duration,
     docker_container: Option<String>,
@@ -403,8 +521,12 @@ impl McpClient {
         let notification_subscribers =
             Arc::new(Mutex::new(Vec::<mpsc::Sender<ServerNotification>>::new()));
 
+        let change_subscribers =
+            Arc::new(Mutex::new(Vec::<mpsc::Sender<ServerNotification>>::new()));
+
         let client = GooseClient::new(
             notification_subscribers.clone(),
+            change_subscribers.clone(),
             provider,
             client_name.clone(),
             capabilities.clone(),
@@ -412,10 +534,13 @@ impl McpClient {
         let client: rmcp::service::RunningService<rmcp::RoleClient, GooseClient> =
             client.serve(transport).await?;
         let server_info = client.peer_info().cloned();
+        let peer = client.peer().clone();
 
         Ok(Self {
             client: Mutex::new(client),
+            peer,
             notification_subscribers,
+            change_subscribers,
             server_info,
             timeout,
             docker_container,
@@ -488,6 +613,10 @@ impl McpClientTrait for McpClient {
         self.server_info.as_ref()
     }
 
+    fn is_closed(&self) -> bool {
+        self.peer.is_transport_closed()
+    }
+
     async fn list_resources(
         &self,
         session_id: &str,
@@ -639,6 +768,71 @@ impl McpClientTrait for McpClient {
         self.notification_subscribers.lock().await.push(tx);
         rx
     }
+
+    async fn subscribe_changes(&self) -> mpsc::Receiver<ServerNotification> {
+        let (tx, rx) = mpsc::channel(16);
+        self.change_subscribers.lock().await.push(tx);
+        rx
+    }
+
+    async fn subscribe_resource(
+        &self,
+        session_id: &str,
+        uri: &str,
+        cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        let res = self
+            .send_request_with_context(
+                session_id,
+                None,
+                ClientRequest::SubscribeRequest(Request::new(SubscribeRequestParams::new(uri))),
+                cancel_token,
+            )
+            .await?;
+
+        match res {
+            ServerResult::EmptyResult(_) => Ok(()),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn unsubscribe_resource(
+        &self,
+        session_id: &str,
+        uri: &str,
+        cancel_token: CancellationToken,
+    ) -> Result<(), Error> {
+        let res = self
+            .send_request_with_context(
+                session_id,
+                None,
+                ClientRequest::UnsubscribeRequest(Request::new(unsubscribe_params(uri))),
+                cancel_token,
+            )
+            .await?;
+
+        match res {
+            ServerResult::EmptyResult(_) => Ok(()),
+            _ => Err(ServiceError::UnexpectedResponse),
+        }
+    }
+
+    async fn update_roots(&self, roots: Vec<Root>) -> Result<(), Error> {
+        let client = self.client.lock().await;
+        let mut current = client.service().roots.lock().await;
+        if *current == roots {
+            return Ok(());
+        }
+        *current = roots;
+        drop(current);
+        client.notify_roots_list_changed().await
+    }
+}
+
+fn unsubscribe_params(uri: &str) -> UnsubscribeRequestParams {
+    // Unlike SubscribeRequestParams, there is no constructor for this outside rmcp
+    serde_json::from_value(serde_json::json!({ "uri": uri }))
+        .expect("a uri makes valid unsubscribe params")
 }
 
 /// Injects the given session_id and working_dir into Extensions._meta.
@@ -714,6 +908,16 @@ fn inject_session_context_into_request(
                 inject_session_context_into_extensions(req.extensions, session_id, working_dir);
             ClientRequest::GetPromptRequest(req)
         }
+        ClientRequest::SubscribeRequest(mut req) => {
+            req.extensions =
+                inject_session_context_into_extensions(req.extensions, session_id, working_dir);
+            ClientRequest::SubscribeRequest(req)
+        }
+        ClientRequest::UnsubscribeRequest(mut req) => {
+            req.extensions =
+                inject_session_context_into_extensions(req.extensions, session_id, working_dir);
+            ClientRequest::UnsubscribeRequest(req)
+        }
         other => other,
     }
 }
@@ -727,11 +931,15 @@ mod tests {
 
     fn new_client(platform: GoosePlatform) -> GooseClient {
         let capabilities = match platform {
-            GoosePlatform::GooseDesktop => GooseMcpClientCapabilities { mcpui: true },
-            GoosePlatform::GooseCli => GooseMcpClientCapabilities { mcpui: false },
+            GoosePlatform::GooseDesktop => GooseMcpClientCapabilities {
+                mcpui: true,
+                ..Default::default()
+            },
+            GoosePlatform::GooseCli => GooseMcpClientCapabilities::default(),
         };
 
         GooseClient::new(
+            Arc::new(Mutex::new(Vec::new())),
             Arc::new(Mutex::new(Vec::new())),
             Arc::new(Mutex::new(None)),
             platform.to_string(),
@@ -747,6 +955,8 @@ mod tests {
             ClientRequest::CallToolRequest(req) => Some(&req.extensions),
             ClientRequest::ListPromptsRequest(req) => Some(&req.extensions),
             ClientRequest::GetPromptRequest(req) => Some(&req.extensions),
+            ClientRequest::SubscribeRequest(req) => Some(&req.extensions),
+            ClientRequest::UnsubscribeRequest(req) => Some(&req.extensions),
             _ => None,
         }
     }
@@ -789,6 +999,18 @@ mod tests {
         ClientRequest::GetPromptRequest(req)
     }
 
+    fn subscribe_request(extensions: Extensions) -> ClientRequest {
+        let mut req = Request::new(SubscribeRequestParams::new("test://resource"));
+        req.extensions = extensions;
+        ClientRequest::SubscribeRequest(req)
+    }
+
+    fn unsubscribe_request(extensions: Extensions) -> ClientRequest {
+        let mut req = Request::new(unsubscribe_params("test://resource"));
+        req.extensions = extensions;
+        ClientRequest::UnsubscribeRequest(req)
+    }
+
     #[test_case(
         Some("ext-session"),
         Some("current-session"),
@@ -835,6 +1057,8 @@ mod tests {
     #[test_case(call_tool_request; "call_tool")]
     #[test_case(list_prompts_request; "list_prompts")]
     #[test_case(get_prompt_request; "get_prompt")]
+    #[test_case(subscribe_request; "subscribe")]
+    #[test_case(unsubscribe_request; "unsubscribe")]
     fn test_request_injects_session(request_builder: fn(Extensions) -> ClientRequest) {
         let session_id = "test-session-id";
         let mut extensions = Extensions::new();
@@ -946,4 +1170,22 @@ mod tests {
 
         assert_eq!(mime_types, &json!(["text/html;profile=mcp-app"]));
     }
+
+    #[test]
+    fn test_client_info_advertises_roots() {
+        let client = new_client(GoosePlatform::GooseCli);
+        let info = ClientHandler::get_info(&client);
+
+        let roots = info.capabilities.roots.expect("roots capability");
+        assert_eq!(roots.list_changed, Some(true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_working_dir_root() {
+        let root = working_dir_root(std::path::Path::new("/home/me/my project"));
+
+        assert_eq!(root.uri, "file:///home/me/my%20project/");
+        assert_eq!(root.name.as_deref(), Some("my project"));
+    }
 }
@@ -1,10 +1,12 @@
 mod agent;
 pub(crate) mod builtin_skills;
+pub mod client_delegate;
 pub mod container;
 pub mod execute_commands;
 pub mod extension;
 pub mod extension_malware_check;
 pub mod extension_manager;
+pub mod extension_supervisor;
 pub mod final_output_tool;
 mod large_response_handler;
 pub mod mcp_client;
@@ -23,6 +25,7 @@ pub mod types;
 pub mod validate_extensions;
 
 pub use agent::{Agent, AgentConfig, AgentEvent, ExtensionLoadResult, GoosePlatform};
+pub use client_delegate::{ClientCommandOutput, ClientDelegate};
 pub use container::Container;
 pub use execute_commands::COMPACT_TRIGGERS;
 pub use extension::{ExtensionConfig, ExtensionError};
@@ -23,28 +23,33 @@ pub async fn inject_moim(
         .collect_moim(session_id, working_dir)
         .await
     {
-        let mut messages = conversation.messages().clone();
-        let idx = messages
-            .iter()
-            .rposition(|m| m.role == Role::Assistant)
-            .unwrap_or(0);
-        messages.insert(idx, Message::user().with_text(moim));
+        return inject_text(conversation, moim);
+    }
+    conversation
+}
 
-        let (fixed, issues) = fix_conversation(Conversation::new_unvalidated(messages));
+/// Adds `text` as a user message just before the last assistant turn, without persisting it.
+pub fn inject_text(conversation: Conversation, text: impl Into<String>) -> Conversation {
+    let mut messages = conversation.messages().clone();
+    let idx = messages
+        .iter()
+        .rposition(|m| m.role == Role::Assistant)
+        .unwrap_or(0);
+    messages.insert(idx, Message::user().with_text(text));
 
-        let has_unexpected_issues = issues.iter().any(|issue| {
-            !issue.contains("Merged consecutive user messages")
-                && !issue.contains("Merged consecutive assistant messages")
-        });
+    let (fixed, issues) = fix_conversation(Conversation::new_unvalidated(messages));
 
-        if has_unexpected_issues {
-            tracing::warn!("MOIM injection caused unexpected issues: {:?}", issues);
-            return conversation;
-        }
+    let has_unexpected_issues = issues.iter().any(|issue| {
+        !issue.contains("Merged consecutive user messages")
+            && !issue.contains("Merged consecutive assistant messages")
+    });
 
-        return fixed;
+    if has_unexpected_issues {
+        tracing::warn!("MOIM injection caused unexpected issues: {:?}", issues);
+        return conversation;
     }
-    conversation
+
+    fixed
 }
 
 #[cfg(test)]
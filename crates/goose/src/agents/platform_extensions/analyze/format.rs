@@ -3,13 +3,13 @@ use std::fmt::Write;
 use std::path::Path;
 
 use super::graph::CallGraph;
-use super::parser::FileAnalysis;
+use super::parser::{FileAnalysis, Symbol};
 
 const SIZE_LIMIT: usize = 50_000;
 const MULTILINE_THRESHOLD: usize = 10;
 
 pub fn format_structure(
-    analyses: &[FileAnalysis],
+    analyses: &[&FileAnalysis],
     root: &Path,
     depth: u32,
     total_files: usize,
@@ -43,7 +43,7 @@ pub fn format_structure(
     let mut lang_loc: HashMap<&str, usize> = HashMap::new();
     for a in analyses {
         if !a.language.is_empty() && a.loc > 0 {
-            *lang_loc.entry(a.language).or_default() += a.loc;
+            *lang_loc.entry(a.language.as_str()).or_default() += a.loc;
         }
     }
     if !lang_loc.is_empty() && total_loc > 0 {
@@ -102,8 +102,7 @@ pub fn format_semantic(analysis: &FileAnalysis, root: &Path) -> String {
     if !analysis.functions.is_empty() {
         let mut call_counts: HashMap<&str, usize> = HashMap::new();
         for call in &analysis.calls {
-            let bare = call.callee.rsplit("::").next().unwrap_or(&call.callee);
-            *call_counts.entry(bare).or_default() += 1;
+            *call_counts.entry(call.bare_callee()).or_default() += 1;
         }
 
         let items: Vec<String> = analysis
@@ -313,6 +312,18 @@ fn is_test_chain(chain: &[super::graph::ChainLink]) -> bool {
             // Swift
             || f.ends_with("Test.swift")
             || f.ends_with("Tests.swift")
+            // C / C++
+            || f.ends_with("_test.c")
+            || f.ends_with("_test.cpp")
+            || f.ends_with("_test.cc")
+            // C#
+            || f.ends_with("Tests.cs")
+            || f.ends_with("Test.cs")
+            // PHP
+            || f.ends_with("Test.php")
+            // Scala
+            || f.ends_with("Spec.scala")
+            || f.ends_with("Test.scala")
             // Directory conventions
             || f.contains("/tests/")
             || f.contains("/test/")
@@ -335,6 +346,75 @@ fn partition_test_chains(chains: &[Chain]) -> (Vec<Chain>, Vec<Chain>) {
     (prod, test)
 }
 
+pub fn format_definitions(symbol: &str, analyses: &[&FileAnalysis], root: &Path) -> String {
+    let mut defs: Vec<(&Path, &Symbol)> = analyses
+        .iter()
+        .flat_map(|a| {
+            a.functions
+                .iter()
+                .chain(&a.classes)
+                .filter(|s| s.name == symbol)
+                .map(|s| (a.path.as_path(), s))
+        })
+        .collect();
+    if defs.is_empty() {
+        return format!(
+            "Symbol '{}' not found in {} analyzed files.\n",
+            symbol,
+            analyses.len()
+        );
+    }
+    defs.sort_by(|a, b| (a.0, a.1.line).cmp(&(b.0, b.1.line)));
+
+    let mut out = String::new();
+    let _ = writeln!(out, "DEFINITIONS: {} ({})\n", symbol, defs.len());
+    for (path, sym) in defs {
+        let display = path.strip_prefix(root).unwrap_or(path);
+        let _ = write!(out, "{}:{} ", display.display(), sym.line);
+        if let Some(ref parent) = sym.parent {
+            let _ = write!(out, "{}.", parent);
+        }
+        let _ = writeln!(out, "{}{}", sym.name, sym.detail.as_deref().unwrap_or(""));
+    }
+    out
+}
+
+pub fn format_references(symbol: &str, analyses: &[&FileAnalysis], root: &Path) -> String {
+    let mut refs: Vec<(&Path, usize, &str)> = analyses
+        .iter()
+        .flat_map(|a| {
+            a.calls
+                .iter()
+                .filter(|c| c.bare_callee() == symbol)
+                .map(|c| (a.path.as_path(), c.line, c.caller.as_str()))
+        })
+        .collect();
+    if refs.is_empty() {
+        return format!(
+            "No references to '{}' found in {} analyzed files.\n",
+            symbol,
+            analyses.len()
+        );
+    }
+    refs.sort();
+    refs.dedup();
+
+    let files: HashSet<&Path> = refs.iter().map(|r| r.0).collect();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "REFERENCES: {} ({} in {} files)\n",
+        symbol,
+        refs.len(),
+        files.len()
+    );
+    for (path, line, caller) in refs {
+        let display = path.strip_prefix(root).unwrap_or(path);
+        let _ = writeln!(out, "{}:{} in {}", display.display(), line, caller);
+    }
+    out
+}
+
 pub fn check_size(output: &str, force: bool) -> Result<String, String> {
     if output.len() > SIZE_LIMIT && !force {
         Err(format!(
@@ -362,7 +442,7 @@ enum TreeNode {
     },
 }
 
-fn build_dir_tree(analyses: &[FileAnalysis], root: &Path) -> Vec<TreeNode> {
+fn build_dir_tree(analyses: &[&FileAnalysis], root: &Path) -> Vec<TreeNode> {
     let mut entries: Vec<(Vec<String>, &FileAnalysis)> = analyses
         .iter()
         .filter_map(|a| {
@@ -371,7 +451,7 @@ fn build_dir_tree(analyses: &[FileAnalysis], root: &Path) -> Vec<TreeNode> {
                 .components()
                 .map(|c| c.as_os_str().to_string_lossy().to_string())
                 .collect();
-            Some((parts, a))
+            Some((parts, *a))
         })
         .collect();
     entries.sort_by(|a, b| a.0.cmp(&b.0));
@@ -1,6 +1,8 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize, Serializer};
+
 use super::parser::{Call, FileAnalysis, Symbol};
 
 /// (file_path, symbol_name, definition_line) — line disambiguates same-name
@@ -18,8 +20,12 @@ struct Node {
     file: PathBuf,
     name: String,
     line: usize,
+    /// Enclosing class or namespace, used to resolve qualified calls like `Type::method`.
+    parent: Option<String>,
 }
 
+#[derive(Deserialize)]
+#[serde(from = "GraphSnapshot")]
 pub struct CallGraph {
     nodes: HashMap<NodeKey, Node>,
     // callee_key → set of caller_keys
@@ -29,7 +35,7 @@ pub struct CallGraph {
 }
 
 impl CallGraph {
-    pub fn build(analyses: &[FileAnalysis]) -> Self {
+    pub fn build(analyses: &[&FileAnalysis]) -> Self {
         let mut nodes: HashMap<NodeKey, Node> = HashMap::new();
         let mut incoming: HashMap<NodeKey, HashSet<NodeKey>> = HashMap::new();
         let mut outgoing: HashMap<NodeKey, HashSet<NodeKey>> = HashMap::new();
@@ -41,6 +47,7 @@ impl CallGraph {
                     file: a.path.clone(),
                     name: sym.name.clone(),
                     line: sym.line,
+                    parent: sym.parent.clone(),
                 });
             };
             for f in &a.functions {
@@ -58,6 +65,7 @@ impl CallGraph {
                 file: a.path.clone(),
                 name: "<module>".to_string(),
                 line: 0,
+                parent: None,
             });
         }
 
@@ -79,8 +87,10 @@ impl CallGraph {
         }
 
         // Build path → language index to prevent cross-language false positives
-        let lang_index: HashMap<&PathBuf, &str> =
-            analyses.iter().map(|a| (&a.path, a.language)).collect();
+        let lang_index: HashMap<&PathBuf, &str> = analyses
+            .iter()
+            .map(|a| (&a.path, a.language.as_str()))
+            .collect();
 
         for a in analyses {
             for call in &a.calls {
@@ -88,7 +98,7 @@ impl CallGraph {
                 let caller_key = resolve_caller_key(a, call, &def_lines)
                     .unwrap_or_else(|| (a.path.clone(), "<module>".to_string(), 0));
                 // Resolve callee: same-file first, then cross-file (same language only)
-                let callee_keys = resolve_callee(a, call, &name_index, &lang_index);
+                let callee_keys = resolve_callee(a, call, &nodes, &name_index, &lang_index);
                 for callee_key in callee_keys {
                     incoming
                         .entry(callee_key.clone())
@@ -209,6 +219,86 @@ impl CallGraph {
     }
 }
 
+/// Flat form of a [`CallGraph`] for the on-disk index. Edges are `(caller, callee)`
+/// positions in `nodes`, since JSON maps cannot be keyed by tuples.
+#[derive(Serialize, Deserialize)]
+struct GraphSnapshot {
+    nodes: Vec<(NodeKey, Option<String>)>,
+    edges: Vec<(usize, usize)>,
+}
+
+impl Serialize for CallGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut keys: Vec<&NodeKey> = self.nodes.keys().collect();
+        keys.sort();
+        let positions: HashMap<&NodeKey, usize> =
+            keys.iter().enumerate().map(|(i, key)| (*key, i)).collect();
+
+        let mut edges: Vec<(usize, usize)> = self
+            .outgoing
+            .iter()
+            .flat_map(|(caller, callees)| {
+                let positions = &positions;
+                callees.iter().filter_map(move |callee| {
+                    Some((*positions.get(caller)?, *positions.get(callee)?))
+                })
+            })
+            .collect();
+        edges.sort_unstable();
+
+        GraphSnapshot {
+            nodes: keys
+                .into_iter()
+                .map(|key| (key.clone(), self.nodes[key].parent.clone()))
+                .collect(),
+            edges,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl From<GraphSnapshot> for CallGraph {
+    fn from(snapshot: GraphSnapshot) -> Self {
+        let mut incoming: HashMap<NodeKey, HashSet<NodeKey>> = HashMap::new();
+        let mut outgoing: HashMap<NodeKey, HashSet<NodeKey>> = HashMap::new();
+        for (caller, callee) in snapshot.edges {
+            let (Some((caller, _)), Some((callee, _))) =
+                (snapshot.nodes.get(caller), snapshot.nodes.get(callee))
+            else {
+                continue;
+            };
+            incoming
+                .entry(callee.clone())
+                .or_default()
+                .insert(caller.clone());
+            outgoing
+                .entry(caller.clone())
+                .or_default()
+                .insert(callee.clone());
+        }
+
+        let nodes = snapshot
+            .nodes
+            .into_iter()
+            .map(|(key, parent)| {
+                let node = Node {
+                    file: key.0.clone(),
+                    name: key.1.clone(),
+                    line: key.2,
+                    parent,
+                };
+                (key, node)
+            })
+            .collect();
+
+        Self {
+            nodes,
+            incoming,
+            outgoing,
+        }
+    }
+}
+
 /// Given a call, find the NodeKey for the caller function. Uses the call's line
 /// number to disambiguate when multiple functions share the same name in a file:
 /// picks the definition whose line is the largest value ≤ call.line.
@@ -233,18 +323,38 @@ fn resolve_caller_key(
 fn resolve_callee(
     analysis: &FileAnalysis,
     call: &Call,
+    nodes: &HashMap<NodeKey, Node>,
     name_index: &HashMap<&str, Vec<NodeKey>>,
     lang_index: &HashMap<&PathBuf, &str>,
 ) -> Vec<NodeKey> {
-    let callee = &call.callee;
-    let caller_lang = analysis.language;
+    let caller_lang = analysis.language.as_str();
 
     // Strip scope prefix for qualified calls like Self::method(), Type::new(),
-    // HashMap::new(), module::func(). The name index is keyed on bare names
-    // (from Symbol.name), but call captures include the full scoped_identifier.
-    let bare_name = callee.rsplit("::").next().unwrap_or(callee);
+    // HashMap::new(), module::func(), Helper.Compute(). The name index is keyed on
+    // bare names (from Symbol.name), but call captures include the full qualified name.
+    let bare_name = call.bare_callee();
 
     if let Some(keys) = name_index.get(bare_name) {
+        // A qualifier naming a class or namespace (C++ `geo::area()`, C# `Helper.Compute()`)
+        // picks the definitions inside it. Qualifiers that are variables match nothing and
+        // fall through to name-based resolution.
+        if let Some(qualifier) = call.qualifier() {
+            let qualified: Vec<NodeKey> = keys
+                .iter()
+                .filter(|key| {
+                    lang_index.get(&key.0).copied() == Some(caller_lang)
+                        && nodes
+                            .get(*key)
+                            .and_then(|n| n.parent.as_deref())
+                            .is_some_and(|parent| parent == qualifier)
+                })
+                .cloned()
+                .collect();
+            if !qualified.is_empty() {
+                return qualified;
+            }
+        }
+
         // Prefer same-file matches; when ambiguous pick nearest by line proximity
         let same_file: Vec<NodeKey> = keys
             .iter()
@@ -0,0 +1,296 @@
+//! On-disk cache of file analyses and the call graph built from them, so repeated
+//! calls only reparse files whose content changed.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::graph::CallGraph;
+use super::languages::lang_for_ext;
+use super::parser::{FileAnalysis, Parser};
+
+/// Bump whenever the parser output changes so indexes written by older builds are discarded.
+const INDEX_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct IndexedFile {
+    size: u64,
+    /// Modification time in nanoseconds since the epoch, checked before hashing.
+    modified: u64,
+    hash: String,
+    analysis: FileAnalysis,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexedGraph {
+    /// Hash over the paths and content hashes of the files the graph was built from.
+    fingerprint: String,
+    graph: CallGraph,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshStats {
+    pub parsed: usize,
+    pub reused: usize,
+    pub removed: usize,
+}
+
+enum Update {
+    Unchanged,
+    Touched { size: u64, modified: u64 },
+    Parsed(Box<IndexedFile>),
+    Gone,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CodeIndex {
+    version: u32,
+    root: PathBuf,
+    files: BTreeMap<PathBuf, IndexedFile>,
+    #[serde(default)]
+    graph: Option<IndexedGraph>,
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    last_refresh: RefreshStats,
+}
+
+impl CodeIndex {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            version: INDEX_VERSION,
+            root: root.to_path_buf(),
+            files: BTreeMap::new(),
+            graph: None,
+            dirty: false,
+            last_refresh: RefreshStats::default(),
+        }
+    }
+
+    pub fn index_path(index_dir: &Path, root: &Path) -> PathBuf {
+        let key = blake3::hash(root.to_string_lossy().as_bytes()).to_hex();
+        index_dir.join(format!("{key}.json"))
+    }
+
+    /// Loads the index for `root`, starting empty when there is none or it was written by
+    /// a different version.
+    pub fn load(index_dir: &Path, root: &Path) -> Self {
+        fs::read(Self::index_path(index_dir, root))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CodeIndex>(&bytes).ok())
+            .filter(|index| index.version == INDEX_VERSION && index.root == root)
+            .unwrap_or_else(|| Self::new(root))
+    }
+
+    /// Writes the index if anything changed since it was loaded or last saved.
+    pub fn save(&mut self, index_dir: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        fs::create_dir_all(index_dir)?;
+        let path = Self::index_path(index_dir, &self.root);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, &path)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn last_refresh(&self) -> RefreshStats {
+        self.last_refresh
+    }
+
+    /// Brings the entries for `files` up to date and returns their analyses. Files are only
+    /// reparsed when their size or modification time changed and their content hash no
+    /// longer matches.
+    pub fn refresh(&mut self, files: &[PathBuf]) -> Vec<&FileAnalysis> {
+        let candidates: Vec<&PathBuf> = files.iter().filter(|f| is_supported(f)).collect();
+
+        let updates: Vec<(&PathBuf, Update)> = candidates
+            .par_iter()
+            .map(|path| (*path, check_file(path, self.files.get(*path))))
+            .collect();
+
+        let mut stats = RefreshStats::default();
+        for (path, update) in updates {
+            match update {
+                Update::Unchanged => stats.reused += 1,
+                Update::Touched { size, modified } => {
+                    if let Some(entry) = self.files.get_mut(path) {
+                        entry.size = size;
+                        entry.modified = modified;
+                    }
+                    stats.reused += 1;
+                    self.dirty = true;
+                }
+                Update::Parsed(entry) => {
+                    self.files.insert(path.clone(), *entry);
+                    stats.parsed += 1;
+                    self.dirty = true;
+                }
+                Update::Gone => {
+                    if self.files.remove(path).is_some() {
+                        stats.removed += 1;
+                        self.dirty = true;
+                    }
+                }
+            }
+        }
+
+        // Entries outside this walk (e.g. below a smaller max_depth) are kept unless the file is gone
+        let visited: HashSet<&PathBuf> = candidates.iter().copied().collect();
+        let before = self.files.len();
+        self.files
+            .retain(|path, _| visited.contains(path) || path.exists());
+        if self.files.len() != before {
+            stats.removed += before - self.files.len();
+            self.dirty = true;
+        }
+
+        self.last_refresh = stats;
+        self.analyses(files)
+    }
+
+    /// The call graph over `files`, rebuilt only when one of them changed since it was last
+    /// built. Call [`CodeIndex::refresh`] first.
+    pub fn call_graph(&mut self, files: &[PathBuf]) -> &CallGraph {
+        let fingerprint = self.fingerprint(files);
+        let graph = match self.graph.take() {
+            Some(graph) if graph.fingerprint == fingerprint => graph,
+            _ => {
+                self.dirty = true;
+                IndexedGraph {
+                    graph: CallGraph::build(&self.analyses(files)),
+                    fingerprint,
+                }
+            }
+        };
+        &self.graph.insert(graph).graph
+    }
+
+    fn analyses(&self, files: &[PathBuf]) -> Vec<&FileAnalysis> {
+        files
+            .iter()
+            .filter_map(|path| self.files.get(path))
+            .map(|entry| &entry.analysis)
+            .collect()
+    }
+
+    fn fingerprint(&self, files: &[PathBuf]) -> String {
+        let mut entries: Vec<(&PathBuf, &str)> = files
+            .iter()
+            .filter_map(|path| Some((path, self.files.get(path)?.hash.as_str())))
+            .collect();
+        entries.sort_unstable();
+
+        let mut hasher = blake3::Hasher::new();
+        for (path, hash) in entries {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(b"\0");
+            hasher.update(hash.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+}
+
+fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| lang_for_ext(ext).is_some())
+}
+
+fn check_file(path: &Path, existing: Option<&IndexedFile>) -> Update {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Update::Gone;
+    };
+    let size = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos() as u64);
+    if existing.is_some_and(|e| e.size == size && e.modified == modified) {
+        return Update::Unchanged;
+    }
+
+    let Ok(bytes) = fs::read(path) else {
+        return Update::Gone;
+    };
+    let hash = blake3::hash(&bytes).to_hex().to_string();
+    if existing.is_some_and(|e| e.hash == hash) {
+        return Update::Touched { size, modified };
+    }
+
+    let Ok(source) = String::from_utf8(bytes) else {
+        return Update::Gone;
+    };
+    match Parser::new().analyze_file(path, &source) {
+        Some(analysis) => Update::Parsed(Box::new(IndexedFile {
+            size,
+            modified,
+            hash,
+            analysis,
+        })),
+        None => Update::Gone,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reparses_only_changed_files_and_survives_reload() {
+        let src = tempdir().unwrap();
+        let index_dir = tempdir().unwrap();
+        let a = src.path().join("a.rs");
+        let b = src.path().join("b.rs");
+        fs::write(&a, "fn process() { validate(); }\n").unwrap();
+        fs::write(&b, "fn validate() {}\n").unwrap();
+        fs::write(src.path().join("notes.txt"), "not code").unwrap();
+        let files = vec![a.clone(), b.clone(), src.path().join("notes.txt")];
+
+        let mut index = CodeIndex::load(index_dir.path(), src.path());
+        assert_eq!(index.refresh(&files).len(), 2);
+        assert_eq!(index.last_refresh().parsed, 2);
+        assert_eq!(index.call_graph(&files).incoming("validate", 1).len(), 1);
+        index.save(index_dir.path()).unwrap();
+
+        let mut index = CodeIndex::load(index_dir.path(), src.path());
+        index.refresh(&files);
+        assert_eq!(
+            index.last_refresh(),
+            RefreshStats {
+                parsed: 0,
+                reused: 2,
+                removed: 0
+            }
+        );
+        assert_eq!(index.call_graph(&files).incoming("validate", 1).len(), 1);
+        assert!(
+            !index.dirty,
+            "nothing changed, so the stored graph is reused"
+        );
+
+        fs::write(&b, "fn validate() {}\nfn helper() { validate(); }\n").unwrap();
+        fs::remove_file(&a).unwrap();
+        index.refresh(&files);
+        assert_eq!(
+            index.last_refresh(),
+            RefreshStats {
+                parsed: 1,
+                reused: 0,
+                removed: 1
+            }
+        );
+        let callers = index.call_graph(&files).incoming("validate", 1);
+        assert_eq!(callers.len(), 1);
+        assert_eq!(callers[0][1].name, "helper");
+    }
+}
@@ -319,6 +319,166 @@ static LANGUAGES: &[LangInfo] = &[
             "#,
         },
     },
+    LangInfo {
+        name: "c",
+        extensions: &["c"],
+        language: || tree_sitter_c::LANGUAGE.into(),
+        fn_kinds: &["function_definition"],
+        fn_name_kinds: &["identifier"],
+        class_kinds: &["struct_specifier", "union_specifier", "enum_specifier"],
+        queries: LangQueries {
+            functions: r#"
+                (function_definition declarator: (function_declarator declarator: (identifier) @name))
+                (function_definition declarator: (pointer_declarator declarator: (function_declarator declarator: (identifier) @name)))
+            "#,
+            classes: r#"
+                (struct_specifier name: (type_identifier) @name body: (field_declaration_list))
+                (union_specifier name: (type_identifier) @name body: (field_declaration_list))
+                (enum_specifier name: (type_identifier) @name body: (enumerator_list))
+            "#,
+            imports: r#"
+                (preproc_include path: (_) @path)
+            "#,
+            calls: r#"
+                (call_expression function: (identifier) @name)
+                (call_expression function: (field_expression field: (field_identifier) @name))
+            "#,
+        },
+    },
+    LangInfo {
+        name: "cpp",
+        // Headers are parsed as C++, which also handles nearly all C headers
+        extensions: &["cpp", "cc", "cxx", "c++", "hpp", "hh", "hxx", "h"],
+        language: || tree_sitter_cpp::LANGUAGE.into(),
+        fn_kinds: &["function_definition"],
+        fn_name_kinds: &["identifier", "field_identifier"],
+        class_kinds: &[
+            "class_specifier",
+            "struct_specifier",
+            "namespace_definition",
+        ],
+        queries: LangQueries {
+            functions: r#"
+                (function_definition declarator: (function_declarator declarator: [(identifier) (field_identifier) (destructor_name) (operator_name) (qualified_identifier)] @name))
+                (function_definition declarator: (pointer_declarator declarator: (function_declarator declarator: [(identifier) (field_identifier) (qualified_identifier)] @name)))
+                (function_definition declarator: (reference_declarator (function_declarator declarator: [(identifier) (field_identifier) (qualified_identifier)] @name)))
+            "#,
+            classes: r#"
+                (class_specifier name: (type_identifier) @name body: (field_declaration_list))
+                (struct_specifier name: (type_identifier) @name body: (field_declaration_list))
+                (enum_specifier name: (type_identifier) @name body: (enumerator_list))
+            "#,
+            imports: r#"
+                (preproc_include path: (_) @path)
+            "#,
+            calls: r#"
+                (call_expression function: (identifier) @name)
+                (call_expression function: (qualified_identifier) @name)
+                (call_expression function: (field_expression field: (field_identifier) @name))
+                (new_expression type: (type_identifier) @name)
+            "#,
+        },
+    },
+    LangInfo {
+        name: "csharp",
+        extensions: &["cs"],
+        language: || tree_sitter_c_sharp::LANGUAGE.into(),
+        fn_kinds: &[
+            "method_declaration",
+            "constructor_declaration",
+            "local_function_statement",
+        ],
+        fn_name_kinds: &["identifier"],
+        class_kinds: &[
+            "class_declaration",
+            "interface_declaration",
+            "struct_declaration",
+            "record_declaration",
+            "enum_declaration",
+        ],
+        queries: LangQueries {
+            functions: r#"
+                (method_declaration name: (identifier) @name)
+                (constructor_declaration name: (identifier) @name)
+                (local_function_statement name: (identifier) @name)
+            "#,
+            classes: r#"
+                (class_declaration name: (identifier) @name)
+                (interface_declaration name: (identifier) @name)
+                (struct_declaration name: (identifier) @name)
+                (record_declaration name: (identifier) @name)
+                (enum_declaration name: (identifier) @name)
+            "#,
+            imports: r#"
+                (using_directive) @path
+            "#,
+            calls: r#"
+                (invocation_expression function: (identifier) @name)
+                (invocation_expression function: (member_access_expression) @name)
+                (object_creation_expression type: (identifier) @name)
+            "#,
+        },
+    },
+    LangInfo {
+        name: "php",
+        extensions: &["php"],
+        language: || tree_sitter_php::LANGUAGE_PHP.into(),
+        fn_kinds: &["function_definition", "method_declaration"],
+        fn_name_kinds: &["name"],
+        class_kinds: &[
+            "class_declaration",
+            "interface_declaration",
+            "trait_declaration",
+            "enum_declaration",
+        ],
+        queries: LangQueries {
+            functions: r#"
+                (function_definition name: (name) @name)
+                (method_declaration name: (name) @name)
+            "#,
+            classes: r#"
+                (class_declaration name: (name) @name)
+                (interface_declaration name: (name) @name)
+                (trait_declaration name: (name) @name)
+                (enum_declaration name: (name) @name)
+            "#,
+            imports: r#"
+                (namespace_use_declaration) @path
+            "#,
+            calls: r#"
+                (function_call_expression function: (name) @name)
+                (member_call_expression name: (name) @name)
+                (scoped_call_expression name: (name) @name)
+                (object_creation_expression (name) @name)
+            "#,
+        },
+    },
+    LangInfo {
+        name: "scala",
+        extensions: &["scala", "sc"],
+        language: || tree_sitter_scala::LANGUAGE.into(),
+        fn_kinds: &["function_definition"],
+        fn_name_kinds: &["identifier"],
+        class_kinds: &["class_definition", "object_definition", "trait_definition"],
+        queries: LangQueries {
+            functions: r#"
+                (function_definition name: (identifier) @name)
+            "#,
+            classes: r#"
+                (class_definition name: (identifier) @name)
+                (object_definition name: (identifier) @name)
+                (trait_definition name: (identifier) @name)
+            "#,
+            imports: r#"
+                (import_declaration) @path
+            "#,
+            calls: r#"
+                (call_expression function: (identifier) @name)
+                (call_expression function: (field_expression field: (identifier) @name))
+                (instance_expression (type_identifier) @name)
+            "#,
+        },
+    },
 ];
 
 pub fn lang_for_ext(ext: &str) -> Option<&'static LangInfo> {
@@ -1,16 +1,18 @@
 pub mod format;
 pub mod graph;
+pub mod index;
 pub mod languages;
 pub mod parser;
 
 use crate::agents::extension::PlatformExtensionContext;
 use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::config::paths::Paths;
 use anyhow::Result;
 use async_trait::async_trait;
 use ignore::WalkBuilder;
+use index::CodeIndex;
 use indoc::indoc;
 use parser::{FileAnalysis, Parser};
-use rayon::prelude::*;
 use rmcp::model::{
     CallToolResult, Content, Implementation, InitializeResult, JsonObject, ListToolsResult,
     ServerCapabilities, Tool, ToolAnnotations,
@@ -18,7 +20,9 @@ use rmcp::model::{
 use schemars::{schema_for, JsonSchema};
 use serde::Deserialize;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 
 pub static EXTENSION_NAME: &str = "analyze";
@@ -39,6 +43,16 @@ pub struct AnalyzeParams {
     /// Allow large outputs without size warning
     #[serde(default)]
     pub force: bool,
+    /// With focus, list only definitions or call sites instead of call chains
+    #[serde(default)]
+    pub mode: Option<LookupMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupMode {
+    FindDefinition,
+    FindReferences,
 }
 
 fn default_max_depth() -> u32 {
@@ -50,6 +64,8 @@ fn default_follow_depth() -> u32 {
 
 pub struct AnalyzeClient {
     info: InitializeResult,
+    index_dir: PathBuf,
+    indexes: Mutex<HashMap<PathBuf, CodeIndex>>,
 }
 
 impl AnalyzeClient {
@@ -61,11 +77,24 @@ impl AnalyzeClient {
             - Directory path → structure overview (file tree with function/class counts)
             - File path → semantic details (functions, classes, imports, call counts)
             - Any path + focus parameter → symbol call graph (incoming/outgoing chains)
+            - Any path + focus + mode find_definition/find_references → where a symbol is
+              defined or called, answered quickly from the cached index
+
+            Directory results are cached on disk and only changed files are reparsed.
 
             For large codebases, delegate analysis to a subagent and retain only the summary.
         "});
 
-        Ok(Self { info })
+        Ok(Self {
+            info,
+            index_dir: Paths::in_state_dir("analyze_index"),
+            indexes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn with_index_dir(mut self, index_dir: PathBuf) -> Self {
+        self.index_dir = index_dir;
+        self
     }
 
     fn schema<T: JsonSchema>() -> JsonObject {
@@ -105,7 +134,14 @@ impl AnalyzeClient {
             .with_priority(0.0)]);
         }
 
-        if let Some(ref focus) = params.focus {
+        if let (Some(ref focus), Some(mode)) = (&params.focus, params.mode) {
+            self.lookup_mode(&path, focus, mode, params.max_depth, params.force)
+        } else if params.mode.is_some() {
+            CallToolResult::error(vec![Content::text(
+                "Error: mode requires a focus symbol to look up",
+            )
+            .with_priority(0.0)])
+        } else if let Some(ref focus) = params.focus {
             self.focused_mode(
                 &path,
                 focus,
@@ -139,16 +175,28 @@ impl AnalyzeClient {
             .collect()
     }
 
+    /// Runs `f` against the cached index for `root`, loading it from disk on first use and
+    /// saving it afterwards if it changed.
+    fn with_index<R>(&self, root: &Path, f: impl FnOnce(&mut CodeIndex) -> R) -> R {
+        let mut indexes = self.indexes.lock().unwrap();
+        let index = indexes
+            .entry(root.to_path_buf())
+            .or_insert_with(|| CodeIndex::load(&self.index_dir, root));
+        let result = f(index);
+        if let Err(e) = index.save(&self.index_dir) {
+            tracing::warn!("Failed to save analyze index for {}: {}", root.display(), e);
+        }
+        result
+    }
+
     fn structure_mode(&self, dir: &Path, max_depth: u32, force: bool) -> CallToolResult {
         let files = Self::collect_files(dir, max_depth);
         let total_files = files.len();
 
-        let analyses: Vec<FileAnalysis> = files
-            .par_iter()
-            .filter_map(|f| Self::analyze_file(f))
-            .collect();
-
-        let output = format::format_structure(&analyses, dir, max_depth, total_files);
+        let output = self.with_index(dir, |index| {
+            let analyses = index.refresh(&files);
+            format::format_structure(&analyses, dir, max_depth, total_files)
+        });
         Self::finish(output, force)
     }
 
@@ -175,24 +223,44 @@ impl AnalyzeClient {
         max_depth: u32,
         force: bool,
     ) -> CallToolResult {
-        let files = if path.is_file() {
-            vec![path.to_path_buf()]
+        let output = if path.is_file() {
+            let root = path.parent().unwrap_or(path);
+            let analyses: Vec<FileAnalysis> = Self::analyze_file(path).into_iter().collect();
+            let g = graph::CallGraph::build(&analyses.iter().collect::<Vec<_>>());
+            format::format_focused(symbol, &g, follow_depth, analyses.len(), root)
         } else {
-            Self::collect_files(path, max_depth)
+            let files = Self::collect_files(path, max_depth);
+            self.with_index(path, |index| {
+                let analyzed = index.refresh(&files).len();
+                let g = index.call_graph(&files);
+                format::format_focused(symbol, g, follow_depth, analyzed, path)
+            })
         };
+        Self::finish(output, force)
+    }
 
-        let analyses: Vec<FileAnalysis> = files
-            .par_iter()
-            .filter_map(|f| Self::analyze_file(f))
-            .collect();
-
-        let root = if path.is_file() {
-            path.parent().unwrap_or(path)
+    fn lookup_mode(
+        &self,
+        path: &Path,
+        symbol: &str,
+        mode: LookupMode,
+        max_depth: u32,
+        force: bool,
+    ) -> CallToolResult {
+        let lookup = |analyses: &[&FileAnalysis], root: &Path| match mode {
+            LookupMode::FindDefinition => format::format_definitions(symbol, analyses, root),
+            LookupMode::FindReferences => format::format_references(symbol, analyses, root),
+        };
+        let output = if path.is_file() {
+            let analyses: Vec<FileAnalysis> = Self::analyze_file(path).into_iter().collect();
+            lookup(
+                &analyses.iter().collect::<Vec<_>>(),
+                path.parent().unwrap_or(path),
+            )
         } else {
-            path
+            let files = Self::collect_files(path, max_depth);
+            self.with_index(path, |index| lookup(&index.refresh(&files), path))
         };
-        let g = graph::CallGraph::build(&analyses);
-        let output = format::format_focused(symbol, &g, follow_depth, analyses.len(), root);
         Self::finish(output, force)
     }
 
@@ -214,7 +282,7 @@ impl McpClientTrait for AnalyzeClient {
     ) -> Result<ListToolsResult, Error> {
         let tool = Tool::new(
             "analyze".to_string(),
-            "Analyze code structure in 3 modes: 1) Directory overview - file tree with LOC/function/class counts to max_depth. 2) File details - functions, classes, imports. 3) Symbol focus - call graphs across directory to max_depth (requires file or directory path, case-sensitive). Typical flow: directory → files → symbols. Functions called >3x show •N.".to_string(),
+            "Analyze code structure in 3 modes: 1) Directory overview - file tree with LOC/function/class counts to max_depth. 2) File details - functions, classes, imports. 3) Symbol focus - call graphs across directory to max_depth (requires file or directory path, case-sensitive); add mode find_definition or find_references to just list where the symbol is defined or called. Typical flow: directory → files → symbols. Functions called >3x show •N.".to_string(),
             Self::schema::<AnalyzeParams>(),
         )
         .annotate(ToolAnnotations::from_raw(
@@ -278,6 +346,8 @@ mod tests {
             extension_manager: None,
             session_manager: Arc::new(SessionManager::new(std::env::temp_dir())),
             session: None,
+            container: None,
+            client_delegate: None,
         }
     }
 
@@ -302,7 +372,10 @@ mod tests {
         )
         .unwrap();
 
-        let client = AnalyzeClient::new(ctx()).unwrap();
+        let index_dir = tempdir().unwrap();
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
         let result = client.analyze(
             AnalyzeParams {
                 path: tmp.path().to_str().unwrap().into(),
@@ -310,6 +383,7 @@ mod tests {
                 max_depth: 3,
                 follow_depth: 2,
                 force: false,
+                mode: None,
             },
             tmp.path().to_path_buf(),
         );
@@ -348,7 +422,10 @@ fn helper() { validate(0); }
         )
         .unwrap();
 
-        let client = AnalyzeClient::new(ctx()).unwrap();
+        let index_dir = tempdir().unwrap();
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
         let result = client.analyze(
             AnalyzeParams {
                 path: file.to_str().unwrap().into(),
@@ -356,6 +433,7 @@ fn helper() { validate(0); }
                 max_depth: 3,
                 follow_depth: 2,
                 force: false,
+                mode: None,
             },
             file.clone(),
         );
@@ -382,7 +460,10 @@ fn helper() { validate(0); }
         fs::write(tmp.path().join("a.rs"), "fn process() { validate(1); }\n").unwrap();
         fs::write(tmp.path().join("b.rs"), "fn validate() { process(); }\n").unwrap();
 
-        let client = AnalyzeClient::new(ctx()).unwrap();
+        let index_dir = tempdir().unwrap();
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
         let result = client.analyze(
             AnalyzeParams {
                 path: tmp.path().to_str().unwrap().into(),
@@ -390,6 +471,7 @@ fn helper() { validate(0); }
                 max_depth: 3,
                 follow_depth: 2,
                 force: false,
+                mode: None,
             },
             tmp.path().to_path_buf(),
         );
@@ -401,9 +483,56 @@ fn helper() { validate(0); }
         assert!(out.contains("files analyzed"));
     }
 
+    #[tokio::test]
+    async fn lookup_modes_use_persisted_index() {
+        let tmp = tempdir().unwrap();
+        let index_dir = tempdir().unwrap();
+        fs::write(
+            tmp.path().join("a.rs"),
+            "fn process() {\n    validate(1);\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            tmp.path().join("b.rs"),
+            "fn validate(x: i32) -> bool { x > 0 }\nfn check() { validate(2); }\n",
+        )
+        .unwrap();
+        let params = |mode| AnalyzeParams {
+            path: tmp.path().to_str().unwrap().into(),
+            focus: Some("validate".into()),
+            max_depth: 3,
+            follow_depth: 2,
+            force: false,
+            mode: Some(mode),
+        };
+
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
+        let result = client.analyze(params(LookupMode::FindDefinition), tmp.path().to_path_buf());
+        let out = text(&result);
+        assert!(out.contains("DEFINITIONS: validate (1)"));
+        assert!(out.contains("b.rs:1 validate(x: i32)"));
+
+        // A fresh client picks up the index written by the first one
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
+        let result = client.analyze(params(LookupMode::FindReferences), tmp.path().to_path_buf());
+        let out = text(&result);
+        assert!(out.contains("REFERENCES: validate (2 in 2 files)"));
+        assert!(out.contains("a.rs:2 in process"));
+        assert!(out.contains("b.rs:2 in check"));
+        let stats = client.with_index(tmp.path(), |index| index.last_refresh());
+        assert_eq!((stats.parsed, stats.reused), (0, 2));
+    }
+
     #[tokio::test]
     async fn error_and_edge() {
-        let client = AnalyzeClient::new(ctx()).unwrap();
+        let index_dir = tempdir().unwrap();
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
 
         // Nonexistent path
         let result = client.analyze(
@@ -413,6 +542,7 @@ fn helper() { validate(0); }
                 max_depth: 3,
                 follow_depth: 2,
                 force: false,
+                mode: None,
             },
             PathBuf::from("/no/such/path"),
         );
@@ -428,6 +558,7 @@ fn helper() { validate(0); }
                 max_depth: 3,
                 follow_depth: 2,
                 force: false,
+                mode: None,
             },
             tmp.path().to_path_buf(),
         );
@@ -438,4 +569,216 @@ fn helper() { validate(0); }
         assert!(format::check_size(&big, false).is_err());
         assert!(format::check_size(&big, true).is_ok());
     }
+
+    /// Writes `files` to a temp dir and returns the structure, semantic (for the first
+    /// file) and focused outputs.
+    fn analyze_fixture(files: &[(&str, &str)], focus: &str) -> (String, String, String) {
+        let tmp = tempdir().unwrap();
+        let index_dir = tempdir().unwrap();
+        for (name, source) in files {
+            fs::write(tmp.path().join(name), source).unwrap();
+        }
+        let client = AnalyzeClient::new(ctx())
+            .unwrap()
+            .with_index_dir(index_dir.path().to_path_buf());
+        let run = |path: PathBuf, focus: Option<&str>| {
+            let result = client.analyze(
+                AnalyzeParams {
+                    path: path.to_str().unwrap().into(),
+                    focus: focus.map(Into::into),
+                    max_depth: 3,
+                    follow_depth: 2,
+                    force: false,
+                    mode: None,
+                },
+                path,
+            );
+            text(&result).to_string()
+        };
+        (
+            run(tmp.path().to_path_buf(), None),
+            run(tmp.path().join(files[0].0), None),
+            run(tmp.path().to_path_buf(), Some(focus)),
+        )
+    }
+
+    #[tokio::test]
+    async fn c_fixture() {
+        let (structure, semantic, focused) = analyze_fixture(
+            &[
+                (
+                    "geometry.c",
+                    r#"#include <math.h>
+#include "geometry.h"
+
+struct point { int x; int y; };
+
+static double square(double v) { return v * v; }
+
+double distance(struct point *a, struct point *b) {
+    return sqrt(square(a->x - b->x) + square(a->y - b->y));
+}
+"#,
+                ),
+                (
+                    "main.c",
+                    "#include <stdio.h>\n\nint main(void) {\n    printf(\"%f\\n\", distance(0, 0));\n    return 0;\n}\n",
+                ),
+            ],
+            "distance",
+        );
+
+        assert!(structure.contains("2 files"));
+        assert!(structure.contains("c 100%"));
+        assert!(semantic.contains("point:4{x,y}"));
+        assert!(semantic.contains("square(double v):6"));
+        assert!(semantic.contains("I: math.h; geometry.h"));
+        assert!(focused.contains("geometry.c:distance:8 → main.c:main:3"));
+        assert!(focused.contains("geometry.c:distance:8 → geometry.c:square:6"));
+    }
+
+    #[tokio::test]
+    async fn cpp_fixture() {
+        let (structure, semantic, focused) = analyze_fixture(
+            &[
+                (
+                    "geo.cpp",
+                    r#"#include <vector>
+namespace geo {
+class Shape : public Base {
+public:
+    int width;
+    double area() const { return scale(width); }
+};
+double scale(int v) { return v * 2.0; }
+}
+double geo::Shape::perimeter(int side) { return side * 4.0; }
+"#,
+                ),
+                (
+                    "audio.cpp",
+                    "namespace audio {\ndouble scale(int v) { return v / 2.0; }\n}\n",
+                ),
+                (
+                    "main.cpp",
+                    "#include \"geo.h\"\nint main() {\n    geo::scale(3);\n    return 0;\n}\n",
+                ),
+            ],
+            "scale",
+        );
+
+        assert!(structure.contains("3 files"));
+        assert!(structure.contains("cpp 100%"));
+        assert!(semantic.contains("Shape:3(Base) {width}"));
+        assert!(semantic.contains("Shape.area:6"));
+        assert!(semantic.contains("geo.scale(int v):8"));
+        assert!(semantic.contains("Shape.perimeter(int side):10"));
+        assert!(semantic.contains("I: vector"));
+        // geo::scale resolves to the namespace it names, not audio::scale
+        assert!(
+            focused.contains("IN:\n  geo.cpp:scale:8\n    → geo.cpp:area:6\n    → main.cpp:main:2")
+        );
+        assert!(!focused.contains("audio.cpp:scale:2 →"));
+    }
+
+    #[tokio::test]
+    async fn csharp_fixture() {
+        let (structure, semantic, focused) = analyze_fixture(
+            &[
+                (
+                    "Program.cs",
+                    r#"using System.Text;
+namespace App {
+public class Program : Base {
+    public static void Main(string[] args) {
+        Helper.Compute(1);
+        var widget = new Widget();
+    }
+}
+}
+"#,
+                ),
+                (
+                    "Helper.cs",
+                    "namespace App {\npublic static class Helper {\n    public static int Compute(int x) { return x * 2; }\n}\n}\n",
+                ),
+                (
+                    "Other.cs",
+                    "namespace App {\npublic class Other {\n    public int Compute(int x) { return x + 1; }\n}\n}\n",
+                ),
+            ],
+            "Compute",
+        );
+
+        assert!(structure.contains("3 files"));
+        assert!(structure.contains("csharp 100%"));
+        assert!(semantic.contains("Program:3(Base)"));
+        assert!(semantic.contains("Program.Main(string[] args):4"));
+        assert!(semantic.contains("I: System.Text"));
+        // Helper.Compute resolves to the Helper class only
+        assert!(focused.contains("Helper.cs:Compute:3 → Program.cs:Main:4"));
+        assert!(!focused.contains("Other.cs:Compute:3 →"));
+    }
+
+    #[tokio::test]
+    async fn php_fixture() {
+        let (structure, semantic, focused) = analyze_fixture(
+            &[
+                (
+                    "Service.php",
+                    r#"<?php
+namespace App;
+use App\Models\User;
+class Service extends Base {
+    public function run(int $a): int {
+        return helper($a);
+    }
+}
+"#,
+                ),
+                (
+                    "helpers.php",
+                    "<?php\nfunction helper($x) { return strlen($x); }\n",
+                ),
+            ],
+            "helper",
+        );
+
+        assert!(structure.contains("2 files"));
+        assert!(structure.contains("php 100%"));
+        assert!(semantic.contains("Service:4(Base)"));
+        assert!(semantic.contains("Service.run(int $a):5"));
+        assert!(semantic.contains(r"I: App\Models\User"));
+        assert!(focused.contains("helpers.php:helper:2 → Service.php:run:5"));
+    }
+
+    #[tokio::test]
+    async fn scala_fixture() {
+        let (structure, semantic, focused) = analyze_fixture(
+            &[
+                (
+                    "Main.scala",
+                    r#"import scala.collection.mutable
+object Main extends App {
+  def run(x: Int): Int = Util.compute(x)
+}
+trait Shape
+"#,
+                ),
+                (
+                    "Util.scala",
+                    "object Util {\n  def compute(x: Int): Int = x * 2\n}\n",
+                ),
+            ],
+            "compute",
+        );
+
+        assert!(structure.contains("2 files"));
+        assert!(structure.contains("scala 100%"));
+        assert!(semantic.contains("Main:2(App)"));
+        assert!(semantic.contains("Shape:5"));
+        assert!(semantic.contains("Main.run(x: Int):3"));
+        assert!(semantic.contains("I: scala.collection.mutable"));
+        assert!(focused.contains("Util.scala:compute:2 → Main.scala:run:3"));
+    }
 }
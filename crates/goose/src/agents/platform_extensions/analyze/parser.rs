@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tree_sitter::{Language, Parser as TsParser, Query, QueryCursor, StreamingIterator};
 
@@ -5,9 +6,10 @@ use super::languages::{lang_for_ext, LangInfo};
 
 // ── Types ──────────────────────────────────────────────────────────────
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FileAnalysis {
     pub path: PathBuf,
-    pub language: &'static str,
+    pub language: String,
     pub loc: usize,
     pub functions: Vec<Symbol>,
     pub classes: Vec<Symbol>,
@@ -15,6 +17,7 @@ pub struct FileAnalysis {
     pub calls: Vec<Call>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Symbol {
     pub name: String,
     pub line: usize,
@@ -22,17 +25,44 @@ pub struct Symbol {
     pub detail: Option<String>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Import {
     pub module: String,
     pub count: usize,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Call {
     pub caller: String,
     pub callee: String,
     pub line: usize,
 }
 
+impl Call {
+    /// The called name without any `Type::`, `namespace::` or `object.` qualifier.
+    pub fn bare_callee(&self) -> &str {
+        split_qualified(&self.callee).1
+    }
+
+    /// The segment right before the called name, e.g. `Helper` for `Helper.Compute` or
+    /// `geo` for `app::geo::area`.
+    pub fn qualifier(&self) -> Option<&str> {
+        split_qualified(&self.callee).0
+    }
+}
+
+fn split_qualified(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once("::").or_else(|| name.rsplit_once('.')) {
+        Some((scope, bare)) => {
+            let last = scope.rsplit([':', '.']).next().unwrap_or(scope);
+            // Drop template arguments from `Box<T>::make`
+            let last = last.split('<').next().unwrap_or(last);
+            (Some(last).filter(|s| !s.is_empty()), bare)
+        }
+        None => (None, name),
+    }
+}
+
 // ── Parser ─────────────────────────────────────────────────────────────
 
 #[derive(Default)]
@@ -61,7 +91,7 @@ impl Parser {
 
         Some(FileAnalysis {
             path: path.to_path_buf(),
-            language: info.name,
+            language: info.name.to_string(),
             loc,
             functions,
             classes,
@@ -89,12 +119,15 @@ fn extract_functions(
     while let Some(m) = matches.next() {
         for cap in m.captures {
             if query.capture_names()[cap.index as usize] == "name" {
-                let name = node_text(source, &cap.node).to_string();
+                // C++ out-of-line definitions (`double Shape::area()`) name their class
+                let (scope, name) = split_qualified(node_text(source, &cap.node));
                 let line = cap.node.start_position().row + 1;
-                let parent = find_enclosing_class(cap.node, source, info);
+                let parent = scope
+                    .map(str::to_string)
+                    .or_else(|| find_enclosing_class(cap.node, source, info));
                 let detail = extract_fn_signature(cap.node, source);
                 symbols.push(Symbol {
-                    name,
+                    name: name.to_string(),
                     line,
                     parent,
                     detail,
@@ -329,6 +362,52 @@ fn extract_inheritance(lang_name: &str, class_node: &tree_sitter::Node, source:
             String::new()
         }
 
+        // C++: class Foo : public Bar { ... }
+        // class_specifier → base_class_clause → type_identifier | qualified_identifier
+        "cpp" => {
+            if let Some(bases) = find_child_by_kind(class_node, "base_class_clause") {
+                if let Some(ti) = find_child_by_kind(&bases, "type_identifier")
+                    .or_else(|| find_child_by_kind(&bases, "qualified_identifier"))
+                {
+                    return node_text(source, &ti).to_string();
+                }
+            }
+            String::new()
+        }
+
+        // C#: class Foo : Bar, IBaz { ... }
+        // class_declaration → base_list → identifier | qualified_name | generic_name
+        "csharp" => {
+            if let Some(bases) = find_child_by_kind(class_node, "base_list") {
+                if let Some(first) = bases.named_child(0) {
+                    return node_text(source, &first).to_string();
+                }
+            }
+            String::new()
+        }
+
+        // PHP: class Foo extends Bar implements Baz { ... }
+        // class_declaration → base_clause → name | qualified_name
+        "php" => {
+            if let Some(base) = find_child_by_kind(class_node, "base_clause") {
+                if let Some(first) = base.named_child(0) {
+                    return node_text(source, &first).to_string();
+                }
+            }
+            String::new()
+        }
+
+        // Scala: class Foo extends Bar with Baz { ... }
+        // class_definition → extends_clause → type_identifier | generic_type
+        "scala" => {
+            if let Some(extends) = find_child_by_kind(class_node, "extends_clause") {
+                if let Some(first) = extends.named_child(0) {
+                    return node_text(source, &first).to_string();
+                }
+            }
+            String::new()
+        }
+
         // Rust: impl Display for MyType → "MyType(impl Display)"
         // impl_item with "for" keyword: trait is the first type_identifier, type is after "for"
         "rust" => {
@@ -424,6 +503,8 @@ fn find_enclosing_class(node: tree_sitter::Node, source: &str, info: &LangInfo)
                 "type_identifier",
                 "constant",
                 "simple_identifier",
+                "namespace_identifier",
+                "name",
             ];
             for kind in name_kinds {
                 if let Some(n) = find_child_by_kind(&parent, kind) {
@@ -527,6 +608,7 @@ fn extract_class_detail(
             &["field_declaration"],
         ),
         "java" | "kotlin" => (&["class_body"], &["field_declaration"]),
+        "c" | "cpp" => (&["field_declaration_list"], &["field_declaration"]),
         _ => return None, // Skip Python (hard), JS/TS/Ruby/Swift for now
     };
 
@@ -637,6 +719,7 @@ fn extract_imports(
 fn normalize_import(s: &str) -> String {
     let s = s
         .trim_start_matches("use ")
+        .trim_start_matches("using ")
         .trim_start_matches("import ")
         .trim_start_matches("from ")
         .trim_start_matches("require_relative ")
@@ -644,7 +727,10 @@ fn normalize_import(s: &str) -> String {
         .trim_start_matches("load ")
         .trim_end_matches(';')
         .trim()
-        .trim_matches(|c| c == '\'' || c == '"');
+        .trim_matches(|c| c == '\'' || c == '"')
+        // C/C++: #include <vector>
+        .trim_start_matches('<')
+        .trim_end_matches('>');
     // Handle Python "from X import Y" → keep just "X"
     let s = s.split(" import ").next().unwrap_or(s);
     // JS/TS: "React from 'react'" or "{ useState } from 'react'" → "react"
@@ -720,6 +806,10 @@ fn find_enclosing_fn(node: tree_sitter::Node, source: &str, info: &LangInfo) ->
                     continue; // skip — not a function declarator
                 }
             }
+            // C/C++ keep the name inside the declarator chain
+            if let Some(name) = declarator_name(&parent, source) {
+                return Some(name);
+            }
             // If this is an anonymous function-like node (closure, async block, arrow
             // function with no name), keep walking up to find the enclosing named function.
             if let Some(name) = find_child_text(&parent, info.fn_name_kinds, source) {
@@ -731,6 +821,25 @@ fn find_enclosing_fn(node: tree_sitter::Node, source: &str, info: &LangInfo) ->
     None
 }
 
+/// Name of a C/C++ function definition, found by following its declarator through
+/// pointer, reference and function declarators (`int *Shape::make()` → `make`).
+fn declarator_name(fn_node: &tree_sitter::Node, source: &str) -> Option<String> {
+    let mut declarator = fn_node.child_by_field_name("declarator")?;
+    while matches!(
+        declarator.kind(),
+        "function_declarator" | "pointer_declarator" | "reference_declarator"
+    ) {
+        declarator = declarator
+            .child_by_field_name("declarator")
+            .or_else(|| declarator.named_child(0))?;
+    }
+    Some(
+        split_qualified(node_text(source, &declarator))
+            .1
+            .to_string(),
+    )
+}
+
 fn find_child_text(node: &tree_sitter::Node, kinds: &[&str], source: &str) -> Option<String> {
     (0..node.child_count() as u32)
         .filter_map(|i| node.child(i))
@@ -1,10 +1,11 @@
 use crate::agents::extension::PlatformExtensionContext;
 use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::session::ChatHistorySearchOptions;
 use anyhow::Result;
 use async_trait::async_trait;
 use indoc::indoc;
 use rmcp::model::{
-    CallToolResult, Content, Implementation, InitializeResult, JsonObject, ListToolsResult,
+    CallToolResult, Content, Implementation, InitializeResult, JsonObject, ListToolsResult, Role,
     ServerCapabilities, Tool, ToolAnnotations,
 };
 use schemars::{schema_for, JsonSchema};
@@ -15,7 +16,7 @@ pub static EXTENSION_NAME: &str = "chatrecall";
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct ChatRecallParams {
-    /// Search keywords. Use multiple related terms/synonyms (e.g., 'database postgres sql'). Mutually exclusive with session_id.
+    /// Search keywords. Use multiple related terms/synonyms (e.g., 'database postgres sql'). Supports "exact phrases", prefix* matches and -excluded terms. Mutually exclusive with session_id.
     #[serde(skip_serializing_if = "Option::is_none")]
     query: Option<String>,
     /// Session ID to load. Returns first/last 3 messages. Mutually exclusive with query.
@@ -30,6 +31,12 @@ struct ChatRecallParams {
     /// ISO 8601 date (e.g., '2025-10-15T23:59:59Z'). Search mode only.
     #[serde(skip_serializing_if = "Option::is_none")]
     before_date: Option<String>,
+    /// Only match messages from this role: 'user' or 'assistant'. Search mode only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    /// Only match sessions started in this working directory. Search mode only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_dir: Option<String>,
 }
 
 pub struct ChatRecallClient {
@@ -50,7 +57,7 @@ impl ChatRecallClient {
                 Search past conversations and load session summaries when the user expects some memory or context.
 
                 Two modes:
-                - Search mode: Use query with keywords/synonyms to find relevant messages
+                - Search mode: Use query with keywords/synonyms to find relevant messages, best matches first
                 - Load mode: Use session_id to get first and last messages of a specific session
             "#}.to_string());
 
@@ -165,18 +172,38 @@ impl ChatRecallClient {
                 .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
                 .map(|dt| dt.with_timezone(&chrono::Utc));
 
-            let exclude_session_id = Some(current_session_id.to_string());
+            let role = match arguments.get("role").and_then(|v| v.as_str()) {
+                None => None,
+                Some("user") => Some(Role::User),
+                Some("assistant") => Some(Role::Assistant),
+                Some(other) => {
+                    return Err(format!(
+                        "Invalid role '{}': expected 'user' or 'assistant'",
+                        other
+                    ))
+                }
+            };
+
+            let working_dir = arguments
+                .get("working_dir")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            let options = ChatHistorySearchOptions {
+                limit: Some(limit),
+                after_date,
+                before_date,
+                exclude_session_id: Some(current_session_id.to_string()),
+                role,
+                working_dir,
+                session_types: vec![],
+                owner_token_id: None,
+            };
 
             match self
                 .context
                 .session_manager
-                .search_chat_history(
-                    &query,
-                    Some(limit),
-                    after_date,
-                    before_date,
-                    exclude_session_id,
-                )
+                .search_chat_history(&query, options)
                 .await
             {
                 Ok(results) => {
@@ -208,7 +235,7 @@ impl ChatRecallClient {
                                     msg_idx + 1,
                                     message.role,
                                     message
-                                        .content
+                                        .snippet
                                         .lines()
                                         .map(|line| format!("   {}", line))
                                         .collect::<Vec<_>>()
@@ -240,7 +267,7 @@ impl ChatRecallClient {
             indoc! {r#"
                 Search past chat or load session summaries. Use when it is clear user expects some memory or context.
 
-                search mode (query): Use multiple keywords/synonyms. Returns messages grouped by session, ordered by recency. Supports date filters.
+                search mode (query): Use multiple keywords/synonyms, "exact phrases", prefix* or -excluded terms. Returns highlighted excerpts grouped by session, most relevant first. Supports date, role and working_dir filters.
                 load mode (session_id): Returns first/last 3 messages of a session.
             "#}
             .to_string(),
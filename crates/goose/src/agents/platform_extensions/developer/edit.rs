@@ -1,6 +1,13 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use super::patch::{apply_hunks, parse_patch, FilePatch, HunkOutcome};
+use crate::agents::{ClientDelegate, Container};
+use crate::session::file_checkpoints::{
+    apply_file_changes, file_mode, FileChange, MAX_CHECKPOINT_BYTES,
+};
+use crate::session::{FileCheckpoint, FileSnapshot};
 use rmcp::model::{CallToolResult, Content};
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -20,6 +27,33 @@ pub struct FileEditParams {
     pub after: String,
 }
 
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EditOperation {
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MultiEditParams {
+    pub path: String,
+    /// Replacements applied in order, each to the result of the previous one. Every before
+    /// text must match exactly and uniquely; if any does not, the file is left untouched.
+    pub edits: Vec<EditOperation>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApplyPatchParams {
+    /// A unified diff such as `git diff` produces. It may change, create or delete several files.
+    pub patch: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UndoParams {
+    /// Only undo the most recent change to this file. Defaults to the most recent change to any file.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
 pub struct EditTools;
 
 impl EditTools {
@@ -93,63 +127,468 @@ impl EditTools {
             }
         };
 
-        let matches: Vec<_> = content.match_indices(&params.before).collect();
+        let new_content = match replace_unique(&content, &params.before, &params.after) {
+            Ok(new_content) => new_content,
+            Err(msg) => return CallToolResult::error(vec![Content::text(msg).with_priority(0.0)]),
+        };
 
-        match matches.len() {
-            0 => {
-                let suggestion = find_similar_context(&content, &params.before);
-                let mut msg = "No match found for the specified text.".to_string();
-                if let Some(hint) = suggestion {
-                    msg.push_str(&format!("\n\nDid you mean:\n```\n{}\n```", hint));
-                }
-                let preview = build_file_preview(&content, NO_MATCH_PREVIEW_LINES);
-                msg.push_str(&format!("\n\nFile preview:\n```\n{}\n```", preview));
-                CallToolResult::error(vec![Content::text(msg).with_priority(0.0)])
+        match fs::write(&path, &new_content) {
+            Ok(()) => {
+                let old_lines = params.before.lines().count();
+                let new_lines = params.after.lines().count();
+                CallToolResult::success(vec![Content::text(format!(
+                    "Edited {} ({} lines -> {} lines)",
+                    params.path, old_lines, new_lines
+                ))
+                .with_priority(0.0)])
             }
-            1 => {
-                let new_content = content.replacen(&params.before, &params.after, 1);
-
-                match fs::write(&path, &new_content) {
-                    Ok(()) => {
-                        let old_lines = params.before.lines().count();
-                        let new_lines = params.after.lines().count();
-                        CallToolResult::success(vec![Content::text(format!(
-                            "Edited {} ({} lines -> {} lines)",
-                            params.path, old_lines, new_lines
-                        ))
-                        .with_priority(0.0)])
-                    }
-                    Err(error) => CallToolResult::error(vec![Content::text(format!(
-                        "Failed to write {}: {}",
-                        params.path, error
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+
+    pub fn multi_edit_with_cwd(
+        &self,
+        params: MultiEditParams,
+        working_dir: Option<&Path>,
+    ) -> CallToolResult {
+        if params.edits.is_empty() {
+            return CallToolResult::error(vec![
+                Content::text("No edits provided.").with_priority(0.0)
+            ]);
+        }
+
+        let path = resolve_path(&params.path, working_dir);
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(error) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read {}: {}",
+                    params.path, error
+                ))
+                .with_priority(0.0)]);
+            }
+        };
+
+        let content = match apply_edits(&params, content) {
+            Ok(content) => content,
+            Err(result) => return result,
+        };
+
+        match fs::write(&path, &content) {
+            Ok(()) => CallToolResult::success(vec![Content::text(format!(
+                "Edited {} ({} edits applied)",
+                params.path,
+                params.edits.len()
+            ))
+            .with_priority(0.0)]),
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+
+    /// Applies a unified diff. Every file is patched in memory first and nothing is written
+    /// unless all hunks of all files could be placed; the writes then succeed or fail together.
+    pub fn apply_patch_with_cwd(
+        &self,
+        params: ApplyPatchParams,
+        working_dir: Option<&Path>,
+    ) -> CallToolResult {
+        let files = match parse_patch_params(&params) {
+            Ok(files) => files,
+            Err(result) => return result,
+        };
+        let read = |path: &Path| fs::read_to_string(path).map_err(|error| error.to_string());
+        let (changes, report) = match plan_patch(&files, working_dir, &read) {
+            Ok(planned) => planned,
+            Err(result) => return result,
+        };
+
+        if let Err(error) = apply_file_changes(&changes) {
+            return patch_not_applied(&format!("{:#}", error), &report);
+        }
+        patch_applied(files.len(), &report)
+    }
+
+    /// Captures a file before a tool changes it so the change can be undone.
+    pub fn snapshot(path: &str, working_dir: Option<&Path>) -> Option<(PathBuf, FileSnapshot)> {
+        let path = resolve_path(path, working_dir);
+        FileSnapshot::capture(&path).map(|snapshot| (path, snapshot))
+    }
+
+    /// Captures every file a patch touches. Unparseable patches are rejected later anyway.
+    pub fn patch_snapshots(
+        &self,
+        params: &ApplyPatchParams,
+        working_dir: Option<&Path>,
+    ) -> Vec<(PathBuf, FileSnapshot)> {
+        parse_patch(&params.patch)
+            .map(|files| {
+                files
+                    .iter()
+                    .flat_map(touched_paths)
+                    .filter_map(|path| Self::snapshot(path, working_dir))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Every file a patch would change. Unparseable patches are rejected later anyway.
+    pub fn patch_paths(&self, params: &ApplyPatchParams) -> Vec<String> {
+        parse_patch(&params.patch)
+            .map(|files| {
+                files
+                    .iter()
+                    .flat_map(touched_paths)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn resolve_undo_path(
+        &self,
+        params: &UndoParams,
+        working_dir: Option<&Path>,
+    ) -> Option<PathBuf> {
+        params
+            .path
+            .as_deref()
+            .map(|path| resolve_path(path, working_dir))
+    }
+
+    pub fn undo_result(checkpoint: Option<FileCheckpoint>) -> CallToolResult {
+        let text = match checkpoint {
+            Some(checkpoint) if checkpoint.existed => format!(
+                "Restored {} to its content before the last {}",
+                checkpoint.path, checkpoint.tool_name
+            ),
+            Some(checkpoint) => format!(
+                "Removed {}, which was created by {}",
+                checkpoint.path, checkpoint.tool_name
+            ),
+            None => "Nothing to undo".to_string(),
+        };
+        CallToolResult::success(vec![Content::text(text).with_priority(0.0)])
+    }
+}
+
+impl EditTools {
+    /// Writes a file that only exists inside the container.
+    pub async fn file_write_in_container(
+        &self,
+        params: FileWriteParams,
+        container: &Container,
+        path: &Path,
+    ) -> CallToolResult {
+        match container.write_file(path, &params.content).await {
+            Ok(()) => CallToolResult::success(vec![Content::text(format!(
+                "Wrote {} ({} lines)",
+                params.path,
+                params.content.lines().count()
+            ))
+            .with_priority(0.0)]),
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+
+    /// Edits a file that only exists inside the container.
+    pub async fn file_edit_in_container(
+        &self,
+        params: FileEditParams,
+        container: &Container,
+        path: &Path,
+    ) -> CallToolResult {
+        let content = match container.read_file(path).await {
+            Ok(content) => content,
+            Err(error) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read {}: {}",
+                    params.path, error
+                ))
+                .with_priority(0.0)]);
+            }
+        };
+
+        let new_content = match replace_unique(&content, &params.before, &params.after) {
+            Ok(new_content) => new_content,
+            Err(msg) => return CallToolResult::error(vec![Content::text(msg).with_priority(0.0)]),
+        };
+
+        match container.write_file(path, &new_content).await {
+            Ok(()) => CallToolResult::success(vec![Content::text(format!(
+                "Edited {} ({} lines -> {} lines)",
+                params.path,
+                params.before.lines().count(),
+                params.after.lines().count()
+            ))
+            .with_priority(0.0)]),
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+}
+
+impl EditTools {
+    /// Writes through the client, so an editor showing the file updates its buffer.
+    pub async fn file_write_with_client(
+        &self,
+        session_id: &str,
+        params: FileWriteParams,
+        working_dir: Option<&Path>,
+        client: &dyn ClientDelegate,
+    ) -> CallToolResult {
+        let path = resolve_path(&params.path, working_dir);
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                if let Err(error) = fs::create_dir_all(parent) {
+                    return CallToolResult::error(vec![Content::text(format!(
+                        "Failed to create directory {}: {}",
+                        parent.display(),
+                        error
                     ))
-                    .with_priority(0.0)]),
+                    .with_priority(0.0)]);
                 }
             }
-            n => {
-                let mut msg = format!(
-                    "Found {} matches. Please provide more context to identify a unique match:\n",
-                    n
-                );
-
-                for (i, (pos, _)) in matches.iter().enumerate().take(2) {
-                    let line_num = count_lines_before(&content, *pos);
-                    let context = get_line_context(&content, line_num, 1);
-                    msg.push_str(&format!(
-                        "\nMatch {} (line {}):\n```\n{}\n```",
-                        i + 1,
-                        line_num,
-                        context
-                    ));
-                }
+        }
+
+        let is_new = !path.exists();
+        match client
+            .write_text_file(session_id, &path, &params.content)
+            .await
+        {
+            Ok(()) => {
+                let action = if is_new { "Created" } else { "Wrote" };
+                CallToolResult::success(vec![Content::text(format!(
+                    "{} {} ({} lines)",
+                    action,
+                    params.path,
+                    params.content.lines().count()
+                ))
+                .with_priority(0.0)])
+            }
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+
+    /// Edits the client's copy of the file, which includes changes the user has not saved.
+    pub async fn file_edit_with_client(
+        &self,
+        session_id: &str,
+        params: FileEditParams,
+        working_dir: Option<&Path>,
+        client: &dyn ClientDelegate,
+    ) -> CallToolResult {
+        let path = resolve_path(&params.path, working_dir);
+
+        let content = match read_through_client(session_id, &path, client).await {
+            Ok(content) => content,
+            Err(error) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read {}: {}",
+                    params.path, error
+                ))
+                .with_priority(0.0)]);
+            }
+        };
+
+        let new_content = match replace_unique(&content, &params.before, &params.after) {
+            Ok(new_content) => new_content,
+            Err(msg) => return CallToolResult::error(vec![Content::text(msg).with_priority(0.0)]),
+        };
+
+        match client
+            .write_text_file(session_id, &path, &new_content)
+            .await
+        {
+            Ok(()) => CallToolResult::success(vec![Content::text(format!(
+                "Edited {} ({} lines -> {} lines)",
+                params.path,
+                params.before.lines().count(),
+                params.after.lines().count()
+            ))
+            .with_priority(0.0)]),
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+
+    /// Applies the edits to the client's copy of the file, which includes unsaved changes.
+    pub async fn multi_edit_with_client(
+        &self,
+        session_id: &str,
+        params: MultiEditParams,
+        working_dir: Option<&Path>,
+        client: &dyn ClientDelegate,
+    ) -> CallToolResult {
+        if params.edits.is_empty() {
+            return CallToolResult::error(vec![
+                Content::text("No edits provided.").with_priority(0.0)
+            ]);
+        }
+
+        let path = resolve_path(&params.path, working_dir);
+        let content = match read_through_client(session_id, &path, client).await {
+            Ok(content) => content,
+            Err(error) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to read {}: {}",
+                    params.path, error
+                ))
+                .with_priority(0.0)]);
+            }
+        };
+        let content = match apply_edits(&params, content) {
+            Ok(content) => content,
+            Err(result) => return result,
+        };
+
+        match client.write_text_file(session_id, &path, &content).await {
+            Ok(()) => CallToolResult::success(vec![Content::text(format!(
+                "Edited {} ({} edits applied)",
+                params.path,
+                params.edits.len()
+            ))
+            .with_priority(0.0)]),
+            Err(error) => CallToolResult::error(vec![Content::text(format!(
+                "Failed to write {}: {}",
+                params.path, error
+            ))
+            .with_priority(0.0)]),
+        }
+    }
+
+    /// Applies a unified diff to the client's copies of the files. The client cannot remove
+    /// files, so deletions happen on disk once every write went through; if anything fails,
+    /// the files already written are put back.
+    pub async fn apply_patch_with_client(
+        &self,
+        session_id: &str,
+        params: ApplyPatchParams,
+        working_dir: Option<&Path>,
+        client: &dyn ClientDelegate,
+    ) -> CallToolResult {
+        let files = match parse_patch_params(&params) {
+            Ok(files) => files,
+            Err(result) => return result,
+        };
+        let mut originals = HashMap::new();
+        for file in &files {
+            if let Some(old_path) = &file.old_path {
+                let path = resolve_path(old_path, working_dir);
+                let content = read_through_client(session_id, &path, client).await;
+                originals.insert(path, content);
+            }
+        }
+        let read = |path: &Path| {
+            originals
+                .get(path)
+                .cloned()
+                .unwrap_or_else(|| Err("file was not read".to_string()))
+        };
+        let (changes, report) = match plan_patch(&files, working_dir, &read) {
+            Ok(planned) => planned,
+            Err(result) => return result,
+        };
 
-                if n > 2 {
-                    msg.push_str(&format!("\n\n...and {} more", n - 2));
+        let (writes, removals): (Vec<_>, Vec<_>) = changes
+            .into_iter()
+            .partition(|change| change.content.is_some());
+        let mut written = Vec::new();
+        let mut failure = None;
+        for change in &writes {
+            let content = String::from_utf8_lossy(change.content.as_deref().unwrap_or_default());
+            let result = match change.path.parent() {
+                Some(parent) if !parent.exists() => {
+                    fs::create_dir_all(parent).map_err(anyhow::Error::from)
+                }
+                _ => Ok(()),
+            };
+            let result = match result {
+                Ok(()) => {
+                    client
+                        .write_text_file(session_id, &change.path, &content)
+                        .await
                 }
+                Err(error) => Err(error),
+            };
+            if let Err(error) = result {
+                failure = Some(format!(
+                    "Failed to write {}: {}",
+                    change.path.display(),
+                    error
+                ));
+                break;
+            }
+            written.push(&change.path);
+        }
+        if failure.is_none() {
+            if let Err(error) = apply_file_changes(&removals) {
+                failure = Some(format!("{:#}", error));
+            }
+        }
+
+        let Some(failure) = failure else {
+            return patch_applied(files.len(), &report);
+        };
+        for path in written.into_iter().rev() {
+            let restored = match originals.get(path) {
+                Some(Ok(original)) => client.write_text_file(session_id, path, original).await,
+                _ => fs::remove_file(path).map_err(anyhow::Error::from),
+            };
+            if let Err(error) = restored {
+                tracing::error!("Failed to roll back {}: {}", path.display(), error);
+            }
+        }
+        patch_not_applied(&failure, &report)
+    }
 
-                CallToolResult::error(vec![Content::text(msg).with_priority(0.0)])
+    /// Captures a file from the client's copy, which is what a change through the client
+    /// replaces, or from the disk when the client cannot read it.
+    pub async fn snapshot_with_client(
+        session_id: &str,
+        path: &str,
+        working_dir: Option<&Path>,
+        client: &dyn ClientDelegate,
+    ) -> Option<(PathBuf, FileSnapshot)> {
+        let path = resolve_path(path, working_dir);
+        if client.can_read_files() {
+            if let Ok(content) = client.read_text_file(session_id, &path).await {
+                if content.len() as u64 > MAX_CHECKPOINT_BYTES {
+                    return None;
+                }
+                let mode = fs::metadata(&path).ok().and_then(|m| file_mode(&m));
+                return Some((
+                    path,
+                    FileSnapshot::Content {
+                        content: content.into_bytes(),
+                        mode,
+                    },
+                ));
             }
         }
+        FileSnapshot::capture(&path).map(|snapshot| (path, snapshot))
     }
 }
 
@@ -159,7 +598,7 @@ impl Default for EditTools {
     }
 }
 
-fn resolve_path(path: &str, working_dir: Option<&Path>) -> PathBuf {
+pub(super) fn resolve_path(path: &str, working_dir: Option<&Path>) -> PathBuf {
     let path = PathBuf::from(path);
     if path.is_absolute() {
         path
@@ -172,6 +611,270 @@ fn resolve_path(path: &str, working_dir: Option<&Path>) -> PathBuf {
     }
 }
 
+/// Replaces the single occurrence of `before`, or explains why there is not exactly one.
+fn replace_unique(content: &str, before: &str, after: &str) -> Result<String, String> {
+    let matches: Vec<_> = content.match_indices(before).collect();
+
+    match matches.len() {
+        0 => {
+            let suggestion = find_similar_context(content, before);
+            let mut msg = "No match found for the specified text.".to_string();
+            if let Some(hint) = suggestion {
+                msg.push_str(&format!("\n\nDid you mean:\n```\n{}\n```", hint));
+            }
+            let preview = build_file_preview(content, NO_MATCH_PREVIEW_LINES);
+            msg.push_str(&format!("\n\nFile preview:\n```\n{}\n```", preview));
+            Err(msg)
+        }
+        1 => Ok(content.replacen(before, after, 1)),
+        n => {
+            let mut msg = format!(
+                "Found {} matches. Please provide more context to identify a unique match:\n",
+                n
+            );
+
+            for (i, (pos, _)) in matches.iter().enumerate().take(2) {
+                let line_num = count_lines_before(content, *pos);
+                let context = get_line_context(content, line_num, 1);
+                msg.push_str(&format!(
+                    "\nMatch {} (line {}):\n```\n{}\n```",
+                    i + 1,
+                    line_num,
+                    context
+                ));
+            }
+
+            if n > 2 {
+                msg.push_str(&format!("\n\n...and {} more", n - 2));
+            }
+
+            Err(msg)
+        }
+    }
+}
+
+/// Reads the client's copy of a file when it can, otherwise the file on disk.
+async fn read_through_client(
+    session_id: &str,
+    path: &Path,
+    client: &dyn ClientDelegate,
+) -> Result<String, String> {
+    if client.can_read_files() {
+        client
+            .read_text_file(session_id, path)
+            .await
+            .map_err(|error| error.to_string())
+    } else {
+        fs::read_to_string(path).map_err(|error| error.to_string())
+    }
+}
+
+/// Applies multi_edit's replacements in order, or explains which one failed.
+fn apply_edits(params: &MultiEditParams, mut content: String) -> Result<String, CallToolResult> {
+    let total = params.edits.len();
+    for (index, edit) in params.edits.iter().enumerate() {
+        content = replace_unique(&content, &edit.before, &edit.after).map_err(|msg| {
+            CallToolResult::error(vec![Content::text(format!(
+                "Edit {} of {} failed, so {} was not changed.\n\n{}",
+                index + 1,
+                total,
+                params.path,
+                msg
+            ))
+            .with_priority(0.0)])
+        })?;
+    }
+    Ok(content)
+}
+
+fn parse_patch_params(params: &ApplyPatchParams) -> Result<Vec<FilePatch>, CallToolResult> {
+    parse_patch(&params.patch).map_err(|error| {
+        CallToolResult::error(vec![Content::text(format!(
+            "Failed to parse patch: {}",
+            error
+        ))
+        .with_priority(0.0)])
+    })
+}
+
+/// Plans every file of a patch, reading current content through `read`. Returns the changes
+/// and the per-file report, or the failure when any hunk could not be placed.
+fn plan_patch(
+    files: &[FilePatch],
+    working_dir: Option<&Path>,
+    read: &dyn Fn(&Path) -> Result<String, String>,
+) -> Result<(Vec<FileChange>, Vec<String>), CallToolResult> {
+    let mut changes = Vec::new();
+    let mut report = Vec::new();
+    let mut failed = false;
+    for file in files {
+        let (planned, summary) = plan_file_patch(file, working_dir, read);
+        report.push(summary);
+        match planned {
+            Some(planned) => changes.extend(planned),
+            None => failed = true,
+        }
+    }
+    if failed {
+        return Err(CallToolResult::error(vec![Content::text(format!(
+            "Patch was not applied; no files were changed.\n\n{}",
+            report.join("\n")
+        ))
+        .with_priority(0.0)]));
+    }
+    Ok((changes, report))
+}
+
+fn patch_not_applied(error: &str, report: &[String]) -> CallToolResult {
+    CallToolResult::error(vec![Content::text(format!(
+        "Patch was not applied; no files were changed. {}\n\n{}",
+        error,
+        report.join("\n")
+    ))
+    .with_priority(0.0)])
+}
+
+fn patch_applied(file_count: usize, report: &[String]) -> CallToolResult {
+    CallToolResult::success(vec![Content::text(format!(
+        "Applied patch to {} file{}:\n{}",
+        file_count,
+        if file_count == 1 { "" } else { "s" },
+        report.join("\n")
+    ))
+    .with_priority(0.0)])
+}
+
+/// The paths a file's part of a patch changes: the file itself and, for a rename, the path
+/// it moves away from.
+fn touched_paths(file: &FilePatch) -> Vec<&str> {
+    match (file.old_path.as_deref(), file.new_path.as_deref()) {
+        (Some(old), Some(new)) if old != new => vec![new, old],
+        _ => vec![file.path()],
+    }
+}
+
+/// Works out the changes a file's part of a patch makes: its new content, its removal, or
+/// both for a rename. Returns `None` if the file cannot be patched, and a per-hunk summary
+/// either way.
+fn plan_file_patch(
+    file: &FilePatch,
+    working_dir: Option<&Path>,
+    read: &dyn Fn(&Path) -> Result<String, String>,
+) -> (Option<Vec<FileChange>>, String) {
+    let path = resolve_path(file.path(), working_dir);
+    let Some(old_path) = &file.old_path else {
+        return plan_new_file(file, path);
+    };
+    let source = resolve_path(old_path, working_dir);
+    let name = if source == path {
+        file.path().to_string()
+    } else {
+        format!("{} (renamed from {})", file.path(), old_path)
+    };
+    if file.new_path.is_none() {
+        return if path.exists() {
+            (
+                Some(vec![FileChange {
+                    path,
+                    content: None,
+                    mode: None,
+                }]),
+                format!("{name}: deleted"),
+            )
+        } else {
+            (None, format!("{name}: failed, file does not exist"))
+        };
+    }
+    if source != path && path.exists() {
+        return (None, format!("{name}: failed, file already exists"));
+    }
+
+    let original = match read(&source) {
+        Ok(content) => content,
+        Err(error) => return (None, format!("{name}: failed to read: {error}")),
+    };
+    let line_ending = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<String> = original.lines().map(str::to_string).collect();
+    let (patched, outcomes) = apply_hunks(&lines, &file.hunks);
+
+    let mut summary = format!("{name}:");
+    for (index, (hunk, outcome)) in file.hunks.iter().zip(&outcomes).enumerate() {
+        summary.push_str(&format!(
+            "\n  hunk {} `{}` {}",
+            index + 1,
+            hunk.header,
+            outcome
+        ));
+        if *outcome == HunkOutcome::Failed {
+            let expected: Vec<&str> = hunk
+                .old_lines()
+                .into_iter()
+                .skip_while(|line| line.trim().is_empty())
+                .collect();
+            match find_similar_context(&original, &expected.join("\n")) {
+                Some(hint) => summary.push_str(&format!(
+                    ": context not found. Did you mean:\n```\n{}\n```",
+                    hint
+                )),
+                None => summary.push_str(": context not found"),
+            }
+        }
+    }
+
+    let changes = patched.map(|patched| {
+        let trailing = original.ends_with('\n') || original.is_empty();
+        // A renamed file keeps the permissions of the file it came from
+        let mode = fs::metadata(&source).ok().and_then(|m| file_mode(&m));
+        let mut changes = vec![FileChange {
+            path: path.clone(),
+            content: Some(join_lines(&patched, line_ending, trailing).into_bytes()),
+            mode,
+        }];
+        if source != path {
+            changes.push(FileChange {
+                path: source,
+                content: None,
+                mode: None,
+            });
+        }
+        changes
+    });
+    (changes, summary)
+}
+
+fn plan_new_file(file: &FilePatch, path: PathBuf) -> (Option<Vec<FileChange>>, String) {
+    let name = file.path();
+    if path.exists() {
+        return (None, format!("{name}: failed, file already exists"));
+    }
+    let Some(content) = apply_hunks(&[], &file.hunks).0 else {
+        return (
+            None,
+            format!("{name}: failed, hunks for a new file may only add lines"),
+        );
+    };
+    (
+        Some(vec![FileChange {
+            path,
+            content: Some(join_lines(&content, "\n", true).into_bytes()),
+            mode: None,
+        }]),
+        format!("{name}: created ({} lines)", content.len()),
+    )
+}
+
+fn join_lines(lines: &[String], line_ending: &str, trailing: bool) -> String {
+    let mut joined = lines.join(line_ending);
+    if trailing && !lines.is_empty() {
+        joined.push_str(line_ending);
+    }
+    joined
+}
+
 fn count_lines_before(content: &str, byte_pos: usize) -> usize {
     content
         .char_indices()
@@ -363,6 +1066,277 @@ mod tests {
         assert_eq!(fs::read_to_string(&path).unwrap(), "keep\nkeep");
     }
 
+    #[test]
+    fn test_multi_edit_applies_in_order() {
+        let dir = setup();
+        let path = dir.path().join("multi.txt");
+        fs::write(&path, "alpha\nbeta\ngamma\n").unwrap();
+        let tools = EditTools::new();
+
+        let result = tools.multi_edit_with_cwd(
+            MultiEditParams {
+                path: path.to_string_lossy().to_string(),
+                edits: vec![
+                    EditOperation {
+                        before: "alpha".to_string(),
+                        after: "one".to_string(),
+                    },
+                    // Sees the result of the previous edit
+                    EditOperation {
+                        before: "one\nbeta".to_string(),
+                        after: "one\ntwo".to_string(),
+                    },
+                ],
+            },
+            None,
+        );
+
+        assert!(!result.is_error.unwrap_or(false));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\ngamma\n");
+    }
+
+    #[test]
+    fn test_multi_edit_is_all_or_nothing() {
+        let dir = setup();
+        let path = dir.path().join("multi.txt");
+        fs::write(&path, "alpha\nbeta\n").unwrap();
+        let tools = EditTools::new();
+
+        let result = tools.multi_edit_with_cwd(
+            MultiEditParams {
+                path: path.to_string_lossy().to_string(),
+                edits: vec![
+                    EditOperation {
+                        before: "alpha".to_string(),
+                        after: "one".to_string(),
+                    },
+                    EditOperation {
+                        before: "delta".to_string(),
+                        after: "four".to_string(),
+                    },
+                ],
+            },
+            None,
+        );
+
+        assert!(result.is_error.unwrap_or(false));
+        assert!(extract_text(&result).starts_with("Edit 2 of 2 failed"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "alpha\nbeta\n");
+    }
+
+    #[test]
+    fn test_apply_patch_across_files() {
+        let dir = setup();
+        fs::write(
+            dir.path().join("lib.rs"),
+            "fn main() {\n    old();\n}\n\nfn helper() {}\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("gone.txt"), "bye\n").unwrap();
+        let tools = EditTools::new();
+
+        let patch = "diff --git a/lib.rs b/lib.rs
+--- a/lib.rs
++++ b/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-  old();
++    new();
+ }
+--- /dev/null
++++ b/docs/notes.md
+@@ -0,0 +1,2 @@
++# Notes
++hello
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+        let result = tools.apply_patch_with_cwd(
+            ApplyPatchParams {
+                patch: patch.to_string(),
+            },
+            Some(dir.path()),
+        );
+
+        assert!(!result.is_error.unwrap_or(false));
+        let text = extract_text(&result);
+        assert!(text.starts_with("Applied patch to 3 files"));
+        assert!(text.contains("applied at line 1 (ignoring whitespace)"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("lib.rs")).unwrap(),
+            "fn main() {\n    new();\n}\n\nfn helper() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("docs/notes.md")).unwrap(),
+            "# Notes\nhello\n"
+        );
+        assert!(!dir.path().join("gone.txt").exists());
+    }
+
+    #[test]
+    fn test_apply_patch_failure_changes_nothing() {
+        let dir = setup();
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "let value = 1;\nother\n").unwrap();
+        let tools = EditTools::new();
+
+        let patch = "--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+ one
+-two
++2
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-let value = 1; // default
++let value = 2;
+";
+        let result = tools.apply_patch_with_cwd(
+            ApplyPatchParams {
+                patch: patch.to_string(),
+            },
+            Some(dir.path()),
+        );
+
+        assert!(result.is_error.unwrap_or(false));
+        let text = extract_text(&result);
+        assert!(text.contains("no files were changed"));
+        assert!(text.contains("a.txt:\n  hunk 1 `@@ -1,2 +1,2 @@` applied at line 1"));
+        assert!(text.contains("hunk 1 `@@ -1 +1 @@` failed: context not found. Did you mean:"));
+        assert!(text.contains("let value = 1;"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_patch_keeps_executable_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = setup();
+        let script = dir.path().join("run.sh");
+        fs::write(&script, "#!/bin/sh\necho one\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let tools = EditTools::new();
+
+        let result = tools.apply_patch_with_cwd(
+            ApplyPatchParams {
+                patch: "--- a/run.sh
++++ b/bin/run.sh
+@@ -1,2 +1,2 @@
+ #!/bin/sh
+-echo one
++echo two
+"
+                .to_string(),
+            },
+            Some(dir.path()),
+        );
+        assert!(!result.is_error.unwrap_or(false));
+        let moved = dir.path().join("bin/run.sh");
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "#!/bin/sh\necho two\n");
+        let mode = fs::metadata(&moved).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode, 0o755);
+
+        let result = tools.apply_patch_with_cwd(
+            ApplyPatchParams {
+                patch: "--- a/bin/run.sh
++++ b/bin/run.sh
+@@ -1,2 +1,2 @@
+ #!/bin/sh
+-echo two
++echo three
+"
+                .to_string(),
+            },
+            Some(dir.path()),
+        );
+        assert!(!result.is_error.unwrap_or(false));
+        let mode = fs::metadata(&moved).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode, 0o755);
+    }
+
+    #[test]
+    fn test_apply_patch_renames_file() {
+        let dir = setup();
+        fs::write(dir.path().join("old.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        let tools = EditTools::new();
+        let params = ApplyPatchParams {
+            patch: "--- a/old.rs
++++ b/src/new.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+-fn b() {}
++fn c() {}
+"
+            .to_string(),
+        };
+
+        let snapshots = tools.patch_snapshots(&params, Some(dir.path()));
+        assert_eq!(
+            snapshots,
+            vec![
+                (dir.path().join("src/new.rs"), FileSnapshot::Missing),
+                (
+                    dir.path().join("old.rs"),
+                    FileSnapshot::Content {
+                        content: b"fn a() {}\nfn b() {}\n".to_vec(),
+                        mode: file_mode(&fs::metadata(dir.path().join("old.rs")).unwrap()),
+                    }
+                ),
+            ]
+        );
+
+        let result = tools.apply_patch_with_cwd(params, Some(dir.path()));
+        assert!(!result.is_error.unwrap_or(false));
+        assert!(extract_text(&result).contains("src/new.rs (renamed from old.rs)"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("src/new.rs")).unwrap(),
+            "fn a() {}\nfn c() {}\n"
+        );
+        assert!(!dir.path().join("old.rs").exists());
+    }
+
+    #[test]
+    fn test_apply_patch_rolls_back_when_a_write_fails() {
+        let dir = setup();
+        fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        // Planning accepts deleting a directory; applying it fails after a.txt was written
+        fs::create_dir_all(dir.path().join("blocked/child")).unwrap();
+        let tools = EditTools::new();
+
+        let result = tools.apply_patch_with_cwd(
+            ApplyPatchParams {
+                patch: "--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+ one
+-two
++2
+--- a/blocked
++++ /dev/null
+@@ -1 +0,0 @@
+-x
+"
+                .to_string(),
+            },
+            Some(dir.path()),
+        );
+
+        assert!(result.is_error.unwrap_or(false));
+        assert!(extract_text(&result).contains("no files were changed"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "one\ntwo\n"
+        );
+        assert!(dir.path().join("blocked/child").is_dir());
+    }
+
     #[test]
     fn test_file_write_resolves_relative_paths_from_working_dir() {
         let dir = setup();
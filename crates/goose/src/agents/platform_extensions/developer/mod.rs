@@ -1,21 +1,32 @@
 pub mod edit;
+mod patch;
+pub mod sandbox;
 pub mod shell;
 pub mod tree;
 
 use crate::agents::extension::PlatformExtensionContext;
 use crate::agents::mcp_client::{Error, McpClientTrait};
+use crate::agents::{ClientDelegate, Container};
+use crate::config::{Config, GooseMode};
+use crate::session::file_checkpoints::apply_file_changes;
+use crate::session::{FileCheckpoint, FileSnapshot, SessionManager};
 use anyhow::Result;
 use async_trait::async_trait;
-use edit::{EditTools, FileEditParams, FileWriteParams};
+use edit::{
+    ApplyPatchParams, EditTools, FileEditParams, FileWriteParams, MultiEditParams, UndoParams,
+};
 use indoc::indoc;
 use rmcp::model::{
     CallToolResult, Content, Implementation, InitializeResult, JsonObject, ListToolsResult,
     ServerCapabilities, Tool, ToolAnnotations,
 };
+use sandbox::{ExecutionBackend, FileTarget, SandboxPolicy};
 use schemars::{schema_for, JsonSchema};
 use serde_json::Value;
-use shell::{ShellOutput, ShellParams, ShellTool};
-use std::path::Path;
+use shell::{
+    ShellInputParams, ShellKillParams, ShellOutput, ShellOutputParams, ShellParams, ShellTool,
+};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use tree::{TreeParams, TreeTool};
@@ -24,13 +35,16 @@ pub static EXTENSION_NAME: &str = "developer";
 
 pub struct DeveloperClient {
     info: InitializeResult,
+    session_manager: Arc<SessionManager>,
+    container: Option<Container>,
+    client_delegate: Option<Arc<dyn ClientDelegate>>,
     shell_tool: Arc<ShellTool>,
     edit_tools: Arc<EditTools>,
     tree_tool: Arc<TreeTool>,
 }
 
 impl DeveloperClient {
-    pub fn new(_context: PlatformExtensionContext) -> Result<Self> {
+    pub fn new(context: PlatformExtensionContext) -> Result<Self> {
         let info = InitializeResult::new(
             ServerCapabilities::builder().enable_tools().build(),
         )
@@ -46,11 +60,19 @@ impl DeveloperClient {
             For editing software, prefer the flow of using tree to understand the codebase structure
             and file sizes. When you need to search, prefer rg which correctly respects gitignored
             content. Then use cat or sed to gather the context you need, always reading before editing.
-            Use write and edit to efficiently make changes. Test and verify as appropriate.
+            Use write and edit to efficiently make changes, multi_edit for several changes to one file
+            and apply_patch for unified diffs spanning files. Test and verify as appropriate.
+            If a write or edit went wrong, use undo to restore the file instead of rewriting it.
+
+            Start dev servers, file watchers and long test suites with shell background=true, then
+            check on them with shell_output and stop them with shell_kill when they are no longer needed.
         "});
 
         Ok(Self {
             info,
+            session_manager: context.session_manager,
+            container: context.container,
+            client_delegate: context.client_delegate,
             shell_tool: Arc::new(ShellTool::new()?),
             edit_tools: Arc::new(EditTools::new()),
             tree_tool: Arc::new(TreeTool::new()),
@@ -100,9 +122,45 @@ impl DeveloperClient {
                 Some(false),
                 Some(false),
             )),
+            Tool::new(
+                "multi_edit".to_string(),
+                "Apply several find-and-replace edits to one file in order, each seeing the result of the previous. Every before text must match exactly and uniquely; if any edit fails, none are applied.".to_string(),
+                Self::schema::<MultiEditParams>(),
+            )
+            .annotate(ToolAnnotations::from_raw(
+                Some("Multi edit".to_string()),
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(false),
+            )),
+            Tool::new(
+                "apply_patch".to_string(),
+                "Apply a unified diff that may modify, create or delete several files. Hunks are placed near their stated line numbers and tolerate whitespace differences. Reports the outcome of each hunk; if any hunk fails, no files are changed.".to_string(),
+                Self::schema::<ApplyPatchParams>(),
+            )
+            .annotate(ToolAnnotations::from_raw(
+                Some("Apply patch".to_string()),
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(false),
+            )),
+            Tool::new(
+                "undo".to_string(),
+                "Revert the most recent file change made in this session, optionally only for one file. Files that were created are removed again. Call repeatedly to step further back.".to_string(),
+                Self::schema::<UndoParams>(),
+            )
+            .annotate(ToolAnnotations::from_raw(
+                Some("Undo".to_string()),
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(false),
+            )),
             Tool::new(
                 "shell".to_string(),
-                "Execute a shell command in the user's default shell in the current dir. Returns an object with stdout and stderr as separate fields. The output of each stream is limited to up to 2000 lines, and longer outputs will be saved to a temporary file.".to_string(),
+                "Execute a shell command in the user's default shell in the current dir. Returns an object with stdout and stderr as separate fields. The output of each stream is limited to up to 2000 lines, and longer outputs will be saved to a temporary file. Set background to start a long-running command and get a handle back immediately.".to_string(),
                 Self::schema::<ShellParams>(),
             )
             .with_output_schema::<ShellOutput>()
@@ -113,6 +171,42 @@ impl DeveloperClient {
                 Some(false),
                 Some(true),
             )),
+            Tool::new(
+                "shell_output".to_string(),
+                "Read the output of a background shell process started with background=true. Returns the lines produced since the previous read, or the last N lines with tail, along with whether the process is still running.".to_string(),
+                Self::schema::<ShellOutputParams>(),
+            )
+            .annotate(ToolAnnotations::from_raw(
+                Some("Shell output".to_string()),
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+            )),
+            Tool::new(
+                "shell_input".to_string(),
+                "Write text to the stdin of a background shell process, optionally closing stdin afterwards.".to_string(),
+                Self::schema::<ShellInputParams>(),
+            )
+            .annotate(ToolAnnotations::from_raw(
+                Some("Shell input".to_string()),
+                Some(false),
+                Some(true),
+                Some(false),
+                Some(true),
+            )),
+            Tool::new(
+                "shell_kill".to_string(),
+                "Stop a background shell process and everything it started, returning any output not read yet.".to_string(),
+                Self::schema::<ShellKillParams>(),
+            )
+            .annotate(ToolAnnotations::from_raw(
+                Some("Shell kill".to_string()),
+                Some(false),
+                Some(true),
+                Some(true),
+                Some(false),
+            )),
             Tool::new(
                 "tree".to_string(),
                 "List a directory tree with line counts. Traversal respects .gitignore rules.".to_string(),
@@ -127,6 +221,183 @@ impl DeveloperClient {
             )),
         ]
     }
+
+    /// Where this session's commands run: the agent's container when it has one, otherwise
+    /// the host, sandboxed according to the session's policy. Falls back to the configured
+    /// policy when the session cannot be read, so a lookup failure never lifts the sandbox.
+    async fn execution_backend(&self, session_id: &str) -> ExecutionBackend {
+        if let Some(container) = &self.container {
+            return ExecutionBackend::Container(container.clone());
+        }
+        let config = Config::global();
+        let policy = match self.session_manager.get_session(session_id, false).await {
+            Ok(session) => SandboxPolicy::for_session(&session, config),
+            Err(_) => SandboxPolicy::from_config(config),
+        };
+        ExecutionBackend::new(policy, config.get_goose_mode().unwrap_or(GooseMode::Auto))
+    }
+
+    /// The client's file access, for files goose would otherwise change on the host itself
+    fn client_files(&self, backend: &ExecutionBackend) -> Option<&dyn ClientDelegate> {
+        if let ExecutionBackend::Container(_) = backend {
+            return None;
+        }
+        self.client_delegate
+            .as_deref()
+            .filter(|client| client.can_write_files())
+    }
+
+    /// The client's terminal, unless commands have to run in the sandbox or container
+    fn client_terminal(&self, backend: &ExecutionBackend) -> Option<&dyn ClientDelegate> {
+        if !matches!(backend, ExecutionBackend::Host) {
+            return None;
+        }
+        self.client_delegate
+            .as_deref()
+            .filter(|client| client.has_terminal())
+    }
+
+    /// Points a file tool's path at the host side of the container mount, if there is one.
+    /// Returns the container and path to use instead when the file is only reachable inside
+    /// the container, or the error when the sandbox does not allow writing it.
+    fn route<'a>(
+        backend: &'a ExecutionBackend,
+        path: &mut String,
+        working_dir: Option<&Path>,
+    ) -> Result<Option<(&'a Container, PathBuf)>, String> {
+        match backend.locate(path, working_dir)? {
+            FileTarget::Container(container, container_path) => {
+                Ok(Some((container, container_path)))
+            }
+            FileTarget::Host(host_path) => {
+                backend.check_write(&host_path, working_dir)?;
+                if let ExecutionBackend::Container(_) = backend {
+                    *path = host_path.display().to_string();
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn write_error(error: String) -> CallToolResult {
+        CallToolResult::error(vec![Content::text(error).with_priority(0.0)])
+    }
+
+    fn outside_mount(tool: &str, container: &Container, path: &Path) -> CallToolResult {
+        CallToolResult::error(vec![Content::text(format!(
+            "Error: {tool} can only change files under the working directory mounted into container {}. \
+             Use write or edit for {}.",
+            container.id(),
+            path.display()
+        ))
+        .with_priority(0.0)])
+    }
+
+    /// Checks the file the next undo would restore against the container mount, as the
+    /// container may have replaced one of its directories with a symlink since the
+    /// checkpoint was taken.
+    async fn check_undo_target(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+        backend: &ExecutionBackend,
+    ) -> Result<(), String> {
+        if !matches!(backend, ExecutionBackend::Container(_)) {
+            return Ok(());
+        }
+        let checkpoints = self
+            .session_manager
+            .list_file_checkpoints(session_id)
+            .await
+            .map_err(|e| format!("Error: {e}"))?;
+        match checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| path.is_none_or(|path| Path::new(&checkpoint.path) == path))
+        {
+            Some(checkpoint) => backend.check_mounted(Path::new(&checkpoint.path)),
+            None => Ok(()),
+        }
+    }
+
+    /// Captures a file before a tool changes it, from the copy the tool is about to change.
+    async fn snapshot(
+        &self,
+        session_id: &str,
+        client: Option<&dyn ClientDelegate>,
+        path: &str,
+        working_dir: Option<&Path>,
+    ) -> Option<(PathBuf, FileSnapshot)> {
+        match client {
+            Some(client) => {
+                EditTools::snapshot_with_client(session_id, path, working_dir, client).await
+            }
+            None => EditTools::snapshot(path, working_dir),
+        }
+    }
+
+    /// Undoes the last change through the client, so an editor that has the file open updates
+    /// its buffer rather than having the file replaced on disk underneath it. The client cannot
+    /// delete files or hold binary content, so those restores go to disk.
+    async fn undo_with_client(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+        client: &dyn ClientDelegate,
+    ) -> Result<Option<FileCheckpoint>> {
+        let Some(checkpoint) = self
+            .session_manager
+            .take_file_checkpoint(session_id, path)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let restored = match checkpoint.change() {
+            Ok(change) => match change.content.as_deref().map(std::str::from_utf8) {
+                Some(Ok(text)) => {
+                    let created = match change.path.parent() {
+                        Some(parent) if !parent.exists() => std::fs::create_dir_all(parent),
+                        _ => Ok(()),
+                    };
+                    match created {
+                        Ok(()) => client.write_text_file(session_id, &change.path, text).await,
+                        Err(error) => Err(error.into()),
+                    }
+                }
+                _ => apply_file_changes(std::slice::from_ref(&change)),
+            },
+            Err(error) => Err(error),
+        };
+        if let Err(error) = restored {
+            self.session_manager
+                .return_file_checkpoints(session_id, std::slice::from_ref(&checkpoint))
+                .await?;
+            return Err(error);
+        }
+        Ok(Some(checkpoint))
+    }
+
+    async fn record_checkpoint(
+        &self,
+        session_id: &str,
+        tool_name: &str,
+        snapshot: Option<(PathBuf, FileSnapshot)>,
+        result: &CallToolResult,
+    ) {
+        let Some((path, snapshot)) = snapshot else {
+            return;
+        };
+        if result.is_error == Some(true) {
+            return;
+        }
+        if let Err(e) = self
+            .session_manager
+            .add_file_checkpoint(session_id, &path, tool_name, snapshot)
+            .await
+        {
+            tracing::warn!("Failed to checkpoint {}: {}", path.display(), e);
+        }
+    }
 }
 
 #[async_trait]
@@ -146,7 +417,7 @@ impl McpClientTrait for DeveloperClient {
 
     async fn call_tool(
         &self,
-        _session_id: &str,
+        session_id: &str,
         name: &str,
         arguments: Option<JsonObject>,
         working_dir: Option<&str>,
@@ -155,25 +426,242 @@ impl McpClientTrait for DeveloperClient {
         let working_dir = working_dir.map(Path::new);
         match name {
             "shell" => match Self::parse_args::<ShellParams>(arguments) {
-                Ok(params) => Ok(self.shell_tool.shell_with_cwd(params, working_dir).await),
+                Ok(params) if params.background => {
+                    let backend = self.execution_backend(session_id).await;
+                    Ok(self
+                        .shell_tool
+                        .start_background(session_id, params, working_dir, &backend))
+                }
+                Ok(params) => {
+                    let backend = self.execution_backend(session_id).await;
+                    if let Some(client) = self.client_terminal(&backend) {
+                        return Ok(self
+                            .shell_tool
+                            .shell_in_client(session_id, params, working_dir, client)
+                            .await);
+                    }
+                    Ok(self
+                        .shell_tool
+                        .shell_with_cwd(params, working_dir, &backend)
+                        .await)
+                }
                 Err(error) => Ok(ShellTool::error_result(&format!("Error: {error}"), None)),
             },
+            "shell_output" => match Self::parse_args::<ShellOutputParams>(arguments) {
+                Ok(params) => Ok(self.shell_tool.read_output(session_id, params).await),
+                Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Error: {error}"
+                ))
+                .with_priority(0.0)])),
+            },
+            "shell_input" => match Self::parse_args::<ShellInputParams>(arguments) {
+                Ok(params) => Ok(self.shell_tool.write_input(session_id, params).await),
+                Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Error: {error}"
+                ))
+                .with_priority(0.0)])),
+            },
+            "shell_kill" => match Self::parse_args::<ShellKillParams>(arguments) {
+                Ok(params) => Ok(self.shell_tool.kill(session_id, params).await),
+                Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Error: {error}"
+                ))
+                .with_priority(0.0)])),
+            },
             "write" => match Self::parse_args::<FileWriteParams>(arguments) {
-                Ok(params) => Ok(self.edit_tools.file_write_with_cwd(params, working_dir)),
+                Ok(mut params) => {
+                    let backend = self.execution_backend(session_id).await;
+                    match Self::route(&backend, &mut params.path, working_dir) {
+                        Err(error) => return Ok(Self::write_error(error)),
+                        Ok(Some((container, path))) => {
+                            return Ok(self
+                                .edit_tools
+                                .file_write_in_container(params, container, &path)
+                                .await);
+                        }
+                        Ok(None) => {}
+                    }
+                    let client = self.client_files(&backend);
+                    let snapshot = self
+                        .snapshot(session_id, client, &params.path, working_dir)
+                        .await;
+                    let result = match client {
+                        Some(client) => {
+                            self.edit_tools
+                                .file_write_with_client(session_id, params, working_dir, client)
+                                .await
+                        }
+                        None => self.edit_tools.file_write_with_cwd(params, working_dir),
+                    };
+                    self.record_checkpoint(session_id, name, snapshot, &result)
+                        .await;
+                    Ok(backend.container_view(result))
+                }
                 Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
                     "Error: {error}"
                 ))
                 .with_priority(0.0)])),
             },
             "edit" => match Self::parse_args::<FileEditParams>(arguments) {
-                Ok(params) => Ok(self.edit_tools.file_edit_with_cwd(params, working_dir)),
+                Ok(mut params) => {
+                    let backend = self.execution_backend(session_id).await;
+                    match Self::route(&backend, &mut params.path, working_dir) {
+                        Err(error) => return Ok(Self::write_error(error)),
+                        Ok(Some((container, path))) => {
+                            return Ok(self
+                                .edit_tools
+                                .file_edit_in_container(params, container, &path)
+                                .await);
+                        }
+                        Ok(None) => {}
+                    }
+                    let client = self.client_files(&backend);
+                    let snapshot = self
+                        .snapshot(session_id, client, &params.path, working_dir)
+                        .await;
+                    let result = match client {
+                        Some(client) => {
+                            self.edit_tools
+                                .file_edit_with_client(session_id, params, working_dir, client)
+                                .await
+                        }
+                        None => self.edit_tools.file_edit_with_cwd(params, working_dir),
+                    };
+                    self.record_checkpoint(session_id, name, snapshot, &result)
+                        .await;
+                    Ok(backend.container_view(result))
+                }
+                Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Error: {error}"
+                ))
+                .with_priority(0.0)])),
+            },
+            "multi_edit" => match Self::parse_args::<MultiEditParams>(arguments) {
+                Ok(mut params) => {
+                    let backend = self.execution_backend(session_id).await;
+                    match Self::route(&backend, &mut params.path, working_dir) {
+                        Err(error) => return Ok(Self::write_error(error)),
+                        Ok(Some((container, path))) => {
+                            return Ok(Self::outside_mount(name, container, &path));
+                        }
+                        Ok(None) => {}
+                    }
+                    let client = self.client_files(&backend);
+                    let snapshot = self
+                        .snapshot(session_id, client, &params.path, working_dir)
+                        .await;
+                    let result = match client {
+                        Some(client) => {
+                            self.edit_tools
+                                .multi_edit_with_client(session_id, params, working_dir, client)
+                                .await
+                        }
+                        None => self.edit_tools.multi_edit_with_cwd(params, working_dir),
+                    };
+                    self.record_checkpoint(session_id, name, snapshot, &result)
+                        .await;
+                    Ok(backend.container_view(result))
+                }
+                Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Error: {error}"
+                ))
+                .with_priority(0.0)])),
+            },
+            "apply_patch" => match Self::parse_args::<ApplyPatchParams>(arguments) {
+                Ok(params) => {
+                    let backend = self.execution_backend(session_id).await;
+                    for path in self.edit_tools.patch_paths(&params) {
+                        let mut routed = path.clone();
+                        match Self::route(&backend, &mut routed, working_dir) {
+                            Err(error) => return Ok(Self::write_error(error)),
+                            Ok(Some((container, path))) => {
+                                return Ok(Self::outside_mount(name, container, &path));
+                            }
+                            // The patch itself still names the container path
+                            Ok(None) if routed != path => {
+                                return Ok(CallToolResult::error(vec![Content::text(format!(
+                                    "Error: use paths relative to the working directory in patches, not {path}"
+                                ))
+                                .with_priority(0.0)]));
+                            }
+                            Ok(None) => {}
+                        }
+                    }
+                    let client = self.client_files(&backend);
+                    let mut snapshots = Vec::new();
+                    for path in self.edit_tools.patch_paths(&params) {
+                        snapshots
+                            .extend(self.snapshot(session_id, client, &path, working_dir).await);
+                    }
+                    let result = match client {
+                        Some(client) => {
+                            self.edit_tools
+                                .apply_patch_with_client(session_id, params, working_dir, client)
+                                .await
+                        }
+                        None => self.edit_tools.apply_patch_with_cwd(params, working_dir),
+                    };
+                    for snapshot in snapshots {
+                        self.record_checkpoint(session_id, name, Some(snapshot), &result)
+                            .await;
+                    }
+                    Ok(backend.container_view(result))
+                }
+                Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Error: {error}"
+                ))
+                .with_priority(0.0)])),
+            },
+            "undo" => match Self::parse_args::<UndoParams>(Some(arguments.unwrap_or_default())) {
+                Ok(params) => {
+                    let path = self.edit_tools.resolve_undo_path(&params, working_dir);
+                    let backend = self.execution_backend(session_id).await;
+                    if let Err(error) = self
+                        .check_undo_target(session_id, path.as_deref(), &backend)
+                        .await
+                    {
+                        return Ok(Self::write_error(error));
+                    }
+                    let undone = match self.client_files(&backend) {
+                        Some(client) => {
+                            self.undo_with_client(session_id, path.as_deref(), client)
+                                .await
+                        }
+                        None => {
+                            self.session_manager
+                                .undo_file_change(session_id, path.as_deref())
+                                .await
+                        }
+                    };
+                    match undone {
+                        Ok(checkpoint) => Ok(EditTools::undo_result(checkpoint)),
+                        Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
+                            "Error: {error}"
+                        ))
+                        .with_priority(0.0)])),
+                    }
+                }
                 Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
                     "Error: {error}"
                 ))
                 .with_priority(0.0)])),
             },
             "tree" => match Self::parse_args::<TreeParams>(arguments) {
-                Ok(params) => Ok(self.tree_tool.tree_with_cwd(params, working_dir)),
+                Ok(mut params) => {
+                    let backend = self.execution_backend(session_id).await;
+                    match backend.locate(&params.path, working_dir) {
+                        Err(error) => Ok(Self::write_error(error)),
+                        Ok(FileTarget::Container(container, path)) => Ok(self
+                            .tree_tool
+                            .tree_in_container(params, container, &path)
+                            .await),
+                        Ok(FileTarget::Host(path)) => {
+                            params.path = path.display().to_string();
+                            Ok(backend
+                                .container_view(self.tree_tool.tree_with_cwd(params, working_dir)))
+                        }
+                    }
+                }
                 Err(error) => Ok(CallToolResult::error(vec![Content::text(format!(
                     "Error: {error}"
                 ))
@@ -189,6 +677,10 @@ impl McpClientTrait for DeveloperClient {
     fn get_info(&self) -> Option<&InitializeResult> {
         Some(&self.info)
     }
+
+    async fn get_moim(&self, session_id: &str) -> Option<String> {
+        self.shell_tool.describe_processes(session_id)
+    }
 }
 
 #[cfg(test)]
@@ -206,7 +698,21 @@ mod tests {
             .map(|t| t.name.to_string())
             .collect();
 
-        assert_eq!(names, vec!["write", "edit", "shell", "tree"]);
+        assert_eq!(
+            names,
+            vec![
+                "write",
+                "edit",
+                "multi_edit",
+                "apply_patch",
+                "undo",
+                "shell",
+                "shell_output",
+                "shell_input",
+                "shell_kill",
+                "tree"
+            ]
+        );
     }
 
     fn test_context(data_dir: std::path::PathBuf) -> PlatformExtensionContext {
@@ -214,6 +720,8 @@ mod tests {
             extension_manager: None,
             session_manager: Arc::new(SessionManager::new(data_dir)),
             session: None,
+            container: None,
+            client_delegate: None,
         }
     }
 
@@ -271,6 +779,66 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn developer_client_undoes_file_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let context = test_context(temp.path().join("sessions"));
+        let cwd = temp.path().join("workspace");
+        fs::create_dir_all(&cwd).unwrap();
+        let session = context
+            .session_manager
+            .create_session(
+                cwd.clone(),
+                "test".into(),
+                crate::session::SessionType::User,
+            )
+            .await
+            .unwrap();
+        let client = DeveloperClient::new(context).unwrap();
+        let call = |name: &'static str, args: JsonObject| {
+            let client = &client;
+            let session_id = session.id.clone();
+            let cwd = cwd.clone();
+            async move {
+                client
+                    .call_tool(
+                        &session_id,
+                        name,
+                        Some(args),
+                        Some(cwd.to_str().unwrap()),
+                        CancellationToken::new(),
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+
+        call("write", object!({ "path": "notes.txt", "content": "one" })).await;
+        call(
+            "edit",
+            object!({ "path": "notes.txt", "before": "one", "after": "two" }),
+        )
+        .await;
+        // Failed edits leave no checkpoint behind
+        let failed = call(
+            "edit",
+            object!({ "path": "notes.txt", "before": "missing", "after": "x" }),
+        )
+        .await;
+        assert_eq!(failed.is_error, Some(true));
+
+        let undo = call("undo", object!({})).await;
+        assert!(first_text(&undo).starts_with("Restored"));
+        assert_eq!(fs::read_to_string(cwd.join("notes.txt")).unwrap(), "one");
+
+        let undo = call("undo", object!({ "path": "notes.txt" })).await;
+        assert!(first_text(&undo).starts_with("Removed"));
+        assert!(!cwd.join("notes.txt").exists());
+
+        let undo = call("undo", object!({})).await;
+        assert_eq!(first_text(&undo), "Nothing to undo");
+    }
+
     #[cfg(not(windows))]
     #[tokio::test]
     async fn developer_client_uses_working_dir_for_shell_tool() {
@@ -296,4 +864,298 @@ mod tests {
         let expected = std::fs::canonicalize(&cwd).unwrap();
         assert_eq!(observed, expected);
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn developer_client_runs_tools_in_container() {
+        let temp = tempfile::tempdir().unwrap();
+        let runtime = crate::agents::container::fake_runtime::install(temp.path());
+        // The fake runtime runs commands on the host, so the "container" side of the mount
+        // has to be a real directory too
+        let host = temp.path().join("project");
+        let mounted = temp.path().join("workspace");
+        let outside = temp.path().join("elsewhere");
+        for dir in [&host, &mounted, &outside] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let mut context = test_context(temp.path().join("sessions"));
+        context.container = Some(
+            Container::new("c1")
+                .with_runtime(runtime.to_str().unwrap())
+                .with_mount(&host, &mounted),
+        );
+        let client = DeveloperClient::new(context).unwrap();
+        let call = |name: &'static str, args: JsonObject| {
+            let client = &client;
+            let cwd = host.clone();
+            async move {
+                client
+                    .call_tool(
+                        "session",
+                        name,
+                        Some(args),
+                        Some(cwd.to_str().unwrap()),
+                        CancellationToken::new(),
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let pwd = call("shell", object!({ "command": "pwd" })).await;
+        assert_eq!(first_text(&pwd), mounted.display().to_string());
+
+        let write = call("write", object!({ "path": "a.txt", "content": "one" })).await;
+        assert_eq!(write.is_error, Some(false));
+        assert_eq!(fs::read_to_string(host.join("a.txt")).unwrap(), "one");
+
+        let absolute = mounted.join("b.txt").display().to_string();
+        call("write", object!({ "path": absolute, "content": "two" })).await;
+        assert_eq!(fs::read_to_string(host.join("b.txt")).unwrap(), "two");
+
+        let elsewhere = outside.join("c.txt").display().to_string();
+        call(
+            "write",
+            object!({ "path": elsewhere.clone(), "content": "three" }),
+        )
+        .await;
+        call(
+            "edit",
+            object!({ "path": elsewhere, "before": "three", "after": "four" }),
+        )
+        .await;
+        assert_eq!(fs::read_to_string(outside.join("c.txt")).unwrap(), "four");
+        assert!(crate::agents::container::fake_runtime::calls(temp.path())
+            .iter()
+            .any(|call| call.starts_with("exec -i c1 cat")));
+
+        // A symlink planted under the mount does not lead file tools out of it on the host
+        std::os::unix::fs::symlink(&outside, host.join("evil")).unwrap();
+        let escape = call(
+            "write",
+            object!({ "path": "evil/d.txt", "content": "five" }),
+        )
+        .await;
+        assert_eq!(escape.is_error, Some(true));
+        assert!(!outside.join("d.txt").exists());
+    }
+
+    /// A client with open buffers that never reach the disk and a terminal that echoes commands
+    #[derive(Default)]
+    struct FakeClient {
+        buffers: std::sync::Mutex<std::collections::HashMap<PathBuf, String>>,
+        commands: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl ClientDelegate for FakeClient {
+        fn can_read_files(&self) -> bool {
+            true
+        }
+
+        fn can_write_files(&self) -> bool {
+            true
+        }
+
+        fn has_terminal(&self) -> bool {
+            true
+        }
+
+        async fn read_text_file(&self, _session_id: &str, path: &Path) -> Result<String> {
+            self.buffers
+                .lock()
+                .unwrap()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("no buffer for {}", path.display()))
+        }
+
+        async fn write_text_file(
+            &self,
+            _session_id: &str,
+            path: &Path,
+            content: &str,
+        ) -> Result<()> {
+            self.buffers
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), content.to_string());
+            Ok(())
+        }
+
+        async fn run_command(
+            &self,
+            _session_id: &str,
+            command_line: &str,
+            _working_dir: Option<&Path>,
+            _timeout_secs: Option<u64>,
+        ) -> Result<crate::agents::ClientCommandOutput> {
+            self.commands.lock().unwrap().push(command_line.to_string());
+            Ok(crate::agents::ClientCommandOutput {
+                output: format!("ran {command_line}"),
+                exit_code: Some(0),
+                timed_out: false,
+                terminal_id: Some("term-1".to_string()),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn developer_client_delegates_to_client() {
+        let temp = tempfile::tempdir().unwrap();
+        let fake = Arc::new(FakeClient::default());
+        let mut context = test_context(temp.path().join("sessions"));
+        context.client_delegate = Some(fake.clone());
+        let client = DeveloperClient::new(context).unwrap();
+        let cwd = temp.path().join("workspace");
+        fs::create_dir_all(&cwd).unwrap();
+        let call = |name: &'static str, args: JsonObject| {
+            let client = &client;
+            let cwd = cwd.clone();
+            async move {
+                client
+                    .call_tool(
+                        "session",
+                        name,
+                        Some(args),
+                        Some(cwd.to_str().unwrap()),
+                        CancellationToken::new(),
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let write = call("write", object!({ "path": "a.txt", "content": "one" })).await;
+        assert_eq!(write.is_error, Some(false));
+        let edit = call(
+            "edit",
+            object!({ "path": "a.txt", "before": "one", "after": "two" }),
+        )
+        .await;
+        assert_eq!(edit.is_error, Some(false));
+        assert_eq!(
+            fake.buffers
+                .lock()
+                .unwrap()
+                .get(&cwd.join("a.txt"))
+                .unwrap(),
+            "two"
+        );
+        assert!(!cwd.join("a.txt").exists());
+
+        let shell = call("shell", object!({ "command": "make test" })).await;
+        assert_eq!(first_text(&shell), "ran make test");
+        assert_eq!(*fake.commands.lock().unwrap(), vec!["make test"]);
+        let terminal = shell
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.0.get(shell::TERMINAL_ID_META));
+        assert_eq!(terminal, Some(&serde_json::json!("term-1")));
+    }
+
+    #[tokio::test]
+    async fn developer_client_patches_client_buffers() {
+        let temp = tempfile::tempdir().unwrap();
+        let fake = Arc::new(FakeClient::default());
+        let mut context = test_context(temp.path().join("sessions"));
+        context.client_delegate = Some(fake.clone());
+        let cwd = temp.path().join("workspace");
+        fs::create_dir_all(&cwd).unwrap();
+        let session = context
+            .session_manager
+            .create_session(
+                cwd.clone(),
+                "test".into(),
+                crate::session::SessionType::User,
+            )
+            .await
+            .unwrap();
+        let session_manager = context.session_manager.clone();
+        let client = DeveloperClient::new(context).unwrap();
+        fake.buffers
+            .lock()
+            .unwrap()
+            .insert(cwd.join("a.txt"), "one\ntwo\n".to_string());
+        let call = |name: &'static str, args: JsonObject| {
+            let client = &client;
+            let session_id = session.id.clone();
+            let cwd = cwd.clone();
+            async move {
+                client
+                    .call_tool(
+                        &session_id,
+                        name,
+                        Some(args),
+                        Some(cwd.to_str().unwrap()),
+                        CancellationToken::new(),
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+        let buffer = |name: &str| fake.buffers.lock().unwrap().get(&cwd.join(name)).cloned();
+
+        let multi = call(
+            "multi_edit",
+            object!({
+                "path": "a.txt",
+                "edits": [
+                    { "before": "one", "after": "uno" },
+                    { "before": "two", "after": "dos" }
+                ]
+            }),
+        )
+        .await;
+        assert_eq!(multi.is_error, Some(false));
+        assert_eq!(buffer("a.txt").as_deref(), Some("uno\ndos\n"));
+
+        let patch = call(
+            "apply_patch",
+            object!({
+                "patch": "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n uno\n-dos\n+tres\n--- /dev/null\n+++ b/b.txt\n@@ -0,0 +1 @@\n+new\n"
+            }),
+        )
+        .await;
+        assert_eq!(patch.is_error, Some(false), "{}", first_text(&patch));
+        assert_eq!(buffer("a.txt").as_deref(), Some("uno\ntres\n"));
+        assert_eq!(buffer("b.txt").as_deref(), Some("new\n"));
+        assert!(!cwd.join("a.txt").exists());
+        assert!(!cwd.join("b.txt").exists());
+
+        // a.txt only exists in the client, so its checkpoints must have been read from there.
+        let checkpoints = session_manager
+            .list_file_checkpoints(&session.id)
+            .await
+            .unwrap();
+        let existed: Vec<_> = checkpoints
+            .iter()
+            .map(|checkpoint| (checkpoint.tool_name.as_str(), checkpoint.existed))
+            .collect();
+        assert_eq!(
+            existed,
+            vec![
+                ("multi_edit", true),
+                ("apply_patch", true),
+                ("apply_patch", false)
+            ]
+        );
+
+        // Undo restores the client's copy and leaves the disk alone
+        let undo = call("undo", object!({ "path": "a.txt" })).await;
+        assert_eq!(undo.is_error, Some(false), "{}", first_text(&undo));
+        assert_eq!(buffer("a.txt").as_deref(), Some("uno\ndos\n"));
+        let undo = call("undo", object!({ "path": "a.txt" })).await;
+        assert_eq!(undo.is_error, Some(false), "{}", first_text(&undo));
+        assert_eq!(buffer("a.txt").as_deref(), Some("one\ntwo\n"));
+        assert!(!cwd.join("a.txt").exists());
+        assert_eq!(
+            session_manager
+                .list_file_checkpoints(&session.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
 }
@@ -0,0 +1,391 @@
+//! Unified diff parsing and fuzzy hunk placement for the `apply_patch` tool.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// The `@@ ... @@` line as written in the patch.
+    pub header: String,
+    /// One-based start line in the original file, 0 when the file was empty.
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find in the file.
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    /// Path before the change, `None` when the patch creates the file.
+    pub old_path: Option<String>,
+    /// Path after the change, `None` when the patch deletes the file.
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FilePatch {
+    /// The path the patch applies to.
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+/// Splits a unified diff into per-file patches. Headers such as `diff --git` and `index`
+/// are skipped, and hunk line counts are not trusted since hand-written diffs often get
+/// them wrong.
+pub fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if is_file_header(&lines, i) {
+            files.push(FilePatch {
+                old_path: parse_header_path(line.strip_prefix("--- ").unwrap_or(line), "a/"),
+                new_path: parse_header_path(
+                    lines[i + 1].strip_prefix("+++ ").unwrap_or(lines[i + 1]),
+                    "b/",
+                ),
+                hunks: Vec::new(),
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let file = files
+                .last_mut()
+                .ok_or_else(|| format!("Hunk `{line}` appears before any --- / +++ file header"))?;
+            let old_start =
+                parse_hunk_start(line).ok_or_else(|| format!("Malformed hunk header `{line}`"))?;
+
+            let mut hunk_lines = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].starts_with("@@") && !is_file_header(&lines, i) {
+                let body = lines[i];
+                if body.starts_with("diff ") {
+                    break;
+                }
+                let mut chars = body.chars();
+                let marker = chars.next();
+                let text = chars.as_str().to_string();
+                match marker {
+                    Some('+') => hunk_lines.push(HunkLine::Add(text)),
+                    Some('-') => hunk_lines.push(HunkLine::Remove(text)),
+                    Some(' ') => hunk_lines.push(HunkLine::Context(text)),
+                    // "\ No newline at end of file"
+                    Some('\\') => {}
+                    // Editors and models often strip the space from blank context lines
+                    None => hunk_lines.push(HunkLine::Context(String::new())),
+                    Some(_) => break,
+                }
+                i += 1;
+            }
+            while matches!(hunk_lines.last(), Some(HunkLine::Context(text)) if text.is_empty()) {
+                hunk_lines.pop();
+            }
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                old_start,
+                lines: hunk_lines,
+            });
+            continue;
+        }
+
+        i += 1;
+    }
+
+    if files.is_empty() {
+        return Err(
+            "No file headers found. Expected a unified diff with --- and +++ lines.".into(),
+        );
+    }
+    if let Some(file) = files
+        .iter()
+        .find(|f| f.hunks.is_empty() && f.old_path.is_some() && f.new_path.is_some())
+    {
+        return Err(format!("Patch for {} contains no hunks", file.path()));
+    }
+    Ok(files)
+}
+
+fn is_file_header(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("--- ")
+        && lines
+            .get(i + 1)
+            .is_some_and(|next| next.starts_with("+++ "))
+}
+
+fn parse_header_path(raw: &str, prefix: &str) -> Option<String> {
+    // Drop the timestamp some diff tools append after a tab
+    let path = raw.split('\t').next().unwrap_or(raw).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(path.strip_prefix(prefix).unwrap_or(path).to_string())
+}
+
+fn parse_hunk_start(header: &str) -> Option<usize> {
+    let old = header.strip_prefix("@@")?.trim_start().strip_prefix('-')?;
+    let digits: String = old.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// How closely a hunk's context had to be matched to place it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fuzz {
+    Exact,
+    TrailingWhitespace,
+    Whitespace,
+}
+
+impl Fuzz {
+    fn matches(self, file_line: &str, patch_line: &str) -> bool {
+        match self {
+            Fuzz::Exact => file_line == patch_line,
+            Fuzz::TrailingWhitespace => file_line.trim_end() == patch_line.trim_end(),
+            Fuzz::Whitespace => file_line
+                .split_whitespace()
+                .eq(patch_line.split_whitespace()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkOutcome {
+    Applied {
+        /// One-based line in the original file where the hunk was placed.
+        line: usize,
+        /// Distance from the line the hunk header asked for.
+        offset: isize,
+        fuzz: Fuzz,
+    },
+    Failed,
+}
+
+impl fmt::Display for HunkOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HunkOutcome::Applied { line, offset, fuzz } => {
+                write!(f, "applied at line {line}")?;
+                let mut notes = Vec::new();
+                if *offset != 0 {
+                    notes.push(format!("offset {offset:+}"));
+                }
+                match fuzz {
+                    Fuzz::Exact => {}
+                    Fuzz::TrailingWhitespace => {
+                        notes.push("ignoring trailing whitespace".to_string())
+                    }
+                    Fuzz::Whitespace => notes.push("ignoring whitespace".to_string()),
+                }
+                if !notes.is_empty() {
+                    write!(f, " ({})", notes.join(", "))?;
+                }
+                Ok(())
+            }
+            HunkOutcome::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// Applies hunks in order to `original`. Each hunk is searched for outward from where its
+/// header says it should be, first exactly and then ignoring whitespace differences. Returns
+/// the new lines, or `None` if any hunk could not be placed, alongside each hunk's outcome.
+pub fn apply_hunks(original: &[String], hunks: &[Hunk]) -> (Option<Vec<String>>, Vec<HunkOutcome>) {
+    let mut output = Vec::with_capacity(original.len());
+    let mut outcomes = Vec::with_capacity(hunks.len());
+    let mut cursor = 0;
+    let mut drift: isize = 0;
+    let mut failed = false;
+
+    for hunk in hunks {
+        let old = hunk.old_lines();
+        let expected =
+            (hunk.old_start.saturating_sub(1) as isize + drift).max(cursor as isize) as usize;
+
+        let Some((start, fuzz)) = locate(original, &old, cursor, expected) else {
+            outcomes.push(HunkOutcome::Failed);
+            failed = true;
+            continue;
+        };
+        let offset = start as isize - hunk.old_start.saturating_sub(1) as isize;
+        drift = offset;
+        outcomes.push(HunkOutcome::Applied {
+            line: start + 1,
+            offset,
+            fuzz,
+        });
+
+        output.extend_from_slice(&original[cursor..start]);
+        let mut position = start;
+        for line in &hunk.lines {
+            match line {
+                // Keep the file's own version of context lines in case they only matched fuzzily
+                HunkLine::Context(_) => {
+                    output.push(original[position].clone());
+                    position += 1;
+                }
+                HunkLine::Remove(_) => position += 1,
+                HunkLine::Add(text) => output.push(text.clone()),
+            }
+        }
+        cursor = position;
+    }
+
+    if failed {
+        return (None, outcomes);
+    }
+    output.extend_from_slice(&original[cursor..]);
+    (Some(output), outcomes)
+}
+
+fn locate(original: &[String], old: &[&str], min: usize, expected: usize) -> Option<(usize, Fuzz)> {
+    if old.is_empty() {
+        return Some((expected.min(original.len()), Fuzz::Exact));
+    }
+    if original.len() < old.len() {
+        return None;
+    }
+    let last = original.len() - old.len();
+    if min > last {
+        return None;
+    }
+    let expected = expected.clamp(min, last);
+
+    for fuzz in [Fuzz::Exact, Fuzz::TrailingWhitespace, Fuzz::Whitespace] {
+        let matches_at = |start: usize| {
+            old.iter()
+                .enumerate()
+                .all(|(i, line)| fuzz.matches(&original[start + i], line))
+        };
+        for distance in 0..=(last - min) {
+            let before = expected.checked_sub(distance).filter(|s| *s >= min);
+            let after = Some(expected + distance).filter(|s| *s <= last && distance > 0);
+            if before.is_none() && after.is_none() {
+                break;
+            }
+            if let Some(start) = before.into_iter().chain(after).find(|s| matches_at(*s)) {
+                return Some((start, fuzz));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_git_style_multi_file_patch() {
+        let patch = "diff --git a/src/a.rs b/src/a.rs\n\
+                     index 123..456 100644\n\
+                     --- a/src/a.rs\n\
+                     +++ b/src/a.rs\n\
+                     @@ -1,2 +1,2 @@\n \
+                     keep\n\
+                     -old\n\
+                     +new\n\
+                     --- /dev/null\n\
+                     +++ b/src/new.rs\n\
+                     @@ -0,0 +1 @@\n\
+                     +created\n";
+
+        let files = parse_patch(patch).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path(), "src/a.rs");
+        assert_eq!(files[0].hunks[0].old_start, 1);
+        assert_eq!(
+            files[0].hunks[0].lines,
+            vec![
+                HunkLine::Context("keep".into()),
+                HunkLine::Remove("old".into()),
+                HunkLine::Add("new".into()),
+            ]
+        );
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].path(), "src/new.rs");
+    }
+
+    #[test]
+    fn rejects_text_without_headers() {
+        assert!(parse_patch("just some text").is_err());
+    }
+
+    #[test]
+    fn applies_hunks_with_offset_and_whitespace_drift() {
+        let original = lines("a\nb\nc\nd\n    indented  \ne\nf");
+        let patch = "--- a/f\n+++ b/f\n\
+                     @@ -1,2 +1,2 @@\n \
+                     b\n\
+                     -c\n\
+                     +C\n\
+                     @@ -3,2 +3,2 @@\n \
+                     indented\n\
+                     -e\n\
+                     +E\n";
+        let files = parse_patch(patch).unwrap();
+
+        let (result, outcomes) = apply_hunks(&original, &files[0].hunks);
+        assert_eq!(result.unwrap(), lines("a\nb\nC\nd\n    indented  \nE\nf"));
+        assert_eq!(
+            outcomes,
+            vec![
+                HunkOutcome::Applied {
+                    line: 2,
+                    offset: 1,
+                    fuzz: Fuzz::Exact
+                },
+                HunkOutcome::Applied {
+                    line: 5,
+                    offset: 2,
+                    fuzz: Fuzz::Whitespace
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_each_failed_hunk() {
+        let original = lines("a\nb\nc");
+        let patch = "--- a/f\n+++ b/f\n\
+                     @@ -1 +1 @@\n\
+                     -missing\n\
+                     +x\n\
+                     @@ -3 +3 @@\n\
+                     -c\n\
+                     +C\n";
+        let files = parse_patch(patch).unwrap();
+
+        let (result, outcomes) = apply_hunks(&original, &files[0].hunks);
+        assert!(result.is_none());
+        assert_eq!(outcomes[0], HunkOutcome::Failed);
+        assert!(matches!(outcomes[1], HunkOutcome::Applied { line: 3, .. }));
+    }
+}
@@ -0,0 +1,759 @@
+//! Execution backends for the developer tools.
+//!
+//! Commands run directly on the host unless a [`SandboxPolicy`] is in effect for the session,
+//! in which case they run under bubblewrap (`bwrap`) on Linux: the filesystem is read-only apart
+//! from the working directory, a private `/tmp` and any extra writable paths, the network can be
+//! cut off, and rlimits cap CPU time and memory. File tools are held to the same write rules.
+//!
+//! When the agent has a [`Container`], commands run in it through the container runtime
+//! instead, and file paths are translated through its mount.
+
+use crate::agents::Container;
+use crate::config::{Config, GooseMode};
+use crate::session::extension_data::ExtensionState;
+use crate::session::Session;
+use rmcp::model::{CallToolResult, RawContent};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use utoipa::ToSchema;
+
+pub const SANDBOX_CONFIG_KEY: &str = "GOOSE_SANDBOX";
+
+/// What a sandboxed command may do. Set globally with `GOOSE_SANDBOX`, in a recipe's
+/// `settings.sandbox`, or for a single session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SandboxPolicy {
+    pub enabled: bool,
+    /// Only sandbox in these goose modes (`auto`, `approve`, `smart_approve`, `chat`); all
+    /// modes when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub modes: Vec<GooseMode>,
+    /// Cut sandboxed commands off from the network
+    pub block_network: bool,
+    /// Writable in addition to the working directory
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub writable_paths: Vec<PathBuf>,
+    /// Address space limit per process in megabytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+    /// CPU time limit per process in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_secs: Option<u64>,
+    /// Wall clock limit per command in seconds, applied on top of the tool's own timeout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl ExtensionState for SandboxPolicy {
+    const EXTENSION_NAME: &'static str = "sandbox";
+    const VERSION: &'static str = "v0";
+}
+
+impl SandboxPolicy {
+    /// `GOOSE_SANDBOX` accepts either a full policy or just `true`.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .get_param::<SandboxPolicy>(SANDBOX_CONFIG_KEY)
+            .ok()
+            .or_else(|| {
+                config
+                    .get_param::<bool>(SANDBOX_CONFIG_KEY)
+                    .ok()
+                    .map(|enabled| SandboxPolicy {
+                        enabled,
+                        ..Default::default()
+                    })
+            })
+    }
+
+    /// The session's own policy, else the recipe's, held to the configured one. Session data
+    /// and recipes are not trusted to loosen the sandbox, so the configured policy is a floor
+    /// they can only tighten.
+    pub fn for_session(session: &Session, config: &Config) -> Option<Self> {
+        let recipe = session
+            .recipe
+            .as_ref()
+            .and_then(|recipe| recipe.settings.as_ref())
+            .and_then(|settings| settings.sandbox.clone());
+        let requested = SandboxPolicy::from_extension_data(&session.extension_data).or(recipe);
+        match (Self::from_config(config), requested) {
+            (Some(floor), Some(requested)) => Some(floor.tightened_by(requested)),
+            (floor, requested) => floor.or(requested),
+        }
+    }
+
+    /// This policy with `other` applied on top, where `other` may only make it stricter: it
+    /// can sandbox more modes and lower limits, but not disable the sandbox, reconnect the
+    /// network or make paths writable outside this policy's writable paths.
+    pub fn tightened_by(self, other: SandboxPolicy) -> Self {
+        if !other.enabled {
+            return self;
+        }
+        if !self.enabled {
+            return other;
+        }
+        let modes = if self.modes.is_empty() || other.modes.is_empty() {
+            Vec::new()
+        } else {
+            let mut modes = self.modes.clone();
+            modes.extend(other.modes.into_iter().filter(|m| !self.modes.contains(m)));
+            modes
+        };
+        let writable_paths = other
+            .writable_paths
+            .into_iter()
+            .filter(|path| {
+                let path = normalize(path);
+                self.writable_paths
+                    .iter()
+                    .any(|root| path.starts_with(normalize(root)))
+            })
+            .collect();
+        let lower = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        SandboxPolicy {
+            enabled: true,
+            modes,
+            block_network: self.block_network || other.block_network,
+            writable_paths,
+            memory_mb: lower(self.memory_mb, other.memory_mb),
+            cpu_secs: lower(self.cpu_secs, other.cpu_secs),
+            timeout_secs: lower(self.timeout_secs, other.timeout_secs),
+        }
+    }
+
+    pub fn applies_to(&self, mode: GooseMode) -> bool {
+        self.enabled && (self.modes.is_empty() || self.modes.contains(&mode))
+    }
+
+    fn writable_roots(&self, working_dir: &Path) -> Vec<PathBuf> {
+        std::iter::once(working_dir.to_path_buf())
+            .chain(self.writable_paths.iter().cloned())
+            .map(|path| normalize(&path))
+            .collect()
+    }
+
+    /// Arguments for `bwrap`, up to and including the `--` before the command.
+    fn bubblewrap_args(&self, working_dir: &Path) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "--ro-bind",
+            "/",
+            "/",
+            "--dev",
+            "/dev",
+            "--proc",
+            "/proc",
+            "--tmpfs",
+            "/tmp",
+        ]
+        .map(String::from)
+        .to_vec();
+        for root in self.writable_roots(working_dir) {
+            if root.exists() {
+                let root = root.to_string_lossy().into_owned();
+                args.extend(["--bind".to_string(), root.clone(), root]);
+            }
+        }
+        if self.block_network {
+            args.push("--unshare-net".to_string());
+        }
+        let chdir = working_dir.to_string_lossy();
+        args.extend(
+            [
+                "--unshare-pid",
+                "--die-with-parent",
+                "--chdir",
+                &chdir,
+                "--",
+            ]
+            .map(String::from),
+        );
+        args
+    }
+}
+
+/// Where the developer tools run commands and write files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ExecutionBackend {
+    #[default]
+    Host,
+    Sandbox(SandboxPolicy),
+    Container(Container),
+}
+
+/// Where a file tool should read and write a path.
+#[derive(Debug, PartialEq)]
+pub enum FileTarget<'a> {
+    /// On the host, which includes container paths under the container's mount
+    Host(PathBuf),
+    /// Only reachable by running commands in the container
+    Container(&'a Container, PathBuf),
+}
+
+impl ExecutionBackend {
+    pub fn new(policy: Option<SandboxPolicy>, mode: GooseMode) -> Self {
+        match policy {
+            Some(policy) if policy.applies_to(mode) => ExecutionBackend::Sandbox(policy),
+            _ => ExecutionBackend::Host,
+        }
+    }
+
+    /// The timeout to enforce: the shorter of the one asked for and the policy's limit.
+    pub fn timeout_secs(&self, requested: Option<u64>) -> Option<u64> {
+        let requested = requested.filter(|secs| *secs > 0);
+        match self {
+            ExecutionBackend::Host | ExecutionBackend::Container(_) => requested,
+            ExecutionBackend::Sandbox(policy) => match (requested, policy.timeout_secs) {
+                (Some(requested), Some(limit)) => Some(requested.min(limit)),
+                (requested, limit) => requested.or(limit),
+            },
+        }
+    }
+
+    /// Starts a command line for `program`, wrapped in the sandbox or the container when
+    /// there is one.
+    pub fn command(
+        &self,
+        program: &str,
+        working_dir: Option<&Path>,
+    ) -> Result<tokio::process::Command, String> {
+        match self {
+            ExecutionBackend::Host => Ok(tokio::process::Command::new(program)),
+            ExecutionBackend::Sandbox(policy) => sandboxed_command(policy, program, working_dir),
+            ExecutionBackend::Container(container) => {
+                let mut command = container.exec_command(working_dir);
+                command.arg(program);
+                Ok(command)
+            }
+        }
+    }
+
+    /// Resolves a path given to a file tool. In a container, relative paths and absolute
+    /// container paths under the mount map to the host directory behind it; anything else
+    /// has to be reached through the container. A path that climbs out of the mount through
+    /// `..`, or through a symlink the container planted under it, is refused, as it would
+    /// otherwise name a host file outside it.
+    pub fn locate(&self, path: &str, working_dir: Option<&Path>) -> Result<FileTarget<'_>, String> {
+        let ExecutionBackend::Container(container) = self else {
+            return Ok(FileTarget::Host(super::edit::resolve_path(
+                path,
+                working_dir,
+            )));
+        };
+        let path = PathBuf::from(path);
+        let in_container = if path.is_absolute() {
+            Some(path.clone())
+        } else {
+            working_dir
+                .and_then(|dir| container.to_container_path(dir))
+                .map(|dir| dir.join(&path))
+        };
+        let Some(in_container) = in_container else {
+            return Ok(FileTarget::Container(container, path));
+        };
+        if container.leaves_mount(&in_container) {
+            return Err(format!(
+                "Error: {} is outside the directory mounted into container {}.",
+                path.display(),
+                container.id()
+            ));
+        }
+        Ok(match container.to_host_path(&in_container) {
+            Some(host) => {
+                self.check_mounted(&host)?;
+                FileTarget::Host(host)
+            }
+            None => FileTarget::Container(container, in_container),
+        })
+    }
+
+    /// Checks that a host path under the container mount still lands inside it once symlinks
+    /// are followed. Anything that can run in the container can create links under the mount,
+    /// and the host would follow them when reading or writing the file.
+    pub fn check_mounted(&self, host_path: &Path) -> Result<(), String> {
+        let ExecutionBackend::Container(container) = self else {
+            return Ok(());
+        };
+        let Some(mount) = container.mount() else {
+            return Ok(());
+        };
+        let root = normalize(&mount.host);
+        if resolve(host_path).is_some_and(|resolved| resolved.starts_with(&root)) {
+            return Ok(());
+        }
+        Err(format!(
+            "Error: {} resolves outside the directory mounted into container {}.",
+            host_path.display(),
+            container.id()
+        ))
+    }
+
+    /// Rewrites host paths under the container mount in a file tool's output to where they
+    /// appear inside the container, which is what the model works with.
+    pub fn container_view(&self, mut result: CallToolResult) -> CallToolResult {
+        let Some(mount) = (match self {
+            ExecutionBackend::Container(container) => container.mount(),
+            _ => None,
+        }) else {
+            return result;
+        };
+        let host = mount.host.display().to_string();
+        let container = mount.container.display().to_string();
+        for content in result.content.iter_mut() {
+            if let RawContent::Text(text) = &mut content.raw {
+                text.text = text.text.replace(&host, &container);
+            }
+        }
+        result
+    }
+
+    /// Rejects writes the sandbox would not allow, so file tools fail the same way shell
+    /// commands do.
+    pub fn check_write(&self, path: &Path, working_dir: Option<&Path>) -> Result<(), String> {
+        let ExecutionBackend::Sandbox(policy) = self else {
+            return Ok(());
+        };
+        let working_dir = current_dir_or(working_dir);
+        let roots = policy.writable_roots(&working_dir);
+        if resolve(path).is_some_and(|target| roots.iter().any(|root| target.starts_with(root))) {
+            return Ok(());
+        }
+        Err(format!(
+            "Sandbox policy violation: {} is outside the writable paths ({}). \
+             Write inside the working directory or add the path to the sandbox's writable_paths.",
+            path.display(),
+            roots
+                .iter()
+                .map(|root| root.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    pub fn timeout_message(&self, requested: Option<u64>) -> String {
+        let requested = requested.filter(|secs| *secs > 0);
+        if let ExecutionBackend::Sandbox(SandboxPolicy {
+            timeout_secs: Some(limit),
+            ..
+        }) = self
+        {
+            if requested.is_none_or(|requested| requested > *limit) {
+                return format!(
+                    "Sandbox policy violation: the command exceeded the sandbox time limit of {limit} seconds"
+                );
+            }
+        }
+        match requested {
+            Some(secs) => format!("Command timed out after {secs} seconds"),
+            None => "Command timed out".to_string(),
+        }
+    }
+
+    /// Explains a failed command when its output shows it ran into the sandbox policy or
+    /// could not reach the container.
+    pub fn explain_failure(&self, stderr: &str, exit_code: Option<i32>) -> Option<String> {
+        let policy = match self {
+            ExecutionBackend::Host => return None,
+            ExecutionBackend::Container(container) => {
+                return explain_container_failure(container, stderr)
+            }
+            ExecutionBackend::Sandbox(policy) => policy,
+        };
+        let stderr = stderr.to_lowercase();
+        if stderr.contains("read-only file system") {
+            return Some(
+                "Sandbox policy violation: the command tried to write outside the working directory, \
+                 which is read-only in the sandbox. /tmp is writable but private to each command."
+                    .to_string(),
+            );
+        }
+        if policy.block_network
+            && [
+                "could not resolve host",
+                "temporary failure in name resolution",
+                "network is unreachable",
+                "name or service not known",
+            ]
+            .iter()
+            .any(|needle| stderr.contains(needle))
+        {
+            return Some(
+                "Sandbox policy violation: network access is blocked for sandboxed commands."
+                    .to_string(),
+            );
+        }
+        // Shells report a child killed by SIGXCPU as 128 + 24
+        if policy.cpu_secs.is_some()
+            && (exit_code == Some(152) || stderr.contains("cpu time limit"))
+        {
+            return policy.cpu_secs.map(|secs| {
+                format!("Sandbox policy violation: the command exceeded the CPU time limit of {secs} seconds.")
+            });
+        }
+        if policy.memory_mb.is_some()
+            && [
+                "cannot allocate memory",
+                "out of memory",
+                "memory allocation",
+                "bad_alloc",
+            ]
+            .iter()
+            .any(|needle| stderr.contains(needle))
+        {
+            return policy.memory_mb.map(|mb| {
+                format!("Sandbox policy violation: the command ran out of memory under the sandbox limit of {mb} MB.")
+            });
+        }
+        None
+    }
+}
+
+fn explain_container_failure(container: &Container, stderr: &str) -> Option<String> {
+    let stderr = stderr.to_lowercase();
+    if stderr.contains("no such container") || stderr.contains("is not running") {
+        return Some(format!(
+            "Container {} is not running. Start it again with `{} start {}` or run goose without a container.",
+            container.id(),
+            container.runtime(),
+            container.id()
+        ));
+    }
+    None
+}
+
+fn current_dir_or(working_dir: Option<&Path>) -> PathBuf {
+    working_dir
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+/// Symlinks followed before a path is given up on, as in the kernel's own limit.
+const MAX_SYMLINKS: usize = 40;
+
+fn normalize(path: &Path) -> PathBuf {
+    resolve(path).unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Resolves symlinks in the part of the path that exists, including dangling ones a write
+/// would follow, and `..` in the rest, so a path cannot escape a root through either. `None`
+/// when the links loop.
+pub(crate) fn resolve(path: &Path) -> Option<PathBuf> {
+    resolve_links(path, MAX_SYMLINKS)
+}
+
+fn resolve_links(path: &Path, links_left: usize) -> Option<PathBuf> {
+    let mut existing = path.to_path_buf();
+    let mut rest = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name.to_os_string());
+                existing = parent.to_path_buf();
+            }
+            _ => break,
+        }
+    }
+    let mut normalized = match existing.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(_) => match std::fs::read_link(&existing) {
+            Ok(target) => {
+                let parent = existing.parent().unwrap_or(Path::new("/"));
+                resolve_links(&parent.join(target), links_left.checked_sub(1)?)?
+            }
+            Err(_) => existing,
+        },
+    };
+    for part in rest.iter().rev() {
+        match Path::new(part).components().next() {
+            Some(Component::ParentDir) => {
+                normalized.pop();
+            }
+            Some(Component::CurDir) | None => {}
+            Some(_) => normalized.push(part),
+        }
+    }
+    Some(normalized)
+}
+
+#[cfg(target_os = "linux")]
+fn sandboxed_command(
+    policy: &SandboxPolicy,
+    program: &str,
+    working_dir: Option<&Path>,
+) -> Result<tokio::process::Command, String> {
+    let bwrap = which::which("bwrap").map_err(|_| {
+        "The sandbox needs bubblewrap (bwrap), which was not found on PATH. Install it \
+         (for example `apt install bubblewrap`) or disable GOOSE_SANDBOX to run commands on the host."
+            .to_string()
+    })?;
+    let working_dir = current_dir_or(working_dir);
+    let mut command = tokio::process::Command::new(bwrap);
+    command
+        .args(policy.bubblewrap_args(&working_dir))
+        .arg(program);
+
+    let memory = policy.memory_mb.map(|mb| mb.saturating_mul(1024 * 1024));
+    let cpu = policy.cpu_secs;
+    if memory.is_some() || cpu.is_some() {
+        // Limits set here are inherited by bwrap and everything the command starts.
+        // SAFETY: the closure runs in the forked child before exec, where only
+        // async-signal-safe calls are allowed. It only calls `setrlimit` and reads errno
+        // through `last_os_error`, with the limits built on the stack from copied integers, so
+        // it neither allocates nor takes locks. Keep it that way.
+        unsafe {
+            command.pre_exec(move || {
+                let limit = |value: u64| libc::rlimit {
+                    rlim_cur: value,
+                    rlim_max: value,
+                };
+                if let Some(bytes) = memory {
+                    if libc::setrlimit(libc::RLIMIT_AS, &limit(bytes)) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                if let Some(secs) = cpu {
+                    if libc::setrlimit(libc::RLIMIT_CPU, &limit(secs)) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+    Ok(command)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sandboxed_command(
+    _policy: &SandboxPolicy,
+    _program: &str,
+    _working_dir: Option<&Path>,
+) -> Result<tokio::process::Command, String> {
+    Err("The sandbox is only available on Linux. Disable GOOSE_SANDBOX to run commands on the host."
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sandbox(policy: SandboxPolicy) -> ExecutionBackend {
+        ExecutionBackend::Sandbox(SandboxPolicy {
+            enabled: true,
+            ..policy
+        })
+    }
+
+    #[test]
+    fn test_policy_modes() {
+        let policy = SandboxPolicy {
+            enabled: true,
+            modes: vec![GooseMode::Auto],
+            ..Default::default()
+        };
+        assert!(policy.applies_to(GooseMode::Auto));
+        assert!(!policy.applies_to(GooseMode::Approve));
+        assert!(!SandboxPolicy::default().applies_to(GooseMode::Auto));
+        assert_eq!(
+            ExecutionBackend::new(Some(policy.clone()), GooseMode::Approve),
+            ExecutionBackend::Host
+        );
+        assert_eq!(
+            ExecutionBackend::new(Some(policy.clone()), GooseMode::Auto),
+            ExecutionBackend::Sandbox(policy)
+        );
+
+        let parsed: SandboxPolicy =
+            serde_yaml::from_str("enabled: true\nmodes: [smart_approve]\nblock_network: true")
+                .unwrap();
+        assert_eq!(parsed.modes, vec![GooseMode::SmartApprove]);
+        assert!(parsed.block_network);
+    }
+
+    #[test]
+    fn test_configured_policy_is_a_floor() {
+        let floor = SandboxPolicy {
+            enabled: true,
+            modes: vec![GooseMode::Auto],
+            writable_paths: vec![PathBuf::from("/data")],
+            memory_mb: Some(1024),
+            timeout_secs: Some(60),
+            ..Default::default()
+        };
+
+        let disabled = SandboxPolicy {
+            writable_paths: vec![PathBuf::from("/")],
+            ..Default::default()
+        };
+        assert_eq!(floor.clone().tightened_by(disabled), floor);
+
+        let looser = SandboxPolicy {
+            enabled: true,
+            modes: vec![GooseMode::Approve],
+            block_network: true,
+            writable_paths: vec![PathBuf::from("/data/cache"), PathBuf::from("/etc")],
+            memory_mb: Some(4096),
+            cpu_secs: Some(30),
+            timeout_secs: None,
+        };
+        assert_eq!(
+            floor.clone().tightened_by(looser),
+            SandboxPolicy {
+                enabled: true,
+                modes: vec![GooseMode::Auto, GooseMode::Approve],
+                block_network: true,
+                writable_paths: vec![PathBuf::from("/data/cache")],
+                memory_mb: Some(1024),
+                cpu_secs: Some(30),
+                timeout_secs: Some(60),
+            }
+        );
+
+        let everywhere = SandboxPolicy {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(floor.tightened_by(everywhere).modes.is_empty());
+    }
+
+    #[test]
+    fn test_timeout_is_capped_by_policy() {
+        let backend = sandbox(SandboxPolicy {
+            timeout_secs: Some(60),
+            ..Default::default()
+        });
+        assert_eq!(backend.timeout_secs(None), Some(60));
+        assert_eq!(backend.timeout_secs(Some(10)), Some(10));
+        assert_eq!(backend.timeout_secs(Some(600)), Some(60));
+        assert_eq!(ExecutionBackend::Host.timeout_secs(Some(0)), None);
+
+        assert_eq!(
+            backend.timeout_message(Some(10)),
+            "Command timed out after 10 seconds"
+        );
+        assert!(backend
+            .timeout_message(None)
+            .contains("sandbox time limit of 60 seconds"));
+    }
+
+    #[test]
+    fn test_bubblewrap_args() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let root = root.to_string_lossy();
+        let policy = SandboxPolicy {
+            enabled: true,
+            block_network: true,
+            writable_paths: vec![PathBuf::from("/definitely/missing")],
+            ..Default::default()
+        };
+
+        let args = policy.bubblewrap_args(dir.path()).join(" ");
+        assert!(args.starts_with("--ro-bind / / --dev /dev --proc /proc --tmpfs /tmp"));
+        assert!(args.contains(&format!("--bind {root} {root}")));
+        assert!(args.contains("--unshare-net"));
+        assert!(!args.contains("/definitely/missing"));
+        assert!(args.ends_with(&format!("--chdir {} --", dir.path().display())));
+    }
+
+    #[test]
+    fn test_check_write() {
+        let dir = TempDir::new().unwrap();
+        let backend = sandbox(SandboxPolicy::default());
+        let working_dir = Some(dir.path());
+
+        assert!(backend
+            .check_write(&dir.path().join("src/new.rs"), working_dir)
+            .is_ok());
+        let error = backend
+            .check_write(&dir.path().join("../escape.txt"), working_dir)
+            .unwrap_err();
+        assert!(error.starts_with("Sandbox policy violation"));
+        assert!(backend
+            .check_write(Path::new("/etc/passwd"), working_dir)
+            .is_err());
+        assert!(ExecutionBackend::Host
+            .check_write(Path::new("/etc/passwd"), working_dir)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_locate_in_container() {
+        let backend = ExecutionBackend::Container(
+            Container::new("c1").with_mount("/home/me/project", "/workspace"),
+        );
+        let working_dir = Some(Path::new("/home/me/project/src"));
+
+        assert!(matches!(
+            backend.locate("main.rs", working_dir),
+            Ok(FileTarget::Host(path)) if path == Path::new("/home/me/project/src/main.rs")
+        ));
+        assert!(matches!(
+            backend.locate("/workspace/src/../README.md", working_dir),
+            Ok(FileTarget::Host(path)) if path == Path::new("/home/me/project/README.md")
+        ));
+        assert!(matches!(
+            backend.locate("/etc/hosts", working_dir),
+            Ok(FileTarget::Container(_, path)) if path == Path::new("/etc/hosts")
+        ));
+        assert!(backend.locate("../../../etc/x", working_dir).is_err());
+        assert!(backend
+            .locate("/workspace/../../etc/x", working_dir)
+            .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_locate_refuses_symlinks_out_of_mount() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::os::unix::fs::symlink("/", project.join("evil")).unwrap();
+        std::os::unix::fs::symlink("/nonexistent/x", project.join("dangling")).unwrap();
+        std::os::unix::fs::symlink("src", project.join("inside")).unwrap();
+        let backend =
+            ExecutionBackend::Container(Container::new("c1").with_mount(&project, "/workspace"));
+        let working_dir = Some(project.as_path());
+
+        assert!(backend.locate("evil/etc/x", working_dir).is_err());
+        assert!(backend
+            .locate("/workspace/evil/etc/x", working_dir)
+            .is_err());
+        assert!(backend.locate("dangling", working_dir).is_err());
+        assert!(matches!(
+            backend.locate("inside/main.rs", working_dir),
+            Ok(FileTarget::Host(path)) if path == project.join("inside/main.rs")
+        ));
+    }
+
+    #[test]
+    fn test_explain_failure() {
+        let backend = sandbox(SandboxPolicy {
+            block_network: true,
+            ..Default::default()
+        });
+        let explained = backend
+            .explain_failure(
+                "touch: cannot touch '/etc/x': Read-only file system",
+                Some(1),
+            )
+            .unwrap();
+        assert!(explained.contains("outside the working directory"));
+        assert!(backend
+            .explain_failure("curl: (6) Could not resolve host: example.com", Some(6))
+            .unwrap()
+            .contains("network access is blocked"));
+        assert_eq!(backend.explain_failure("no such file", Some(1)), None);
+        assert_eq!(
+            ExecutionBackend::Host.explain_failure("Read-only file system", Some(1)),
+            None
+        );
+    }
+}
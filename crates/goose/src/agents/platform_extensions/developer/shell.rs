@@ -1,15 +1,21 @@
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::OnceLock;
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
-use rmcp::model::{CallToolResult, Content};
+use rmcp::model::{CallToolResult, Content, JsonObject, Meta};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio_stream::{wrappers::SplitStream, StreamExt};
-
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin};
+use tokio::task::JoinHandle;
+use tokio_stream::{wrappers::SplitStream, Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+
+use super::sandbox::ExecutionBackend;
+use crate::agents::{ClientDelegate, Container};
 use crate::subprocess::SubprocessExt;
 
 const OUTPUT_LIMIT_LINES: usize = 2000;
@@ -18,11 +24,54 @@ const OUTPUT_PREVIEW_LINES: usize = 50;
 
 const OUTPUT_SLOTS: usize = 8;
 
+/// Result metadata key naming the client terminal a command ran in
+pub const TERMINAL_ID_META: &str = "terminal_id";
+
+/// Lines kept per background process; older output is dropped once a process exceeds this.
+const BACKGROUND_BUFFER_LINES: usize = 10_000;
+const KILL_GRACE: Duration = Duration::from_secs(2);
+/// How long an exited background process is kept around for its final output to be read.
+/// Pruning it also stops anything it left running in its process group.
+const EXITED_TTL: Duration = Duration::from_secs(30 * 60);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Deserialize, JsonSchema)]
 pub struct ShellParams {
     pub command: String,
     #[serde(default)]
     pub timeout_secs: Option<u64>,
+    /// Start the command in the background and return a handle immediately instead of
+    /// waiting for it to exit. Use for dev servers, watchers and long test runs.
+    #[serde(default)]
+    pub background: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShellOutputParams {
+    /// Handle returned when the background process was started.
+    pub handle: String,
+    /// Return the last N lines instead of the output produced since the previous read.
+    #[serde(default)]
+    pub tail: Option<usize>,
+    /// Wait up to this many seconds for new output or for the process to exit.
+    #[serde(default)]
+    pub wait_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShellInputParams {
+    pub handle: String,
+    /// Text written to the process stdin as-is. Include a trailing newline to submit a line.
+    #[serde(default)]
+    pub input: String,
+    /// Close stdin after writing, signalling end of input.
+    #[serde(default)]
+    pub close: bool,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ShellKillParams {
+    pub handle: String,
 }
 
 #[derive(Debug, Serialize, JsonSchema)]
@@ -36,6 +85,9 @@ pub struct ShellOutput {
     /// True if the command was killed because it exceeded the timeout.
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub timed_out: bool,
+    /// Handle of the background process, when the command was started with `background`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<String>,
 }
 
 /// Resolve the user's full PATH by running a login shell.
@@ -80,9 +132,80 @@ fn user_login_path() -> Option<&'static str> {
     CACHED.get_or_init(resolve_login_shell_path).as_deref()
 }
 
+/// Output collected from a background process, shared with the tasks that drive it.
+#[derive(Default)]
+struct OutputBuffer {
+    lines: VecDeque<(bool, String)>,
+    /// Lines discarded from the front once the buffer was full.
+    dropped: usize,
+    /// Set once the process has exited; the inner value is `None` if it was killed.
+    exit: Option<Option<i32>>,
+    exited_at: Option<Instant>,
+}
+
+impl OutputBuffer {
+    fn total(&self) -> usize {
+        self.dropped + self.lines.len()
+    }
+
+    fn push(&mut self, line: (bool, String)) {
+        if self.lines.len() == BACKGROUND_BUFFER_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Lines from absolute position `from` onwards, with the count of requested lines
+    /// that had already been dropped.
+    fn since(&self, from: usize) -> (Vec<(bool, String)>, usize) {
+        let skip = from.saturating_sub(self.dropped);
+        let missed = self.dropped.saturating_sub(from);
+        (self.lines.iter().skip(skip).cloned().collect(), missed)
+    }
+
+    fn status(&self) -> String {
+        match self.exit {
+            None => "running".to_string(),
+            Some(Some(code)) => format!("exited with code {code}"),
+            Some(None) => "killed".to_string(),
+        }
+    }
+}
+
+struct BackgroundProcess {
+    command: String,
+    pid: Option<u32>,
+    started_at: Instant,
+    output: Arc<Mutex<OutputBuffer>>,
+    /// Absolute line position up to which output has been returned.
+    read_cursor: usize,
+    stdin: Arc<tokio::sync::Mutex<Option<ChildStdin>>>,
+    kill: CancellationToken,
+    waiter: Option<JoinHandle<()>>,
+}
+
+impl Drop for BackgroundProcess {
+    fn drop(&mut self) {
+        self.kill.cancel();
+        // The supervisor kills and reaps the process, but when the agent is dropped along with
+        // its runtime it may never run again, so the process group is killed here as well.
+        // This happens even once the shell has exited: servers and watchers it started stay in
+        // its group, and the group id is not reused while any of them is alive.
+        // The child itself is killed on drop and reaped by tokio.
+        if let Some(pid) = self.pid {
+            kill_process_group(pid);
+        }
+    }
+}
+
 pub struct ShellTool {
     output_dir: tempfile::TempDir,
     call_index: AtomicUsize,
+    handle_index: AtomicUsize,
+    /// Background processes by session id, then by handle. Dropping the tool, which happens
+    /// when the agent of the session is dropped, kills them all.
+    processes: Mutex<HashMap<String, HashMap<String, BackgroundProcess>>>,
 }
 
 impl ShellTool {
@@ -90,27 +213,90 @@ impl ShellTool {
         Ok(Self {
             output_dir: tempfile::tempdir()?,
             call_index: AtomicUsize::new(0),
+            handle_index: AtomicUsize::new(0),
+            processes: Mutex::new(HashMap::new()),
         })
     }
 
     pub async fn shell(&self, params: ShellParams) -> CallToolResult {
-        self.shell_with_cwd(params, None).await
+        self.shell_with_cwd(params, None, &ExecutionBackend::Host)
+            .await
     }
 
     pub async fn shell_with_cwd(
         &self,
         params: ShellParams,
         working_dir: Option<&std::path::Path>,
+        backend: &ExecutionBackend,
     ) -> CallToolResult {
         if params.command.trim().is_empty() {
             return Self::error_result("Command cannot be empty.", None);
         }
 
-        let execution = match run_command(&params.command, params.timeout_secs, working_dir).await {
+        let timeout_secs = backend.timeout_secs(params.timeout_secs);
+        let execution = match run_command(&params.command, timeout_secs, working_dir, backend).await
+        {
             Ok(execution) => execution,
             Err(error) => return Self::error_result(&error, None),
         };
+        self.execution_result(&params, execution, backend)
+    }
+
+    /// Runs the command in the client's terminal, where the user can watch it. The terminal
+    /// hands back stdout and stderr as one stream, so everything is reported as stdout.
+    pub async fn shell_in_client(
+        &self,
+        session_id: &str,
+        params: ShellParams,
+        working_dir: Option<&std::path::Path>,
+        client: &dyn ClientDelegate,
+    ) -> CallToolResult {
+        if params.command.trim().is_empty() {
+            return Self::error_result("Command cannot be empty.", None);
+        }
 
+        let output = match client
+            .run_command(
+                session_id,
+                &params.command,
+                working_dir,
+                params.timeout_secs.filter(|secs| *secs > 0),
+            )
+            .await
+        {
+            Ok(output) => output,
+            Err(error) => {
+                return Self::error_result(
+                    &format!("Failed to run command in the client terminal: {error}"),
+                    None,
+                )
+            }
+        };
+        let execution = ExecutionOutput {
+            lines: output
+                .output
+                .lines()
+                .map(|line| (false, line.to_string()))
+                .collect(),
+            exit_code: output.exit_code,
+            timed_out: output.timed_out,
+        };
+
+        let mut result = self.execution_result(&params, execution, &ExecutionBackend::Host);
+        if let Some(terminal_id) = output.terminal_id {
+            let mut meta = JsonObject::new();
+            meta.insert(TERMINAL_ID_META.to_string(), terminal_id.into());
+            result.meta = Some(Meta(meta));
+        }
+        result
+    }
+
+    fn execution_result(
+        &self,
+        params: &ShellParams,
+        execution: ExecutionOutput,
+        backend: &ExecutionBackend,
+    ) -> CallToolResult {
         // Derive stdout, stderr, and interleaved display from the single tagged-line buffer
         let (raw_stdout, raw_stderr, interleaved) = split_lines(&execution.lines);
 
@@ -138,6 +324,7 @@ impl ShellTool {
             stderr: truncated_stderr,
             exit_code: execution.exit_code,
             timed_out: execution.timed_out,
+            handle: None,
         };
         let structured_content = serde_json::to_value(&shell_output).ok();
         let mut rendered = match render_output(&interleaved, &format!("output-{slot}"), output_dir)
@@ -147,14 +334,10 @@ impl ShellTool {
         };
 
         let is_error = if execution.timed_out {
-            if let Some(timeout_secs) = params.timeout_secs {
-                rendered.push_str(&format!(
-                    "\n\nCommand timed out after {} seconds",
-                    timeout_secs
-                ));
-            } else {
-                rendered.push_str("\n\nCommand timed out");
-            }
+            rendered.push_str(&format!(
+                "\n\n{}",
+                backend.timeout_message(params.timeout_secs)
+            ));
             true
         } else {
             execution.exit_code.unwrap_or(1) != 0
@@ -164,6 +347,9 @@ impl ShellTool {
             if let Some(code) = execution.exit_code.filter(|c| *c != 0) {
                 rendered.push_str(&format!("\n\nCommand exited with code {code}"));
             }
+            if let Some(explanation) = backend.explain_failure(&raw_stderr, execution.exit_code) {
+                rendered.push_str(&format!("\n\n{explanation}"));
+            }
             let mut result =
                 CallToolResult::error(vec![Content::text(rendered).with_priority(0.0)]);
             result.structured_content = structured_content;
@@ -181,11 +367,424 @@ impl ShellTool {
             stderr: message.to_string(),
             exit_code,
             timed_out: false,
+            handle: None,
         };
         let mut result = CallToolResult::error(vec![Content::text(message).with_priority(0.0)]);
         result.structured_content = serde_json::to_value(&shell_output).ok();
         result
     }
+
+    fn text_result(text: String, is_error: bool) -> CallToolResult {
+        let content = vec![Content::text(text).with_priority(0.0)];
+        if is_error {
+            CallToolResult::error(content)
+        } else {
+            CallToolResult::success(content)
+        }
+    }
+
+    fn next_slot(&self) -> usize {
+        self.call_index.fetch_add(1, Ordering::Relaxed) % OUTPUT_SLOTS
+    }
+
+    fn unknown_handle(&self, session_id: &str, handle: &str) -> CallToolResult {
+        let processes = self.processes.lock().unwrap();
+        let mut known: Vec<&String> = processes
+            .get(session_id)
+            .map(|session| session.keys().collect())
+            .unwrap_or_default();
+        known.sort();
+        let known = if known.is_empty() {
+            "none".to_string()
+        } else {
+            known
+                .into_iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        Self::text_result(
+            format!("Unknown process handle '{handle}'. Known handles: {known}"),
+            true,
+        )
+    }
+
+    /// Starts `params.command` without waiting for it and tracks it under `session_id`.
+    pub fn start_background(
+        &self,
+        session_id: &str,
+        params: ShellParams,
+        working_dir: Option<&std::path::Path>,
+        backend: &ExecutionBackend,
+    ) -> CallToolResult {
+        if params.command.trim().is_empty() {
+            return Self::error_result("Command cannot be empty.", None);
+        }
+        self.prune_exited(EXITED_TTL);
+
+        let (mut child, in_container) =
+            match spawn_command(&params.command, working_dir, true, backend) {
+                Ok(spawned) => spawned,
+                Err(error) => return Self::error_result(&error, None),
+            };
+        let stdin = child.stdin.take();
+        let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+            let _ = child.start_kill();
+            return Self::error_result("Failed to capture process output", None);
+        };
+
+        let output = Arc::new(Mutex::new(OutputBuffer::default()));
+        let kill = CancellationToken::new();
+        let pid = child.id();
+        let reader = tokio::spawn({
+            let output = output.clone();
+            async move {
+                let mut lines = tagged_lines(stdout, stderr);
+                while let Some((is_stderr, Ok(line))) = lines.next().await {
+                    let text = String::from_utf8_lossy(&line).into_owned();
+                    output.lock().unwrap().push((is_stderr, text));
+                }
+            }
+        });
+        let waiter = tokio::spawn(supervise(
+            child,
+            in_container,
+            reader,
+            output.clone(),
+            kill.clone(),
+            backend.timeout_secs(params.timeout_secs),
+        ));
+
+        let handle = format!(
+            "bg-{}",
+            self.handle_index.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let process = BackgroundProcess {
+            command: params.command.clone(),
+            pid,
+            started_at: Instant::now(),
+            output,
+            read_cursor: 0,
+            stdin: Arc::new(tokio::sync::Mutex::new(stdin)),
+            kill,
+            waiter: Some(waiter),
+        };
+        self.processes
+            .lock()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_default()
+            .insert(handle.clone(), process);
+
+        let message = format!(
+            "Started background process {handle}{pid}: {command}\n\
+             Use shell_output to read its output, shell_input to write to its stdin \
+             and shell_kill to stop it.",
+            pid = pid.map(|pid| format!(" (pid {pid})")).unwrap_or_default(),
+            command = params.command,
+        );
+        let shell_output = ShellOutput {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: None,
+            timed_out: false,
+            handle: Some(handle),
+        };
+        let mut result = Self::text_result(message, false);
+        result.structured_content = serde_json::to_value(&shell_output).ok();
+        result
+    }
+
+    /// Returns output a background process produced since the last read, or its last
+    /// `tail` lines. Exited processes are forgotten once their final output has been read.
+    pub async fn read_output(&self, session_id: &str, params: ShellOutputParams) -> CallToolResult {
+        let Some(output) = self.with_process(session_id, &params.handle, |p| p.output.clone())
+        else {
+            return self.unknown_handle(session_id, &params.handle);
+        };
+
+        if let Some(wait_secs) = params.wait_secs.filter(|_| params.tail.is_none()) {
+            let cursor = self
+                .with_process(session_id, &params.handle, |p| p.read_cursor)
+                .unwrap_or_default();
+            let deadline = Instant::now() + Duration::from_secs(wait_secs);
+            while Instant::now() < deadline {
+                {
+                    let buffer = output.lock().unwrap();
+                    if buffer.total() > cursor || buffer.exit.is_some() {
+                        break;
+                    }
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+
+        let mut processes = self.processes.lock().unwrap();
+        let Some(session) = processes.get_mut(session_id) else {
+            drop(processes);
+            return self.unknown_handle(session_id, &params.handle);
+        };
+        let Some(process) = session.get_mut(&params.handle) else {
+            drop(processes);
+            return self.unknown_handle(session_id, &params.handle);
+        };
+
+        let buffer = process.output.lock().unwrap();
+        let total = buffer.total();
+        let from = match params.tail {
+            Some(tail) => total.saturating_sub(tail),
+            None => process.read_cursor,
+        };
+        let (lines, missed) = buffer.since(from);
+        let status = buffer.status();
+        let finished = buffer.exit.is_some();
+        drop(buffer);
+        process.read_cursor = total;
+
+        let mut header = format!(
+            "{} {} after {}s",
+            params.handle,
+            status,
+            process.started_at.elapsed().as_secs()
+        );
+        if missed > 0 {
+            header.push_str(&format!(
+                " ({missed} earlier lines were dropped from the buffer)"
+            ));
+        }
+        if finished {
+            session.remove(&params.handle);
+            if session.is_empty() {
+                processes.remove(session_id);
+            }
+        }
+        drop(processes);
+
+        let (_, _, interleaved) = split_lines(&lines);
+        let body = if interleaved.is_empty() {
+            "(no new output)".to_string()
+        } else {
+            let slot = self.next_slot();
+            match truncate_output(
+                &interleaved,
+                &format!("output-{slot}"),
+                self.output_dir.path(),
+            ) {
+                Ok(body) => body,
+                Err(error) => return Self::text_result(error, true),
+            }
+        };
+        Self::text_result(format!("{header}\n\n{body}"), false)
+    }
+
+    /// Writes to the stdin of a background process, optionally closing it afterwards.
+    pub async fn write_input(&self, session_id: &str, params: ShellInputParams) -> CallToolResult {
+        let Some(stdin) = self.with_process(session_id, &params.handle, |p| p.stdin.clone()) else {
+            return self.unknown_handle(session_id, &params.handle);
+        };
+
+        let mut stdin = stdin.lock().await;
+        let Some(pipe) = stdin.as_mut() else {
+            return Self::text_result(format!("Stdin of {} is closed", params.handle), true);
+        };
+        let written = async {
+            pipe.write_all(params.input.as_bytes()).await?;
+            pipe.flush().await
+        }
+        .await;
+        if let Err(error) = written {
+            *stdin = None;
+            return Self::text_result(
+                format!("Failed to write to {}: {error}", params.handle),
+                true,
+            );
+        }
+        if params.close {
+            *stdin = None;
+        }
+
+        let mut message = format!("Wrote {} bytes to {}", params.input.len(), params.handle);
+        if params.close {
+            message.push_str(" and closed its stdin");
+        }
+        Self::text_result(message, false)
+    }
+
+    /// Kills a background process and returns whatever output had not been read yet.
+    pub async fn kill(&self, session_id: &str, params: ShellKillParams) -> CallToolResult {
+        let process = self
+            .processes
+            .lock()
+            .unwrap()
+            .get_mut(session_id)
+            .and_then(|session| session.remove(&params.handle));
+        let Some(mut process) = process else {
+            return self.unknown_handle(session_id, &params.handle);
+        };
+
+        process.kill.cancel();
+        if let Some(waiter) = process.waiter.take() {
+            let _ = tokio::time::timeout(KILL_GRACE, waiter).await;
+        }
+
+        let buffer = process.output.lock().unwrap();
+        let (lines, _) = buffer.since(process.read_cursor);
+        let status = buffer.status();
+        drop(buffer);
+
+        let (_, _, interleaved) = split_lines(&lines);
+        let mut message = format!("{} {status}", params.handle);
+        if !interleaved.is_empty() {
+            let slot = self.next_slot();
+            match truncate_output(
+                &interleaved,
+                &format!("output-{slot}"),
+                self.output_dir.path(),
+            ) {
+                Ok(body) => message.push_str(&format!("\n\nUnread output:\n{body}")),
+                Err(error) => return Self::text_result(error, true),
+            }
+        }
+        Self::text_result(message, false)
+    }
+
+    /// Forgets processes that exited more than `ttl` ago without their final output being read.
+    fn prune_exited(&self, ttl: Duration) {
+        let mut processes = self.processes.lock().unwrap();
+        for session in processes.values_mut() {
+            session.retain(|_, process| {
+                process
+                    .output
+                    .lock()
+                    .unwrap()
+                    .exited_at
+                    .is_none_or(|exited_at| exited_at.elapsed() < ttl)
+            });
+        }
+        processes.retain(|_, session| !session.is_empty());
+    }
+
+    /// One line per background process of the session, or `None` when there are none.
+    pub fn describe_processes(&self, session_id: &str) -> Option<String> {
+        self.prune_exited(EXITED_TTL);
+        let processes = self.processes.lock().unwrap();
+        let session = processes.get(session_id).filter(|s| !s.is_empty())?;
+
+        let mut handles: Vec<&String> = session.keys().collect();
+        handles.sort();
+        let mut lines = vec!["Background shell processes:".to_string()];
+        for handle in handles {
+            let process = &session[handle];
+            let buffer = process.output.lock().unwrap();
+            let unread = buffer.total().saturating_sub(process.read_cursor);
+            lines.push(format!(
+                "• {handle}{pid}: `{command}` - {status}, {unread} unread lines",
+                pid = process
+                    .pid
+                    .map(|pid| format!(" (pid {pid})"))
+                    .unwrap_or_default(),
+                command = process.command,
+                status = buffer.status(),
+            ));
+        }
+        Some(lines.join("\n"))
+    }
+
+    fn with_process<T>(
+        &self,
+        session_id: &str,
+        handle: &str,
+        f: impl FnOnce(&BackgroundProcess) -> T,
+    ) -> Option<T> {
+        let processes = self.processes.lock().unwrap();
+        processes.get(session_id)?.get(handle).map(f)
+    }
+}
+
+/// Waits for a background process to exit, killing it on request or after its timeout,
+/// and records the exit status once the process is gone.
+async fn supervise(
+    mut child: Child,
+    in_container: Option<ContainerCommand>,
+    reader: JoinHandle<()>,
+    output: Arc<Mutex<OutputBuffer>>,
+    kill: CancellationToken,
+    timeout_secs: Option<u64>,
+) {
+    let timeout = async {
+        match timeout_secs {
+            Some(secs) => tokio::time::sleep(Duration::from_secs(secs)).await,
+            None => std::future::pending().await,
+        }
+    };
+    let exit_code = tokio::select! {
+        status = child.wait() => status.ok().and_then(|status| status.code()),
+        _ = kill.cancelled() => {
+            kill_in_container(in_container.as_ref()).await;
+            kill_process_tree(&mut child);
+            let _ = child.wait().await;
+            None
+        }
+        _ = timeout => {
+            kill_in_container(in_container.as_ref()).await;
+            kill_process_tree(&mut child);
+            let _ = child.wait().await;
+            None
+        }
+    };
+    // Let the reader drain what the process wrote before exiting, unless something it
+    // spawned is still holding the pipes open.
+    let _ = tokio::time::timeout(KILL_GRACE, reader).await;
+    let mut output = output.lock().unwrap();
+    output.exit = Some(exit_code);
+    output.exited_at = Some(Instant::now());
+}
+
+/// Kills the process and, on unix, everything else in its process group.
+fn kill_process_tree(child: &mut Child) {
+    if let Some(pid) = child.id() {
+        kill_process_group(pid);
+    }
+    let _ = child.start_kill();
+}
+
+/// Background commands run in their own process group, so this also reaches servers and
+/// watchers started by the shell.
+fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    {
+        // SAFETY: killpg takes no pointers and touches no memory of this process; it only
+        // sends a signal, and fails with ESRCH when nothing is left in the group.
+        let result = unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+        if result != 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() != Some(libc::ESRCH) {
+                tracing::debug!("Failed to kill process group {}: {}", pid, error);
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// A command started in a container, where it keeps running when the local exec client
+/// is killed.
+struct ContainerCommand {
+    container: Container,
+    pid_file: String,
+}
+
+async fn kill_in_container(command: Option<&ContainerCommand>) {
+    let Some(command) = command else {
+        return;
+    };
+    if let Err(error) = command.container.kill_recorded(&command.pid_file).await {
+        tracing::warn!(
+            "Failed to kill command in container {}: {}",
+            command.container.id(),
+            error
+        );
+    }
 }
 
 struct ExecutionOutput {
@@ -199,24 +798,9 @@ async fn run_command(
     command_line: &str,
     timeout_secs: Option<u64>,
     working_dir: Option<&std::path::Path>,
+    backend: &ExecutionBackend,
 ) -> Result<ExecutionOutput, String> {
-    let mut command = build_shell_command(command_line);
-    if let Some(path) = working_dir {
-        command.current_dir(path);
-    }
-
-    #[cfg(not(windows))]
-    if let Some(path) = user_login_path() {
-        command.env("PATH", path);
-    }
-
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-    command.stdin(Stdio::null());
-
-    let mut child = command
-        .spawn()
-        .map_err(|error| format!("Failed to spawn shell command: {}", error))?;
+    let (mut child, in_container) = spawn_command(command_line, working_dir, false, backend)?;
 
     let child_stdout = child
         .stdout
@@ -237,6 +821,7 @@ async fn run_command(
                 .code(),
             Err(_) => {
                 timed_out = true;
+                kill_in_container(in_container.as_ref()).await;
                 let _ = child.start_kill();
                 let _ = child.wait().await;
                 None
@@ -262,10 +847,60 @@ async fn run_command(
     })
 }
 
-fn build_shell_command(command_line: &str) -> tokio::process::Command {
+fn spawn_command(
+    command_line: &str,
+    working_dir: Option<&std::path::Path>,
+    background: bool,
+    backend: &ExecutionBackend,
+) -> Result<(Child, Option<ContainerCommand>), String> {
+    let (mut command, in_container) = build_shell_command(command_line, working_dir, backend)?;
+    if let Some(path) = working_dir {
+        command.current_dir(path);
+    }
+
+    #[cfg(not(windows))]
+    if let Some(path) = user_login_path() {
+        command.env("PATH", path);
+    }
+
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+    if background {
+        command.stdin(Stdio::piped());
+        command.kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+    } else {
+        command.stdin(Stdio::null());
+    }
+
+    let child = command
+        .spawn()
+        .map_err(|error| format!("Failed to spawn shell command: {}", error))?;
+    Ok((child, in_container))
+}
+
+fn build_shell_command(
+    command_line: &str,
+    working_dir: Option<&std::path::Path>,
+    backend: &ExecutionBackend,
+) -> Result<(tokio::process::Command, Option<ContainerCommand>), String> {
+    // The host's shell says nothing about what the image ships, so containers get plain sh
+    if let ExecutionBackend::Container(container) = backend {
+        let pid_file = format!("/tmp/goose-{}.pid", uuid::Uuid::new_v4());
+        let command = container.exec_shell(command_line, working_dir, &pid_file);
+        return Ok((
+            command,
+            Some(ContainerCommand {
+                container: container.clone(),
+                pid_file,
+            }),
+        ));
+    }
+
     #[cfg(windows)]
     let mut command = {
-        let mut command = tokio::process::Command::new("cmd");
+        let mut command = backend.command("cmd", working_dir)?;
         command.arg("/C").arg(command_line);
         command
     };
@@ -277,13 +912,13 @@ fn build_shell_command(command_line: &str) -> tokio::process::Command {
         } else {
             std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string())
         };
-        let mut command = tokio::process::Command::new(shell);
+        let mut command = backend.command(&shell, working_dir)?;
         command.arg("-c").arg(command_line);
         command
     };
 
     command.set_no_window();
-    command
+    Ok((command, None))
 }
 
 /// Split tagged lines into (stdout, stderr, interleaved) strings.
@@ -318,9 +953,7 @@ async fn collect_tagged_lines(
     stdout: tokio::process::ChildStdout,
     stderr: tokio::process::ChildStderr,
 ) -> Result<Vec<(bool, String)>, std::io::Error> {
-    let stdout_lines = SplitStream::new(BufReader::new(stdout).split(b'\n')).map(|l| (false, l));
-    let stderr_lines = SplitStream::new(BufReader::new(stderr).split(b'\n')).map(|l| (true, l));
-    let mut merged = stdout_lines.merge(stderr_lines);
+    let mut merged = tagged_lines(stdout, stderr);
 
     let mut lines = Vec::new();
     while let Some((is_stderr, line)) = merged.next().await {
@@ -330,6 +963,16 @@ async fn collect_tagged_lines(
     Ok(lines)
 }
 
+/// Stream of raw lines from stdout and stderr in arrival order, tagged with `is_stderr`.
+fn tagged_lines(
+    stdout: tokio::process::ChildStdout,
+    stderr: tokio::process::ChildStderr,
+) -> impl Stream<Item = (bool, std::io::Result<Vec<u8>>)> + Unpin {
+    let stdout_lines = SplitStream::new(BufReader::new(stdout).split(b'\n')).map(|l| (false, l));
+    let stderr_lines = SplitStream::new(BufReader::new(stderr).split(b'\n')).map(|l| (true, l));
+    stdout_lines.merge(stderr_lines)
+}
+
 fn render_output(
     full_output: &str,
     label: &str,
@@ -408,6 +1051,7 @@ mod tests {
             .shell(ShellParams {
                 command: "echo hello".to_string(),
                 timeout_secs: None,
+                background: false,
             })
             .await;
 
@@ -423,6 +1067,7 @@ mod tests {
             .shell(ShellParams {
                 command: "echo fail && exit 7".to_string(),
                 timeout_secs: None,
+                background: false,
             })
             .await;
 
@@ -440,8 +1085,10 @@ mod tests {
                 ShellParams {
                     command: "pwd".to_string(),
                     timeout_secs: None,
+                    background: false,
                 },
                 Some(dir.path()),
+                &ExecutionBackend::Host,
             )
             .await;
 
@@ -451,6 +1098,280 @@ mod tests {
         assert_eq!(observed, expected);
     }
 
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn timed_out_container_command_is_killed_in_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let runtime = crate::agents::container::fake_runtime::install(dir.path());
+        let backend = ExecutionBackend::Container(
+            Container::new("c1").with_runtime(runtime.to_str().unwrap()),
+        );
+        let marker = dir.path().join("sleep.pid");
+        let tool = ShellTool::new().unwrap();
+
+        let result = tool
+            .shell_with_cwd(
+                ShellParams {
+                    command: format!("sleep 30 & echo $! > {}; wait", marker.display()),
+                    timeout_secs: Some(1),
+                    background: false,
+                },
+                None,
+                &backend,
+            )
+            .await;
+        assert_eq!(result.is_error, Some(true));
+
+        // The fake runtime runs the command on the host, where the kill has to reach it
+        let pid = std::fs::read_to_string(&marker).unwrap();
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).ok();
+        assert!(stat.is_none_or(|stat| stat.split_whitespace().nth(2) == Some("Z")));
+    }
+
+    fn background(command: &str) -> ShellParams {
+        ShellParams {
+            command: command.to_string(),
+            timeout_secs: None,
+            background: true,
+        }
+    }
+
+    fn started_handle(result: &CallToolResult) -> String {
+        assert_eq!(result.is_error, Some(false));
+        result.structured_content.as_ref().unwrap()["handle"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    async fn read(tool: &ShellTool, session: &str, handle: &str) -> String {
+        let result = tool
+            .read_output(
+                session,
+                ShellOutputParams {
+                    handle: handle.to_string(),
+                    tail: None,
+                    wait_secs: Some(5),
+                },
+            )
+            .await;
+        extract_text(&result).to_string()
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn background_process_streams_stdin_to_output() {
+        let tool = ShellTool::new().unwrap();
+        let handle = started_handle(&tool.start_background(
+            "s1",
+            background("cat"),
+            None,
+            &ExecutionBackend::Host,
+        ));
+
+        let result = tool
+            .write_input(
+                "s1",
+                ShellInputParams {
+                    handle: handle.clone(),
+                    input: "ping\n".to_string(),
+                    close: false,
+                },
+            )
+            .await;
+        assert_eq!(result.is_error, Some(false));
+        let output = read(&tool, "s1", &handle).await;
+        assert!(output.starts_with(&format!("{handle} running")));
+        assert!(output.ends_with("ping"));
+
+        // Handles are scoped to the session that started them
+        let other = read(&tool, "s2", &handle).await;
+        assert!(other.starts_with("Unknown process handle"));
+
+        tool.write_input(
+            "s1",
+            ShellInputParams {
+                handle: handle.clone(),
+                input: String::new(),
+                close: true,
+            },
+        )
+        .await;
+        let mut output = read(&tool, "s1", &handle).await;
+        while output.contains("running") {
+            output = read(&tool, "s1", &handle).await;
+        }
+        assert!(output.contains("exited with code 0"));
+
+        // Exited processes are forgotten once read
+        assert!(tool.describe_processes("s1").is_none());
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn background_process_can_be_killed() {
+        let tool = ShellTool::new().unwrap();
+        let handle = started_handle(&tool.start_background(
+            "s1",
+            background("echo started; sleep 30"),
+            None,
+            &ExecutionBackend::Host,
+        ));
+        assert!(tool.describe_processes("s1").unwrap().contains("sleep 30"));
+        // Wait for the echo so the kill has unread output to hand back
+        while !tool
+            .describe_processes("s1")
+            .unwrap()
+            .contains("1 unread lines")
+        {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let result = tool
+            .kill(
+                "s1",
+                ShellKillParams {
+                    handle: handle.clone(),
+                },
+            )
+            .await;
+        let text = extract_text(&result);
+        assert!(text.starts_with(&format!("{handle} killed")));
+        assert!(text.contains("started"));
+        assert!(tool.describe_processes("s1").is_none());
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn background_output_reads_incrementally() {
+        let tool = ShellTool::new().unwrap();
+        let handle = started_handle(&tool.start_background(
+            "s1",
+            background("printf 'one\\ntwo\\n'; sleep 30"),
+            None,
+            &ExecutionBackend::Host,
+        ));
+
+        let mut output = read(&tool, "s1", &handle).await;
+        while !output.contains("two") {
+            output.push_str(&read(&tool, "s1", &handle).await);
+        }
+        assert!(read(&tool, "s1", &handle)
+            .await
+            .ends_with("(no new output)"));
+
+        let tail = tool
+            .read_output(
+                "s1",
+                ShellOutputParams {
+                    handle: handle.clone(),
+                    tail: Some(1),
+                    wait_secs: None,
+                },
+            )
+            .await;
+        assert!(extract_text(&tail).ends_with("\n\ntwo"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropping_the_tool_kills_background_processes() {
+        let tool = ShellTool::new().unwrap();
+        let handle = started_handle(&tool.start_background(
+            "s1",
+            background("sleep 30"),
+            None,
+            &ExecutionBackend::Host,
+        ));
+        let pid = tool
+            .with_process("s1", &handle, |p| p.pid)
+            .flatten()
+            .unwrap();
+
+        drop(tool);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok();
+        assert!(stat.is_none_or(|stat| stat.split_whitespace().nth(2) == Some("Z")));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn dropping_the_tool_kills_what_an_exited_shell_left_running() {
+        let tool = ShellTool::new().unwrap();
+        let handle = started_handle(&tool.start_background(
+            "s1",
+            background("sleep 30 >/dev/null 2>&1 </dev/null & echo $!"),
+            None,
+            &ExecutionBackend::Host,
+        ));
+        let mut output = read(&tool, "s1", &handle).await;
+        let orphan = loop {
+            if let Some(pid) = output
+                .lines()
+                .find_map(|line| line.trim().parse::<u32>().ok())
+            {
+                break pid;
+            }
+            output.push_str(&read(&tool, "s1", &handle).await);
+        };
+        while tool
+            .with_process("s1", &handle, |p| p.output.lock().unwrap().exit.is_none())
+            .unwrap_or(false)
+        {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(std::fs::metadata(format!("/proc/{orphan}")).is_ok());
+
+        drop(tool);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let stat = std::fs::read_to_string(format!("/proc/{orphan}/stat")).ok();
+        assert!(stat.is_none_or(|stat| stat.split_whitespace().nth(2) == Some("Z")));
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn unread_exited_processes_are_pruned() {
+        let tool = ShellTool::new().unwrap();
+        for session in ["s1", "s2"] {
+            started_handle(&tool.start_background(
+                session,
+                background("echo done"),
+                None,
+                &ExecutionBackend::Host,
+            ));
+        }
+        let sleeper = started_handle(&tool.start_background(
+            "s1",
+            background("sleep 30"),
+            None,
+            &ExecutionBackend::Host,
+        ));
+
+        while !tool.describe_processes("s2").unwrap().contains("exited") {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        tool.prune_exited(Duration::ZERO);
+        assert!(tool.describe_processes("s2").is_none());
+        assert!(tool.describe_processes("s1").unwrap().contains(&sleeper));
+    }
+
+    #[test]
+    fn output_buffer_drops_oldest_lines() {
+        let mut buffer = OutputBuffer::default();
+        for i in 0..BACKGROUND_BUFFER_LINES + 5 {
+            buffer.push((false, i.to_string()));
+        }
+        assert_eq!(buffer.total(), BACKGROUND_BUFFER_LINES + 5);
+
+        let (lines, missed) = buffer.since(2);
+        assert_eq!(missed, 3);
+        assert_eq!(lines[0].1, "5");
+
+        let (lines, missed) = buffer.since(BACKGROUND_BUFFER_LINES + 4);
+        assert_eq!(missed, 0);
+        assert_eq!(lines.len(), 1);
+    }
+
     #[test]
     fn render_output_returns_full_output_when_under_limit() {
         let dir = tempfile::tempdir().unwrap();
@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 
+use crate::agents::Container;
 use ignore::WalkBuilder;
 use rmcp::model::{CallToolResult, Content};
 use schemars::JsonSchema;
@@ -18,6 +19,9 @@ fn default_depth() -> u32 {
     2
 }
 
+/// Lists `$1` down to depth `$2` (unlimited when empty) as `d <dir>` and `wc -l` lines.
+const CONTAINER_LIST_SCRIPT: &str = r#"find "$1" -mindepth 1 ${2:+-maxdepth "$2"} -name '.*' -prune -o -type d -exec printf 'd %s\n' {} + -o -type f -exec wc -l {} +"#;
+
 pub struct TreeTool;
 
 impl TreeTool {
@@ -67,16 +71,52 @@ impl TreeTool {
             Some(depth as usize)
         };
 
-        let mut tree = collect_tree(&root, max_depth);
-        tree.compute_total_lines();
+        render_tree(collect_tree(&root, max_depth))
+    }
 
-        let mut output = String::new();
-        tree.render_into(0, &mut output);
-        if output.is_empty() {
-            output.push_str("(empty directory)");
-        }
+    /// Lists a directory that only exists inside the container. Hidden entries are skipped
+    /// there too, but `.gitignore` rules are not applied.
+    pub async fn tree_in_container(
+        &self,
+        params: TreeParams,
+        container: &Container,
+        root: &Path,
+    ) -> CallToolResult {
+        let max_depth = if params.depth == 0 {
+            String::new()
+        } else {
+            (params.depth + 1).to_string()
+        };
+        let output = container
+            .exec_command(None)
+            .args(["sh", "-c", CONTAINER_LIST_SCRIPT, "sh"])
+            .arg(root)
+            .arg(max_depth)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await;
+        let output = match output {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to list {}: {}",
+                    root.display(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+                .with_priority(0.0)]);
+            }
+            Err(error) => {
+                return CallToolResult::error(vec![Content::text(format!(
+                    "Failed to list {}: {}",
+                    root.display(),
+                    error
+                ))
+                .with_priority(0.0)]);
+            }
+        };
 
-        CallToolResult::success(vec![Content::text(output).with_priority(0.0)])
+        let listing = String::from_utf8_lossy(&output.stdout);
+        render_tree(parse_listing(&root.to_string_lossy(), &listing))
     }
 }
 
@@ -190,6 +230,48 @@ fn collect_tree(root: &Path, max_depth: Option<usize>) -> DirectoryNode {
     tree
 }
 
+/// Builds a tree from the output of [`CONTAINER_LIST_SCRIPT`]. Lines outside `root`, such as
+/// the totals `wc` prints, are skipped.
+fn parse_listing(root: &str, listing: &str) -> DirectoryNode {
+    let prefix = format!("{}/", root.trim_end_matches('/'));
+    let mut tree = DirectoryNode::default();
+    for line in listing.lines() {
+        let (path, line_count) = match line.strip_prefix("d ") {
+            Some(path) => (path, None),
+            None => match line.trim_start().split_once(' ') {
+                Some((count, path)) => match count.parse() {
+                    Ok(count) => (path, Some(count)),
+                    Err(_) => continue,
+                },
+                None => continue,
+            },
+        };
+        let Some(components) = path
+            .strip_prefix(&prefix)
+            .and_then(|rel| relative_components(Path::new(rel)))
+        else {
+            continue;
+        };
+        match line_count {
+            Some(count) => tree.insert_file(&components, count),
+            None => tree.insert_dir(&components),
+        }
+    }
+    tree
+}
+
+fn render_tree(mut tree: DirectoryNode) -> CallToolResult {
+    tree.compute_total_lines();
+
+    let mut output = String::new();
+    tree.render_into(0, &mut output);
+    if output.is_empty() {
+        output.push_str("(empty directory)");
+    }
+
+    CallToolResult::success(vec![Content::text(output).with_priority(0.0)])
+}
+
 fn relative_components(path: &Path) -> Option<Vec<String>> {
     let mut components = Vec::new();
     for component in path.components() {
@@ -298,4 +380,38 @@ mod tests {
         assert!(!text.contains("ignored"));
         assert!(!text.contains("debug.log"));
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tree_lists_directories_inside_container() {
+        let dir = setup_tree();
+        fs::write(dir.path().join(".hidden"), "skip\n").unwrap();
+        let bin = tempfile::tempdir().unwrap();
+        let runtime = crate::agents::container::fake_runtime::install(bin.path());
+        let container = Container::new("c1").with_runtime(runtime.to_str().unwrap());
+
+        let result = TreeTool::new()
+            .tree_in_container(
+                TreeParams {
+                    path: dir.path().display().to_string(),
+                    depth: 2,
+                },
+                &container,
+                dir.path(),
+            )
+            .await;
+
+        assert_eq!(
+            extract_text(&result),
+            TreeTool::new()
+                .tree(TreeParams {
+                    path: dir.path().display().to_string(),
+                    depth: 2,
+                })
+                .content[0]
+                .as_text()
+                .unwrap()
+                .text
+        );
+    }
 }
@@ -155,6 +155,10 @@ pub struct PlatformExtensionContext {
         Option<std::sync::Weak<crate::agents::extension_manager::ExtensionManager>>,
     pub session_manager: std::sync::Arc<crate::session::SessionManager>,
     pub session: Option<std::sync::Arc<Session>>,
+    /// Container the agent runs extensions in, for extensions that execute commands
+    pub container: Option<crate::agents::Container>,
+    /// The client's own file and terminal access, when it offers any
+    pub client_delegate: Option<std::sync::Arc<dyn crate::agents::ClientDelegate>>,
 }
 
 impl PlatformExtensionContext {
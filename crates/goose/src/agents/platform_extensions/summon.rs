@@ -16,8 +16,8 @@ use crate::providers;
 use crate::recipe::build_recipe::build_recipe_from_template;
 use crate::recipe::local_recipes::load_local_recipe_file;
 use crate::recipe::{Recipe, Settings, RECIPE_FILE_EXTENSIONS};
-use crate::session::extension_data::EnabledExtensionsState;
-use crate::session::SessionType;
+use crate::session::extension_data::{EnabledExtensionsState, ExtensionState};
+use crate::session::{DelegatedFrom, Session, SessionType};
 use anyhow::Result;
 use async_trait::async_trait;
 use rmcp::model::{
@@ -1161,15 +1161,8 @@ impl SummonClient {
         );
 
         let subagent_session = self
-            .context
-            .session_manager
-            .create_session(
-                working_dir,
-                "Delegated task".to_string(),
-                SessionType::SubAgent,
-            )
-            .await
-            .map_err(|e| format!("Failed to create subagent session: {}", e))?;
+            .create_subagent_session(session_id, working_dir, "Delegated task".to_string())
+            .await?;
 
         let (notif_tx, notif_rx) = tokio::sync::mpsc::unbounded_channel::<ServerNotification>();
         Self::spawn_notification_bridge(
@@ -1390,6 +1383,9 @@ impl SummonClient {
             goose_provider: params.provider.clone(),
             temperature: params.temperature,
             max_turns: None,
+            budget: None,
+            response_cache: None,
+            sandbox: None,
         });
 
         let mut builder = Recipe::builder()
@@ -1570,6 +1566,39 @@ impl SummonClient {
         }
     }
 
+    /// Creates the session a delegated task runs in. Its spend is billed to the delegating
+    /// session as well, so subagents can't be used to get around that session's budgets.
+    async fn create_subagent_session(
+        &self,
+        parent_session_id: &str,
+        working_dir: PathBuf,
+        name: String,
+    ) -> Result<Session, String> {
+        let manager = &self.context.session_manager;
+        let session = manager
+            .create_session(working_dir, name, SessionType::SubAgent)
+            .await
+            .map_err(|e| format!("Failed to create subagent session: {}", e))?;
+
+        let mut extension_data = session.extension_data.clone();
+        let linked = DelegatedFrom {
+            session_id: parent_session_id.to_string(),
+        }
+        .to_extension_data(&mut extension_data);
+        let linked = match linked {
+            Ok(()) => {
+                manager
+                    .update(&session.id)
+                    .extension_data(extension_data)
+                    .apply()
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        linked.map_err(|e| format!("Failed to link subagent session: {}", e))?;
+        Ok(session)
+    }
+
     async fn handle_async_delegate(
         &self,
         session_id: &str,
@@ -1613,11 +1642,8 @@ impl SummonClient {
         );
 
         let subagent_session = self
-            .context
-            .session_manager
-            .create_session(working_dir, description.clone(), SessionType::SubAgent)
-            .await
-            .map_err(|e| format!("Failed to create subagent session: {}", e))?;
+            .create_subagent_session(session_id, working_dir, description.clone())
+            .await?;
 
         let task_id = subagent_session.id.clone();
 
@@ -1822,6 +1848,8 @@ mod tests {
             extension_manager: None,
             session_manager: Arc::new(crate::session::SessionManager::instance()),
             session: None,
+            container: None,
+            client_delegate: None,
         }
     }
 
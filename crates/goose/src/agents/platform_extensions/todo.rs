@@ -1,7 +1,6 @@
 use crate::agents::extension::PlatformExtensionContext;
 use crate::agents::mcp_client::{Error, McpClientTrait};
-use crate::session::extension_data;
-use crate::session::extension_data::ExtensionState;
+use crate::session::extension_data::{ExtensionData, ExtensionState, TodoList, TodoStatus};
 use anyhow::Result;
 use async_trait::async_trait;
 use indoc::indoc;
@@ -11,18 +10,60 @@ use rmcp::model::{
 };
 use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
 use tokio_util::sync::CancellationToken;
 
 pub static EXTENSION_NAME: &str = "todo";
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
-struct TodoWriteParams {
-    content: String,
+struct NewTodoItem {
+    title: String,
+    /// Id of the item this one is a sub-task of
+    #[serde(default)]
+    parent_id: Option<u32>,
+    #[serde(default)]
+    notes: Option<String>,
+    /// Defaults to pending
+    #[serde(default)]
+    status: Option<TodoStatus>,
 }
 
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct TodoAddParams {
+    items: Vec<NewTodoItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct TodoItemUpdate {
+    id: u32,
+    #[serde(default)]
+    status: Option<TodoStatus>,
+    #[serde(default)]
+    title: Option<String>,
+    /// Replaces the existing notes; an empty string clears them
+    #[serde(default)]
+    notes: Option<String>,
+    /// Delete the item and its sub-tasks
+    #[serde(default)]
+    remove: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct TodoUpdateParams {
+    updates: Vec<TodoItemUpdate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct TodoListParams {}
+
 pub struct TodoClient {
     info: InitializeResult,
     context: PlatformExtensionContext,
+    /// One lock per session around loading, changing and saving its list, as the model often
+    /// sends several todo calls in one turn and they run at the same time.
+    session_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
 }
 
 impl TodoClient {
@@ -34,107 +75,228 @@ impl TodoClient {
             )
             .with_instructions(
                 indoc! {r#"
-                Your todo content is automatically available in your context.
+                Your todo list is automatically available in your context.
 
                 Workflow:
-                - Start: write initial checklist
-                - During: update progress
-                - End: verify all complete
-
-                Template:
-                - [x] Requirement 1
-                - [ ] Task
-                  - [ ] Sub-task
-                - [ ] Requirement 2
-                - [ ] Another task
+                - Start: todo_add every explicit and implicit requirement, nesting sub-tasks with parent_id
+                - During: todo_update items to in_progress, done or blocked as you go
+                - End: verify all items are done
+
+                Items render as `- [ ] #id title` with [~] in progress, [x] done and [!] blocked.
             "#}
                 .to_string(),
             );
 
-        Ok(Self { info, context })
+        Ok(Self {
+            info,
+            context,
+            session_locks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    async fn lock_session(&self, session_id: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .session_locks
+            .lock()
+            .unwrap()
+            .entry(session_id.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+
+    fn parse_args<T: serde::de::DeserializeOwned>(
+        arguments: Option<JsonObject>,
+    ) -> Result<T, String> {
+        let value = arguments
+            .map(serde_json::Value::Object)
+            .ok_or("Missing arguments")?;
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse arguments: {e}"))
+    }
+
+    fn max_items() -> usize {
+        std::env::var("GOOSE_TODO_MAX_ITEMS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(200)
+    }
+
+    async fn load(&self, session_id: &str) -> Result<(ExtensionData, TodoList), String> {
+        let session = self
+            .context
+            .session_manager
+            .get_session(session_id, false)
+            .await
+            .map_err(|_| "Failed to read session metadata".to_string())?;
+        let list = TodoList::load(&session.extension_data);
+        Ok((session.extension_data, list))
+    }
+
+    async fn save(
+        &self,
+        session_id: &str,
+        mut extension_data: ExtensionData,
+        list: &TodoList,
+    ) -> Result<(), String> {
+        list.to_extension_data(&mut extension_data)
+            .map_err(|_| "Failed to serialize TODO state".to_string())?;
+        self.context
+            .session_manager
+            .update(session_id)
+            .extension_data(extension_data)
+            .apply()
+            .await
+            .map_err(|_| "Failed to update session metadata".to_string())
     }
 
-    async fn handle_write_todo(
+    async fn handle_add(
         &self,
         session_id: &str,
         arguments: Option<JsonObject>,
     ) -> Result<Vec<Content>, String> {
-        let content = arguments
-            .as_ref()
-            .ok_or("Missing arguments")?
-            .get("content")
-            .and_then(|v| v.as_str())
-            .ok_or("Missing required parameter: content")?
-            .to_string();
-
-        let char_count = content.chars().count();
-        let max_chars = std::env::var("GOOSE_TODO_MAX_CHARS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(50_000);
+        let params: TodoAddParams = Self::parse_args(arguments)?;
+        let _guard = self.lock_session(session_id).await;
+        let (extension_data, mut list) = self.load(session_id).await?;
 
-        if max_chars > 0 && char_count > max_chars {
+        let max_items = Self::max_items();
+        if max_items > 0 && list.items.len() + params.items.len() > max_items {
             return Err(format!(
-                "Todo list too large: {} chars (max: {})",
-                char_count, max_chars
+                "Todo list too large: {} items (max: {}). Remove finished items first.",
+                list.items.len() + params.items.len(),
+                max_items
             ));
         }
 
-        let manager = &self.context.session_manager;
-        match manager.get_session(session_id, false).await {
-            Ok(mut session) => {
-                let todo_state = extension_data::TodoState::new(content);
-                if todo_state
-                    .to_extension_data(&mut session.extension_data)
-                    .is_ok()
-                {
-                    match manager
-                        .update(session_id)
-                        .extension_data(session.extension_data)
-                        .apply()
-                        .await
-                    {
-                        Ok(_) => Ok(vec![Content::text(format!(
-                            "Updated ({} chars)",
-                            char_count
-                        ))]),
-                        Err(_) => Err("Failed to update session metadata".to_string()),
-                    }
-                } else {
-                    Err("Failed to serialize TODO state".to_string())
+        let mut added = Vec::new();
+        for item in params.items {
+            let id = list
+                .add(
+                    item.title,
+                    item.status.unwrap_or_default(),
+                    item.parent_id,
+                    item.notes.filter(|n| !n.trim().is_empty()),
+                )
+                .map_err(|e| e.to_string())?;
+            added.push(format!("#{id}"));
+        }
+        self.save(session_id, extension_data, &list).await?;
+
+        Ok(vec![Content::text(format!(
+            "Added {}. {}",
+            added.join(", "),
+            Self::progress(&list)
+        ))])
+    }
+
+    async fn handle_update(
+        &self,
+        session_id: &str,
+        arguments: Option<JsonObject>,
+    ) -> Result<Vec<Content>, String> {
+        let params: TodoUpdateParams = Self::parse_args(arguments)?;
+        let _guard = self.lock_session(session_id).await;
+        let (extension_data, mut list) = self.load(session_id).await?;
+
+        for update in params.updates {
+            if update.remove {
+                if list.remove(update.id) == 0 {
+                    return Err(format!("No todo item with id {}", update.id));
                 }
+                continue;
+            }
+            let item = list
+                .get_mut(update.id)
+                .ok_or_else(|| format!("No todo item with id {}", update.id))?;
+            if let Some(status) = update.status {
+                item.status = status;
+            }
+            if let Some(title) = update.title.filter(|t| !t.trim().is_empty()) {
+                item.title = title;
+            }
+            if let Some(notes) = update.notes {
+                item.notes = Some(notes).filter(|n| !n.trim().is_empty());
             }
-            Err(_) => Err("Failed to read session metadata".to_string()),
         }
+        self.save(session_id, extension_data, &list).await?;
+
+        Ok(vec![Content::text(format!(
+            "Updated. {}",
+            Self::progress(&list)
+        ))])
     }
 
-    fn get_tools() -> Vec<Tool> {
-        let schema = schema_for!(TodoWriteParams);
-        let schema_value =
-            serde_json::to_value(schema).expect("Failed to serialize TodoWriteParams schema");
-
-        vec![Tool::new(
-            "todo_write".to_string(),
-            indoc! {r#"
-                    Overwrite the entire TODO content.
-
-                    The content persists across conversation turns and compaction. Use this for:
-                    - Task tracking and progress updates
-                    - Important notes and reminders
-
-                    WARNING: This operation completely replaces the existing content. Always include
-                    all content you want to keep, not just the changes.
-                "#}
-            .to_string(),
-            schema_value.as_object().unwrap().clone(),
+    async fn handle_list(&self, session_id: &str) -> Result<Vec<Content>, String> {
+        let (_, list) = self.load(session_id).await?;
+        if list.items.is_empty() {
+            return Ok(vec![Content::text("No todo items yet.")]);
+        }
+        Ok(vec![Content::text(format!(
+            "{}\n{}",
+            list.to_markdown(),
+            Self::progress(&list)
+        ))])
+    }
+
+    fn progress(list: &TodoList) -> String {
+        let (done, total) = list.progress();
+        format!("{done}/{total} done")
+    }
+
+    fn tool<T: JsonSchema>(name: &str, description: &str, annotations: ToolAnnotations) -> Tool {
+        let schema = serde_json::to_value(schema_for!(T)).expect("Failed to serialize todo schema");
+        Tool::new(
+            name.to_string(),
+            description.to_string(),
+            schema.as_object().unwrap().clone(),
         )
-        .annotate(ToolAnnotations::from_raw(
-            Some("Write TODO".to_string()),
-            Some(false),
-            Some(true),
-            Some(false),
-            Some(false),
-        ))]
+        .annotate(annotations)
+    }
+
+    fn get_tools() -> Vec<Tool> {
+        vec![
+            Self::tool::<TodoAddParams>(
+                "todo_add",
+                indoc! {r#"
+                    Add items to the todo list. Returns the new ids.
+
+                    The list persists across conversation turns and compaction. Nest sub-tasks by
+                    setting parent_id to an existing item's id.
+                "#},
+                ToolAnnotations::from_raw(
+                    Some("Add TODO".to_string()),
+                    Some(false),
+                    Some(false),
+                    Some(false),
+                    Some(false),
+                ),
+            ),
+            Self::tool::<TodoUpdateParams>(
+                "todo_update",
+                indoc! {r#"
+                    Change the status, title or notes of items by id, or remove them.
+
+                    Statuses: pending, in_progress, done, blocked. Only send the items that changed.
+                "#},
+                ToolAnnotations::from_raw(
+                    Some("Update TODO".to_string()),
+                    Some(false),
+                    Some(true),
+                    Some(false),
+                    Some(false),
+                ),
+            ),
+            Self::tool::<TodoListParams>(
+                "todo_list",
+                "Show the todo list with ids, statuses and notes.",
+                ToolAnnotations::from_raw(
+                    Some("List TODO".to_string()),
+                    Some(true),
+                    Some(false),
+                    Some(true),
+                    Some(false),
+                ),
+            ),
+        ]
     }
 }
 
@@ -162,7 +324,9 @@ impl McpClientTrait for TodoClient {
         _cancellation_token: CancellationToken,
     ) -> Result<CallToolResult, Error> {
         let content = match name {
-            "todo_write" => self.handle_write_todo(session_id, arguments).await,
+            "todo_add" => self.handle_add(session_id, arguments).await,
+            "todo_update" => self.handle_update(session_id, arguments).await,
+            "todo_list" => self.handle_list(session_id).await,
             _ => Err(format!("Unknown tool: {}", name)),
         };
 
@@ -187,14 +351,61 @@ impl McpClientTrait for TodoClient {
             .await
             .ok()?;
 
-        match extension_data::TodoState::from_extension_data(&metadata.extension_data) {
-            Some(state) if !state.content.trim().is_empty() => {
-                Some(format!("Current tasks and notes:\n{}\n", state.content))
-            }
-            _ => Some(
-                "Current tasks and notes:\nOnce given a task, immediately update your todo with all explicit and implicit requirements\n"
+        let list = TodoList::load(&metadata.extension_data);
+        if list.items.is_empty() {
+            return Some(
+                "Current tasks and notes:\nOnce given a task, immediately add all explicit and implicit requirements to your todo list\n"
                     .to_string(),
-            ),
+            );
         }
+        Some(format!(
+            "Current tasks and notes ({}):\n{}",
+            Self::progress(&list),
+            list.to_markdown()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionManager, SessionType};
+    use rmcp::object;
+
+    #[tokio::test]
+    async fn concurrent_adds_are_all_kept() {
+        let temp = tempfile::tempdir().unwrap();
+        let session_manager = Arc::new(SessionManager::new(temp.path().to_path_buf()));
+        let session = session_manager
+            .create_session(temp.path().to_path_buf(), "todo".into(), SessionType::User)
+            .await
+            .unwrap();
+        let client = TodoClient::new(PlatformExtensionContext {
+            extension_manager: None,
+            session_manager: session_manager.clone(),
+            session: None,
+            container: None,
+            client_delegate: None,
+        })
+        .unwrap();
+
+        let adds = (0..8).map(|i| {
+            client.call_tool(
+                &session.id,
+                "todo_add",
+                Some(object!({ "items": [{ "title": format!("task {i}") }] })),
+                None,
+                CancellationToken::new(),
+            )
+        });
+        for result in futures::future::join_all(adds).await {
+            assert_eq!(result.unwrap().is_error, Some(false));
+        }
+
+        let session = session_manager
+            .get_session(&session.id, false)
+            .await
+            .unwrap();
+        assert_eq!(TodoList::load(&session.extension_data).items.len(), 8);
     }
 }
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use regex::Regex;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use async_stream::try_stream;
@@ -20,6 +21,10 @@ use crate::providers::toolshim::{
     augment_message_with_tool_calls, convert_tool_messages_to_text,
     modify_system_prompt_for_tool_json, OllamaInterpreter,
 };
+use crate::session::budget::{BudgetLimits, BudgetStatus, DelegatedFrom};
+use crate::session::extension_data::ExtensionState;
+use crate::session::usage_costs::usage_cost;
+use crate::session::{Session, SessionManager};
 use rmcp::model::Tool;
 
 async fn enhance_model_error(error: ProviderError, provider: &Arc<dyn Provider>) -> ProviderError {
@@ -132,12 +137,43 @@ async fn toolshim_postprocess(
         .map_err(|e| ProviderError::ExecutionError(format!("Failed to augment message: {}", e)))
 }
 
+/// How many delegation hops [`billed_session`] follows before it stops looking for the root.
+const MAX_DELEGATION_DEPTH: usize = 16;
+
+/// The session whose budgets cover `session`'s spend: the root of the chain of sessions that
+/// delegated to it, or the session itself. The walk stops at a missing session, a cycle or
+/// [`MAX_DELEGATION_DEPTH`] hops, billing the last session it reached.
+async fn billed_session(manager: &SessionManager, session: Session) -> Session {
+    let mut visited = HashSet::from([session.id.clone()]);
+    let mut current = session;
+    for _ in 0..MAX_DELEGATION_DEPTH {
+        let Some(delegated_from) = DelegatedFrom::from_extension_data(&current.extension_data)
+        else {
+            break;
+        };
+        if !visited.insert(delegated_from.session_id.clone()) {
+            tracing::warn!(
+                session_id = %current.id,
+                "session delegation chain loops back on itself"
+            );
+            break;
+        }
+        match manager.get_session(&delegated_from.session_id, false).await {
+            Ok(parent) => current = parent,
+            Err(_) => break,
+        }
+    }
+    current
+}
+
 impl Agent {
     pub async fn prepare_tools_and_prompt(
         &self,
         session_id: &str,
         working_dir: &std::path::Path,
     ) -> Result<(Vec<Tool>, Vec<Tool>, String)> {
+        self.extension_manager.restart_stopped_extensions().await;
+
         // Get tools from extension manager
         let mut tools = self.list_tools(session_id, None).await;
 
@@ -424,8 +460,45 @@ impl Agent {
             .apply()
             .await?;
 
+        // Cache hits cost nothing, so they stay out of the spend ledger
+        if !usage.cache_hit
+            && (usage.usage.input_tokens.is_some() || usage.usage.output_tokens.is_some())
+        {
+            let provider_name = match usage.provider.clone().or(session.provider_name.clone()) {
+                Some(name) => name,
+                None => self.provider().await?.get_name().to_string(),
+            };
+            let cost = usage_cost(&provider_name, &usage.model, &usage.usage);
+            let billed_to = billed_session(&manager, session).await;
+            manager
+                .record_usage_cost(
+                    session_id,
+                    &provider_name,
+                    &usage.model,
+                    &usage.usage,
+                    cost,
+                    &billed_to,
+                )
+                .await?;
+        }
+
         Ok(())
     }
+
+    /// Compare the spend recorded so far against the configured budgets. A subagent, however
+    /// deeply nested, is held to the budgets of the session at the root of its delegation chain.
+    pub(crate) async fn check_budget(&self, session_id: &str) -> Result<BudgetStatus> {
+        let manager = self.config.session_manager.clone();
+        let session = manager.get_session(session_id, false).await?;
+        let session = billed_session(&manager, session).await;
+
+        let limits = BudgetLimits::from_config(session.recipe.as_ref());
+        if limits.is_empty() {
+            return Ok(BudgetStatus::WithinBudget);
+        }
+        let spend = manager.get_budget_spend(&session).await?;
+        Ok(limits.evaluate(&spend))
+    }
 }
 
 #[cfg(test)]
@@ -468,6 +541,94 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn session_metrics_feed_the_budget() -> anyhow::Result<()> {
+        let _guard = env_lock::lock_env([
+            ("GOOSE_SESSION_BUDGET", Some("1")),
+            ("GOOSE_DAILY_BUDGET", None),
+            ("GOOSE_RECIPE_BUDGET", None),
+            ("GOOSE_BUDGET_WARNING_THRESHOLD", Some("0.5")),
+        ]);
+        let agent = crate::agents::Agent::new();
+        let session = agent
+            .config
+            .session_manager
+            .create_session(
+                std::env::current_dir().unwrap(),
+                "test-budget".to_string(),
+                SessionType::Hidden,
+            )
+            .await?;
+        assert_eq!(
+            agent.check_budget(&session.id).await?,
+            BudgetStatus::WithinBudget
+        );
+
+        // gpt-4o input costs a few dollars per million tokens
+        let usage = ProviderUsage::new(
+            "gpt-4o".to_string(),
+            Usage::new(Some(200_000), Some(0), Some(200_000)),
+        )
+        .with_provider("openai");
+        agent
+            .update_session_metrics(&session.id, None, &usage, false)
+            .await?;
+        assert!(matches!(
+            agent.check_budget(&session.id).await?,
+            BudgetStatus::Warning(_)
+        ));
+
+        agent
+            .update_session_metrics(&session.id, None, &usage, false)
+            .await?;
+        assert!(matches!(
+            agent.check_budget(&session.id).await?,
+            BudgetStatus::Exceeded(_)
+        ));
+        Ok(())
+    }
+
+    async fn delegate(manager: &SessionManager, child: &str, parent: &str) -> anyhow::Result<()> {
+        let mut extension_data = manager.get_session(child, false).await?.extension_data;
+        DelegatedFrom {
+            session_id: parent.to_string(),
+        }
+        .to_extension_data(&mut extension_data)?;
+        manager
+            .update(child)
+            .extension_data(extension_data)
+            .apply()
+            .await
+    }
+
+    #[tokio::test]
+    async fn nested_subagents_bill_the_root_session() -> anyhow::Result<()> {
+        let agent = crate::agents::Agent::new();
+        let manager = agent.config.session_manager.clone();
+        let mut ids = Vec::new();
+        for name in ["root", "child", "grandchild"] {
+            let session = manager
+                .create_session(
+                    std::env::current_dir().unwrap(),
+                    format!("test-billing-{name}"),
+                    SessionType::Hidden,
+                )
+                .await?;
+            ids.push(session.id);
+        }
+        delegate(&manager, &ids[1], &ids[0]).await?;
+        delegate(&manager, &ids[2], &ids[1]).await?;
+
+        let grandchild = manager.get_session(&ids[2], false).await?;
+        assert_eq!(billed_session(&manager, grandchild).await.id, ids[0]);
+
+        // A chain that loops back ends at the last session before the loop
+        delegate(&manager, &ids[0], &ids[2]).await?;
+        let grandchild = manager.get_session(&ids[2], false).await?;
+        assert_eq!(billed_session(&manager, grandchild).await.id, ids[0]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn prepare_tools_returns_sorted_tools_including_frontend() -> anyhow::Result<()> {
         let agent = crate::agents::Agent::new();
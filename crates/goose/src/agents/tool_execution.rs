@@ -8,8 +8,10 @@ use futures::{Stream, StreamExt};
 use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 
+use crate::audit::{AuditDecision, AuditEntry, AuditLog};
 use crate::config::permission::PermissionLevel;
 use crate::mcp_utils::ToolResult;
+use crate::permission::permission_judge::PermissionCheckResult;
 use crate::permission::Permission;
 use rmcp::model::{Content, ServerNotification};
 
@@ -33,7 +35,9 @@ use super::agent::{tool_stream, ToolStream};
 use crate::agents::Agent;
 use crate::conversation::message::{Message, ToolRequest};
 use crate::session::Session;
-use crate::tool_inspection::get_security_finding_id_from_results;
+use crate::tool_inspection::{
+    get_security_finding_id_from_results, InspectionAction, InspectionResult,
+};
 
 pub const DECLINED_RESPONSE: &str = "The user has declined to run this tool. \
     DO NOT attempt to call this tool again. \
@@ -48,6 +52,39 @@ pub const CHAT_MODE_TOOL_SKIPPED_RESPONSE: &str = "Let the user know the tool ca
                                         2. **Outline Steps** - Break down the steps.\n \
                                         If needed, adjust the explanation based on user preferences or questions.";
 
+/// Records the tool calls the inspectors approved or denied without asking the user.
+pub(crate) fn audit_inspector_decisions(
+    session_id: &str,
+    permission_check_result: &PermissionCheckResult,
+    inspection_results: &[InspectionResult],
+) {
+    let decided = permission_check_result
+        .approved
+        .iter()
+        .map(|request| (request, AuditDecision::Allowed, InspectionAction::Allow))
+        .chain(
+            permission_check_result
+                .denied
+                .iter()
+                .map(|request| (request, AuditDecision::Denied, InspectionAction::Deny)),
+        );
+    for (request, decision, action) in decided {
+        let Ok(tool_call) = &request.tool_call else {
+            continue;
+        };
+        let deciding = inspection_results
+            .iter()
+            .find(|result| result.tool_request_id == request.id && result.action == action);
+        AuditLog::record(AuditEntry::tool_call(
+            session_id,
+            tool_call,
+            decision,
+            deciding.map_or("inspector", |result| result.inspector_name.as_str()),
+            deciding.map_or("", |result| result.reason.as_str()),
+        ));
+    }
+}
+
 impl Agent {
     pub(crate) fn handle_approval_tool_requests<'a>(
         &'a self,
@@ -96,7 +133,19 @@ impl Agent {
                             );
                         }
 
-                        if confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow {
+                        let allowed = confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow;
+                        AuditLog::record(AuditEntry::tool_call(
+                            &session.id,
+                            &tool_call,
+                            if allowed { AuditDecision::Allowed } else { AuditDecision::Denied },
+                            "user",
+                            serde_json::to_value(&confirmation.permission)
+                                .ok()
+                                .and_then(|value| value.as_str().map(str::to_string))
+                                .unwrap_or_default(),
+                        ));
+
+                        if allowed {
                             let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone(), cancellation_token.clone(), session).await;
                             let mut futures = tool_futures.lock().await;
 
@@ -144,6 +193,7 @@ impl Agent {
 
     pub(crate) fn handle_frontend_tool_request<'a>(
         &'a self,
+        session_id: &'a str,
         tool_request: &'a ToolRequest,
         message_tool_response: Arc<Mutex<Message>>,
     ) -> BoxStream<'a, anyhow::Result<Message>> {
@@ -157,6 +207,7 @@ impl Agent {
                         );
 
                         if let Some((id, result)) = self.tool_result_rx.lock().await.recv().await {
+                            let result = self.redact_secrets(session_id, &id, result).await;
                             let mut response = message_tool_response.lock().await;
                             *response = response.clone().with_tool_response_with_metadata(
                                 id,
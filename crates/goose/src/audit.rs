@@ -0,0 +1,350 @@
+//! Append-only audit log of tool permission decisions and configuration changes.
+//!
+//! Entries are written as JSON lines to `audit/audit.jsonl` in the state directory. Once the
+//! file grows past [`MAX_FILE_BYTES`] it is rotated to `audit.1.jsonl`, shifting older files up
+//! and dropping the oldest beyond [`MAX_ROTATED_FILES`].
+
+use crate::config::paths::Paths;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rmcp::model::CallToolRequestParams;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use utoipa::ToSchema;
+
+const AUDIT_DIR: &str = "audit";
+const AUDIT_FILE_STEM: &str = "audit";
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 5;
+const ARGUMENTS_SUMMARY_CHARS: usize = 200;
+const DEFAULT_QUERY_LIMIT: usize = 100;
+
+static AUDIT_LOG: LazyLock<Arc<AuditLog>> =
+    LazyLock::new(|| Arc::new(AuditLog::new(Paths::in_state_dir(AUDIT_DIR))));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    ToolCall,
+    ConfigChange,
+}
+
+impl std::str::FromStr for AuditKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tool_call" => Ok(AuditKind::ToolCall),
+            "config_change" => Ok(AuditKind::ConfigChange),
+            _ => Err(anyhow::anyhow!(
+                "Invalid audit kind: {} (expected tool_call or config_change)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditDecision {
+    Allowed,
+    Denied,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: AuditKind,
+    /// Tool calls: the arguments, truncated. Config changes: what was changed.
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// SHA-256 of the tool call arguments as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<AuditDecision>,
+    /// What made the decision: `user` for a confirmation, otherwise the tool inspector
+    /// (`permission`, `security`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Config key, extension or provider that was changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Who made a config change, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn tool_call(
+        session_id: &str,
+        tool_call: &CallToolRequestParams,
+        decision: AuditDecision,
+        source: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        let arguments = serde_json::to_string(&tool_call.arguments.clone().unwrap_or_default())
+            .unwrap_or_default();
+        let mut summary: String = arguments.chars().take(ARGUMENTS_SUMMARY_CHARS).collect();
+        if summary.len() < arguments.len() {
+            summary.push('…');
+        }
+        Self {
+            timestamp: Utc::now(),
+            kind: AuditKind::ToolCall,
+            summary,
+            session_id: Some(session_id.to_string()),
+            tool: Some(tool_call.name.to_string()),
+            arguments_hash: Some(format!("{:x}", Sha256::digest(arguments.as_bytes()))),
+            decision: Some(decision),
+            source: Some(source.into()),
+            reason: Some(reason.into()),
+            key: None,
+            actor: None,
+        }
+    }
+
+    pub fn config_change(
+        key: impl Into<String>,
+        summary: impl Into<String>,
+        actor: Option<String>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            kind: AuditKind::ConfigChange,
+            summary: summary.into(),
+            session_id: None,
+            tool: None,
+            arguments_hash: None,
+            decision: None,
+            source: None,
+            reason: None,
+            key: Some(key.into()),
+            actor,
+        }
+    }
+}
+
+/// Which entries [`AuditLog::query`] returns.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub session_id: Option<String>,
+    pub tool: Option<String>,
+    pub kind: Option<AuditKind>,
+    /// Most recent entries to return, 100 when unset
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp < until)
+            && self
+                .session_id
+                .as_ref()
+                .is_none_or(|id| entry.session_id.as_ref() == Some(id))
+            && self
+                .tool
+                .as_ref()
+                .is_none_or(|tool| entry.tool.as_ref() == Some(tool))
+            && self.kind.is_none_or(|kind| entry.kind == kind)
+    }
+}
+
+pub struct AuditLog {
+    dir: PathBuf,
+    max_file_bytes: u64,
+    write_lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            max_file_bytes: MAX_FILE_BYTES,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn instance() -> Arc<AuditLog> {
+        Arc::clone(&AUDIT_LOG)
+    }
+
+    /// Records an entry in the global audit log. Failures are logged rather than returned so
+    /// that auditing never gets in the way of the action being audited.
+    pub fn record(entry: AuditEntry) {
+        if let Err(e) = Self::instance().append(&entry) {
+            tracing::warn!("Failed to write audit log entry: {}", e);
+        }
+    }
+
+    /// The current file when `index` is 0, otherwise the `index`th most recent rotated file.
+    fn file_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.dir.join(format!("{}.jsonl", AUDIT_FILE_STEM))
+        } else {
+            self.dir
+                .join(format!("{}.{}.jsonl", AUDIT_FILE_STEM, index))
+        }
+    }
+
+    fn rotate(&self) -> Result<()> {
+        let _ = fs::remove_file(self.file_path(MAX_ROTATED_FILES));
+        for index in (0..MAX_ROTATED_FILES).rev() {
+            let from = self.file_path(index);
+            if from.exists() {
+                fs::rename(&from, self.file_path(index + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.dir)?;
+        let path = self.file_path(0);
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 > self.max_file_bytes {
+            self.rotate()?;
+        }
+
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        // A single write keeps lines whole when several goose processes append at once.
+        options.open(&path)?.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn read_entries(path: &Path, filter: &AuditFilter, entries: &mut Vec<AuditEntry>) {
+        let Ok(file) = fs::File::open(path) else {
+            return;
+        };
+        for line in BufReader::new(file).lines().map_while(|line| line.ok()) {
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                Ok(_) => {}
+                Err(e) => tracing::debug!("Skipping unreadable audit line: {}", e),
+            }
+        }
+    }
+
+    /// Entries matching the filter, most recent first.
+    pub fn query(&self, filter: &AuditFilter) -> Vec<AuditEntry> {
+        let mut entries = Vec::new();
+        for index in (0..=MAX_ROTATED_FILES).rev() {
+            Self::read_entries(&self.file_path(index), filter, &mut entries);
+        }
+        entries.reverse();
+        entries.truncate(filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::object;
+    use tempfile::TempDir;
+
+    fn shell_call(command: &str) -> CallToolRequestParams {
+        CallToolRequestParams::new("developer__shell")
+            .with_arguments(object!({ "command": command }))
+    }
+
+    #[test]
+    fn test_append_and_query() {
+        let dir = TempDir::new().unwrap();
+        let log = AuditLog::new(dir.path().to_path_buf());
+
+        log.append(&AuditEntry::tool_call(
+            "s1",
+            &shell_call("ls"),
+            AuditDecision::Allowed,
+            "permission",
+            "Auto mode - all tools approved",
+        ))
+        .unwrap();
+        log.append(&AuditEntry::tool_call(
+            "s2",
+            &shell_call("rm -rf /"),
+            AuditDecision::Denied,
+            "user",
+            "deny_once",
+        ))
+        .unwrap();
+        log.append(&AuditEntry::config_change(
+            "GOOSE_PROVIDER",
+            "set",
+            Some("owner".to_string()),
+        ))
+        .unwrap();
+
+        let all = log.query(&AuditFilter::default());
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].kind, AuditKind::ConfigChange);
+        assert_eq!(all[2].summary, r#"{"command":"ls"}"#);
+        assert_ne!(all[1].arguments_hash, all[2].arguments_hash);
+
+        let denied = log.query(&AuditFilter {
+            session_id: Some("s2".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].decision, Some(AuditDecision::Denied));
+
+        let tool_calls = log.query(&AuditFilter {
+            kind: Some(AuditKind::ToolCall),
+            limit: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].session_id.as_deref(), Some("s2"));
+    }
+
+    #[test]
+    fn test_rotation_keeps_entries_queryable() {
+        let dir = TempDir::new().unwrap();
+        let mut log = AuditLog::new(dir.path().to_path_buf());
+        // Every entry goes to a new file
+        log.max_file_bytes = 1;
+
+        for i in 0..(MAX_ROTATED_FILES + 3) {
+            log.append(&AuditEntry::config_change(
+                format!("KEY_{}", i),
+                "set",
+                None,
+            ))
+            .unwrap();
+        }
+
+        assert!(log.file_path(1).exists());
+        assert!(!log.file_path(MAX_ROTATED_FILES + 1).exists());
+        let entries = log.query(&AuditFilter::default());
+        assert_eq!(entries.len(), MAX_ROTATED_FILES + 1);
+        assert_eq!(
+            entries[0].key.as_deref(),
+            Some(format!("KEY_{}", MAX_ROTATED_FILES + 2).as_str())
+        );
+    }
+}
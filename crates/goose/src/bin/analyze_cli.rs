@@ -54,7 +54,7 @@ fn main() {
         } else {
             &path
         };
-        let g = graph::CallGraph::build(&analyses);
+        let g = graph::CallGraph::build(&analyses.iter().collect::<Vec<_>>());
         format::format_focused(symbol, &g, cli.follow, analyses.len(), root)
     } else if path.is_file() {
         // Semantic mode: single file details
@@ -79,7 +79,12 @@ fn main() {
             .par_iter()
             .filter_map(|f| AnalyzeClient::analyze_file(f))
             .collect();
-        format::format_structure(&analyses, &path, cli.depth, total_files)
+        format::format_structure(
+            &analyses.iter().collect::<Vec<_>>(),
+            &path,
+            cli.depth,
+            total_files,
+        )
     };
 
     match format::check_size(&output, cli.force) {
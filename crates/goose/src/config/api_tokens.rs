@@ -0,0 +1,344 @@
+use crate::config::paths::Paths;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::SystemTime;
+use utoipa::ToSchema;
+
+const API_TOKENS_FILE: &str = "api_tokens.yaml";
+const TOKEN_PREFIX: &str = "goose";
+const TOKEN_ID_LENGTH: usize = 8;
+const TOKEN_SECRET_LENGTH: usize = 40;
+
+/// Whether a presented secret equals the expected one. Both are hashed first so the comparison
+/// takes the same time however many leading characters match, and whatever their lengths.
+pub fn secrets_match(presented: &str, expected: &str) -> bool {
+    digests_match(
+        &Sha256::digest(presented.as_bytes()),
+        &Sha256::digest(expected.as_bytes()),
+    )
+}
+
+fn digests_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && std::hint::black_box(a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y))) == 0
+}
+
+static API_TOKEN_STORE: LazyLock<Arc<ApiTokenStore>> =
+    LazyLock::new(|| Arc::new(ApiTokenStore::new(Paths::config_dir())));
+
+/// What a goose-server API token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// List and read the sessions the token owns
+    ReadSessions,
+    /// Start agents and chat in the sessions the token owns
+    Reply,
+    /// Read and change configuration and providers
+    ManageConfig,
+    /// Add and remove extensions
+    ManageExtensions,
+    /// Create and run scheduled jobs
+    Schedule,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 5] = [
+        TokenScope::ReadSessions,
+        TokenScope::Reply,
+        TokenScope::ManageConfig,
+        TokenScope::ManageExtensions,
+        TokenScope::Schedule,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::ReadSessions => "read-sessions",
+            TokenScope::Reply => "reply",
+            TokenScope::ManageConfig => "manage-config",
+            TokenScope::ManageExtensions => "manage-extensions",
+            TokenScope::Schedule => "schedule",
+        }
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TokenScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TokenScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid token scope: {} (expected one of {})",
+                    s,
+                    TokenScope::ALL.map(|scope| scope.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+/// A token as stored on disk. Only a SHA-256 hash of the secret is kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    id: String,
+    name: String,
+    scopes: Vec<TokenScope>,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Public description of a token, without its secret.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+impl From<&StoredToken> for ApiToken {
+    fn from(token: &StoredToken) -> Self {
+        ApiToken {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            created_at: token.created_at,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CachedTokens {
+    modified: Option<SystemTime>,
+    tokens: Vec<StoredToken>,
+}
+
+/// Named goose-server API tokens, kept in `api_tokens.yaml` in the config directory.
+///
+/// The file is re-read whenever it changes on disk, so tokens minted or revoked from the CLI
+/// take effect in a running server.
+pub struct ApiTokenStore {
+    path: PathBuf,
+    cache: RwLock<CachedTokens>,
+}
+
+impl ApiTokenStore {
+    pub fn new(config_dir: PathBuf) -> Self {
+        Self {
+            path: config_dir.join(API_TOKENS_FILE),
+            cache: RwLock::new(CachedTokens::default()),
+        }
+    }
+
+    pub fn instance() -> Arc<ApiTokenStore> {
+        Arc::clone(&API_TOKEN_STORE)
+    }
+
+    fn hash(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+
+    fn read_file(&self) -> Result<Vec<StoredToken>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) if contents.trim().is_empty() => Ok(Vec::new()),
+            Ok(contents) => serde_yaml::from_str(&contents)
+                .map_err(|e| anyhow!("Failed to parse {}: {}", self.path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_file(&self, tokens: &[StoredToken]) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp_path = self.path.with_extension("yaml.tmp");
+        fs::write(&temp_path, serde_yaml::to_string(tokens)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&temp_path, fs::Permissions::from_mode(0o600))?;
+        }
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn with_tokens<T>(&self, f: impl FnOnce(&[StoredToken]) -> T) -> Result<T> {
+        let modified = self.modified();
+        {
+            let cache = self.cache.read().unwrap();
+            if modified.is_some() && cache.modified == modified {
+                return Ok(f(&cache.tokens));
+            }
+        }
+        let tokens = self.read_file()?;
+        let mut cache = self.cache.write().unwrap();
+        *cache = CachedTokens { modified, tokens };
+        Ok(f(&cache.tokens))
+    }
+
+    pub fn list(&self) -> Result<Vec<ApiToken>> {
+        self.with_tokens(|tokens| tokens.iter().map(ApiToken::from).collect())
+    }
+
+    /// Mints a token and returns its description along with the secret, which is not stored and
+    /// cannot be shown again.
+    pub fn create(&self, name: &str, scopes: Vec<TokenScope>) -> Result<(ApiToken, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Token name cannot be empty"));
+        }
+        if scopes.is_empty() {
+            return Err(anyhow!("A token needs at least one scope"));
+        }
+
+        let mut tokens = self.read_file()?;
+        if tokens.iter().any(|t| t.name == name) {
+            return Err(anyhow!("A token named '{}' already exists", name));
+        }
+
+        let mut rng = rand::thread_rng();
+        let id: String = (&mut rng)
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_ID_LENGTH)
+            .map(|c| char::from(c).to_ascii_lowercase())
+            .collect();
+        let secret: String = rng
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_SECRET_LENGTH)
+            .map(char::from)
+            .collect();
+        let token = format!("{}_{}_{}", TOKEN_PREFIX, id, secret);
+
+        let mut scopes = scopes;
+        scopes.sort_by_key(|scope| scope.as_str());
+        scopes.dedup();
+        let stored = StoredToken {
+            id,
+            name: name.to_string(),
+            scopes,
+            token_hash: Self::hash(&token),
+            created_at: Utc::now(),
+        };
+        let info = ApiToken::from(&stored);
+        tokens.push(stored);
+        self.write_file(&tokens)?;
+        Ok((info, token))
+    }
+
+    /// Revokes the token with the given id or name. Returns whether one was found.
+    pub fn revoke(&self, id_or_name: &str) -> Result<bool> {
+        let mut tokens = self.read_file()?;
+        let before = tokens.len();
+        tokens.retain(|t| t.id != id_or_name && t.name != id_or_name);
+        if tokens.len() == before {
+            return Ok(false);
+        }
+        self.write_file(&tokens)?;
+        Ok(true)
+    }
+
+    /// Looks up the token a client presented. Returns `None` for unknown or revoked tokens.
+    pub fn authenticate(&self, token: &str) -> Option<ApiToken> {
+        let id = token
+            .strip_prefix(TOKEN_PREFIX)?
+            .strip_prefix('_')?
+            .split('_')
+            .next()?;
+        let hash = Self::hash(token);
+        self.with_tokens(|tokens| {
+            tokens
+                .iter()
+                .find(|t| t.id == id && digests_match(t.token_hash.as_bytes(), hash.as_bytes()))
+                .map(ApiToken::from)
+        })
+        .map_err(|e| tracing::warn!("Failed to read API tokens: {}", e))
+        .ok()
+        .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_authenticate_revoke() {
+        let dir = TempDir::new().unwrap();
+        let store = ApiTokenStore::new(dir.path().to_path_buf());
+
+        let (info, token) = store
+            .create("ci", vec![TokenScope::Reply, TokenScope::ReadSessions])
+            .unwrap();
+        assert!(token.starts_with("goose_"));
+        assert_eq!(
+            info.scopes,
+            vec![TokenScope::ReadSessions, TokenScope::Reply]
+        );
+        assert!(store.create("ci", vec![TokenScope::Reply]).is_err());
+
+        let contents = fs::read_to_string(dir.path().join(API_TOKENS_FILE)).unwrap();
+        assert!(!contents.contains(&token));
+
+        assert_eq!(store.authenticate(&token), Some(info.clone()));
+        assert_eq!(store.authenticate(&format!("{}x", token)), None);
+        assert_eq!(store.authenticate("test"), None);
+
+        // A second store sees changes made through the first, like a running server does
+        // when tokens are revoked from the CLI.
+        let other = ApiTokenStore::new(dir.path().to_path_buf());
+        assert!(other.authenticate(&token).is_some());
+        assert!(store.revoke("ci").unwrap());
+        assert!(!store.revoke(&info.id).unwrap());
+        assert_eq!(other.authenticate(&token), None);
+        assert!(other.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scope_parsing() {
+        assert_eq!(
+            "manage-extensions".parse::<TokenScope>().unwrap(),
+            TokenScope::ManageExtensions
+        );
+        assert!("admin".parse::<TokenScope>().is_err());
+        assert_eq!(
+            serde_json::to_string(&TokenScope::ReadSessions).unwrap(),
+            "\"read-sessions\""
+        );
+    }
+
+    #[test]
+    fn test_secrets_match() {
+        assert!(secrets_match("secret", "secret"));
+        assert!(!secrets_match("secreT", "secret"));
+        assert!(!secrets_match("secret-and-more", "secret"));
+        assert!(!secrets_match("", "secret"));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod api_tokens;
 pub mod base;
 pub mod declarative_providers;
 mod experiments;
@@ -11,6 +12,7 @@ pub mod signup_openrouter;
 pub mod signup_tetrate;
 
 pub use crate::agents::ExtensionConfig;
+pub use api_tokens::{secrets_match, ApiToken, ApiTokenStore, TokenScope};
 pub use base::{Config, ConfigError};
 pub use declarative_providers::DeclarativeProviderConfig;
 pub use experiments::ExperimentManager;
@@ -0,0 +1,534 @@
+use super::telegram::split_message;
+use super::{
+    download_attachment, Gateway, GatewayConfig, GatewayHandler, IncomingMessage, MessageSink,
+    OutgoingMessage, PlatformUser,
+};
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use reqwest::Client;
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_util::sync::CancellationToken;
+
+const DISCORD_API_BASE: &str = "https://discord.com/api/v10";
+const MAX_MESSAGE_LENGTH: usize = 2000;
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+const OP_DISPATCH: u8 = 0;
+const OP_HEARTBEAT: u8 = 1;
+const OP_IDENTIFY: u8 = 2;
+const OP_RECONNECT: u8 = 7;
+const OP_INVALID_SESSION: u8 = 9;
+const OP_HELLO: u8 = 10;
+
+/// GUILD_MESSAGES | DIRECT_MESSAGES | MESSAGE_CONTENT
+const INTENTS: u64 = (1 << 9) | (1 << 12) | (1 << 15);
+
+/// Discord over the gateway websocket, replying through the REST API.
+///
+/// Direct messages are always answered; in servers the bot only answers when mentioned.
+/// Discord threads are channels, so each channel or thread maps to its own session.
+pub struct DiscordGateway {
+    bot_token: String,
+    api_base: String,
+    client: Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayPayload {
+    op: u8,
+    #[serde(default)]
+    d: serde_json::Value,
+    s: Option<u64>,
+    t: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordMessage {
+    id: String,
+    channel_id: String,
+    guild_id: Option<String>,
+    author: DiscordUser,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    mentions: Vec<DiscordUser>,
+    #[serde(default)]
+    attachments: Vec<DiscordAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordUser {
+    id: String,
+    username: String,
+    global_name: Option<String>,
+    #[serde(default)]
+    bot: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiscordAttachment {
+    filename: String,
+    content_type: Option<String>,
+    url: String,
+    size: Option<u64>,
+}
+
+impl DiscordGateway {
+    pub fn new(config: &GatewayConfig) -> anyhow::Result<Self> {
+        let bot_token = config.platform_config["bot_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing bot_token in platform_config"))?
+            .to_string();
+
+        Ok(Self {
+            bot_token,
+            api_base: config.platform_config["api_base"]
+                .as_str()
+                .unwrap_or(DISCORD_API_BASE)
+                .trim_end_matches('/')
+                .to_string(),
+            client: Client::new(),
+        })
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}{}", self.api_base, path)
+    }
+
+    fn authorization(&self) -> String {
+        format!("Bot {}", self.bot_token)
+    }
+
+    async fn gateway_url(&self) -> anyhow::Result<String> {
+        let resp: serde_json::Value = self
+            .client
+            .get(self.api_url("/gateway/bot"))
+            .header("Authorization", self.authorization())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        resp["url"]
+            .as_str()
+            .map(|url| format!("{}/?v=10&encoding=json", url.trim_end_matches('/')))
+            .ok_or_else(|| anyhow::anyhow!("Discord returned no gateway URL"))
+    }
+
+    /// Reads one gateway connection until Discord asks us to reconnect or it drops. Sessions
+    /// are not resumed; a reconnect identifies again.
+    async fn run_socket(
+        &self,
+        sink: &MessageSink,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        let url = self.gateway_url().await?;
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+
+        let hello = match socket.next().await {
+            Some(Ok(WsMessage::Text(text))) => {
+                serde_json::from_str::<GatewayPayload>(text.as_str())?
+            }
+            other => anyhow::bail!("expected Discord hello, got {:?}", other),
+        };
+        if hello.op != OP_HELLO {
+            anyhow::bail!("expected Discord hello, got op {}", hello.op);
+        }
+        let period = std::time::Duration::from_millis(
+            hello.d["heartbeat_interval"].as_u64().unwrap_or(41_250),
+        );
+        let mut heartbeat = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+
+        let identify = serde_json::json!({
+            "op": OP_IDENTIFY,
+            "d": {
+                "token": self.bot_token,
+                "intents": INTENTS,
+                "properties": { "os": std::env::consts::OS, "browser": "goose", "device": "goose" },
+            },
+        });
+        socket
+            .send(WsMessage::Text(identify.to_string().into()))
+            .await?;
+
+        let mut sequence: Option<u64> = None;
+        let mut bot_id: Option<String> = None;
+
+        loop {
+            let frame = tokio::select! {
+                _ = cancel.cancelled() => {
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                _ = heartbeat.tick() => {
+                    let beat = serde_json::json!({ "op": OP_HEARTBEAT, "d": sequence });
+                    socket.send(WsMessage::Text(beat.to_string().into())).await?;
+                    continue;
+                }
+                frame = socket.next() => frame,
+            };
+            let text = match frame {
+                Some(Ok(WsMessage::Text(text))) => text,
+                Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+            };
+
+            let payload: GatewayPayload = match serde_json::from_str(text.as_str()) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!(error = %e, "unparseable Discord gateway frame");
+                    continue;
+                }
+            };
+            if payload.s.is_some() {
+                sequence = payload.s;
+            }
+
+            match payload.op {
+                OP_DISPATCH => match payload.t.as_deref() {
+                    Some("READY") => {
+                        bot_id = payload.d["user"]["id"].as_str().map(str::to_string);
+                        tracing::info!("Discord gateway connected");
+                    }
+                    Some("MESSAGE_CREATE") => {
+                        match serde_json::from_value::<DiscordMessage>(payload.d) {
+                            Ok(message) => self.dispatch(message, bot_id.as_deref(), sink),
+                            Err(e) => tracing::warn!(error = %e, "unparseable Discord message"),
+                        }
+                    }
+                    _ => {}
+                },
+                OP_HEARTBEAT => {
+                    let beat = serde_json::json!({ "op": OP_HEARTBEAT, "d": sequence });
+                    socket
+                        .send(WsMessage::Text(beat.to_string().into()))
+                        .await?;
+                }
+                OP_RECONNECT | OP_INVALID_SESSION => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn dispatch(&self, message: DiscordMessage, bot_id: Option<&str>, sink: &MessageSink) {
+        let Some((incoming, attachments)) = to_incoming(message, bot_id) else {
+            return;
+        };
+
+        let client = self.client.clone();
+        let sink = sink.clone();
+        tokio::spawn(async move {
+            let mut incoming = incoming;
+            for attachment in attachments {
+                let mime_type = attachment
+                    .content_type
+                    .unwrap_or_else(|| "application/octet-stream".to_string());
+                if let Some(downloaded) = download_attachment(
+                    client.get(attachment.url),
+                    attachment.filename,
+                    mime_type,
+                    attachment.size,
+                )
+                .await
+                {
+                    incoming.attachments.push(downloaded);
+                }
+            }
+            sink(incoming);
+        });
+    }
+
+    async fn post(&self, path: &str, body: serde_json::Value) -> anyhow::Result<()> {
+        self.client
+            .post(self.api_url(path))
+            .header("Authorization", self.authorization())
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Turns a Discord message into a message for the handler, along with the attachments still
+/// to be downloaded. Returns `None` for bot messages and server messages that do not mention
+/// the bot.
+fn to_incoming(
+    message: DiscordMessage,
+    bot_id: Option<&str>,
+) -> Option<(IncomingMessage, Vec<DiscordAttachment>)> {
+    if message.author.bot {
+        return None;
+    }
+    if message.guild_id.is_some()
+        && !message
+            .mentions
+            .iter()
+            .any(|user| Some(user.id.as_str()) == bot_id)
+    {
+        return None;
+    }
+
+    let mut text = message.content;
+    if let Some(bot_id) = bot_id {
+        for mention in [format!("<@{bot_id}>"), format!("<@!{bot_id}>")] {
+            text = text.replace(&mention, "");
+        }
+    }
+
+    let incoming = IncomingMessage {
+        user: PlatformUser {
+            platform: "discord".to_string(),
+            user_id: message.author.id,
+            display_name: Some(
+                message
+                    .author
+                    .global_name
+                    .unwrap_or(message.author.username),
+            ),
+            thread_id: Some(message.channel_id),
+        },
+        text: text.trim().to_string(),
+        platform_message_id: Some(message.id),
+        attachments: vec![],
+    };
+    Some((incoming, message.attachments))
+}
+
+#[async_trait]
+impl Gateway for DiscordGateway {
+    fn gateway_type(&self) -> &str {
+        "discord"
+    }
+
+    async fn start(
+        &self,
+        handler: GatewayHandler,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let sink = handler.into_sink("discord");
+        tracing::info!("Discord gateway starting websocket loop");
+
+        while !cancel.is_cancelled() {
+            if let Err(e) = self.run_socket(&sink, &cancel).await {
+                tracing::error!(error = %e, "Discord gateway error");
+                tokio::select! {
+                    _ = cancel.cancelled() => {}
+                    _ = tokio::time::sleep(RETRY_DELAY) => {}
+                }
+            }
+        }
+
+        tracing::info!("Discord gateway shutting down");
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        user: &PlatformUser,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<()> {
+        let channel_id = user
+            .thread_id
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no Discord channel for user {}", user.user_id))?;
+
+        match message {
+            OutgoingMessage::Text { body } => {
+                let content = super::discord_format::markdown_to_discord(&body);
+                for chunk in split_message(&content, MAX_MESSAGE_LENGTH) {
+                    self.post(
+                        &format!("/channels/{}/messages", channel_id),
+                        serde_json::json!({ "content": chunk }),
+                    )
+                    .await?;
+                }
+            }
+            OutgoingMessage::Typing => {
+                self.post(
+                    &format!("/channels/{}/typing", channel_id),
+                    serde_json::json!({}),
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn validate_config(&self) -> anyhow::Result<()> {
+        let resp = self
+            .client
+            .get(self.api_url("/users/@me"))
+            .header("Authorization", self.authorization())
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("invalid Discord bot token: HTTP {}", resp.status());
+        }
+
+        let me: serde_json::Value = resp.json().await?;
+        if let Some(username) = me["username"].as_str() {
+            tracing::info!(bot = %username, "Discord bot verified");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn message(value: serde_json::Value) -> DiscordMessage {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn server_messages_need_a_mention() {
+        let unmentioned = message(serde_json::json!({
+            "id": "1",
+            "channel_id": "C1",
+            "guild_id": "G1",
+            "author": { "id": "U1", "username": "ada" },
+            "content": "chatting amongst ourselves",
+        }));
+        assert!(to_incoming(unmentioned, Some("BOT")).is_none());
+
+        let from_bot = message(serde_json::json!({
+            "id": "2",
+            "channel_id": "D1",
+            "author": { "id": "BOT", "username": "goose", "bot": true },
+            "content": "Paired!",
+        }));
+        assert!(to_incoming(from_bot, Some("BOT")).is_none());
+
+        let dm = message(serde_json::json!({
+            "id": "3",
+            "channel_id": "D1",
+            "author": { "id": "U1", "username": "ada", "global_name": "Ada" },
+            "content": "hello",
+        }));
+        let (incoming, _) = to_incoming(dm, Some("BOT")).unwrap();
+        assert_eq!(incoming.user.thread_id.as_deref(), Some("D1"));
+        assert_eq!(incoming.user.display_name.as_deref(), Some("Ada"));
+    }
+
+    #[tokio::test]
+    async fn gateway_round_trip_against_mock_api() {
+        let api = MockServer::start().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+
+        Mock::given(method("GET"))
+            .and(path("/gateway/bot"))
+            .and(header("authorization", "Bot test-token"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "url": ws_url })),
+            )
+            .mount(&api)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/attachments/diagram.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0x89, b'P', b'N', b'G']))
+            .mount(&api)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/channels/T1/messages"))
+            .and(body_json(serde_json::json!({
+                "content": "```\nx | y\n--+--\n1 | 2\n```"
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&api)
+            .await;
+
+        let attachment_url = format!("{}/attachments/diagram.png", api.uri());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let hello = serde_json::json!({ "op": 10, "d": { "heartbeat_interval": 45000 } });
+            socket
+                .send(WsMessage::Text(hello.to_string().into()))
+                .await
+                .unwrap();
+            let identify = socket.next().await.unwrap().unwrap();
+
+            let frames = [
+                serde_json::json!({
+                    "op": 0, "s": 1, "t": "READY",
+                    "d": { "user": { "id": "BOT", "username": "goose" } },
+                }),
+                serde_json::json!({
+                    "op": 0, "s": 2, "t": "MESSAGE_CREATE",
+                    "d": {
+                        "id": "M1",
+                        "channel_id": "T1",
+                        "guild_id": "G1",
+                        "author": { "id": "U1", "username": "ada" },
+                        "content": "<@BOT> what is this?",
+                        "mentions": [{ "id": "BOT", "username": "goose", "bot": true }],
+                        "attachments": [{
+                            "filename": "diagram.png",
+                            "content_type": "image/png",
+                            "url": attachment_url,
+                            "size": 4,
+                        }],
+                    },
+                }),
+                serde_json::json!({ "op": 7, "d": null }),
+            ];
+            for frame in frames {
+                socket
+                    .send(WsMessage::Text(frame.to_string().into()))
+                    .await
+                    .unwrap();
+            }
+            identify
+        });
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sink: MessageSink = Arc::new(move |incoming| {
+            tx.send(incoming).unwrap();
+        });
+        let gateway = DiscordGateway::new(&GatewayConfig {
+            gateway_type: "discord".to_string(),
+            platform_config: serde_json::json!({
+                "bot_token": "test-token",
+                "api_base": api.uri(),
+            }),
+            max_sessions: 0,
+        })
+        .unwrap();
+        gateway
+            .run_socket(&sink, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let identify: serde_json::Value =
+            serde_json::from_str(server.await.unwrap().to_text().unwrap()).unwrap();
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["token"], "test-token");
+
+        let incoming = rx.recv().await.unwrap();
+        assert_eq!(incoming.text, "what is this?");
+        assert_eq!(incoming.user.user_id, "U1");
+        assert_eq!(incoming.attachments[0].mime_type, "image/png");
+
+        gateway
+            .send_message(
+                &incoming.user,
+                OutgoingMessage::Text {
+                    body: "| x | y |\n|---|---|\n| 1 | 2 |".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Adapt markdown for Discord.
+///
+/// Discord renders most markdown itself, so the source is passed through except for the
+/// pieces it cannot display: tables become aligned code blocks, headings below `###` become
+/// bold lines and horizontal rules become a divider.
+pub fn markdown_to_discord(markdown: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    let mut output = String::with_capacity(markdown.len());
+    let mut copied_to = 0;
+
+    let mut table: Option<TableBuilder> = None;
+    let mut deep_heading: Option<String> = None;
+
+    for (event, range) in Parser::new_ext(markdown, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Table(_)) => {
+                output.push_str(markdown.get(copied_to..range.start).unwrap_or_default());
+                table = Some(TableBuilder::default());
+            }
+            Event::End(TagEnd::Table) => {
+                if let Some(table) = table.take() {
+                    output.push_str(&table.render());
+                }
+                keep_line_end(&mut output, markdown, &range);
+                copied_to = range.end;
+            }
+            Event::Start(Tag::Heading { level, .. }) if level > HeadingLevel::H3 => {
+                output.push_str(markdown.get(copied_to..range.start).unwrap_or_default());
+                deep_heading = Some(String::new());
+            }
+            Event::End(TagEnd::Heading(_)) if deep_heading.is_some() => {
+                let text = deep_heading.take().unwrap_or_default();
+                output.push_str(&format!("**{}**", text.trim()));
+                keep_line_end(&mut output, markdown, &range);
+                copied_to = range.end;
+            }
+            Event::Rule => {
+                output.push_str(markdown.get(copied_to..range.start).unwrap_or_default());
+                output.push_str("———");
+                keep_line_end(&mut output, markdown, &range);
+                copied_to = range.end;
+            }
+            Event::Start(Tag::TableRow) | Event::Start(Tag::TableHead) => {
+                if let Some(table) = table.as_mut() {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Event::Start(Tag::TableCell) => {
+                if let Some(row) = table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(String::new());
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(cell) = table
+                    .as_mut()
+                    .and_then(|t| t.rows.last_mut())
+                    .and_then(|row| row.last_mut())
+                {
+                    cell.push_str(&text);
+                } else if let Some(heading) = deep_heading.as_mut() {
+                    heading.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    output.push_str(markdown.get(copied_to..).unwrap_or_default());
+    output.trim().to_string()
+}
+
+/// Replaced blocks keep the line break that ended them in the source.
+fn keep_line_end(output: &mut String, markdown: &str, range: &Range<usize>) {
+    if markdown
+        .get(range.clone())
+        .is_some_and(|s| s.ends_with('\n'))
+    {
+        output.push('\n');
+    }
+}
+
+#[derive(Default)]
+struct TableBuilder {
+    rows: Vec<Vec<String>>,
+}
+
+impl TableBuilder {
+    fn render(&self) -> String {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut lines = Vec::with_capacity(self.rows.len() + 1);
+        for (index, row) in self.rows.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(i, width)| {
+                    format!("{:<width$}", row.get(i).map(String::as_str).unwrap_or(""))
+                })
+                .collect();
+            lines.push(cells.join(" | ").trim_end().to_string());
+            if index == 0 {
+                let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+                lines.push(rule.join("-+-"));
+            }
+        }
+        format!("```\n{}\n```", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_markdown_passes_through() {
+        let md = "# Title\n\n**bold** and *italic* with `code` and [a link](https://example.com)\n\n- item";
+        assert_eq!(markdown_to_discord(md), md);
+    }
+
+    #[test]
+    fn table_becomes_code_block() {
+        let md =
+            "Results:\n\n| Name | Count |\n|------|-------|\n| a | 1 |\n| longer | 22 |\n\nDone.";
+        assert_eq!(
+            markdown_to_discord(md),
+            "Results:\n\n```\nName   | Count\n-------+------\na      | 1\nlonger | 22\n```\n\nDone."
+        );
+    }
+
+    #[test]
+    fn deep_heading_and_rule() {
+        assert_eq!(
+            markdown_to_discord("#### Details\n\nabove\n\n---\n\nbelow"),
+            "**Details**\n\nabove\n\n———\n\nbelow"
+        );
+    }
+}
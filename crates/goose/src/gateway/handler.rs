@@ -1,7 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use futures::StreamExt;
 use tokio_util::sync::CancellationToken;
 
@@ -99,6 +101,7 @@ impl GatewayHandler {
                 }
             }
             PairingState::Paired { session_id, .. } => {
+                let session_id = self.resolve_session(&message.user, session_id).await?;
                 self.relay_to_session(&message, &session_id).await?;
             }
         }
@@ -119,6 +122,62 @@ impl GatewayHandler {
     }
 
     async fn complete_pairing(&self, user: &PlatformUser) -> anyhow::Result<()> {
+        let session_id = self.create_session(user).await?;
+
+        let now = chrono::Utc::now().timestamp();
+        self.pairing_store
+            .set(
+                user,
+                PairingState::Paired {
+                    session_id: session_id.clone(),
+                    paired_at: now,
+                },
+            )
+            .await?;
+        self.pairing_store
+            .set_thread_session(user, &session_id)
+            .await?;
+
+        self.gateway
+            .send_message(
+                user,
+                OutgoingMessage::Text {
+                    body: "Paired! You can now chat with goose.".into(),
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Picks the session for the thread a paired user wrote in. Messages outside a thread use
+    /// the session created at pairing; each new thread starts a fresh session.
+    async fn resolve_session(
+        &self,
+        user: &PlatformUser,
+        paired_session_id: String,
+    ) -> anyhow::Result<String> {
+        if user.thread_id.is_none() {
+            return Ok(paired_session_id);
+        }
+        if let Some(session_id) = self.pairing_store.thread_session(user).await {
+            let session_manager = self.agent_manager.session_manager();
+            if session_manager
+                .get_session(&session_id, false)
+                .await
+                .is_ok()
+            {
+                return Ok(session_id);
+            }
+        }
+        let session_id = self.create_session(user).await?;
+        self.pairing_store
+            .set_thread_session(user, &session_id)
+            .await?;
+        Ok(session_id)
+    }
+
+    async fn create_session(&self, user: &PlatformUser) -> anyhow::Result<String> {
         let working_dir = gateway_working_dir(&user.platform, &user.user_id);
         std::fs::create_dir_all(&working_dir)?;
 
@@ -160,28 +219,7 @@ impl GatewayHandler {
         }
 
         update.apply().await?;
-
-        let now = chrono::Utc::now().timestamp();
-        self.pairing_store
-            .set(
-                user,
-                PairingState::Paired {
-                    session_id: session.id.clone(),
-                    paired_at: now,
-                },
-            )
-            .await?;
-
-        self.gateway
-            .send_message(
-                user,
-                OutgoingMessage::Text {
-                    body: "Paired! You can now chat with goose.".into(),
-                },
-            )
-            .await?;
-
-        Ok(())
+        Ok(session.id)
     }
 
     /// Sync the session's provider, model, and extensions with the current
@@ -298,7 +336,7 @@ impl GatewayHandler {
         agent.load_extensions_from_session(&session).await;
 
         let cancel = CancellationToken::new();
-        let user_message = Message::user().with_text(&message.text);
+        let user_message = user_message_with_attachments(message, &session.working_dir);
 
         // Cap tool-calling loops so the agent doesn't run away doing
         // dozens of tool calls before responding.  After this many
@@ -436,11 +474,13 @@ impl GatewayHandler {
                 Ok(AgentEvent::ModelChange {
                     ref model,
                     ref mode,
+                    ref provider,
                 }) => {
                     tracing::debug!(
                         session_id,
                         model,
                         mode,
+                        provider = provider.as_deref(),
                         "gateway stream: model change #{event_count}"
                     );
                 }
@@ -502,6 +542,46 @@ impl GatewayHandler {
     }
 }
 
+/// Images are passed to the model inline; other attachments are saved into the session's
+/// working directory so the agent can open them with its tools.
+fn user_message_with_attachments(message: &IncomingMessage, working_dir: &Path) -> Message {
+    let mut text = message.text.clone();
+    let mut images = Vec::new();
+
+    for attachment in &message.attachments {
+        if attachment.mime_type.starts_with("image/") {
+            images.push(attachment);
+            continue;
+        }
+        let file_name = Path::new(&attachment.filename)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "attachment".to_string());
+        // Prefixed so attachments sharing a name don't overwrite each other
+        let path = working_dir.join("attachments").join(format!(
+            "{}-{}",
+            uuid::Uuid::new_v4().simple(),
+            file_name
+        ));
+        let saved = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&path, &attachment.data));
+        match saved {
+            Ok(()) => text.push_str(&format!("\n\n[Attached file: {}]", path.display())),
+            Err(e) => {
+                tracing::warn!(error = %e, file = %attachment.filename, "failed to save gateway attachment")
+            }
+        }
+    }
+
+    let mut user_message = Message::user().with_text(text.trim());
+    for image in images {
+        user_message = user_message.with_image(BASE64.encode(&image.data), &image.mime_type);
+    }
+    user_message
+}
+
 fn gateway_working_dir(platform: &str, user_id: &str) -> PathBuf {
     Paths::config_dir()
         .join("gateway")
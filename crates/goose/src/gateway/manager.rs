@@ -77,6 +77,14 @@ impl GatewayManager {
 
     /// Load saved gateway configs and start them. Called once at server startup.
     pub async fn check_auto_start(&self) {
+        if let Err(e) = self
+            .pairing_store
+            .prune_thread_sessions(self.agent_manager.session_manager())
+            .await
+        {
+            tracing::warn!(error = %e, "failed to prune gateway thread sessions");
+        }
+
         let configs = match Self::load_saved_configs() {
             Ok(configs) => configs,
             Err(e) => {
@@ -316,6 +324,7 @@ impl GatewayManager {
             platform: platform.to_string(),
             user_id: user_id.to_string(),
             display_name: None,
+            thread_id: None,
         };
         let state = self.pairing_store.get(&user).await?;
         if matches!(state, PairingState::Paired { .. }) {
@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+
+use super::telegram::split_message;
+use super::{
+    download_attachment, Gateway, GatewayConfig, GatewayHandler, IncomingMessage, MessageSink,
+    OutgoingMessage, PlatformUser,
+};
+use async_trait::async_trait;
+use pulldown_cmark::{Options, Parser};
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use tokio_util::sync::CancellationToken;
+
+const SYNC_TIMEOUT_MS: u64 = 30_000;
+const MAX_MESSAGE_LENGTH: usize = 30_000;
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+const TYPING_TIMEOUT_MS: u64 = 30_000;
+
+/// Only message events matter; leaving out presence and state keeps each sync small.
+const SYNC_FILTER: &str = r#"{"presence":{"types":[]},"account_data":{"types":[]},"room":{"state":{"types":[]},"ephemeral":{"types":[]},"timeline":{"types":["m.room.message"],"limit":50}}}"#;
+
+/// A Matrix account driven through the client-server API with a long-polling `/sync` loop.
+///
+/// Rooms the bot is invited to are joined automatically. Pairing is per Matrix user and each
+/// room maps to its own session, so a user can keep separate conversations in separate rooms.
+pub struct MatrixGateway {
+    homeserver: String,
+    access_token: String,
+    client: Client,
+    user_id: OnceCell<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SyncResponse {
+    next_batch: String,
+    #[serde(default)]
+    rooms: SyncRooms,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SyncRooms {
+    #[serde(default)]
+    join: HashMap<String, JoinedRoom>,
+    #[serde(default)]
+    invite: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JoinedRoom {
+    #[serde(default)]
+    timeline: Timeline,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Timeline {
+    #[serde(default)]
+    events: Vec<RoomEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoomEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    sender: String,
+    event_id: Option<String>,
+    #[serde(default)]
+    content: serde_json::Value,
+}
+
+impl MatrixGateway {
+    pub fn new(config: &GatewayConfig) -> anyhow::Result<Self> {
+        let homeserver = config.platform_config["homeserver"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing homeserver in platform_config"))?
+            .trim_end_matches('/')
+            .to_string();
+        let access_token = config.platform_config["access_token"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("missing access_token in platform_config"))?
+            .to_string();
+
+        Ok(Self {
+            homeserver,
+            access_token,
+            client: Client::new(),
+            user_id: OnceCell::new(),
+        })
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/_matrix/client{}", self.homeserver, path)
+    }
+
+    fn room_url(&self, room_id: &str, rest: &str) -> String {
+        self.api_url(&format!(
+            "/v3/rooms/{}/{}",
+            urlencoding::encode(room_id),
+            rest
+        ))
+    }
+
+    /// The bot's own Matrix ID, looked up once so its messages can be ignored.
+    async fn user_id(&self) -> anyhow::Result<&str> {
+        let user_id = self
+            .user_id
+            .get_or_try_init(|| async {
+                let resp: serde_json::Value = self
+                    .client
+                    .get(self.api_url("/v3/account/whoami"))
+                    .bearer_auth(&self.access_token)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                resp["user_id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("Matrix whoami returned no user_id"))
+            })
+            .await?;
+        Ok(user_id)
+    }
+
+    async fn sync(&self, since: Option<&str>, timeout_ms: u64) -> anyhow::Result<SyncResponse> {
+        let mut url = url::Url::parse(&self.api_url("/v3/sync"))?;
+        url.query_pairs_mut()
+            .append_pair("timeout", &timeout_ms.to_string())
+            .append_pair("filter", SYNC_FILTER);
+        if let Some(since) = since {
+            url.query_pairs_mut().append_pair("since", since);
+        }
+
+        Ok(self
+            .client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .timeout(std::time::Duration::from_millis(timeout_ms + 10_000))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Runs the sync loop until cancelled, keeping `since` at the last batch seen so a retry
+    /// after an error picks up where this one stopped. Without a batch to resume from, the first
+    /// sync only records where the timeline stands, so messages sent while goose was offline
+    /// are not answered late.
+    async fn run_sync(
+        &self,
+        since: &mut Option<String>,
+        sink: &MessageSink,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        let own_id = self.user_id().await?.to_string();
+        if since.is_none() {
+            let initial = self.sync(None, 0).await?;
+            self.join_invites(&initial.rooms).await;
+            *since = Some(initial.next_batch);
+        }
+
+        while let Some(batch) = since.as_deref() {
+            let response = tokio::select! {
+                _ = cancel.cancelled() => return Ok(()),
+                response = self.sync(Some(batch), SYNC_TIMEOUT_MS) => response?,
+            };
+            *since = Some(response.next_batch);
+            self.join_invites(&response.rooms).await;
+
+            for (room_id, room) in response.rooms.join {
+                for event in room.timeline.events {
+                    if let Some((incoming, media)) = to_incoming(&room_id, event, &own_id) {
+                        self.dispatch(incoming, media, sink);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn join_invites(&self, rooms: &SyncRooms) {
+        for room_id in rooms.invite.keys() {
+            let joined = self
+                .client
+                .post(self.room_url(room_id, "join"))
+                .bearer_auth(&self.access_token)
+                .json(&serde_json::json!({}))
+                .send()
+                .await
+                .and_then(|r| r.error_for_status());
+            match joined {
+                Ok(_) => tracing::info!(room = %room_id, "joined Matrix room"),
+                Err(e) => tracing::warn!(room = %room_id, error = %e, "failed to join Matrix room"),
+            }
+        }
+    }
+
+    fn dispatch(&self, incoming: IncomingMessage, media: Option<MediaRef>, sink: &MessageSink) {
+        let Some(media) = media else {
+            sink(incoming);
+            return;
+        };
+
+        let Some(download_url) = self.media_url(&media.mxc) else {
+            tracing::warn!(url = %media.mxc, "unsupported Matrix media URL");
+            sink(incoming);
+            return;
+        };
+        let request = self
+            .client
+            .get(download_url)
+            .bearer_auth(&self.access_token);
+        let sink = sink.clone();
+        tokio::spawn(async move {
+            let mut incoming = incoming;
+            if let Some(attachment) =
+                download_attachment(request, media.filename, media.mime_type, media.size).await
+            {
+                incoming.attachments.push(attachment);
+            }
+            sink(incoming);
+        });
+    }
+
+    fn media_url(&self, mxc: &str) -> Option<String> {
+        let (server, media_id) = mxc.strip_prefix("mxc://")?.split_once('/')?;
+        Some(self.api_url(&format!(
+            "/v1/media/download/{}/{}",
+            urlencoding::encode(server),
+            urlencoding::encode(media_id)
+        )))
+    }
+
+    async fn send_text(&self, room_id: &str, text: &str) -> anyhow::Result<()> {
+        for chunk in split_message(text, MAX_MESSAGE_LENGTH) {
+            let txn_id = uuid::Uuid::new_v4().to_string();
+            self.client
+                .put(self.room_url(room_id, &format!("send/m.room.message/{}", txn_id)))
+                .bearer_auth(&self.access_token)
+                .json(&serde_json::json!({
+                    "msgtype": "m.text",
+                    "body": chunk,
+                    "format": "org.matrix.custom.html",
+                    "formatted_body": markdown_to_matrix_html(&chunk),
+                }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+
+    async fn send_typing(&self, room_id: &str) -> anyhow::Result<()> {
+        let user_id = self.user_id().await?;
+        self.client
+            .put(self.room_url(room_id, &format!("typing/{}", urlencoding::encode(user_id))))
+            .bearer_auth(&self.access_token)
+            .json(&serde_json::json!({ "typing": true, "timeout": TYPING_TIMEOUT_MS }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+struct MediaRef {
+    mxc: String,
+    filename: String,
+    mime_type: String,
+    size: Option<u64>,
+}
+
+/// Turns a room event into a message for the handler. Returns `None` for the bot's own
+/// messages, edits and anything that is not a user message.
+fn to_incoming(
+    room_id: &str,
+    event: RoomEvent,
+    own_id: &str,
+) -> Option<(IncomingMessage, Option<MediaRef>)> {
+    if event.event_type != "m.room.message" || event.sender == own_id {
+        return None;
+    }
+    let content = &event.content;
+    if content["m.relates_to"]["rel_type"] == "m.replace" {
+        return None;
+    }
+
+    let body = content["body"].as_str().unwrap_or_default().to_string();
+    let (text, media) = match content["msgtype"].as_str()? {
+        "m.text" | "m.notice" | "m.emote" => (body, None),
+        "m.image" | "m.file" | "m.audio" | "m.video" => {
+            let media = content["url"].as_str().map(|mxc| MediaRef {
+                mxc: mxc.to_string(),
+                filename: content["filename"].as_str().unwrap_or(&body).to_string(),
+                mime_type: content["info"]["mimetype"]
+                    .as_str()
+                    .unwrap_or("application/octet-stream")
+                    .to_string(),
+                size: content["info"]["size"].as_u64(),
+            });
+            // The body is the file name unless a caption was given alongside it
+            let caption = content["filename"]
+                .as_str()
+                .filter(|filename| *filename != body)
+                .map(|_| body)
+                .unwrap_or_default();
+            (caption, media)
+        }
+        _ => return None,
+    };
+
+    let incoming = IncomingMessage {
+        user: PlatformUser {
+            platform: "matrix".to_string(),
+            user_id: event.sender,
+            display_name: None,
+            thread_id: Some(room_id.to_string()),
+        },
+        text,
+        platform_message_id: event.event_id,
+        attachments: vec![],
+    };
+    Some((incoming, media))
+}
+
+fn markdown_to_matrix_html(markdown: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    let mut html = String::with_capacity(markdown.len());
+    pulldown_cmark::html::push_html(&mut html, Parser::new_ext(markdown, options));
+    html.trim_end().to_string()
+}
+
+#[async_trait]
+impl Gateway for MatrixGateway {
+    fn gateway_type(&self) -> &str {
+        "matrix"
+    }
+
+    async fn start(
+        &self,
+        handler: GatewayHandler,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let sink = handler.into_sink("matrix");
+        tracing::info!(homeserver = %self.homeserver, "Matrix gateway starting sync loop");
+
+        let mut since = None;
+        while !cancel.is_cancelled() {
+            if let Err(e) = self.run_sync(&mut since, &sink, &cancel).await {
+                tracing::error!(error = %e, "Matrix sync error");
+                tokio::select! {
+                    _ = cancel.cancelled() => {}
+                    _ = tokio::time::sleep(RETRY_DELAY) => {}
+                }
+            }
+        }
+
+        tracing::info!("Matrix gateway shutting down");
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        user: &PlatformUser,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<()> {
+        let room_id = user
+            .thread_id
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("no Matrix room for user {}", user.user_id))?;
+
+        match message {
+            OutgoingMessage::Text { body } => self.send_text(room_id, &body).await,
+            OutgoingMessage::Typing => self.send_typing(room_id).await,
+        }
+    }
+
+    async fn validate_config(&self) -> anyhow::Result<()> {
+        let user_id = self
+            .user_id()
+            .await
+            .map_err(|e| anyhow::anyhow!("invalid Matrix access token: {}", e))?;
+        tracing::info!(user = %user_id, "Matrix account verified");
+        Ok(())
+    }
+
+    fn info(&self) -> HashMap<String, String> {
+        let mut info = HashMap::from([("homeserver".to_string(), self.homeserver.clone())]);
+        if let Some(user_id) = self.user_id.get() {
+            info.insert("user_id".to_string(), user_id.clone());
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+    use wiremock::matchers::{
+        body_partial_json, method, path, path_regex, query_param, query_param_is_missing,
+    };
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sync_body(next_batch: &str, rooms: serde_json::Value) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "next_batch": next_batch,
+            "rooms": rooms,
+        }))
+    }
+
+    #[tokio::test]
+    async fn sync_loop_maps_rooms_to_threads() {
+        let homeserver = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/account/whoami"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "user_id": "@goose:hs" })),
+            )
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param_is_missing("since"))
+            .respond_with(sync_body(
+                "s1",
+                serde_json::json!({
+                    "join": { "!room:hs": { "timeline": { "events": [{
+                        "type": "m.room.message",
+                        "sender": "@ada:hs",
+                        "content": { "msgtype": "m.text", "body": "sent while offline" },
+                    }]}}},
+                    "invite": { "!new:hs": {} },
+                }),
+            ))
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/_matrix/client/v3/rooms/%21new%3Ahs/join"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param("since", "s1"))
+            .respond_with(sync_body(
+                "s2",
+                serde_json::json!({
+                    "join": { "!room:hs": { "timeline": { "events": [
+                        {
+                            "type": "m.room.message",
+                            "sender": "@goose:hs",
+                            "content": { "msgtype": "m.text", "body": "my own reply" },
+                        },
+                        {
+                            "type": "m.room.message",
+                            "sender": "@ada:hs",
+                            "event_id": "$e1",
+                            "content": {
+                                "msgtype": "m.file",
+                                "body": "summarise this",
+                                "filename": "notes.txt",
+                                "url": "mxc://hs/abc",
+                                "info": { "mimetype": "text/plain", "size": 5 },
+                            },
+                        },
+                    ]}}},
+                }),
+            ))
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param("since", "s2"))
+            .respond_with(
+                sync_body("s3", serde_json::json!({}))
+                    .set_delay(std::time::Duration::from_secs(30)),
+            )
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v1/media/download/hs/abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("notes"))
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path_regex(
+                r"^/_matrix/client/v3/rooms/%21room%3Ahs/send/m\.room\.message/.+$",
+            ))
+            .and(body_partial_json(serde_json::json!({
+                "body": "**done**",
+                "formatted_body": "<p><strong>done</strong></p>",
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&homeserver)
+            .await;
+
+        let gateway = Arc::new(
+            MatrixGateway::new(&GatewayConfig {
+                gateway_type: "matrix".to_string(),
+                platform_config: serde_json::json!({
+                    "homeserver": homeserver.uri(),
+                    "access_token": "token",
+                }),
+                max_sessions: 0,
+            })
+            .unwrap(),
+        );
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sink: MessageSink = Arc::new(move |incoming| {
+            tx.send(incoming).unwrap();
+        });
+        let cancel = CancellationToken::new();
+        let sync = tokio::spawn({
+            let gateway = gateway.clone();
+            let cancel = cancel.clone();
+            async move { gateway.run_sync(&mut None, &sink, &cancel).await }
+        });
+
+        let incoming = rx.recv().await.unwrap();
+        cancel.cancel();
+        sync.await.unwrap().unwrap();
+        assert!(
+            rx.try_recv().is_err(),
+            "backlog and own messages are skipped"
+        );
+
+        assert_eq!(incoming.text, "summarise this");
+        assert_eq!(incoming.user.user_id, "@ada:hs");
+        assert_eq!(incoming.user.thread_id.as_deref(), Some("!room:hs"));
+        assert_eq!(incoming.attachments[0].filename, "notes.txt");
+        assert_eq!(incoming.attachments[0].data, b"notes");
+
+        gateway
+            .send_message(
+                &incoming.user,
+                OutgoingMessage::Text {
+                    body: "**done**".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn sync_resumes_after_an_error() {
+        let homeserver = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/account/whoami"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "user_id": "@goose:hs" })),
+            )
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param_is_missing("since"))
+            .respond_with(sync_body("s1", serde_json::json!({})))
+            .expect(1)
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param("since", "s1"))
+            .respond_with(ResponseTemplate::new(502))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param("since", "s1"))
+            .respond_with(sync_body(
+                "s2",
+                serde_json::json!({
+                    "join": { "!room:hs": { "timeline": { "events": [{
+                        "type": "m.room.message",
+                        "sender": "@ada:hs",
+                        "content": { "msgtype": "m.text", "body": "sent during the outage" },
+                    }]}}},
+                }),
+            ))
+            .mount(&homeserver)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(query_param("since", "s2"))
+            .respond_with(
+                sync_body("s3", serde_json::json!({}))
+                    .set_delay(std::time::Duration::from_secs(30)),
+            )
+            .mount(&homeserver)
+            .await;
+
+        let gateway = MatrixGateway::new(&GatewayConfig {
+            gateway_type: "matrix".to_string(),
+            platform_config: serde_json::json!({
+                "homeserver": homeserver.uri(),
+                "access_token": "token",
+            }),
+            max_sessions: 0,
+        })
+        .unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sink: MessageSink = Arc::new(move |incoming| {
+            tx.send(incoming).unwrap();
+        });
+        let cancel = CancellationToken::new();
+
+        let mut since = None;
+        assert!(gateway.run_sync(&mut since, &sink, &cancel).await.is_err());
+        assert_eq!(since.as_deref(), Some("s1"));
+
+        let incoming = {
+            let resumed = gateway.run_sync(&mut since, &sink, &cancel);
+            tokio::pin!(resumed);
+            let incoming = tokio::select! {
+                result = &mut resumed => panic!("sync stopped: {:?}", result),
+                incoming = rx.recv() => incoming.unwrap(),
+            };
+            cancel.cancel();
+            resumed.await.unwrap();
+            incoming
+        };
+
+        assert_eq!(incoming.text, "sent during the outage");
+        assert_eq!(since.as_deref(), Some("s2"));
+    }
+}
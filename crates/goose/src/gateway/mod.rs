@@ -1,12 +1,20 @@
+pub mod discord;
+pub mod discord_format;
 pub mod handler;
 pub mod manager;
+pub mod matrix;
 pub mod pairing;
+pub mod slack;
+pub mod slack_format;
 pub mod telegram;
 pub mod telegram_format;
+pub mod webhook;
 
 use async_trait::async_trait;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use utoipa::ToSchema;
 
@@ -17,6 +25,10 @@ pub struct PlatformUser {
     pub platform: String,
     pub user_id: String,
     pub display_name: Option<String>,
+    /// Conversation the message arrived in, for platforms with threads or channels. Each
+    /// thread gets its own session; pairing is per user and ignores it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<String>,
 }
 
 impl PartialEq for PlatformUser {
@@ -43,14 +55,84 @@ pub struct IncomingMessage {
     pub attachments: Vec<Attachment>,
 }
 
+/// Attachments larger than this are skipped rather than downloaded.
+pub const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct Attachment {
     pub filename: String,
     pub mime_type: String,
     pub data: Vec<u8>,
 }
 
+/// Receives messages from gateways that read a websocket, so the socket loop can be driven
+/// without a full [`GatewayHandler`].
+pub(crate) type MessageSink = Arc<dyn Fn(IncomingMessage) + Send + Sync>;
+
+impl GatewayHandler {
+    pub(crate) fn into_sink(self, platform: &'static str) -> MessageSink {
+        Arc::new(move |incoming| {
+            let handler = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handler.handle_message(incoming).await {
+                    tracing::error!(error = %e, platform, "error handling gateway message");
+                }
+            });
+        })
+    }
+}
+
+/// Downloads an attachment, skipping it when the platform reports or serves more than
+/// [`MAX_ATTACHMENT_BYTES`]. The body is read in chunks and the download abandoned as soon as
+/// it grows past the limit, so a platform that reports no size cannot make it buffer more.
+pub(crate) async fn download_attachment(
+    request: reqwest::RequestBuilder,
+    filename: String,
+    mime_type: String,
+    size: Option<u64>,
+) -> Option<Attachment> {
+    if size.is_some_and(|size| size > MAX_ATTACHMENT_BYTES) {
+        tracing::warn!(file = %filename, "skipping oversized gateway attachment");
+        return None;
+    }
+    let response = match request.send().await.and_then(|r| r.error_for_status()) {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!(error = %e, file = %filename, "failed to download gateway attachment");
+            return None;
+        }
+    };
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_ATTACHMENT_BYTES)
+    {
+        tracing::warn!(file = %filename, "skipping oversized gateway attachment");
+        return None;
+    }
+
+    let mut data = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                tracing::warn!(error = %e, file = %filename, "failed to download gateway attachment");
+                return None;
+            }
+        };
+        if (data.len() + chunk.len()) as u64 > MAX_ATTACHMENT_BYTES {
+            tracing::warn!(file = %filename, "skipping oversized gateway attachment");
+            return None;
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Some(Attachment {
+        filename,
+        mime_type,
+        data,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum OutgoingMessage {
@@ -94,9 +176,13 @@ pub trait Gateway: Send + Sync + 'static {
     }
 }
 
-pub fn create_gateway(config: &mut GatewayConfig) -> anyhow::Result<std::sync::Arc<dyn Gateway>> {
+pub fn create_gateway(config: &mut GatewayConfig) -> anyhow::Result<Arc<dyn Gateway>> {
     match config.gateway_type.as_str() {
-        "telegram" => Ok(std::sync::Arc::new(telegram::TelegramGateway::new(config)?)),
+        "telegram" => Ok(Arc::new(telegram::TelegramGateway::new(config)?)),
+        "slack" => Ok(Arc::new(slack::SlackGateway::new(config)?)),
+        "discord" => Ok(Arc::new(discord::DiscordGateway::new(config)?)),
+        "matrix" => Ok(Arc::new(matrix::MatrixGateway::new(config)?)),
+        "webhook" => Ok(Arc::new(webhook::WebhookGateway::new(config)?)),
         other => anyhow::bail!("Unknown gateway type: {}", other),
     }
 }
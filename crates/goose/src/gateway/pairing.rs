@@ -1,14 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
+use crate::config::paths::Paths;
 use crate::config::Config;
+use crate::session::SessionManager;
 
 use super::{PairingState, PlatformUser};
 
 const PAIRINGS_CONFIG_KEY: &str = "gateway_pairings";
 const PENDING_CODES_CONFIG_KEY: &str = "gateway_pending_codes";
+/// Where thread sessions were kept in config.yaml before they moved to their own file.
+const THREAD_SESSIONS_CONFIG_KEY: &str = "gateway_thread_sessions";
+const THREAD_SESSIONS_FILE: &str = "gateway/thread_sessions.json";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct StoredPairing {
@@ -25,15 +32,36 @@ struct StoredPendingCode {
     expires_at: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredThreadSession {
+    platform: String,
+    user_id: String,
+    thread_id: String,
+    session_id: String,
+}
+
+type ThreadKey = (PlatformUser, String);
+
 pub struct PairingStore {
     pairings: RwLock<HashMap<PlatformUser, PairingState>>,
+    /// Session of each thread, kept in the gateway's own state file rather than config.yaml
+    /// since it grows with every thread.
+    thread_sessions: RwLock<HashMap<ThreadKey, String>>,
+    thread_sessions_path: PathBuf,
 }
 
 impl PairingStore {
     pub fn new() -> anyhow::Result<Self> {
+        Self::with_thread_sessions_file(Paths::in_state_dir(THREAD_SESSIONS_FILE))
+    }
+
+    fn with_thread_sessions_file(thread_sessions_path: PathBuf) -> anyhow::Result<Self> {
         let pairings = Self::load_pairings_from_config();
+        let thread_sessions = Self::load_thread_sessions(&thread_sessions_path)?;
         Ok(Self {
             pairings: RwLock::new(pairings),
+            thread_sessions: RwLock::new(thread_sessions),
+            thread_sessions_path,
         })
     }
 
@@ -46,12 +74,71 @@ impl PairingStore {
                 platform: entry.platform,
                 user_id: entry.user_id,
                 display_name: entry.display_name,
+                thread_id: None,
             };
             map.insert(user, entry.state);
         }
         map
     }
 
+    /// Reads the thread sessions file, moving entries an older version left in config.yaml
+    /// over to it the first time.
+    fn load_thread_sessions(path: &Path) -> anyhow::Result<HashMap<ThreadKey, String>> {
+        let entries: Vec<StoredThreadSession> = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let config = Config::global();
+                match config.get_param::<Vec<StoredThreadSession>>(THREAD_SESSIONS_CONFIG_KEY) {
+                    Ok(entries) => {
+                        Self::write_thread_sessions(path, &entries)?;
+                        let _ = config.delete(THREAD_SESSIONS_CONFIG_KEY);
+                        entries
+                    }
+                    Err(_) => Vec::new(),
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let user = PlatformUser {
+                    platform: entry.platform,
+                    user_id: entry.user_id,
+                    display_name: None,
+                    thread_id: None,
+                };
+                ((user, entry.thread_id), entry.session_id)
+            })
+            .collect())
+    }
+
+    fn write_thread_sessions(path: &Path, entries: &[StoredThreadSession]) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec(entries)?)?;
+        fs::rename(&temp_path, path)
+            .map_err(|e| anyhow::anyhow!("failed to save gateway thread sessions: {}", e))
+    }
+
+    fn save_thread_sessions(
+        &self,
+        thread_sessions: &HashMap<ThreadKey, String>,
+    ) -> anyhow::Result<()> {
+        let entries: Vec<StoredThreadSession> = thread_sessions
+            .iter()
+            .map(|((user, thread_id), session_id)| StoredThreadSession {
+                platform: user.platform.clone(),
+                user_id: user.user_id.clone(),
+                thread_id: thread_id.clone(),
+                session_id: session_id.clone(),
+            })
+            .collect();
+        Self::write_thread_sessions(&self.thread_sessions_path, &entries)
+    }
+
     fn save_pairings_to_config(
         pairings: &HashMap<PlatformUser, PairingState>,
     ) -> anyhow::Result<()> {
@@ -98,7 +185,74 @@ impl PairingStore {
     pub async fn remove(&self, user: &PlatformUser) -> anyhow::Result<()> {
         let mut pairings = self.pairings.write().await;
         pairings.remove(user);
-        Self::save_pairings_to_config(&pairings)
+        Self::save_pairings_to_config(&pairings)?;
+        self.remove_thread_sessions(|owner| owner == user).await
+    }
+
+    /// The session bound to the thread `user` wrote in, if one was created before.
+    pub async fn thread_session(&self, user: &PlatformUser) -> Option<String> {
+        let thread_id = user.thread_id.clone()?;
+        self.thread_sessions
+            .read()
+            .await
+            .get(&(user.clone(), thread_id))
+            .cloned()
+    }
+
+    pub async fn set_thread_session(
+        &self,
+        user: &PlatformUser,
+        session_id: &str,
+    ) -> anyhow::Result<()> {
+        let Some(thread_id) = user.thread_id.clone() else {
+            return Ok(());
+        };
+        let mut thread_sessions = self.thread_sessions.write().await;
+        thread_sessions.insert((user.clone(), thread_id), session_id.to_string());
+        self.save_thread_sessions(&thread_sessions)
+    }
+
+    /// Forgets threads whose sessions have been deleted, so they start over with a new one.
+    pub async fn prune_thread_sessions(
+        &self,
+        session_manager: &SessionManager,
+    ) -> anyhow::Result<()> {
+        let session_ids: HashSet<String> = self
+            .thread_sessions
+            .read()
+            .await
+            .values()
+            .cloned()
+            .collect();
+        let mut deleted = HashSet::new();
+        for session_id in session_ids {
+            if session_manager
+                .get_session(&session_id, false)
+                .await
+                .is_err()
+            {
+                deleted.insert(session_id);
+            }
+        }
+        if deleted.is_empty() {
+            return Ok(());
+        }
+        let mut thread_sessions = self.thread_sessions.write().await;
+        thread_sessions.retain(|_, session_id| !deleted.contains(session_id));
+        self.save_thread_sessions(&thread_sessions)
+    }
+
+    async fn remove_thread_sessions(
+        &self,
+        matches: impl Fn(&PlatformUser) -> bool,
+    ) -> anyhow::Result<()> {
+        let mut thread_sessions = self.thread_sessions.write().await;
+        let before = thread_sessions.len();
+        thread_sessions.retain(|(owner, _), _| !matches(owner));
+        if thread_sessions.len() == before {
+            return Ok(());
+        }
+        self.save_thread_sessions(&thread_sessions)
     }
 
     pub async fn store_pending_code(
@@ -150,6 +304,8 @@ impl PairingStore {
         pairings.retain(|user, _| user.platform != platform);
         let removed = before - pairings.len();
         Self::save_pairings_to_config(&pairings)?;
+        self.remove_thread_sessions(|owner| owner.platform == platform)
+            .await?;
         Ok(removed)
     }
 
@@ -177,6 +333,49 @@ impl PairingStore {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session::SessionType;
+
+    #[tokio::test]
+    async fn test_thread_sessions_persist_until_their_session_is_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gateway").join("thread_sessions.json");
+        let session_manager = SessionManager::new(dir.path().join("sessions"));
+        let session = session_manager
+            .create_session(
+                dir.path().to_path_buf(),
+                "thread".to_string(),
+                SessionType::Gateway,
+            )
+            .await
+            .unwrap();
+        let user = |thread: &str| PlatformUser {
+            platform: "slack".to_string(),
+            user_id: "U1".to_string(),
+            display_name: None,
+            thread_id: Some(thread.to_string()),
+        };
+
+        let store = PairingStore::with_thread_sessions_file(path.clone()).unwrap();
+        store
+            .set_thread_session(&user("live"), &session.id)
+            .await
+            .unwrap();
+        store
+            .set_thread_session(&user("gone"), "deleted-session")
+            .await
+            .unwrap();
+
+        let store = PairingStore::with_thread_sessions_file(path.clone()).unwrap();
+        assert_eq!(
+            store.thread_session(&user("live")).await,
+            Some(session.id.clone())
+        );
+        store.prune_thread_sessions(&session_manager).await.unwrap();
+
+        let store = PairingStore::with_thread_sessions_file(path).unwrap();
+        assert_eq!(store.thread_session(&user("live")).await, Some(session.id));
+        assert_eq!(store.thread_session(&user("gone")).await, None);
+    }
 
     #[test]
     fn test_code_generation() {
@@ -0,0 +1,543 @@
+use super::telegram::split_message;
+use super::{
+    download_attachment, Gateway, GatewayConfig, GatewayHandler, IncomingMessage, MessageSink,
+    OutgoingMessage, PlatformUser,
+};
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use reqwest::Client;
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_util::sync::CancellationToken;
+
+const SLACK_API_BASE: &str = "https://slack.com/api";
+const MAX_MESSAGE_LENGTH: usize = 4000;
+const RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Slack over Socket Mode: events arrive on a websocket opened with the app-level token
+/// (`xapp-…`) and replies are posted through the Web API with the bot token (`xoxb-…`).
+///
+/// Direct messages and `@mentions` are handled. Each Slack thread maps to its own session;
+/// top-level direct messages share the session created at pairing.
+pub struct SlackGateway {
+    bot_token: String,
+    app_token: String,
+    api_base: String,
+    client: Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackResponse {
+    ok: bool,
+    error: Option<String>,
+    url: Option<String>,
+    user: Option<String>,
+    team: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SocketEnvelope {
+    #[serde(rename = "type")]
+    envelope_type: String,
+    envelope_id: Option<String>,
+    payload: Option<EventsApiPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsApiPayload {
+    event: Option<SlackEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    subtype: Option<String>,
+    user: Option<String>,
+    bot_id: Option<String>,
+    channel: Option<String>,
+    channel_type: Option<String>,
+    #[serde(default)]
+    text: String,
+    ts: Option<String>,
+    thread_ts: Option<String>,
+    #[serde(default)]
+    files: Vec<SlackFile>,
+    user_profile: Option<SlackUserProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackFile {
+    name: Option<String>,
+    mimetype: Option<String>,
+    url_private_download: Option<String>,
+    size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackUserProfile {
+    display_name: Option<String>,
+    real_name: Option<String>,
+}
+
+impl SlackGateway {
+    pub fn new(config: &GatewayConfig) -> anyhow::Result<Self> {
+        let token = |key: &str| {
+            config.platform_config[key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("missing {} in platform_config", key))
+        };
+
+        Ok(Self {
+            bot_token: token("bot_token")?,
+            app_token: token("app_token")?,
+            api_base: config.platform_config["api_base"]
+                .as_str()
+                .unwrap_or(SLACK_API_BASE)
+                .trim_end_matches('/')
+                .to_string(),
+            client: Client::new(),
+        })
+    }
+
+    fn api_url(&self, method: &str) -> String {
+        format!("{}/{}", self.api_base, method)
+    }
+
+    async fn call(
+        &self,
+        method: &str,
+        token: &str,
+        body: serde_json::Value,
+    ) -> anyhow::Result<SlackResponse> {
+        let resp: SlackResponse = self
+            .client
+            .post(self.api_url(method))
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if !resp.ok {
+            anyhow::bail!(
+                "Slack API error from {}: {}",
+                method,
+                resp.error.as_deref().unwrap_or("unknown")
+            );
+        }
+        Ok(resp)
+    }
+
+    /// Reads one Socket Mode connection until Slack asks us to reconnect or it drops.
+    async fn run_socket(
+        &self,
+        sink: &MessageSink,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<()> {
+        let url = self
+            .call(
+                "apps.connections.open",
+                &self.app_token,
+                serde_json::json!({}),
+            )
+            .await?
+            .url
+            .ok_or_else(|| anyhow::anyhow!("Slack returned no Socket Mode URL"))?;
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await?;
+
+        loop {
+            let frame = tokio::select! {
+                _ = cancel.cancelled() => {
+                    let _ = socket.close(None).await;
+                    return Ok(());
+                }
+                frame = socket.next() => frame,
+            };
+            let text = match frame {
+                Some(Ok(WsMessage::Text(text))) => text,
+                Some(Ok(WsMessage::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(e.into()),
+            };
+
+            let envelope: SocketEnvelope = match serde_json::from_str(text.as_str()) {
+                Ok(envelope) => envelope,
+                Err(e) => {
+                    tracing::warn!(error = %e, "unparseable Slack Socket Mode frame");
+                    continue;
+                }
+            };
+
+            // Slack redelivers anything that is not acknowledged within a few seconds
+            if let Some(id) = &envelope.envelope_id {
+                let ack = serde_json::json!({ "envelope_id": id }).to_string();
+                socket.send(WsMessage::Text(ack.into())).await?;
+            }
+
+            match envelope.envelope_type.as_str() {
+                "hello" => tracing::info!("Slack Socket Mode connected"),
+                "disconnect" => return Ok(()),
+                "events_api" => {
+                    if let Some(event) = envelope.payload.and_then(|p| p.event) {
+                        self.dispatch(event, sink);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn dispatch(&self, event: SlackEvent, sink: &MessageSink) {
+        let Some((incoming, files)) = to_incoming(event) else {
+            return;
+        };
+
+        let client = self.client.clone();
+        let bot_token = self.bot_token.clone();
+        let sink = sink.clone();
+        tokio::spawn(async move {
+            let mut incoming = incoming;
+            for file in files {
+                let Some(url) = file.url_private_download else {
+                    continue;
+                };
+                let request = client.get(url).bearer_auth(&bot_token);
+                let filename = file.name.unwrap_or_else(|| "attachment".to_string());
+                let mime_type = file
+                    .mimetype
+                    .unwrap_or_else(|| "application/octet-stream".to_string());
+                if let Some(attachment) =
+                    download_attachment(request, filename, mime_type, file.size).await
+                {
+                    incoming.attachments.push(attachment);
+                }
+            }
+            sink(incoming);
+        });
+    }
+
+    async fn send_text(&self, user: &PlatformUser, text: &str) -> anyhow::Result<()> {
+        let (channel, thread_ts) = reply_target(user)?;
+        let mrkdwn = super::slack_format::markdown_to_slack_mrkdwn(text);
+        for chunk in split_message(&mrkdwn, MAX_MESSAGE_LENGTH) {
+            let mut body = serde_json::json!({
+                "channel": channel,
+                "text": chunk,
+                "mrkdwn": true,
+            });
+            if let Some(thread_ts) = thread_ts {
+                body["thread_ts"] = serde_json::json!(thread_ts);
+            }
+            self.call("chat.postMessage", &self.bot_token, body).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns a Slack event into a message for the handler, along with the files still to be
+/// downloaded. Returns `None` for events goose should not answer, such as its own posts.
+fn to_incoming(event: SlackEvent) -> Option<(IncomingMessage, Vec<SlackFile>)> {
+    let is_dm = event.event_type == "message" && event.channel_type.as_deref() == Some("im");
+    let is_mention = event.event_type == "app_mention";
+    if !(is_dm || is_mention) || event.bot_id.is_some() {
+        return None;
+    }
+    if !matches!(event.subtype.as_deref(), None | Some("file_share")) {
+        return None;
+    }
+
+    let user_id = event.user?;
+    let channel = event.channel?;
+    // Mentions in a channel always get a threaded reply, so the thread is keyed by the
+    // mention itself when it did not already start inside one
+    let thread_ts = match (event.thread_ts, is_mention) {
+        (Some(thread_ts), _) => Some(thread_ts),
+        (None, true) => event.ts.clone(),
+        (None, false) => None,
+    };
+    let thread_id = match thread_ts {
+        Some(thread_ts) => format!("{channel}:{thread_ts}"),
+        None => channel,
+    };
+    let display_name = event.user_profile.and_then(|profile| {
+        profile
+            .display_name
+            .filter(|name| !name.is_empty())
+            .or(profile.real_name)
+    });
+
+    let incoming = IncomingMessage {
+        user: PlatformUser {
+            platform: "slack".to_string(),
+            user_id,
+            display_name,
+            thread_id: Some(thread_id),
+        },
+        text: clean_text(&event.text),
+        platform_message_id: event.ts,
+        attachments: vec![],
+    };
+    Some((incoming, event.files))
+}
+
+/// Strips `<@U123>` mentions and undoes Slack's escaping of `&`, `<` and `>`.
+fn clean_text(text: &str) -> String {
+    let mut cleaned = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<@") {
+        let (before, tail) = rest.split_at(start);
+        cleaned.push_str(before);
+        match tail.find('>') {
+            Some(end) => rest = tail.split_at(end + 1).1,
+            None => {
+                rest = tail;
+                break;
+            }
+        }
+    }
+    cleaned.push_str(rest);
+
+    cleaned
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+fn reply_target(user: &PlatformUser) -> anyhow::Result<(&str, Option<&str>)> {
+    let thread_id = user
+        .thread_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("no Slack channel for user {}", user.user_id))?;
+    Ok(match thread_id.split_once(':') {
+        Some((channel, thread_ts)) => (channel, Some(thread_ts)),
+        None => (thread_id, None),
+    })
+}
+
+#[async_trait]
+impl Gateway for SlackGateway {
+    fn gateway_type(&self) -> &str {
+        "slack"
+    }
+
+    async fn start(
+        &self,
+        handler: GatewayHandler,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let sink = handler.into_sink("slack");
+        tracing::info!("Slack gateway starting Socket Mode loop");
+
+        while !cancel.is_cancelled() {
+            if let Err(e) = self.run_socket(&sink, &cancel).await {
+                tracing::error!(error = %e, "Slack Socket Mode error");
+                tokio::select! {
+                    _ = cancel.cancelled() => {}
+                    _ = tokio::time::sleep(RETRY_DELAY) => {}
+                }
+            }
+        }
+
+        tracing::info!("Slack gateway shutting down");
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        user: &PlatformUser,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<()> {
+        match message {
+            OutgoingMessage::Text { body } => self.send_text(user, &body).await,
+            // Bots have no typing indicator in the Events API
+            OutgoingMessage::Typing => Ok(()),
+        }
+    }
+
+    async fn validate_config(&self) -> anyhow::Result<()> {
+        let resp = self
+            .call("auth.test", &self.bot_token, serde_json::json!({}))
+            .await
+            .map_err(|e| anyhow::anyhow!("invalid Slack bot token: {}", e))?;
+        if !self.app_token.starts_with("xapp-") {
+            anyhow::bail!("Slack app_token must be an app-level token (xapp-…)");
+        }
+        tracing::info!(
+            bot = resp.user.as_deref().unwrap_or_default(),
+            team = resp.team.as_deref().unwrap_or_default(),
+            "Slack bot verified"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn gateway(api_base: &str) -> SlackGateway {
+        SlackGateway::new(&GatewayConfig {
+            gateway_type: "slack".to_string(),
+            platform_config: serde_json::json!({
+                "bot_token": "xoxb-test",
+                "app_token": "xapp-test",
+                "api_base": api_base,
+            }),
+            max_sessions: 0,
+        })
+        .unwrap()
+    }
+
+    fn event(value: serde_json::Value) -> SlackEvent {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn mentions_are_threaded_and_dms_are_not() {
+        let (mention, _) = to_incoming(event(serde_json::json!({
+            "type": "app_mention",
+            "user": "U1",
+            "channel": "C1",
+            "text": "<@UBOT> compare a &lt; b",
+            "ts": "100.1",
+        })))
+        .unwrap();
+        assert_eq!(mention.user.thread_id.as_deref(), Some("C1:100.1"));
+        assert_eq!(mention.text, "compare a < b");
+
+        let (dm, _) = to_incoming(event(serde_json::json!({
+            "type": "message",
+            "channel_type": "im",
+            "user": "U1",
+            "channel": "D1",
+            "text": "hi",
+            "ts": "100.2",
+        })))
+        .unwrap();
+        assert_eq!(dm.user.thread_id.as_deref(), Some("D1"));
+        assert_eq!(dm.user, mention.user, "pairing is per Slack user");
+
+        let bot_echo = event(serde_json::json!({
+            "type": "message",
+            "channel_type": "im",
+            "bot_id": "B1",
+            "channel": "D1",
+            "text": "Paired!",
+        }));
+        assert!(to_incoming(bot_echo).is_none());
+    }
+
+    #[tokio::test]
+    async fn socket_mode_round_trip_against_mock_api() {
+        let api = MockServer::start().await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+
+        Mock::given(method("POST"))
+            .and(path("/apps.connections.open"))
+            .and(header("authorization", "Bearer xapp-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "url": ws_url,
+            })))
+            .mount(&api)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/files/notes.txt"))
+            .and(header("authorization", "Bearer xoxb-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("file body"))
+            .mount(&api)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat.postMessage"))
+            .and(body_partial_json(serde_json::json!({
+                "channel": "D1",
+                "thread_ts": "200.1",
+                "text": "*done*",
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "ok": true })),
+            )
+            .expect(1)
+            .mount(&api)
+            .await;
+
+        let file_url = format!("{}/files/notes.txt", api.uri());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let frames = [
+                serde_json::json!({ "type": "hello" }),
+                serde_json::json!({
+                    "type": "events_api",
+                    "envelope_id": "env-1",
+                    "payload": { "event": {
+                        "type": "message",
+                        "subtype": "file_share",
+                        "channel_type": "im",
+                        "user": "U1",
+                        "channel": "D1",
+                        "text": "please read this",
+                        "ts": "200.2",
+                        "thread_ts": "200.1",
+                        "user_profile": { "display_name": "", "real_name": "Ada" },
+                        "files": [{
+                            "name": "notes.txt",
+                            "mimetype": "text/plain",
+                            "url_private_download": file_url,
+                            "size": 9,
+                        }],
+                    }},
+                }),
+            ];
+            for frame in frames {
+                socket
+                    .send(WsMessage::Text(frame.to_string().into()))
+                    .await
+                    .unwrap();
+            }
+            let ack = socket.next().await.unwrap().unwrap();
+            socket.close(None).await.ok();
+            ack
+        });
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sink: MessageSink = Arc::new(move |incoming| {
+            tx.send(incoming).unwrap();
+        });
+        let gateway = gateway(&api.uri());
+        gateway
+            .run_socket(&sink, &CancellationToken::new())
+            .await
+            .unwrap();
+
+        let ack = server.await.unwrap();
+        assert_eq!(ack.to_text().unwrap(), r#"{"envelope_id":"env-1"}"#);
+
+        let incoming = rx.recv().await.unwrap();
+        assert_eq!(incoming.text, "please read this");
+        assert_eq!(incoming.user.display_name.as_deref(), Some("Ada"));
+        assert_eq!(incoming.attachments.len(), 1);
+        assert_eq!(incoming.attachments[0].data, b"file body");
+
+        gateway
+            .send_message(
+                &incoming.user,
+                OutgoingMessage::Text {
+                    body: "**done**".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// Convert markdown into Slack `mrkdwn`.
+///
+/// Slack uses single-character emphasis (`*bold*`, `_italic_`, `~strike~`), angle-bracket
+/// links and has no headings or tables, so those are approximated. `&`, `<` and `>` must be
+/// escaped or Slack treats them as control sequences.
+pub fn markdown_to_slack_mrkdwn(markdown: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES;
+    let parser = Parser::new_ext(markdown, options);
+
+    let mut output = String::with_capacity(markdown.len());
+    let mut list_number: Option<u64> = None;
+    let mut quote_depth = 0usize;
+
+    for event in parser {
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::Paragraph => {}
+                Tag::Heading { .. } | Tag::Strong => output.push('*'),
+                Tag::Emphasis => output.push('_'),
+                Tag::Strikethrough => output.push('~'),
+                Tag::CodeBlock(_) => output.push_str("```\n"),
+                Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                    output.push('<');
+                    output.push_str(&escape_mrkdwn(&dest_url));
+                    output.push('|');
+                }
+                Tag::List(start) => {
+                    list_number = start;
+                }
+                Tag::Item => {
+                    if let Some(n) = list_number.as_mut() {
+                        output.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    } else {
+                        output.push_str("• ");
+                    }
+                }
+                Tag::BlockQuote(_) => {
+                    quote_depth += 1;
+                    output.push_str("> ");
+                }
+                Tag::TableCell => output.push_str("| "),
+                _ => {}
+            },
+            Event::End(tag_end) => match tag_end {
+                TagEnd::Paragraph => output.push('\n'),
+                TagEnd::Heading(_) => output.push_str("*\n"),
+                TagEnd::Strong => output.push('*'),
+                TagEnd::Emphasis => output.push('_'),
+                TagEnd::Strikethrough => output.push('~'),
+                TagEnd::CodeBlock => output.push_str("```\n"),
+                TagEnd::Link | TagEnd::Image => output.push('>'),
+                TagEnd::List(_) => {
+                    list_number = None;
+                }
+                TagEnd::Item | TagEnd::TableHead | TagEnd::TableRow => output.push('\n'),
+                TagEnd::TableCell => output.push(' '),
+                TagEnd::BlockQuote(_) => {
+                    quote_depth = quote_depth.saturating_sub(1);
+                }
+                _ => {}
+            },
+            Event::Text(text) => {
+                let escaped = escape_mrkdwn(&text);
+                if quote_depth > 0 {
+                    output.push_str(&escaped.replace('\n', "\n> "));
+                } else {
+                    output.push_str(&escaped);
+                }
+            }
+            Event::Code(code) => {
+                output.push('`');
+                output.push_str(&escape_mrkdwn(&code));
+                output.push('`');
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                output.push('\n');
+                if quote_depth > 0 {
+                    output.push_str("> ");
+                }
+            }
+            Event::Rule => output.push_str("———\n"),
+            _ => {}
+        }
+    }
+
+    collapse_newlines(&output).trim().to_string()
+}
+
+/// Collapse runs of 3+ newlines down to 2, leaving code blocks untouched.
+fn collapse_newlines(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_code = false;
+    let mut blank_run = 0;
+
+    for line in text.split('\n') {
+        if line.starts_with("```") {
+            in_code = !in_code;
+        }
+        if line.is_empty() && !in_code {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    result
+}
+
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emphasis_uses_single_markers() {
+        assert_eq!(
+            markdown_to_slack_mrkdwn("This is **bold**, *italic* and ~~gone~~"),
+            "This is *bold*, _italic_ and ~gone~"
+        );
+    }
+
+    #[test]
+    fn links_use_angle_brackets() {
+        assert_eq!(
+            markdown_to_slack_mrkdwn("Visit [Rust](https://rust-lang.org) docs"),
+            "Visit <https://rust-lang.org|Rust> docs"
+        );
+    }
+
+    #[test]
+    fn heading_becomes_bold_line() {
+        assert_eq!(
+            markdown_to_slack_mrkdwn("## Summary\n\nDone."),
+            "*Summary*\nDone."
+        );
+    }
+
+    #[test]
+    fn code_block_drops_language_and_keeps_blank_lines() {
+        let mrkdwn = markdown_to_slack_mrkdwn("```rust\nfn a() {}\n\n\nfn b() {}\n```");
+        assert_eq!(mrkdwn, "```\nfn a() {}\n\n\nfn b() {}\n```");
+    }
+
+    #[test]
+    fn control_characters_escaped() {
+        assert_eq!(
+            markdown_to_slack_mrkdwn("1 < 2 & 3 > 0"),
+            "1 &lt; 2 &amp; 3 &gt; 0"
+        );
+    }
+
+    #[test]
+    fn lists_and_quotes() {
+        let mrkdwn = markdown_to_slack_mrkdwn("- one\n- two\n\n1. first\n\n> quoted\n> text");
+        assert!(mrkdwn.contains("• one\n• two"));
+        assert!(mrkdwn.contains("1. first"));
+        assert!(mrkdwn.contains("> quoted\n> text"));
+        assert!(!mrkdwn.contains("\n\n\n"));
+    }
+}
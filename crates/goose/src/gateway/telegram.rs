@@ -158,6 +158,7 @@ impl TelegramGateway {
                 }
                 name
             }),
+            thread_id: None,
         }
     }
 }
@@ -274,7 +275,7 @@ impl Gateway for TelegramGateway {
 }
 
 #[allow(clippy::string_slice)]
-fn split_message(text: &str, max_len: usize) -> Vec<String> {
+pub(super) fn split_message(text: &str, max_len: usize) -> Vec<String> {
     if text.len() <= max_len {
         return vec![text.to_string()];
     }
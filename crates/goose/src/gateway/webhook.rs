@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use super::{
+    Attachment, Gateway, GatewayConfig, GatewayHandler, IncomingMessage, MessageSink,
+    OutgoingMessage, PlatformUser, MAX_ATTACHMENT_BYTES,
+};
+use async_trait::async_trait;
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+pub const SIGNATURE_HEADER: &str = "x-goose-signature";
+pub const TIMESTAMP_HEADER: &str = "x-goose-timestamp";
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:8787";
+/// Signed requests older or newer than this are rejected. Within the window replays are
+/// caught by remembering the signatures already accepted.
+const MAX_CLOCK_SKEW_SECS: i64 = 300;
+
+/// A generic HTTP gateway for tools that can only speak webhooks.
+///
+/// Messages are POSTed to `listen` as JSON shaped like [`IncomingMessage`], with attachment
+/// data base64 encoded. Replies are POSTed to `callback_url`. Both directions are signed
+/// with HMAC-SHA256 over `"{timestamp}.{body}"` using the shared `secret`, sent as
+/// `X-Goose-Signature: sha256=<hex>` alongside `X-Goose-Timestamp`.
+pub struct WebhookGateway {
+    listen: SocketAddr,
+    callback_url: String,
+    secret: String,
+    client: Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookMessage {
+    user: WebhookUser,
+    text: String,
+    #[serde(default)]
+    platform_message_id: Option<String>,
+    #[serde(default)]
+    attachments: Vec<WebhookAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookUser {
+    user_id: String,
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    thread_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookAttachment {
+    filename: String,
+    mime_type: String,
+    /// Base64 encoded content.
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookReply<'a> {
+    user: &'a PlatformUser,
+    message: &'a OutgoingMessage,
+}
+
+struct WebhookState {
+    secret: String,
+    sink: MessageSink,
+    /// Signatures accepted within the clock skew window, with their timestamps.
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl WebhookState {
+    /// Records a verified signature, returning false if it was already used.
+    fn first_use(&self, signature: &str, timestamp: i64, now: i64) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, ts| *ts + MAX_CLOCK_SKEW_SECS >= now);
+        seen.insert(signature.to_ascii_lowercase(), timestamp)
+            .is_none()
+    }
+}
+
+impl WebhookGateway {
+    pub fn new(config: &GatewayConfig) -> anyhow::Result<Self> {
+        let field = |key: &str| {
+            config.platform_config[key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow::anyhow!("missing {} in platform_config", key))
+        };
+        let listen = config.platform_config["listen"]
+            .as_str()
+            .unwrap_or(DEFAULT_LISTEN)
+            .parse()
+            .map_err(|e| anyhow::anyhow!("invalid listen address: {}", e))?;
+
+        Ok(Self {
+            listen,
+            callback_url: field("callback_url")?,
+            secret: field("secret")?,
+            client: Client::new(),
+        })
+    }
+
+    /// Accepts signed messages on `listener` until cancelled.
+    async fn serve(
+        &self,
+        listener: TcpListener,
+        sink: MessageSink,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let state = Arc::new(WebhookState {
+            secret: self.secret.clone(),
+            sink,
+            seen: Mutex::new(HashMap::new()),
+        });
+        // Base64 inflates attachments by a third, plus room for the rest of the message
+        let body_limit = (MAX_ATTACHMENT_BYTES as usize) * 4 / 3 + 64 * 1024;
+        let app = Router::new()
+            .route("/", post(receive))
+            .layer(DefaultBodyLimit::max(body_limit))
+            .with_state(state);
+
+        axum::serve(listener, app)
+            .with_graceful_shutdown(cancel.cancelled_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+async fn receive(
+    State(state): State<Arc<WebhookState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let (Some(timestamp), Some(signature)) = (header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER))
+    else {
+        return StatusCode::UNAUTHORIZED;
+    };
+    let now = chrono::Utc::now().timestamp();
+    if !verify(&state.secret, timestamp, &body, signature, now) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    // verify() has already checked the timestamp parses
+    let timestamp = timestamp.parse().unwrap_or_default();
+    if !state.first_use(signature, timestamp, now) {
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let Ok(message) = serde_json::from_slice::<WebhookMessage>(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let attachments: Result<Vec<Attachment>, _> = message
+        .attachments
+        .into_iter()
+        .map(|a| {
+            BASE64.decode(a.data).map(|data| Attachment {
+                filename: a.filename,
+                mime_type: a.mime_type,
+                data,
+            })
+        })
+        .collect();
+    let Ok(attachments) = attachments else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    (state.sink)(IncomingMessage {
+        user: PlatformUser {
+            platform: "webhook".to_string(),
+            user_id: message.user.user_id,
+            display_name: message.user.display_name,
+            thread_id: message.user.thread_id,
+        },
+        text: message.text,
+        platform_message_id: message.platform_message_id,
+        attachments,
+    });
+    StatusCode::ACCEPTED
+}
+
+fn mac(secret: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+/// The `X-Goose-Signature` value for `body` sent at `timestamp`.
+pub fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let digest = mac(secret, timestamp, body).finalize().into_bytes();
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+fn verify(secret: &str, timestamp: &str, body: &[u8], signature: &str, now: i64) -> bool {
+    let fresh = timestamp
+        .parse::<i64>()
+        .is_ok_and(|ts| (now - ts).abs() <= MAX_CLOCK_SKEW_SECS);
+    let Some(expected) = signature.strip_prefix("sha256=").and_then(decode_hex) else {
+        return false;
+    };
+    fresh && mac(secret, timestamp, body).verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[async_trait]
+impl Gateway for WebhookGateway {
+    fn gateway_type(&self) -> &str {
+        "webhook"
+    }
+
+    async fn start(
+        &self,
+        handler: GatewayHandler,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(self.listen).await?;
+        tracing::info!(listen = %self.listen, "Webhook gateway listening");
+        self.serve(listener, handler.into_sink("webhook"), cancel)
+            .await?;
+        tracing::info!("Webhook gateway shutting down");
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        user: &PlatformUser,
+        message: OutgoingMessage,
+    ) -> anyhow::Result<()> {
+        // Webhook consumers have no typing indicator to drive
+        if matches!(message, OutgoingMessage::Typing) {
+            return Ok(());
+        }
+
+        let body = serde_json::to_vec(&WebhookReply {
+            user,
+            message: &message,
+        })?;
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        self.client
+            .post(&self.callback_url)
+            .header("content-type", "application/json")
+            .header(TIMESTAMP_HEADER, &timestamp)
+            .header(SIGNATURE_HEADER, sign(&self.secret, &timestamp, &body))
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn validate_config(&self) -> anyhow::Result<()> {
+        let url = url::Url::parse(&self.callback_url)
+            .map_err(|e| anyhow::anyhow!("invalid callback_url: {}", e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("callback_url must be http or https");
+        }
+        if self.secret.len() < 16 {
+            anyhow::bail!("webhook secret must be at least 16 characters");
+        }
+        Ok(())
+    }
+
+    fn info(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("listen".to_string(), self.listen.to_string()),
+            ("callback_url".to_string(), self.callback_url.clone()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SECRET: &str = "0123456789abcdef";
+
+    fn gateway(callback_url: &str) -> WebhookGateway {
+        WebhookGateway::new(&GatewayConfig {
+            gateway_type: "webhook".to_string(),
+            platform_config: serde_json::json!({
+                "callback_url": callback_url,
+                "secret": SECRET,
+                "listen": "127.0.0.1:0",
+            }),
+            max_sessions: 0,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn signatures_reject_tampering_and_replays() {
+        let body = br#"{"text":"hi"}"#;
+        let signature = sign(SECRET, "1000", body);
+        assert!(verify(SECRET, "1000", body, &signature, 1000));
+        assert!(!verify(
+            SECRET,
+            "1000",
+            br#"{"text":"bye"}"#,
+            &signature,
+            1000
+        ));
+        assert!(!verify("another-secret!!", "1000", body, &signature, 1000));
+        assert!(!verify(SECRET, "1000", body, &signature, 1000 + 301));
+        assert!(!verify(SECRET, "1000", body, "sha256=zz", 1000));
+    }
+
+    #[tokio::test]
+    async fn accepts_signed_messages_and_signs_replies() {
+        let callback = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/reply"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&callback)
+            .await;
+
+        let gateway = Arc::new(gateway(&format!("{}/reply", callback.uri())));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sink: MessageSink = Arc::new(move |incoming| {
+            tx.send(incoming).unwrap();
+        });
+        let cancel = CancellationToken::new();
+        let server = tokio::spawn({
+            let gateway = gateway.clone();
+            let cancel = cancel.clone();
+            async move { gateway.serve(listener, sink, cancel).await }
+        });
+
+        let body = serde_json::json!({
+            "user": { "user_id": "build-bot", "thread_id": "pipeline-42" },
+            "text": "why did this fail?",
+            "attachments": [{
+                "filename": "log.txt",
+                "mime_type": "text/plain",
+                "data": BASE64.encode("error: boom"),
+            }],
+        })
+        .to_string();
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let client = Client::new();
+
+        let unsigned = client.post(&url).body(body.clone()).send().await.unwrap();
+        assert_eq!(unsigned.status(), StatusCode::UNAUTHORIZED);
+
+        let send_signed = || {
+            client
+                .post(&url)
+                .header(TIMESTAMP_HEADER, &timestamp)
+                .header(SIGNATURE_HEADER, sign(SECRET, &timestamp, body.as_bytes()))
+                .body(body.clone())
+                .send()
+        };
+        let signed = send_signed().await.unwrap();
+        assert_eq!(signed.status(), StatusCode::ACCEPTED);
+        let replayed = send_signed().await.unwrap();
+        assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+
+        let incoming = rx.recv().await.unwrap();
+        assert_eq!(incoming.user.platform, "webhook");
+        assert_eq!(incoming.user.thread_id.as_deref(), Some("pipeline-42"));
+        assert_eq!(incoming.attachments[0].data, b"error: boom");
+
+        gateway
+            .send_message(
+                &incoming.user,
+                OutgoingMessage::Text {
+                    body: "the tests timed out".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        cancel.cancel();
+        server.await.unwrap().unwrap();
+
+        let requests = callback.received_requests().await.unwrap();
+        let reply = &requests[0];
+        let header = |name: &str| reply.headers.get(name).unwrap().to_str().unwrap();
+        assert!(verify(
+            SECRET,
+            header(TIMESTAMP_HEADER),
+            &reply.body,
+            header(SIGNATURE_HEADER),
+            chrono::Utc::now().timestamp(),
+        ));
+        let reply: serde_json::Value = serde_json::from_slice(&reply.body).unwrap();
+        assert_eq!(reply["user"]["user_id"], "build-bot");
+        assert_eq!(reply["message"]["type"], "text");
+        assert_eq!(reply["message"]["body"], "the tests timed out");
+    }
+}
@@ -10,7 +10,7 @@ use crate::hints::import_files::read_referenced_files;
 pub const GOOSE_HINTS_FILENAME: &str = ".goosehints";
 pub const AGENTS_MD_FILENAME: &str = "AGENTS.md";
 
-fn find_git_root(start_dir: &Path) -> Option<&Path> {
+pub(crate) fn find_git_root(start_dir: &Path) -> Option<&Path> {
     let mut check_dir = start_dir;
 
     loop {
@@ -27,7 +27,7 @@ fn find_git_root(start_dir: &Path) -> Option<&Path> {
     None
 }
 
-fn get_local_directories(git_root: Option<&Path>, cwd: &Path) -> Vec<PathBuf> {
+pub(crate) fn get_local_directories(git_root: Option<&Path>, cwd: &Path) -> Vec<PathBuf> {
     match git_root {
         Some(git_root) => {
             let mut directories = Vec::new();
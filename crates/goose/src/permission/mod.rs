@@ -1,8 +1,10 @@
 pub mod permission_confirmation;
 pub mod permission_inspector;
 pub mod permission_judge;
+pub mod permission_rules;
 pub mod permission_store;
 
 pub use permission_confirmation::{Permission, PermissionConfirmation};
 pub use permission_inspector::PermissionInspector;
+pub use permission_rules::PermissionRules;
 pub use permission_store::ToolPermissionStore;
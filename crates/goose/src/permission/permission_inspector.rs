@@ -4,27 +4,99 @@ use crate::config::permission::PermissionLevel;
 use crate::config::{GooseMode, PermissionManager};
 use crate::conversation::message::{Message, ToolRequest};
 use crate::permission::permission_judge::{detect_read_only_tools, PermissionCheckResult};
+use crate::permission::permission_rules::PermissionRules;
+use crate::session::SessionManager;
 use crate::tool_inspection::{InspectionAction, InspectionResult, ToolInspector};
 use anyhow::Result;
 use async_trait::async_trait;
 use rmcp::model::Tool;
-use std::collections::HashSet;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+/// Modification time and length of a rules file, or `None` while it does not exist.
+type FileStamp = Option<(SystemTime, u64)>;
+
+struct CachedRules {
+    stamps: Vec<(PathBuf, FileStamp)>,
+    rules: Arc<PermissionRules>,
+}
 
 /// Permission Inspector that handles tool permission checking
 pub struct PermissionInspector {
     pub permission_manager: Arc<PermissionManager>,
     provider: SharedProvider,
+    session_manager: Arc<SessionManager>,
     readonly_tools: RwLock<HashSet<String>>,
+    rules_cache: Mutex<HashMap<PathBuf, CachedRules>>,
 }
 
 impl PermissionInspector {
-    pub fn new(permission_manager: Arc<PermissionManager>, provider: SharedProvider) -> Self {
+    pub fn new(
+        permission_manager: Arc<PermissionManager>,
+        provider: SharedProvider,
+        session_manager: Arc<SessionManager>,
+    ) -> Self {
         Self {
             permission_manager,
             provider,
+            session_manager,
             readonly_tools: RwLock::new(HashSet::new()),
+            rules_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Loads the argument-aware rules that apply to the session's working directory, returning
+    /// them together with that directory.
+    pub async fn load_rules(&self, session_id: &str) -> (Arc<PermissionRules>, PathBuf) {
+        let working_dir = match self.session_manager.get_session(session_id, false).await {
+            Ok(session) => session.working_dir,
+            Err(_) => std::env::current_dir().unwrap_or_default(),
+        };
+        let config_dir = self
+            .permission_manager
+            .get_config_path()
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let rules = self.cached_rules(&config_dir, &working_dir);
+        (rules, working_dir)
+    }
+
+    /// Rules for a working directory, parsed again only when one of its rules files has been
+    /// created, changed or removed since they were last loaded.
+    fn cached_rules(&self, config_dir: &Path, working_dir: &Path) -> Arc<PermissionRules> {
+        let stamps: Vec<(PathBuf, FileStamp)> =
+            PermissionRules::candidate_files(config_dir, working_dir)
+                .into_iter()
+                .map(|file| {
+                    let stamp = std::fs::metadata(&file)
+                        .and_then(|metadata| {
+                            metadata
+                                .modified()
+                                .map(|modified| (modified, metadata.len()))
+                        })
+                        .ok();
+                    (file, stamp)
+                })
+                .collect();
+
+        let mut cache = self.rules_cache.lock().unwrap();
+        if let Some(cached) = cache.get(working_dir) {
+            if cached.stamps == stamps {
+                return cached.rules.clone();
+            }
         }
+        let rules = Arc::new(PermissionRules::load(config_dir, working_dir));
+        cache.insert(
+            working_dir.to_path_buf(),
+            CachedRules {
+                stamps,
+                rules: rules.clone(),
+            },
+        );
+        rules
     }
 
     // readonly_tools is per-agent to avoid concurrent session clobbering; write-annotated
@@ -131,11 +203,40 @@ impl ToolInspector for PermissionInspector {
         let mut results = Vec::new();
         let permission_manager = &self.permission_manager;
         let mut llm_detect_candidates: Vec<&ToolRequest> = Vec::new();
+        let rules = match goose_mode {
+            GooseMode::Approve | GooseMode::SmartApprove => Some(self.load_rules(session_id).await),
+            GooseMode::Chat | GooseMode::Auto => None,
+        };
 
         for request in tool_requests {
             if let Ok(tool_call) = &request.tool_call {
                 let tool_name = &tool_call.name;
 
+                // 0. Argument-aware rules take precedence over tool-level permissions
+                let rule_match = rules.as_ref().and_then(|(rules, working_dir)| {
+                    rules.evaluate(tool_name, tool_call.arguments.as_ref(), working_dir)
+                });
+                if let Some(rule_match) = rule_match {
+                    let explanation = rule_match.explain();
+                    tracing::debug!(tool_name = %tool_name, "{}", explanation);
+                    let action = match rule_match.action {
+                        PermissionLevel::AlwaysAllow => InspectionAction::Allow,
+                        PermissionLevel::NeverAllow => InspectionAction::Deny,
+                        PermissionLevel::AskBefore => {
+                            InspectionAction::RequireApproval(Some(explanation.clone()))
+                        }
+                    };
+                    results.push(InspectionResult {
+                        tool_request_id: request.id.clone(),
+                        action,
+                        reason: explanation,
+                        confidence: 1.0,
+                        inspector_name: self.name().to_string(),
+                        finding_id: None,
+                    });
+                    continue;
+                }
+
                 let action = match goose_mode {
                     GooseMode::Chat => continue,
                     GooseMode::Auto => InspectionAction::Allow,
@@ -288,7 +389,11 @@ mod tests {
         if let Some(level) = cache {
             pm.update_smart_approve_permission("tool", level);
         }
-        let inspector = PermissionInspector::new(pm, Arc::new(Mutex::new(None)));
+        let inspector = PermissionInspector::new(
+            pm,
+            Arc::new(Mutex::new(None)),
+            Arc::new(SessionManager::new(tempfile::tempdir().unwrap().keep())),
+        );
         if smart_approved {
             *inspector.readonly_tools.write().unwrap() = ["tool".to_string()].into_iter().collect();
         }
@@ -304,4 +409,82 @@ mod tests {
             .unwrap();
         assert_eq!(results[0].action, expected);
     }
+
+    #[test_case(GooseMode::Approve, "git status", InspectionAction::Allow; "rule_allows")]
+    #[test_case(GooseMode::SmartApprove, "rm -rf build", InspectionAction::Deny; "rule_denies")]
+    #[test_case(GooseMode::Approve, "make", InspectionAction::Allow; "falls_back_to_user_permission")]
+    #[test_case(GooseMode::Auto, "rm -rf build", InspectionAction::Allow; "auto_ignores_rules")]
+    #[tokio::test]
+    async fn test_inspect_permission_rules(
+        mode: GooseMode,
+        command: &str,
+        expected: InspectionAction,
+    ) {
+        let config_dir = tempfile::tempdir().unwrap().keep();
+        std::fs::write(
+            config_dir.join(crate::permission::permission_rules::PERMISSION_RULES_FILE),
+            "rules:\n  - tool: shell\n    args:\n      command:\n        prefix: git status\n    action: always_allow\n  - tool: shell\n    args:\n      command:\n        prefix: rm\n    action: never_allow\n",
+        )
+        .unwrap();
+        let pm = Arc::new(PermissionManager::new(config_dir));
+        pm.update_user_permission("developer__shell", PermissionLevel::AlwaysAllow);
+
+        let session_manager = Arc::new(SessionManager::new(tempfile::tempdir().unwrap().keep()));
+        let working_dir = tempfile::tempdir().unwrap().keep();
+        let session = session_manager
+            .create_session(
+                working_dir,
+                "test".into(),
+                crate::session::SessionType::User,
+            )
+            .await
+            .unwrap();
+
+        let inspector = PermissionInspector::new(pm, Arc::new(Mutex::new(None)), session_manager);
+        let req = ToolRequest {
+            id: "req".into(),
+            tool_call: Ok(CallToolRequestParams::new("developer__shell")
+                .with_arguments(object!({ "command": command }))),
+            metadata: None,
+            tool_meta: None,
+        };
+        let results = inspector
+            .inspect(&session.id, &[req], &[], mode)
+            .await
+            .unwrap();
+        assert_eq!(results[0].action, expected);
+    }
+
+    #[tokio::test]
+    async fn test_rules_reload_when_file_changes() {
+        let config_dir = tempfile::tempdir().unwrap().keep();
+        let rules_file =
+            config_dir.join(crate::permission::permission_rules::PERMISSION_RULES_FILE);
+        let pm = Arc::new(PermissionManager::new(config_dir.clone()));
+        let inspector = PermissionInspector::new(
+            pm,
+            Arc::new(Mutex::new(None)),
+            Arc::new(SessionManager::new(tempfile::tempdir().unwrap().keep())),
+        );
+        let working_dir = tempfile::tempdir().unwrap();
+
+        let empty = inspector.cached_rules(&config_dir, working_dir.path());
+        assert!(empty.is_empty());
+        std::fs::write(
+            &rules_file,
+            "rules:\n  - tool: shell\n    args:\n      command:\n        prefix: ls\n    action: always_allow\n",
+        )
+        .unwrap();
+        let loaded = inspector.cached_rules(&config_dir, working_dir.path());
+        assert!(!loaded.is_empty());
+        assert!(Arc::ptr_eq(
+            &loaded,
+            &inspector.cached_rules(&config_dir, working_dir.path())
+        ));
+
+        std::fs::remove_file(&rules_file).unwrap();
+        assert!(inspector
+            .cached_rules(&config_dir, working_dir.path())
+            .is_empty());
+    }
 }
@@ -320,11 +320,32 @@ impl ConfigKey {
 pub struct ProviderUsage {
     pub model: String,
     pub usage: Usage,
+    /// The provider that served the request, when it was chosen by a router
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Whether the response was replayed from the response cache instead of the provider
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache_hit: bool,
 }
 
 impl ProviderUsage {
     pub fn new(model: String, usage: Usage) -> Self {
-        Self { model, usage }
+        Self {
+            model,
+            usage,
+            provider: None,
+            cache_hit: false,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    pub fn with_cache_hit(mut self) -> Self {
+        self.cache_hit = true;
+        self
     }
 
     /// Ensures this ProviderUsage has token counts, estimating them if necessary
@@ -352,6 +373,8 @@ impl ProviderUsage {
         ProviderUsage {
             model: self.model.clone(),
             usage: self.usage + other.usage,
+            provider: self.provider.clone().or_else(|| other.provider.clone()),
+            cache_hit: self.cache_hit && other.cache_hit,
         }
     }
 }
@@ -451,6 +474,15 @@ pub trait LeadWorkerProviderTrait {
     fn get_settings(&self) -> (usize, usize, usize);
 }
 
+/// Trait for RouterProvider-specific functionality
+pub trait RouterProviderTrait {
+    /// Get the (provider, model) pairs the router can use, in failover order
+    fn get_backends(&self) -> Vec<(String, String)>;
+
+    /// Get the provider name and model config of the backend that served the last request
+    fn get_active_backend(&self) -> (String, ModelConfig);
+}
+
 /// Base trait for AI providers (OpenAI, Anthropic, etc)
 #[async_trait]
 pub trait Provider: Send + Sync {
@@ -624,12 +656,20 @@ pub trait Provider: Send + Sync {
         None
     }
 
+    /// Check if this provider is a RouterProvider
+    fn as_router(&self) -> Option<&dyn RouterProviderTrait> {
+        None
+    }
+
     /// Get the currently active model name
     /// For regular providers, this returns the configured model
     /// For LeadWorkerProvider, this returns the currently active model (lead or worker)
+    /// For RouterProvider, this returns the model of the backend that served the last request
     fn get_active_model_name(&self) -> String {
         if let Some(lead_worker) = self.as_lead_worker() {
             lead_worker.get_active_model()
+        } else if let Some(router) = self.as_router() {
+            router.get_active_backend().1.model_name
         } else {
             self.get_model_config().model_name
         }
@@ -1,6 +1,13 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+use super::base::Provider;
+use crate::config::Config;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingRequest {
@@ -26,3 +33,43 @@ pub trait EmbeddingCapable {
         texts: Vec<String>,
     ) -> Result<Vec<Vec<f32>>>;
 }
+
+/// Embeds texts for the memory extension's semantic recall, matching
+/// `goose_mcp::MemoryEmbedder`.
+pub type MemoryEmbedder = Arc<
+    dyn Fn(Vec<String>) -> Pin<Box<dyn Future<Output = Result<Vec<Vec<f32>>>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// The embedder for the memory extension, when `GOOSE_MEMORY_EMBEDDINGS` is enabled. Embeddings
+/// come from `GOOSE_MEMORY_EMBEDDING_PROVIDER`, or the main provider when that is not set; the
+/// provider is created the first time something is embedded.
+pub fn memory_embedder() -> Option<MemoryEmbedder> {
+    let config = Config::global();
+    if !config
+        .get_param::<bool>("GOOSE_MEMORY_EMBEDDINGS")
+        .unwrap_or(false)
+    {
+        return None;
+    }
+    let provider_name = config
+        .get_param::<String>("GOOSE_MEMORY_EMBEDDING_PROVIDER")
+        .or_else(|_| config.get_goose_provider())
+        .ok()?;
+
+    let provider: Arc<OnceCell<Arc<dyn Provider>>> = Arc::default();
+    Some(Arc::new(move |texts| {
+        let provider = Arc::clone(&provider);
+        let provider_name = provider_name.clone();
+        Box::pin(async move {
+            let provider = provider
+                .get_or_try_init(|| super::create_with_default_model(&provider_name, Vec::new()))
+                .await?;
+            if !provider.supports_embeddings() {
+                anyhow::bail!("Provider '{}' does not support embeddings", provider_name);
+            }
+            Ok(provider.create_embeddings("", texts).await?)
+        })
+    }))
+}
@@ -1,4 +1,5 @@
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use super::{
     anthropic::AnthropicProvider,
@@ -22,6 +23,8 @@ use super::{
     openai::OpenAiProvider,
     openrouter::OpenRouterProvider,
     provider_registry::ProviderRegistry,
+    response_cache::{CachingProvider, ResponseCacheConfig},
+    router::{RouterEntry, RouterEntryConfig, RouterProvider},
     sagemaker_tgi::SageMakerTgiProvider,
     snowflake::SnowflakeProvider,
     tetrate::TetrateProvider,
@@ -31,6 +34,7 @@ use super::{
 use crate::config::ExtensionConfig;
 use crate::model::ModelConfig;
 use crate::providers::base::ProviderType;
+use crate::recipe::Recipe;
 use crate::{
     config::declarative_providers::register_declarative_providers,
     providers::provider_registry::ProviderEntry,
@@ -118,9 +122,41 @@ pub async fn create(
     name: &str,
     model: ModelConfig,
     extensions: Vec<ExtensionConfig>,
+) -> Result<Arc<dyn Provider>> {
+    let provider = create_uncached(name, model, extensions).await?;
+    if ResponseCacheConfig::enabled_in_config() {
+        tracing::info!("Serving repeated requests from the response cache");
+        return Ok(CachingProvider::wrap(provider));
+    }
+    Ok(provider)
+}
+
+/// Creates the provider for a session started from `recipe`, caching responses when the recipe
+/// asks for it.
+pub async fn create_for_recipe(
+    name: &str,
+    model: ModelConfig,
+    extensions: Vec<ExtensionConfig>,
+    recipe: Option<&Recipe>,
+) -> Result<Arc<dyn Provider>> {
+    let provider = create(name, model, extensions).await?;
+    Ok(CachingProvider::wrap_for_recipe(provider, recipe))
+}
+
+async fn create_uncached(
+    name: &str,
+    model: ModelConfig,
+    extensions: Vec<ExtensionConfig>,
 ) -> Result<Arc<dyn Provider>> {
     let config = crate::config::Config::global();
 
+    if let Ok(entries) = config.get_param::<Vec<RouterEntryConfig>>("GOOSE_PROVIDER_ROUTER") {
+        if !entries.is_empty() && is_configured_default(name, &model) {
+            tracing::info!("Creating provider router with {} entries", entries.len());
+            return create_router(entries, extensions).await;
+        }
+    }
+
     if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
         tracing::info!("Creating lead/worker provider from environment variables");
         return create_lead_worker_from_env(name, &model, &lead_model_name, extensions).await;
@@ -130,6 +166,19 @@ pub async fn create(
     constructor(model, extensions).await
 }
 
+/// The router stands in for the configured default provider only, so a
+/// provider or model picked explicitly (a recipe, a subagent, `--provider`)
+/// is used as asked.
+fn is_configured_default(name: &str, model: &ModelConfig) -> bool {
+    let config = crate::config::Config::global();
+    config
+        .get_param::<String>("GOOSE_PROVIDER")
+        .is_ok_and(|provider| provider == name)
+        && config
+            .get_param::<String>("GOOSE_MODEL")
+            .is_ok_and(|model_name| model_name == model.model_name)
+}
+
 pub async fn create_with_default_model(
     name: impl AsRef<str>,
     extensions: Vec<ExtensionConfig>,
@@ -213,6 +262,34 @@ async fn create_lead_worker_from_env(
     )))
 }
 
+async fn create_router(
+    entries: Vec<RouterEntryConfig>,
+    extensions: Vec<ExtensionConfig>,
+) -> Result<Arc<dyn Provider>> {
+    let mut router_entries = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let constructor = get_from_registry(&entry.provider)
+            .await?
+            .constructor
+            .clone();
+        let model_config = ModelConfig::new(&entry.model)?
+            .with_canonical_limits(&entry.provider)
+            .with_context_limit(entry.context_limit);
+        let provider = constructor(model_config, extensions.clone()).await?;
+
+        let mut router_entry = RouterEntry::new(provider).with_route(entry.route);
+        if let Some(seconds) = entry.cooldown_seconds {
+            router_entry = router_entry.with_cooldown(Duration::from_secs(seconds));
+        }
+        if let Some(threshold) = entry.failure_threshold {
+            router_entry = router_entry.with_failure_threshold(threshold);
+        }
+        router_entries.push(router_entry);
+    }
+
+    Ok(Arc::new(RouterProvider::new(router_entries)?))
+}
+
 fn create_worker_model_config(
     default_model: &ModelConfig,
     provider_name: &str,
@@ -252,6 +329,7 @@ mod tests {
         expected_fallback: usize,
     ) {
         let _guard = env_lock::lock_env([
+            ("GOOSE_PROVIDER_ROUTER", None),
             ("GOOSE_LEAD_MODEL", Some("gpt-4o")),
             ("GOOSE_LEAD_PROVIDER", None),
             ("GOOSE_LEAD_TURNS", lead_turns),
@@ -281,6 +359,7 @@ mod tests {
     #[tokio::test]
     async fn test_create_regular_provider_without_lead_config() {
         let _guard = env_lock::lock_env([
+            ("GOOSE_PROVIDER_ROUTER", None),
             ("GOOSE_LEAD_MODEL", None),
             ("GOOSE_LEAD_PROVIDER", None),
             ("GOOSE_LEAD_TURNS", None),
@@ -301,6 +380,74 @@ mod tests {
         assert_eq!(provider.get_model_config().model_name, "gpt-4o-mini");
     }
 
+    #[tokio::test]
+    async fn test_create_router_provider() {
+        let _guard = env_lock::lock_env([
+            (
+                "GOOSE_PROVIDER_ROUTER",
+                Some(
+                    r#"[{"provider": "openai", "model": "gpt-4o", "route": "tools"},
+                        {"provider": "openai", "model": "gpt-4o-mini", "context_limit": 32000}]"#,
+                ),
+            ),
+            ("GOOSE_PROVIDER", Some("openai")),
+            ("GOOSE_MODEL", Some("gpt-4o-mini")),
+            ("GOOSE_LEAD_MODEL", Some("gpt-4o")),
+            ("OPENAI_API_KEY", Some("fake-openai-no-keyring")),
+            ("OPENAI_CUSTOM_HEADERS", Some("")),
+        ]);
+
+        let provider = create(
+            "openai",
+            ModelConfig::new_or_fail("gpt-4o-mini").with_canonical_limits("openai"),
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+        assert!(provider.as_lead_worker().is_none());
+        let router = provider.as_router().unwrap();
+        assert_eq!(
+            router.get_backends(),
+            vec![
+                ("openai".to_string(), "gpt-4o".to_string()),
+                ("openai".to_string(), "gpt-4o-mini".to_string()),
+            ]
+        );
+        assert_eq!(router.get_active_backend().1.model_name, "gpt-4o");
+
+        // An explicitly chosen model is not swapped for the router
+        let provider = create(
+            "openai",
+            ModelConfig::new_or_fail("gpt-4.1").with_canonical_limits("openai"),
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+        assert!(provider.as_router().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_cached_provider_keeps_lead_worker() {
+        let _guard = env_lock::lock_env([
+            ("GOOSE_RESPONSE_CACHE", Some("true")),
+            ("GOOSE_PROVIDER_ROUTER", None),
+            ("GOOSE_LEAD_MODEL", Some("gpt-4o")),
+            ("GOOSE_LEAD_PROVIDER", None),
+            ("OPENAI_API_KEY", Some("fake-openai-no-keyring")),
+            ("OPENAI_CUSTOM_HEADERS", Some("")),
+        ]);
+
+        let provider = create(
+            "openai",
+            ModelConfig::new_or_fail("gpt-4o-mini").with_canonical_limits("openai"),
+            Vec::new(),
+        )
+        .await
+        .unwrap();
+        let (lead, worker) = provider.as_lead_worker().unwrap().get_model_info();
+        assert_eq!((lead.as_str(), worker.as_str()), ("gpt-4o", "gpt-4o-mini"));
+    }
+
     #[test_case::test_case(None, None, 16_000 ; "no overrides uses default")]
     #[test_case::test_case(Some("32000"), None, 32_000 ; "worker limit overrides default")]
     #[test_case::test_case(Some("32000"), Some("64000"), 32_000 ; "worker limit takes priority over global")]
@@ -33,7 +33,9 @@ pub mod openai_compatible;
 pub mod openrouter;
 pub mod provider_registry;
 pub mod provider_test;
+pub mod response_cache;
 mod retry;
+pub mod router;
 pub mod sagemaker_tgi;
 pub mod snowflake;
 pub mod testprovider;
@@ -45,6 +47,7 @@ pub mod venice;
 pub mod xai;
 
 pub use init::{
-    create, create_with_default_model, create_with_named_model, providers, refresh_custom_providers,
+    create, create_for_recipe, create_with_default_model, create_with_named_model, providers,
+    refresh_custom_providers,
 };
 pub use retry::{retry_operation, RetryConfig};
@@ -0,0 +1,614 @@
+use anyhow::Result;
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::StreamExt;
+use rmcp::model::{CallToolResult, Role, Tool};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::base::{
+    LeadWorkerProviderTrait, MessageStream, PermissionRouting, Provider, ProviderUsage,
+    RouterProviderTrait,
+};
+use super::errors::ProviderError;
+use super::retry::RetryConfig;
+use crate::config::paths::Paths;
+use crate::config::Config;
+use crate::conversation::message::{Message, MessageContent, ToolResponse};
+use crate::model::ModelConfig;
+use crate::permission::permission_confirmation::PermissionConfirmation;
+use crate::recipe::Recipe;
+
+pub const RESPONSE_CACHE_KEY: &str = "GOOSE_RESPONSE_CACHE";
+pub const RESPONSE_CACHE_TTL_KEY: &str = "GOOSE_RESPONSE_CACHE_TTL";
+pub const RESPONSE_CACHE_MAX_SIZE_KEY: &str = "GOOSE_RESPONSE_CACHE_MAX_SIZE";
+
+const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DEFAULT_MAX_SIZE_MB: u64 = 256;
+const ENTRY_EXTENSION: &str = "json";
+/// How often `put` lists the cache directory to drop expired entries. In between, it only
+/// does so when the entries it wrote push the cache past its size limit.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone)]
+pub struct ResponseCacheConfig {
+    pub dir: PathBuf,
+    /// Entries older than this are treated as misses and removed.
+    pub ttl: Duration,
+    /// Oldest entries are evicted once the cache grows past this many bytes.
+    pub max_bytes: u64,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: Paths::in_state_dir("response_cache"),
+            ttl: DEFAULT_TTL,
+            max_bytes: DEFAULT_MAX_SIZE_MB * 1024 * 1024,
+        }
+    }
+}
+
+impl ResponseCacheConfig {
+    /// Whether `GOOSE_RESPONSE_CACHE` turns caching on for every provider.
+    pub fn enabled_in_config() -> bool {
+        Config::global()
+            .get_param::<bool>(RESPONSE_CACHE_KEY)
+            .unwrap_or(false)
+    }
+
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            ttl: config
+                .get_param::<u64>(RESPONSE_CACHE_TTL_KEY)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.ttl),
+            max_bytes: config
+                .get_param::<u64>(RESPONSE_CACHE_MAX_SIZE_KEY)
+                .map(|mb| mb * 1024 * 1024)
+                .unwrap_or(defaults.max_bytes),
+            ..defaults
+        }
+    }
+}
+
+/// Messages reduced to what the model sees. Internal routing metadata such as
+/// `tool_meta` and the default `is_error: false` flag are dropped so that they do not
+/// change request hashes.
+pub(crate) fn stable_messages(messages: &[Message]) -> Vec<(Role, Vec<MessageContent>)> {
+    messages
+        .iter()
+        .map(|msg| {
+            let mut cleaned_content: Vec<_> = msg.content.to_vec();
+
+            for content in &mut cleaned_content {
+                match content {
+                    MessageContent::ToolRequest(ref mut req) => {
+                        req.tool_meta = None;
+                    }
+                    MessageContent::ToolResponse(ToolResponse {
+                        tool_result:
+                            Ok(
+                                ref mut result @ CallToolResult {
+                                    is_error: Some(false),
+                                    ..
+                                },
+                            ),
+                        ..
+                    }) => {
+                        result.is_error = None;
+                    }
+                    _ => {}
+                }
+            }
+            (msg.role.clone(), cleaned_content)
+        })
+        .collect()
+}
+
+/// Key of a request. Session ids and message timestamps are not part of it, so the same
+/// conversation replayed in a new session hits the cache.
+pub fn request_key(
+    provider: &str,
+    model_config: &ModelConfig,
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> String {
+    let request = serde_json::json!({
+        "provider": provider,
+        "model": model_config.model_name,
+        "temperature": model_config.temperature,
+        "max_tokens": model_config.max_tokens,
+        "request_params": model_config.request_params,
+        "reasoning": model_config.reasoning,
+        "system": system,
+        "messages": stable_messages(messages),
+        "tools": tools,
+    });
+    let mut hasher = Sha256::new();
+    hasher.update(request.to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+type Chunk = (Option<Message>, Option<ProviderUsage>);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: u64,
+    chunks: Vec<Chunk>,
+}
+
+/// On-disk store of complete response streams, one file per request key.
+pub struct ResponseCache {
+    config: ResponseCacheConfig,
+    tracked: Mutex<Tracked>,
+}
+
+/// What the cache knows about its directory without listing it.
+#[derive(Default)]
+struct Tracked {
+    /// Size of the entries as of the last sweep, plus everything written since.
+    bytes: u64,
+    swept_at: Option<Instant>,
+}
+
+/// Creates `dir` and writes `path` so that only the current user can read cached responses,
+/// which contain whole conversations.
+fn write_private(dir: &Path, path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(path)?, content)
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self {
+            config,
+            tracked: Mutex::new(Tracked::default()),
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.config.dir.join(format!("{}.{}", key, ENTRY_EXTENSION))
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<Chunk>> {
+        let path = self.entry_path(key);
+        let content = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str::<CacheEntry>(&content) {
+            Ok(entry)
+                if Self::now().saturating_sub(entry.created_at) <= self.config.ttl.as_secs() =>
+            {
+                Some(entry.chunks)
+            }
+            _ => {
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    pub fn put(&self, key: &str, chunks: Vec<Chunk>) -> Result<()> {
+        let entry = CacheEntry {
+            created_at: Self::now(),
+            chunks,
+        };
+        let content = serde_json::to_vec(&entry)?;
+        let path = self.entry_path(key);
+        let temp_path = path.with_extension("tmp");
+        write_private(&self.config.dir, &temp_path, &content)?;
+        fs::rename(&temp_path, &path)?;
+
+        let sweep = {
+            let mut tracked = self.tracked.lock().unwrap();
+            tracked.bytes += content.len() as u64;
+            tracked.bytes > self.config.max_bytes
+                || tracked
+                    .swept_at
+                    .is_none_or(|swept_at| swept_at.elapsed() >= SWEEP_INTERVAL)
+        };
+        if sweep {
+            self.evict()?;
+        }
+        Ok(())
+    }
+
+    /// Removes expired entries, then the least recently written ones until the cache fits
+    /// within its size limit. `put` calls this on its first write, every `SWEEP_INTERVAL`
+    /// and whenever the cache may have outgrown its limit.
+    pub fn evict(&self) -> Result<()> {
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&self.config.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(ENTRY_EXTENSION) {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((modified, metadata.len(), path));
+        }
+
+        let now = SystemTime::now();
+        entries.retain(|(modified, _, path)| {
+            let expired = now
+                .duration_since(*modified)
+                .is_ok_and(|age| age > self.config.ttl);
+            if expired {
+                let _ = fs::remove_file(path);
+            }
+            !expired
+        });
+
+        entries.sort_by_key(|(modified, _, _)| *modified);
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (_, len, path) in entries {
+            if total <= self.config.max_bytes {
+                break;
+            }
+            fs::remove_file(&path)?;
+            total = total.saturating_sub(len);
+        }
+        *self.tracked.lock().unwrap() = Tracked {
+            bytes: total,
+            swept_at: Some(Instant::now()),
+        };
+        Ok(())
+    }
+}
+
+/// Wraps a provider so that identical requests are answered from a [`ResponseCache`].
+/// Fresh responses are streamed through unchanged and stored once they complete; cache
+/// hits replay the recorded stream with [`ProviderUsage::cache_hit`] set.
+pub struct CachingProvider {
+    inner: Arc<dyn Provider>,
+    cache: Arc<ResponseCache>,
+}
+
+impl CachingProvider {
+    pub fn new(inner: Arc<dyn Provider>, config: ResponseCacheConfig) -> Self {
+        Self {
+            inner,
+            cache: Arc::new(ResponseCache::new(config)),
+        }
+    }
+
+    /// Wraps `provider` with the cache configured through the environment.
+    pub fn wrap(provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        Arc::new(Self::new(provider, ResponseCacheConfig::from_config()))
+    }
+
+    /// Wraps `provider` when `recipe` asks for the cache with `settings.response_cache`.
+    /// Providers from [`create`](super::create) are already wrapped when `GOOSE_RESPONSE_CACHE`
+    /// is on, so they are returned as they are.
+    pub fn wrap_for_recipe(
+        provider: Arc<dyn Provider>,
+        recipe: Option<&Recipe>,
+    ) -> Arc<dyn Provider> {
+        let requested = recipe
+            .and_then(|recipe| recipe.settings.as_ref())
+            .and_then(|settings| settings.response_cache)
+            .unwrap_or(false);
+        if requested && !ResponseCacheConfig::enabled_in_config() {
+            Self::wrap(provider)
+        } else {
+            provider
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for CachingProvider {
+    fn get_name(&self) -> &str {
+        self.inner.get_name()
+    }
+
+    async fn stream(
+        &self,
+        model_config: &ModelConfig,
+        session_id: &str,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let key = request_key(self.inner.get_name(), model_config, system, messages, tools);
+
+        if let Some(chunks) = self.cache.get(&key) {
+            tracing::debug!("Response cache hit for {}", key);
+            let replay = chunks
+                .into_iter()
+                .map(|(message, usage)| Ok((message, usage.map(ProviderUsage::with_cache_hit))));
+            return Ok(Box::pin(futures::stream::iter(replay)));
+        }
+
+        let mut stream = self
+            .inner
+            .stream(model_config, session_id, system, messages, tools)
+            .await?;
+        let cache = self.cache.clone();
+        Ok(Box::pin(try_stream! {
+            let mut chunks = Vec::new();
+            while let Some(item) = stream.next().await {
+                let (message, usage) = item?;
+                chunks.push((message.clone(), usage.clone()));
+                yield (message, usage);
+            }
+            if let Err(e) = cache.put(&key, chunks) {
+                tracing::warn!("Failed to store response in cache: {}", e);
+            }
+        }))
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn retry_config(&self) -> RetryConfig {
+        self.inner.retry_config()
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Vec<String>, ProviderError> {
+        self.inner.fetch_supported_models().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn supports_cache_control(&self) -> bool {
+        self.inner.supports_cache_control().await
+    }
+
+    async fn create_embeddings(
+        &self,
+        session_id: &str,
+        texts: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(session_id, texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn as_router(&self) -> Option<&dyn RouterProviderTrait> {
+        self.inner.as_router()
+    }
+
+    async fn configure_oauth(&self) -> Result<(), ProviderError> {
+        self.inner.configure_oauth().await
+    }
+
+    fn permission_routing(&self) -> PermissionRouting {
+        self.inner.permission_routing()
+    }
+
+    async fn handle_permission_confirmation(
+        &self,
+        request_id: &str,
+        confirmation: &PermissionConfirmation,
+    ) -> bool {
+        self.inner
+            .handle_permission_confirmation(request_id, confirmation)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    struct CountingProvider {
+        calls: AtomicUsize,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        fn get_name(&self) -> &str {
+            "counting"
+        }
+
+        async fn stream(
+            &self,
+            _model_config: &ModelConfig,
+            _session_id: &str,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<MessageStream, ProviderError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            let usage = ProviderUsage::new(
+                "counting-model".to_string(),
+                Usage::new(Some(10), Some(5), Some(15)),
+            );
+            let last = if self.fail {
+                Err(ProviderError::ServerError("connection reset".into()))
+            } else {
+                Ok((
+                    Some(Message::assistant().with_text(call.to_string())),
+                    Some(usage),
+                ))
+            };
+            let chunks = vec![
+                Ok((Some(Message::assistant().with_text("call ")), None)),
+                last,
+            ];
+            Ok(Box::pin(futures::stream::iter(chunks)))
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new_or_fail("counting-model")
+        }
+    }
+
+    fn cache_config(dir: &TempDir) -> ResponseCacheConfig {
+        ResponseCacheConfig {
+            dir: dir.path().to_path_buf(),
+            ttl: DEFAULT_TTL,
+            max_bytes: u64::MAX,
+        }
+    }
+
+    fn caching(dir: &TempDir, fail: bool) -> (Arc<CountingProvider>, CachingProvider) {
+        let inner = Arc::new(CountingProvider {
+            calls: AtomicUsize::new(0),
+            fail,
+        });
+        let provider = CachingProvider::new(inner.clone(), cache_config(dir));
+        (inner, provider)
+    }
+
+    async fn ask(
+        provider: &CachingProvider,
+        text: &str,
+    ) -> Result<(String, ProviderUsage), ProviderError> {
+        let model_config = provider.get_model_config();
+        let (message, usage) = provider
+            .complete(
+                &model_config,
+                "session",
+                "system",
+                &[Message::user().with_text(text)],
+                &[],
+            )
+            .await?;
+        Ok((message.as_concat_text(), usage))
+    }
+
+    fn entry_count(dir: &TempDir) -> usize {
+        fs::read_dir(dir.path()).map(|d| d.count()).unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn replays_identical_requests() {
+        let dir = TempDir::new().unwrap();
+        let (inner, provider) = caching(&dir, false);
+
+        let (first, usage) = ask(&provider, "hello").await.unwrap();
+        assert_eq!(first, "call 0");
+        assert!(!usage.cache_hit);
+
+        let (second, usage) = ask(&provider, "hello").await.unwrap();
+        assert_eq!(second, first);
+        assert!(usage.cache_hit);
+        assert_eq!(usage.usage.total_tokens, Some(15));
+
+        let (other, _) = ask(&provider, "something else").await.unwrap();
+        assert_eq!(other, "call 1");
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_streams_are_not_cached() {
+        let dir = TempDir::new().unwrap();
+        let (inner, provider) = caching(&dir, true);
+
+        assert!(ask(&provider, "hello").await.is_err());
+        assert!(ask(&provider, "hello").await.is_err());
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(entry_count(&dir), 0);
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let dir = TempDir::new().unwrap();
+        let cache = ResponseCache::new(cache_config(&dir));
+        cache.put("key", vec![(None, None)]).unwrap();
+        assert!(cache.get("key").is_some());
+
+        let stale = CacheEntry {
+            created_at: ResponseCache::now() - DEFAULT_TTL.as_secs() - 1,
+            chunks: vec![(None, None)],
+        };
+        fs::write(cache.entry_path("key"), serde_json::to_vec(&stale).unwrap()).unwrap();
+        assert!(cache.get("key").is_none());
+        assert_eq!(entry_count(&dir), 0);
+    }
+
+    #[test]
+    fn evicts_oldest_entries_past_the_size_limit() {
+        let dir = TempDir::new().unwrap();
+        let chunks = || vec![(Some(Message::assistant().with_text("cached")), None)];
+
+        let unbounded = ResponseCache::new(cache_config(&dir));
+        unbounded.put("old", chunks()).unwrap();
+        let entry_size = fs::metadata(unbounded.entry_path("old")).unwrap().len();
+
+        let bounded = ResponseCache::new(ResponseCacheConfig {
+            max_bytes: entry_size * 3 / 2,
+            ..cache_config(&dir)
+        });
+        bounded.put("new", chunks()).unwrap();
+        assert!(bounded.get("old").is_none());
+        assert!(bounded.get("new").is_some());
+    }
+
+    #[test]
+    fn puts_under_the_limit_do_not_sweep() {
+        let dir = TempDir::new().unwrap();
+        let cache = ResponseCache::new(cache_config(&dir));
+        cache.put("first", vec![(None, None)]).unwrap();
+
+        let stray = dir.path().join("stray.json");
+        fs::write(&stray, b"{}").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&stray)
+            .unwrap()
+            .set_modified(SystemTime::now() - DEFAULT_TTL * 2)
+            .unwrap();
+        cache.put("second", vec![(None, None)]).unwrap();
+        assert!(stray.exists());
+
+        cache.evict().unwrap();
+        assert!(!stray.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn entries_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let cache = ResponseCache::new(ResponseCacheConfig {
+            dir: dir.path().join("cache"),
+            ..cache_config(&dir)
+        });
+        cache.put("key", vec![(None, None)]).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir.path().join("cache")), 0o700);
+        assert_eq!(mode(&cache.entry_path("key")), 0o600);
+    }
+}
@@ -0,0 +1,612 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use rmcp::model::Tool;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::base::{MessageStream, Provider, ProviderDef, ProviderMetadata, RouterProviderTrait};
+use super::errors::ProviderError;
+use super::retry::should_retry;
+use crate::conversation::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::token_counter::create_token_counter;
+
+const ROUTER_PROVIDER_NAME: &str = "router";
+pub const DEFAULT_COOLDOWN_SECONDS: u64 = 60;
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+/// Which kind of request a router entry accepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteKind {
+    /// Every request
+    #[default]
+    Any,
+    /// Turns that continue a tool loop, i.e. the last message carries tool results
+    Tools,
+    /// Everything else
+    Chat,
+}
+
+impl RouteKind {
+    fn accepts(self, tool_turn: bool) -> bool {
+        match self {
+            RouteKind::Any => true,
+            RouteKind::Tools => tool_turn,
+            RouteKind::Chat => !tool_turn,
+        }
+    }
+}
+
+/// One backend in the `GOOSE_PROVIDER_ROUTER` list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouterEntryConfig {
+    pub provider: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_limit: Option<usize>,
+    #[serde(default)]
+    pub route: RouteKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<u32>,
+}
+
+/// A constructed backend together with its routing and circuit breaker settings
+pub struct RouterEntry {
+    provider: Arc<dyn Provider>,
+    route: RouteKind,
+    cooldown: Duration,
+    failure_threshold: u32,
+}
+
+impl RouterEntry {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self {
+            provider,
+            route: RouteKind::Any,
+            cooldown: Duration::from_secs(DEFAULT_COOLDOWN_SECONDS),
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+        }
+    }
+
+    pub fn with_route(mut self, route: RouteKind) -> Self {
+        self.route = route;
+        self
+    }
+
+    /// How long the entry is skipped once its circuit opens
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Consecutive failures that open the circuit
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct EntryHealth {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl EntryHealth {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|until| until > now)
+    }
+}
+
+/// A provider that sends each request to the first suitable backend in an ordered list,
+/// failing over to the next one on rate limits, server and network errors.
+///
+/// Backends that keep failing have their circuit opened for a cooldown and are skipped until
+/// it expires. Entries can be restricted to tool turns or plain chat, and requests that do
+/// not fit an entry's context limit prefer entries that can hold them.
+pub struct RouterProvider {
+    entries: Vec<RouterEntry>,
+    health: Mutex<Vec<EntryHealth>>,
+    active: Mutex<usize>,
+}
+
+impl RouterProvider {
+    pub fn new(entries: Vec<RouterEntry>) -> Result<Self> {
+        if entries.is_empty() {
+            return Err(anyhow!("RouterProvider needs at least one entry"));
+        }
+        Ok(Self {
+            health: Mutex::new(vec![EntryHealth::default(); entries.len()]),
+            entries,
+            active: Mutex::new(0),
+        })
+    }
+
+    fn active_index(&self) -> usize {
+        *self.active.lock().unwrap()
+    }
+
+    fn active_provider(&self) -> &Arc<dyn Provider> {
+        &self.entries[self.active_index()].provider
+    }
+
+    /// Whether the entry at `index` is currently skipped by its circuit breaker
+    pub fn is_circuit_open(&self, index: usize) -> bool {
+        self.health
+            .lock()
+            .unwrap()
+            .get(index)
+            .is_some_and(|health| health.is_open(Instant::now()))
+    }
+
+    async fn estimate_tokens(system: &str, messages: &[Message], tools: &[Tool]) -> Option<usize> {
+        match create_token_counter().await {
+            Ok(counter) => Some(counter.count_chat_tokens(system, messages, tools)),
+            Err(e) => {
+                tracing::debug!(
+                    "Router could not count tokens, skipping size routing: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Order the entries to try for a request: those whose route and context limit fit
+    /// come first, and entries with an open circuit are only used when nothing else is left.
+    fn candidates(&self, tool_turn: bool, estimated_tokens: Option<usize>) -> Vec<usize> {
+        let all: Vec<usize> = (0..self.entries.len()).collect();
+
+        let routed: Vec<usize> = all
+            .iter()
+            .copied()
+            .filter(|&i| self.entries[i].route.accepts(tool_turn))
+            .collect();
+        let routed = if routed.is_empty() { all } else { routed };
+
+        let (fitting, too_small): (Vec<usize>, Vec<usize>) = routed.into_iter().partition(|&i| {
+            estimated_tokens.is_none_or(|tokens| {
+                tokens <= self.entries[i].provider.get_model_config().context_limit()
+            })
+        });
+        let ordered = fitting.into_iter().chain(too_small);
+
+        let now = Instant::now();
+        let health = self.health.lock().unwrap();
+        let (ready, mut cooling): (Vec<usize>, Vec<usize>) =
+            ordered.partition(|&i| !health[i].is_open(now));
+        if !ready.is_empty() {
+            return ready;
+        }
+        cooling.sort_by_key(|&i| health[i].open_until);
+        cooling
+    }
+
+    fn record_success(&self, index: usize) {
+        self.health.lock().unwrap()[index] = EntryHealth::default();
+        *self.active.lock().unwrap() = index;
+    }
+
+    fn record_failure(&self, index: usize, error: &ProviderError) {
+        let entry = &self.entries[index];
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[index];
+        health.consecutive_failures += 1;
+
+        let cooldown = match error {
+            ProviderError::RateLimitExceeded {
+                retry_delay: Some(delay),
+                ..
+            } => Some(*delay),
+            _ if health.consecutive_failures >= entry.failure_threshold => Some(entry.cooldown),
+            _ => None,
+        };
+        if let Some(cooldown) = cooldown {
+            tracing::warn!(
+                "Opening circuit for {} ({}) for {:?} after {} consecutive failures",
+                entry.provider.get_name(),
+                entry.provider.get_model_config().model_name,
+                cooldown,
+                health.consecutive_failures
+            );
+            health.open_until = Some(Instant::now() + cooldown);
+        }
+    }
+
+    /// Start a stream and wait for its first item, so that errors surfacing before any
+    /// output can still be failed over.
+    async fn open_stream(
+        provider: &Arc<dyn Provider>,
+        model_config: &ModelConfig,
+        session_id: &str,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let mut stream = provider
+            .stream(model_config, session_id, system, messages, tools)
+            .await?;
+        let first = match stream.next().await {
+            Some(Err(e)) => return Err(e),
+            first => first,
+        };
+
+        let provider_name = provider.get_name().to_string();
+        let tagged = futures::stream::iter(first).chain(stream).map(move |item| {
+            item.map(|(message, usage)| {
+                (
+                    message,
+                    usage.map(|usage| usage.with_provider(provider_name.clone())),
+                )
+            })
+        });
+        Ok(Box::pin(tagged))
+    }
+}
+
+fn is_tool_turn(messages: &[Message], tools: &[Tool]) -> bool {
+    !tools.is_empty()
+        && messages.last().is_some_and(|message| {
+            message
+                .content
+                .iter()
+                .any(|content| matches!(content, MessageContent::ToolResponse(_)))
+        })
+}
+
+fn should_fail_over(error: &ProviderError) -> bool {
+    should_retry(error)
+        || matches!(
+            error,
+            ProviderError::CreditsExhausted { .. } | ProviderError::ContextLengthExceeded(_)
+        )
+}
+
+impl RouterProviderTrait for RouterProvider {
+    fn get_backends(&self) -> Vec<(String, String)> {
+        self.entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.provider.get_name().to_string(),
+                    entry.provider.get_model_config().model_name,
+                )
+            })
+            .collect()
+    }
+
+    fn get_active_backend(&self) -> (String, ModelConfig) {
+        let provider = self.active_provider();
+        (provider.get_name().to_string(), provider.get_model_config())
+    }
+}
+
+impl ProviderDef for RouterProvider {
+    type Provider = Self;
+
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            ROUTER_PROVIDER_NAME,
+            "Provider Router",
+            "A provider that fails over between an ordered list of providers and models",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn from_env(
+        _model: ModelConfig,
+        _extensions: Vec<crate::config::ExtensionConfig>,
+    ) -> BoxFuture<'static, Result<Self::Provider>> {
+        Box::pin(async { Err(anyhow!("RouterProvider must be constructed explicitly")) })
+    }
+}
+
+#[async_trait]
+impl Provider for RouterProvider {
+    fn get_name(&self) -> &str {
+        self.active_provider().get_name()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.active_provider().get_model_config()
+    }
+
+    async fn stream(
+        &self,
+        _model_config: &ModelConfig,
+        session_id: &str,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let tool_turn = is_tool_turn(messages, tools);
+        let estimated_tokens = if self.entries.len() > 1 {
+            Self::estimate_tokens(system, messages, tools).await
+        } else {
+            None
+        };
+
+        let mut last_error = None;
+        for index in self.candidates(tool_turn, estimated_tokens) {
+            let provider = &self.entries[index].provider;
+            let model_config = provider.get_model_config();
+
+            match Self::open_stream(provider, &model_config, session_id, system, messages, tools)
+                .await
+            {
+                Ok(stream) => {
+                    self.record_success(index);
+                    super::base::set_current_model(&model_config.model_name);
+                    return Ok(stream);
+                }
+                Err(e) if should_fail_over(&e) => {
+                    tracing::warn!(
+                        "{} ({}) failed, trying the next backend: {}",
+                        provider.get_name(),
+                        model_config.model_name,
+                        e
+                    );
+                    if !matches!(e, ProviderError::ContextLengthExceeded(_)) {
+                        self.record_failure(index, &e);
+                    }
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ExecutionError("No router backend accepted the request".to_string())
+        }))
+    }
+
+    async fn fetch_supported_models(&self) -> Result<Vec<String>, ProviderError> {
+        let mut all_models = Vec::new();
+        for entry in &self.entries {
+            all_models.extend(entry.provider.fetch_supported_models().await?);
+        }
+        all_models.sort();
+        all_models.dedup();
+        Ok(all_models)
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.provider.supports_embeddings())
+    }
+
+    async fn create_embeddings(
+        &self,
+        session_id: &str,
+        texts: Vec<String>,
+    ) -> Result<Vec<Vec<f32>>, ProviderError> {
+        match self
+            .entries
+            .iter()
+            .find(|entry| entry.provider.supports_embeddings())
+        {
+            Some(entry) => entry.provider.create_embeddings(session_id, texts).await,
+            None => Err(ProviderError::ExecutionError(
+                "No router backend supports embeddings".to_string(),
+            )),
+        }
+    }
+
+    fn as_router(&self) -> Option<&dyn RouterProviderTrait> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::message::Message;
+    use crate::providers::base::{stream_from_single_message, ProviderUsage, Usage};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockProvider {
+        name: String,
+        model_config: ModelConfig,
+        error: Option<ProviderError>,
+        calls: AtomicUsize,
+    }
+
+    impl MockProvider {
+        fn new(name: &str, model: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                model_config: ModelConfig::new_or_fail(model),
+                error: None,
+                calls: AtomicUsize::new(0),
+            }
+        }
+
+        fn failing(name: &str, model: &str, error: ProviderError) -> Self {
+            Self {
+                error: Some(error),
+                ..Self::new(name, model)
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn get_name(&self) -> &str {
+            &self.name
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model_config.clone()
+        }
+
+        async fn stream(
+            &self,
+            _model_config: &ModelConfig,
+            _session_id: &str,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<MessageStream, ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(error) = &self.error {
+                return Err(error.clone());
+            }
+            Ok(stream_from_single_message(
+                Message::assistant().with_text(format!("from {}", self.name)),
+                ProviderUsage::new(self.model_config.model_name.clone(), Usage::default()),
+            ))
+        }
+    }
+
+    fn server_error() -> ProviderError {
+        ProviderError::ServerError("503".to_string())
+    }
+
+    async fn complete(
+        router: &RouterProvider,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> ProviderUsage {
+        let model_config = router.get_model_config();
+        router
+            .complete(&model_config, "test-session-id", "system", messages, tools)
+            .await
+            .unwrap()
+            .1
+    }
+
+    #[tokio::test]
+    async fn fails_over_and_reports_backend() {
+        let primary = Arc::new(MockProvider::failing("anthropic", "claude", server_error()));
+        let secondary = Arc::new(MockProvider::new("openai", "gpt-4o"));
+        let router = RouterProvider::new(vec![
+            RouterEntry::new(primary.clone()).with_failure_threshold(2),
+            RouterEntry::new(secondary.clone()),
+        ])
+        .unwrap();
+
+        let usage = complete(&router, &[], &[]).await;
+        assert_eq!(usage.model, "gpt-4o");
+        assert_eq!(usage.provider.as_deref(), Some("openai"));
+        assert_eq!(router.get_active_backend().0, "openai");
+        assert_eq!(router.get_model_config().model_name, "gpt-4o");
+        assert!(!router.is_circuit_open(0));
+
+        complete(&router, &[], &[]).await;
+        assert!(router.is_circuit_open(0));
+
+        complete(&router, &[], &[]).await;
+        assert_eq!(primary.calls(), 2);
+        assert_eq!(secondary.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_delay_opens_circuit_and_cooldown_expires() {
+        let primary = Arc::new(MockProvider::failing(
+            "anthropic",
+            "claude",
+            ProviderError::RateLimitExceeded {
+                details: "slow down".to_string(),
+                retry_delay: Some(Duration::from_millis(50)),
+            },
+        ));
+        let secondary = Arc::new(MockProvider::new("openai", "gpt-4o"));
+        let router = RouterProvider::new(vec![
+            RouterEntry::new(primary.clone()),
+            RouterEntry::new(secondary.clone()),
+        ])
+        .unwrap();
+
+        complete(&router, &[], &[]).await;
+        assert!(router.is_circuit_open(0));
+        complete(&router, &[], &[]).await;
+        assert_eq!(primary.calls(), 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        complete(&router, &[], &[]).await;
+        assert_eq!(primary.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn non_retryable_errors_are_returned() {
+        let primary = Arc::new(MockProvider::failing(
+            "anthropic",
+            "claude",
+            ProviderError::Authentication("bad key".to_string()),
+        ));
+        let secondary = Arc::new(MockProvider::new("openai", "gpt-4o"));
+        let router = RouterProvider::new(vec![
+            RouterEntry::new(primary),
+            RouterEntry::new(secondary.clone()),
+        ])
+        .unwrap();
+
+        let model_config = router.get_model_config();
+        let result = router
+            .complete(&model_config, "test-session-id", "system", &[], &[])
+            .await;
+        assert!(matches!(result, Err(ProviderError::Authentication(_))));
+        assert_eq!(secondary.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn routes_tool_turns_and_chat_separately() {
+        let tools_backend = Arc::new(MockProvider::new("anthropic", "claude"));
+        let chat_backend = Arc::new(MockProvider::new("ollama", "qwen"));
+        let router = RouterProvider::new(vec![
+            RouterEntry::new(tools_backend).with_route(RouteKind::Tools),
+            RouterEntry::new(chat_backend).with_route(RouteKind::Chat),
+        ])
+        .unwrap();
+
+        let tool = Tool::new("shell", "run a command", serde_json::Map::new());
+        let tool_result = Message::user()
+            .with_tool_response("call-1", Ok(rmcp::model::CallToolResult::success(vec![])));
+
+        let usage = complete(
+            &router,
+            &[Message::user().with_text("hi")],
+            std::slice::from_ref(&tool),
+        )
+        .await;
+        assert_eq!(usage.provider.as_deref(), Some("ollama"));
+
+        let usage = complete(&router, &[tool_result], &[tool]).await;
+        assert_eq!(usage.provider.as_deref(), Some("anthropic"));
+    }
+
+    #[tokio::test]
+    async fn prefers_entries_whose_context_fits() {
+        let small = MockProvider {
+            model_config: ModelConfig::new_or_fail("small").with_context_limit(Some(50)),
+            ..MockProvider::new("ollama", "small")
+        };
+        let large = Arc::new(MockProvider::new("anthropic", "large"));
+        let router = RouterProvider::new(vec![
+            RouterEntry::new(Arc::new(small)),
+            RouterEntry::new(large),
+        ])
+        .unwrap();
+
+        let long = Message::user().with_text("word ".repeat(100));
+        let usage = complete(&router, &[long], &[]).await;
+        assert_eq!(usage.model, "large");
+
+        let usage = complete(&router, &[Message::user().with_text("hi")], &[]).await;
+        assert_eq!(usage.model, "small");
+    }
+}
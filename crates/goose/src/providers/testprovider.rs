@@ -11,10 +11,11 @@ use std::sync::{Arc, Mutex};
 use super::base::stream_from_single_message;
 use super::base::{MessageStream, Provider, ProviderDef, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
-use crate::conversation::message::{Message, ToolResponse};
+use super::response_cache::stable_messages;
+use crate::conversation::message::Message;
 use crate::model::ModelConfig;
 use futures::future::BoxFuture;
-use rmcp::model::{CallToolResult, Tool};
+use rmcp::model::Tool;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TestInput {
@@ -74,40 +75,7 @@ impl TestProvider {
     }
 
     fn hash_input(messages: &[Message]) -> String {
-        use crate::conversation::message::MessageContent;
-
-        // Strip internal metadata (e.g. tool_meta/_meta) from content before hashing.
-        // This metadata is used for internal routing (like goose_extension ownership)
-        // and isn't part of the semantic input the LLM sees, so it shouldn't affect
-        // replay matching.
-        let stable_messages: Vec<_> = messages
-            .iter()
-            .map(|msg| {
-                let mut cleaned_content: Vec<_> = msg.content.to_vec();
-
-                for content in &mut cleaned_content {
-                    match content {
-                        MessageContent::ToolRequest(ref mut req) => {
-                            req.tool_meta = None;
-                        }
-                        MessageContent::ToolResponse(ToolResponse {
-                            tool_result:
-                                Ok(
-                                    ref mut result @ CallToolResult {
-                                        is_error: Some(false),
-                                        ..
-                                    },
-                                ),
-                            ..
-                        }) => {
-                            result.is_error = None;
-                        }
-                        _ => {}
-                    }
-                }
-                (msg.role.clone(), cleaned_content)
-            })
-            .collect();
+        let stable_messages = stable_messages(messages);
         let serialized = serde_json::to_string(&stable_messages).unwrap_or_default();
         let mut hasher = Sha256::new();
         hasher.update(serialized.as_bytes());
@@ -5,6 +5,7 @@ use std::fmt;
 use std::path::Path;
 
 use crate::agents::extension::ExtensionConfig;
+use crate::agents::platform_extensions::developer::sandbox::SandboxPolicy;
 use crate::agents::types::RetryConfig;
 use crate::recipe::read_recipe_file_content::read_recipe_file;
 use crate::recipe::yaml_format_utils::reformat_fields_with_multiline_values;
@@ -107,6 +108,18 @@ pub struct Settings {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_turns: Option<usize>,
+
+    /// Maximum spend in USD across all runs of this recipe in a day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<f64>,
+
+    /// Replay identical provider requests from the on-disk response cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_cache: Option<bool>,
+
+    /// Run developer shell commands and file writes under this sandbox policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxPolicy>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -19,7 +19,7 @@ use crate::config::{resolve_extensions_for_new_session, Config};
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
 use crate::posthog;
-use crate::providers::create;
+use crate::providers::create_for_recipe;
 use crate::recipe::Recipe;
 use crate::scheduler_trait::SchedulerTrait;
 use crate::session::session_manager::SessionType;
@@ -827,7 +827,8 @@ async fn execute_job(
         agent.add_extension(ext.clone(), &session.id).await?;
     }
 
-    let agent_provider = create(&provider_name, model_config, extensions).await?;
+    let agent_provider =
+        create_for_recipe(&provider_name, model_config, extensions, Some(&recipe)).await?;
     agent.update_provider(agent_provider, &session.id).await?;
 
     let mut jobs_guard = jobs.lock().await;
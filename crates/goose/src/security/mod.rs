@@ -1,6 +1,7 @@
 pub mod classification_client;
 pub mod patterns;
 pub mod scanner;
+pub mod secrets;
 pub mod security_inspector;
 
 use crate::config::Config;
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// A session and the branches forked from it.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBranch {
+    pub session_id: String,
+    pub name: String,
+    pub parent_session_id: Option<String>,
+    /// Last message of the parent that this branch shares
+    pub fork_message_id: Option<String>,
+    /// Messages in the branch, including those shared with its parent
+    pub message_count: usize,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Branches forked from this session, oldest first
+    pub children: Vec<SessionBranch>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SessionBranch {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(SessionBranch {
+            session_id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            parent_session_id: row.try_get("parent_session_id")?,
+            fork_message_id: row.try_get("fork_message_id")?,
+            message_count: row.try_get::<i64, _>("message_count")? as usize,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            children: Vec::new(),
+        })
+    }
+}
+
+impl SessionBranch {
+    /// Assembles the tree rooted at `root_id` from flat rows ordered by creation time.
+    pub fn build_tree(root_id: &str, rows: Vec<SessionBranch>) -> Option<SessionBranch> {
+        let mut by_parent: HashMap<String, Vec<SessionBranch>> = HashMap::new();
+        let mut root = None;
+        for row in rows {
+            if row.session_id == root_id {
+                root = Some(row);
+            } else if let Some(parent) = row.parent_session_id.clone() {
+                by_parent.entry(parent).or_default().push(row);
+            }
+        }
+
+        fn attach(node: &mut SessionBranch, by_parent: &mut HashMap<String, Vec<SessionBranch>>) {
+            node.children = by_parent.remove(&node.session_id).unwrap_or_default();
+            for child in &mut node.children {
+                attach(child, by_parent);
+            }
+        }
+
+        let mut root = root?;
+        attach(&mut root, &mut by_parent);
+        Some(root)
+    }
+
+    /// Sessions in the tree, depth first, with their depth.
+    pub fn flatten(&self) -> Vec<(usize, &SessionBranch)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            nodes.push((depth, node));
+            stack.extend(node.children.iter().rev().map(|child| (depth + 1, child)));
+        }
+        nodes
+    }
+
+    /// Drops the sessions `keep` rejects. Branches of a dropped session take its place as
+    /// roots, without the link back to it, so nothing about the dropped session is left.
+    pub fn retain(self, keep: &impl Fn(&str) -> bool) -> Vec<SessionBranch> {
+        let kept = keep(&self.session_id);
+        let children = self
+            .children
+            .into_iter()
+            .flat_map(|child| child.retain(keep))
+            .collect();
+        if kept {
+            return vec![SessionBranch { children, ..self }];
+        }
+        children
+            .into_iter()
+            .map(|child| SessionBranch {
+                parent_session_id: None,
+                fork_message_id: None,
+                ..child
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.session_id == session_id
+            || self.children.iter().any(|child| child.contains(session_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, parent: Option<&str>) -> SessionBranch {
+        SessionBranch {
+            session_id: id.to_string(),
+            name: id.to_string(),
+            parent_session_id: parent.map(str::to_string),
+            fork_message_id: parent.map(|_| "msg".to_string()),
+            message_count: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn builds_nested_branches_in_order() {
+        let rows = vec![
+            row("root", None),
+            row("a", Some("root")),
+            row("b", Some("root")),
+            row("a1", Some("a")),
+        ];
+        let tree = SessionBranch::build_tree("root", rows).unwrap();
+        let order: Vec<_> = tree
+            .flatten()
+            .into_iter()
+            .map(|(depth, node)| (depth, node.session_id.as_str()))
+            .collect();
+        assert_eq!(order, vec![(0, "root"), (1, "a"), (2, "a1"), (1, "b")]);
+
+        assert!(SessionBranch::build_tree("missing", vec![row("a", None)]).is_none());
+    }
+
+    #[test]
+    fn retain_promotes_branches_of_dropped_sessions() {
+        let rows = vec![
+            row("root", None),
+            row("a", Some("root")),
+            row("b", Some("root")),
+            row("a1", Some("a")),
+        ];
+        let tree = SessionBranch::build_tree("root", rows).unwrap();
+        let forest = tree.retain(&|id| id != "root" && id != "b");
+
+        assert_eq!(forest.len(), 1);
+        assert_eq!(forest[0].session_id, "a");
+        assert_eq!(forest[0].parent_session_id, None);
+        assert_eq!(forest[0].fork_message_id, None);
+        assert_eq!(forest[0].children.len(), 1);
+        assert_eq!(
+            forest[0].children[0].parent_session_id.as_deref(),
+            Some("a")
+        );
+        assert!(forest[0].contains("a1"));
+        assert!(!forest[0].contains("b"));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::config::Config;
+use crate::recipe::Recipe;
+use crate::session::extension_data::ExtensionState;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const SESSION_BUDGET_KEY: &str = "GOOSE_SESSION_BUDGET";
+pub const DAILY_BUDGET_KEY: &str = "GOOSE_DAILY_BUDGET";
+pub const RECIPE_BUDGET_KEY: &str = "GOOSE_RECIPE_BUDGET";
+pub const BUDGET_WARNING_KEY: &str = "GOOSE_BUDGET_WARNING_THRESHOLD";
+
+const DEFAULT_WARNING_THRESHOLD: f64 = 0.8;
+
+/// Spend limits in USD. Daily limits reset at local midnight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetLimits {
+    pub session: Option<f64>,
+    pub daily: Option<f64>,
+    /// Applies per recipe across all of its runs in a day.
+    pub recipe: Option<f64>,
+    /// Fraction of a limit at which warnings start.
+    pub warning_threshold: f64,
+}
+
+impl Default for BudgetLimits {
+    fn default() -> Self {
+        Self {
+            session: None,
+            daily: None,
+            recipe: None,
+            warning_threshold: DEFAULT_WARNING_THRESHOLD,
+        }
+    }
+}
+
+impl BudgetLimits {
+    /// Reads the configured limits. A recipe's own `settings.budget` takes precedence over
+    /// `GOOSE_RECIPE_BUDGET`, which only applies to sessions running a recipe.
+    pub fn from_config(recipe: Option<&Recipe>) -> Self {
+        let config = Config::global();
+        let positive = |key: &str| {
+            config
+                .get_param::<f64>(key)
+                .ok()
+                .filter(|limit| *limit > 0.0)
+        };
+
+        let recipe_limit = recipe.and_then(|recipe| {
+            recipe
+                .settings
+                .as_ref()
+                .and_then(|settings| settings.budget)
+                .or_else(|| positive(RECIPE_BUDGET_KEY))
+        });
+
+        Self {
+            session: positive(SESSION_BUDGET_KEY),
+            daily: positive(DAILY_BUDGET_KEY),
+            recipe: recipe_limit,
+            warning_threshold: config
+                .get_param::<f64>(BUDGET_WARNING_KEY)
+                .ok()
+                .filter(|threshold| (0.0..=1.0).contains(threshold))
+                .unwrap_or(DEFAULT_WARNING_THRESHOLD),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.session.is_none() && self.daily.is_none() && self.recipe.is_none()
+    }
+
+    /// Compares spend against each limit. The first exceeded limit wins, otherwise the
+    /// limit closest to being reached produces a warning.
+    pub fn evaluate(&self, spend: &BudgetSpend) -> BudgetStatus {
+        let checks = [
+            ("session", self.session, spend.session),
+            ("daily", self.daily, spend.daily),
+            ("recipe", self.recipe, spend.recipe),
+        ];
+
+        let mut warning: Option<(f64, String)> = None;
+        for (scope, limit, spent) in checks {
+            let Some(limit) = limit else { continue };
+            if spent >= limit {
+                return BudgetStatus::Exceeded(format!(
+                    "The {} budget of ${:.2} has been reached (${:.2} spent).",
+                    scope, limit, spent
+                ));
+            }
+            let ratio = spent / limit;
+            if ratio >= self.warning_threshold
+                && warning.as_ref().is_none_or(|(worst, _)| ratio > *worst)
+            {
+                warning = Some((
+                    ratio,
+                    format!(
+                        "${:.2} of the ${:.2} {} budget has been spent.",
+                        spent, limit, scope
+                    ),
+                ));
+            }
+        }
+
+        match warning {
+            Some((_, message)) => BudgetStatus::Warning(message),
+            None => BudgetStatus::WithinBudget,
+        }
+    }
+}
+
+/// Spend in USD counted against each limit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetSpend {
+    pub session: f64,
+    pub daily: f64,
+    pub recipe: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetStatus {
+    WithinBudget,
+    Warning(String),
+    Exceeded(String),
+}
+
+/// Identifies a recipe in the spend ledger, so recipes that happen to share a title don't
+/// share a budget. Only the parts of a recipe that stay the same whatever parameter values a
+/// run is given are hashed, so every run of a recipe counts against the same budget.
+pub fn recipe_budget_key(recipe: &Recipe) -> String {
+    let identity = serde_json::json!([
+        recipe.title,
+        recipe.description,
+        recipe.author,
+        recipe.parameters,
+    ]);
+    format!("{:x}", Sha256::digest(identity.to_string().as_bytes()))
+}
+
+/// Stored on a subagent's session, naming the session that delegated to it. The subagent's
+/// spend is booked to that session too and counts against its budgets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DelegatedFrom {
+    pub session_id: String,
+}
+
+impl ExtensionState for DelegatedFrom {
+    const EXTENSION_NAME: &'static str = "delegated_from";
+    const VERSION: &'static str = "v0";
+}
+
+/// Start of the current local day, where daily limits reset.
+pub fn start_of_today() -> DateTime<Utc> {
+    Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> BudgetLimits {
+        BudgetLimits {
+            session: Some(5.0),
+            daily: Some(20.0),
+            recipe: None,
+            warning_threshold: 0.8,
+        }
+    }
+
+    #[test]
+    fn within_budget_below_threshold() {
+        let spend = BudgetSpend {
+            session: 1.0,
+            daily: 10.0,
+            recipe: 100.0,
+        };
+        assert_eq!(limits().evaluate(&spend), BudgetStatus::WithinBudget);
+        assert!(BudgetLimits::default().is_empty());
+    }
+
+    #[test]
+    fn warns_on_the_closest_limit() {
+        let spend = BudgetSpend {
+            session: 4.1,
+            daily: 19.0,
+            recipe: 0.0,
+        };
+        assert_eq!(
+            limits().evaluate(&spend),
+            BudgetStatus::Warning("$19.00 of the $20.00 daily budget has been spent.".into())
+        );
+    }
+
+    #[test]
+    fn exceeding_any_limit_stops() {
+        let spend = BudgetSpend {
+            session: 5.2,
+            daily: 6.0,
+            recipe: 0.0,
+        };
+        assert_eq!(
+            limits().evaluate(&spend),
+            BudgetStatus::Exceeded(
+                "The session budget of $5.00 has been reached ($5.20 spent).".into()
+            )
+        );
+    }
+
+    #[test]
+    fn recipes_are_keyed_by_identity_not_title() {
+        let recipe = |description: &str, instructions: &str| {
+            Recipe::builder()
+                .title("nightly")
+                .description(description)
+                .instructions(instructions)
+                .build()
+                .unwrap()
+        };
+        let nightly = recipe_budget_key(&recipe("runs every night", "check repo a"));
+        // A run rendered with different parameter values is still the same recipe
+        assert_eq!(
+            nightly,
+            recipe_budget_key(&recipe("runs every night", "check repo b"))
+        );
+        assert_ne!(
+            nightly,
+            recipe_budget_key(&recipe("a different recipe", "check repo a"))
+        );
+    }
+}
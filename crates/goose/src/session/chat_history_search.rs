@@ -1,10 +1,19 @@
 use crate::conversation::message::MessageContent;
+use crate::session::session_manager::{SessionType, SESSION_OWNERS_TABLE};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use rmcp::model::Role;
 use serde::Serialize;
 use sqlx::{Pool, Sqlite};
 use std::collections::HashMap;
 
+pub const FTS_TABLE: &str = "messages_fts";
+
+const SNIPPET_OPEN: &str = "**";
+const SNIPPET_CLOSE: &str = "**";
+const SNIPPET_ELLIPSIS: &str = "…";
+const SNIPPET_TOKENS: i64 = 16;
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ChatRecallResult {
     pub session_id: String,
@@ -12,6 +21,8 @@ pub struct ChatRecallResult {
     pub session_working_dir: String,
     pub last_activity: DateTime<Utc>,
     pub total_messages_in_session: usize,
+    /// Best (lowest) BM25 score among the matching messages of this session
+    pub score: f64,
     pub messages: Vec<ChatRecallMessage>,
 }
 
@@ -19,6 +30,9 @@ pub struct ChatRecallResult {
 pub struct ChatRecallMessage {
     pub role: String,
     pub content: String,
+    /// Excerpt around the match with matched terms wrapped in `**`
+    pub snippet: String,
+    pub score: f64,
     pub timestamp: DateTime<Utc>,
 }
 
@@ -28,61 +42,216 @@ pub struct ChatRecallResults {
     pub total_matches: usize,
 }
 
-type SqlQueryRow = (
-    String,
-    String,
-    String,
-    DateTime<Utc>,
-    String,
-    String,
-    DateTime<Utc>,
-);
-
-type SessionMessageGroup = (
-    String,
-    String,
-    DateTime<Utc>,
-    Vec<(String, String, DateTime<Utc>)>,
-);
+/// Optional filters applied on top of the full-text query.
+#[derive(Debug, Clone, Default)]
+pub struct ChatHistorySearchOptions {
+    pub limit: Option<usize>,
+    pub after_date: Option<DateTime<Utc>>,
+    pub before_date: Option<DateTime<Utc>>,
+    pub exclude_session_id: Option<String>,
+    pub role: Option<Role>,
+    pub working_dir: Option<String>,
+    pub session_types: Vec<SessionType>,
+    /// Only match sessions created through this API token.
+    pub owner_token_id: Option<String>,
+}
+
+struct SqlQueryRow {
+    session_id: String,
+    session_description: String,
+    session_working_dir: String,
+    role: String,
+    content_json: String,
+    timestamp: DateTime<Utc>,
+    score: f64,
+    snippet: String,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SqlQueryRow {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(Self {
+            session_id: row.try_get("session_id")?,
+            session_description: row.try_get("session_description")?,
+            session_working_dir: row.try_get("session_working_dir")?,
+            role: row.try_get("role")?,
+            content_json: row.try_get("content_json")?,
+            timestamp: row.try_get("timestamp")?,
+            score: row.try_get("score")?,
+            snippet: row.try_get("snippet")?,
+        })
+    }
+}
+
+struct SessionMessageGroup {
+    description: String,
+    working_dir: String,
+    messages: Vec<ChatRecallMessage>,
+}
+
+/// Text of a message that is worth indexing: only what the user and the model actually wrote,
+/// not tool payloads or serialized JSON.
+pub fn searchable_text(content: &[MessageContent]) -> String {
+    content
+        .iter()
+        .filter_map(|c| c.as_text())
+        .filter(|t| !t.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Translates a user query into an FTS5 MATCH expression.
+///
+/// Supported syntax:
+/// - `word` matches the word (stemmed), terms are OR'd and ranked by BM25
+/// - `"exact phrase"` matches the phrase
+/// - `prefix*` matches any word starting with `prefix`
+/// - `-word`, `-"phrase"` or `NOT word` excludes messages containing it
+///
+/// Every term is quoted before being handed to FTS5, so punctuation in the query cannot
+/// break the expression. Returns `None` when the query has no positive terms.
+pub fn build_fts_query(query: &str) -> Option<String> {
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+    let mut negate_next = false;
+
+    for token in tokenize_query(query) {
+        if !token.quoted && token.text == "NOT" {
+            negate_next = true;
+            continue;
+        }
+        if !token.quoted && (token.text == "AND" || token.text == "OR") {
+            continue;
+        }
+
+        let mut text = token.text.as_str();
+        let mut negate = std::mem::take(&mut negate_next) || token.negated;
+        if !token.quoted {
+            if let Some(stripped) = text.strip_prefix('-') {
+                negate = true;
+                text = stripped;
+            }
+        }
+
+        let (text, prefix) = match text.strip_suffix('*') {
+            Some(stripped) if !token.quoted => (stripped, true),
+            _ => (text, false),
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let mut term = format!("\"{}\"", text.replace('"', "\"\""));
+        if prefix {
+            term.push('*');
+        }
+
+        if negate {
+            exclude.push(term);
+        } else {
+            include.push(term);
+        }
+    }
+
+    if include.is_empty() {
+        return None;
+    }
+
+    let mut expr = format!("({})", include.join(" OR "));
+    for term in exclude {
+        expr.push_str(" NOT ");
+        expr.push_str(&term);
+    }
+    Some(expr)
+}
+
+struct QueryToken {
+    text: String,
+    quoted: bool,
+    negated: bool,
+}
+
+fn tokenize_query(query: &str) -> Vec<QueryToken> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let negated = c == '-' && {
+            let mut lookahead = chars.clone();
+            lookahead.next();
+            lookahead.peek() == Some(&'"')
+        };
+        if negated {
+            chars.next();
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            let mut text = String::new();
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                text.push(c);
+            }
+            tokens.push(QueryToken {
+                text,
+                quoted: true,
+                negated,
+            });
+        } else {
+            let mut text = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                text.push(c);
+                chars.next();
+            }
+            tokens.push(QueryToken {
+                text,
+                quoted: false,
+                negated: false,
+            });
+        }
+    }
+
+    tokens
+}
 
 pub struct ChatHistorySearch<'a> {
     pool: &'a Pool<Sqlite>,
     query: &'a str,
     limit: usize,
-    after_date: Option<DateTime<Utc>>,
-    before_date: Option<DateTime<Utc>>,
-    exclude_session_id: Option<String>,
+    options: ChatHistorySearchOptions,
 }
 
 impl<'a> ChatHistorySearch<'a> {
-    pub fn new(
-        pool: &'a Pool<Sqlite>,
-        query: &'a str,
-        limit: Option<usize>,
-        after_date: Option<DateTime<Utc>>,
-        before_date: Option<DateTime<Utc>>,
-        exclude_session_id: Option<String>,
-    ) -> Self {
+    pub fn new(pool: &'a Pool<Sqlite>, query: &'a str, options: ChatHistorySearchOptions) -> Self {
         Self {
             pool,
             query,
-            limit: limit.unwrap_or(10),
-            after_date,
-            before_date,
-            exclude_session_id,
+            limit: options.limit.unwrap_or(10),
+            options,
         }
     }
 
     pub async fn execute(self) -> Result<ChatRecallResults> {
-        let keywords = self.parse_keywords();
-        if keywords.is_empty() {
+        let Some(fts_query) = build_fts_query(self.query) else {
             return Ok(ChatRecallResults {
                 results: vec![],
                 total_matches: 0,
             });
-        }
+        };
 
-        let rows = self.fetch_rows(&keywords).await?;
+        let rows = self.fetch_rows(&fts_query).await?;
         let session_messages = self.process_rows(rows);
         let session_totals = self.get_session_totals(&session_messages).await?;
         let results = self.convert_to_results(session_messages, session_totals);
@@ -90,22 +259,37 @@ impl<'a> ChatHistorySearch<'a> {
         Ok(results)
     }
 
-    async fn fetch_rows(&self, keywords: &[String]) -> Result<Vec<SqlQueryRow>> {
-        let sql = self.build_sql(keywords);
-        let mut query_builder = sqlx::query_as::<_, SqlQueryRow>(&sql);
-
-        for keyword in keywords {
-            query_builder = query_builder.bind(keyword);
-        }
+    async fn fetch_rows(&self, fts_query: &str) -> Result<Vec<SqlQueryRow>> {
+        let sql = self.build_sql();
+        let mut query_builder = sqlx::query_as::<_, SqlQueryRow>(&sql)
+            .bind(SNIPPET_OPEN)
+            .bind(SNIPPET_CLOSE)
+            .bind(SNIPPET_ELLIPSIS)
+            .bind(SNIPPET_TOKENS)
+            .bind(fts_query);
 
-        if let Some(exclude_id) = &self.exclude_session_id {
+        if let Some(exclude_id) = &self.options.exclude_session_id {
             query_builder = query_builder.bind(exclude_id);
         }
-
-        if let Some(after) = self.after_date {
+        if let Some(role) = &self.options.role {
+            query_builder = query_builder.bind(match role {
+                Role::User => "user",
+                Role::Assistant => "assistant",
+            });
+        }
+        if let Some(working_dir) = &self.options.working_dir {
+            query_builder = query_builder.bind(working_dir);
+        }
+        for session_type in &self.options.session_types {
+            query_builder = query_builder.bind(session_type.to_string());
+        }
+        if let Some(token_id) = &self.options.owner_token_id {
+            query_builder = query_builder.bind(token_id);
+        }
+        if let Some(after) = self.options.after_date {
             query_builder = query_builder.bind(after);
         }
-        if let Some(before) = self.before_date {
+        if let Some(before) = self.options.before_date {
             query_builder = query_builder.bind(before);
         }
 
@@ -114,59 +298,52 @@ impl<'a> ChatHistorySearch<'a> {
         Ok(query_builder.fetch_all(self.pool).await?)
     }
 
-    fn parse_keywords(&self) -> Vec<String> {
-        self.query
-            .split_whitespace()
-            .map(|word| format!("%{}%", word.to_lowercase()))
-            .collect()
-    }
-
-    fn build_sql(&self, keywords: &[String]) -> String {
-        let mut sql = String::from(
+    fn build_sql(&self) -> String {
+        let mut sql = format!(
             r#"
-            SELECT 
+            SELECT
                 s.id as session_id,
-                s.description as session_description,
+                COALESCE(NULLIF(s.name, ''), s.description) as session_description,
                 s.working_dir as session_working_dir,
-                s.created_at as session_created_at,
                 m.role,
                 m.content_json,
-                m.timestamp
-            FROM messages m
+                m.timestamp,
+                bm25({FTS_TABLE}) as score,
+                snippet({FTS_TABLE}, 0, ?, ?, ?, ?) as snippet
+            FROM {FTS_TABLE}
+            INNER JOIN messages m ON m.id = {FTS_TABLE}.rowid
             INNER JOIN sessions s ON m.session_id = s.id
-            WHERE EXISTS (
-                SELECT 1 FROM json_each(m.content_json) 
-                WHERE json_extract(value, '$.type') = 'text' 
-                AND (
-        "#,
+            WHERE {FTS_TABLE} MATCH ?
+        "#
         );
 
-        for (i, _) in keywords.iter().enumerate() {
-            if i > 0 {
-                sql.push_str(" OR ");
-            }
-            sql.push_str("LOWER(json_extract(value, '$.text')) LIKE ?");
-        }
-
-        sql.push_str(
-            r#"
-                )
-            )
-        "#,
-        );
-
-        if self.exclude_session_id.is_some() {
+        if self.options.exclude_session_id.is_some() {
             sql.push_str(" AND s.id != ?");
         }
-
-        if self.after_date.is_some() {
+        if self.options.role.is_some() {
+            sql.push_str(" AND m.role = ?");
+        }
+        if self.options.working_dir.is_some() {
+            sql.push_str(" AND s.working_dir = ?");
+        }
+        if !self.options.session_types.is_empty() {
+            let placeholders = vec!["?"; self.options.session_types.len()].join(", ");
+            sql.push_str(&format!(" AND s.session_type IN ({})", placeholders));
+        }
+        if self.options.owner_token_id.is_some() {
+            sql.push_str(&format!(
+                " AND s.id IN (SELECT session_id FROM {} WHERE token_id = ?)",
+                SESSION_OWNERS_TABLE
+            ));
+        }
+        if self.options.after_date.is_some() {
             sql.push_str(" AND m.timestamp >= ?");
         }
-        if self.before_date.is_some() {
+        if self.options.before_date.is_some() {
             sql.push_str(" AND m.timestamp <= ?");
         }
 
-        sql.push_str(" ORDER BY m.timestamp DESC LIMIT ?");
+        sql.push_str(" ORDER BY score, m.timestamp DESC LIMIT ?");
 
         sql
     }
@@ -174,31 +351,31 @@ impl<'a> ChatHistorySearch<'a> {
     fn process_rows(&self, rows: Vec<SqlQueryRow>) -> HashMap<String, SessionMessageGroup> {
         let mut session_messages: HashMap<String, SessionMessageGroup> = HashMap::new();
 
-        for (
-            session_id,
-            session_description,
-            session_working_dir,
-            session_created_at,
-            role,
-            content_json,
-            timestamp,
-        ) in rows
-        {
-            if let Ok(content_vec) = serde_json::from_str::<Vec<MessageContent>>(&content_json) {
-                let text_parts = Self::extract_text_content(content_vec);
-
-                if !text_parts.is_empty() {
-                    let entry = session_messages.entry(session_id.clone()).or_insert((
-                        session_description.clone(),
-                        session_working_dir.clone(),
-                        session_created_at,
-                        Vec::new(),
-                    ));
-                    entry
-                        .3
-                        .push((role.clone(), text_parts.join("\n"), timestamp));
-                }
+        for row in rows {
+            let Ok(content_vec) = serde_json::from_str::<Vec<MessageContent>>(&row.content_json)
+            else {
+                continue;
+            };
+            let text_parts = Self::extract_text_content(content_vec);
+            if text_parts.is_empty() {
+                continue;
             }
+
+            let entry =
+                session_messages
+                    .entry(row.session_id)
+                    .or_insert_with(|| SessionMessageGroup {
+                        description: row.session_description,
+                        working_dir: row.session_working_dir,
+                        messages: Vec::new(),
+                    });
+            entry.messages.push(ChatRecallMessage {
+                role: row.role,
+                content: text_parts.join("\n"),
+                snippet: row.snippet,
+                score: row.score,
+                timestamp: row.timestamp,
+            });
         }
 
         session_messages
@@ -243,39 +420,41 @@ impl<'a> ChatHistorySearch<'a> {
     ) -> ChatRecallResults {
         let mut results: Vec<ChatRecallResult> = session_messages
             .into_iter()
-            .map(
-                |(session_id, (description, working_dir, _created_at, messages))| {
-                    let message_vec: Vec<ChatRecallMessage> = messages
-                        .into_iter()
-                        .map(|(role, content, timestamp)| ChatRecallMessage {
-                            role,
-                            content,
-                            timestamp,
-                        })
-                        .collect();
-
-                    let last_activity = message_vec
-                        .iter()
-                        .map(|m| m.timestamp)
-                        .max()
-                        .unwrap_or_else(chrono::Utc::now);
-
-                    let total_messages_in_session =
-                        session_totals.get(&session_id).copied().unwrap_or(0);
-
-                    ChatRecallResult {
-                        session_id,
-                        session_description: description,
-                        session_working_dir: working_dir,
-                        last_activity,
-                        total_messages_in_session,
-                        messages: message_vec,
-                    }
-                },
-            )
+            .map(|(session_id, group)| {
+                let last_activity = group
+                    .messages
+                    .iter()
+                    .map(|m| m.timestamp)
+                    .max()
+                    .unwrap_or_else(chrono::Utc::now);
+
+                let score = group
+                    .messages
+                    .iter()
+                    .map(|m| m.score)
+                    .fold(f64::INFINITY, f64::min);
+
+                let total_messages_in_session =
+                    session_totals.get(&session_id).copied().unwrap_or(0);
+
+                ChatRecallResult {
+                    session_id,
+                    session_description: group.description,
+                    session_working_dir: group.working_dir,
+                    last_activity,
+                    total_messages_in_session,
+                    score,
+                    messages: group.messages,
+                }
+            })
             .collect();
 
-        results.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+        // bm25() returns lower values for better matches
+        results.sort_by(|a, b| {
+            a.score
+                .total_cmp(&b.score)
+                .then_with(|| b.last_activity.cmp(&a.last_activity))
+        });
 
         let total_matches = results.iter().map(|r| r.messages.len()).sum();
         ChatRecallResults {
@@ -284,3 +463,22 @@ impl<'a> ChatHistorySearch<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("database postgres", Some(r#"("database" OR "postgres")"#) ; "plain terms are ored")]
+    #[test_case(r#""connection pool" timeout"#, Some(r#"("connection pool" OR "timeout")"#) ; "phrase")]
+    #[test_case("migrat*", Some(r#"("migrat"*)"#) ; "prefix")]
+    #[test_case("deploy -staging", Some(r#"("deploy") NOT "staging""#) ; "dash excludes")]
+    #[test_case("deploy NOT staging", Some(r#"("deploy") NOT "staging""#) ; "not keyword excludes")]
+    #[test_case(r#"deploy -"dry run""#, Some(r#"("deploy") NOT "dry run""#) ; "excluded phrase")]
+    #[test_case(r#"a"b OR c"#, Some(r#"("a""b" OR "c")"#) ; "quotes are escaped")]
+    #[test_case("-only", None ; "only exclusions")]
+    #[test_case("   ", None ; "blank")]
+    fn test_build_fts_query(query: &str, expected: Option<&str>) {
+        assert_eq!(build_fts_query(query).as_deref(), expected);
+    }
+}
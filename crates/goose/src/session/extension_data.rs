@@ -5,7 +5,8 @@ use crate::config::base::Config;
 use crate::config::extensions::is_extension_available;
 use crate::config::ExtensionConfig;
 use crate::session::SessionManager;
-use anyhow::Result;
+use anyhow::{bail, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -81,7 +82,7 @@ pub trait ExtensionState: Sized + Serialize + for<'de> Deserialize<'de> {
     }
 }
 
-/// TODO extension state implementation
+/// Legacy free-text TODO state, superseded by [`TodoList`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TodoState {
     pub content: String,
@@ -99,6 +100,249 @@ impl TodoState {
     }
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Done,
+    Blocked,
+}
+
+impl TodoStatus {
+    fn checkbox(self) -> &'static str {
+        match self {
+            TodoStatus::Pending => "[ ]",
+            TodoStatus::InProgress => "[~]",
+            TodoStatus::Done => "[x]",
+            TodoStatus::Blocked => "[!]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItem {
+    pub id: u32,
+    pub title: String,
+    pub status: TodoStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// TODO extension state: an ordered list of items, nested through `parent_id`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoList {
+    pub items: Vec<TodoItem>,
+    #[serde(default)]
+    next_id: u32,
+}
+
+impl ExtensionState for TodoList {
+    const EXTENSION_NAME: &'static str = "todo";
+    const VERSION: &'static str = "v1";
+}
+
+impl TodoList {
+    /// Load the list, converting a legacy markdown [`TodoState`] if no list was saved yet
+    pub fn load(extension_data: &ExtensionData) -> Self {
+        <Self as ExtensionState>::from_extension_data(extension_data)
+            .or_else(|| {
+                TodoState::from_extension_data(extension_data)
+                    .map(|legacy| Self::from_markdown(&legacy.content))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Convert a markdown checklist into items. Indentation becomes nesting, `[x]` marks an
+    /// item done, and any other text is kept as notes on the item before it.
+    pub fn from_markdown(content: &str) -> Self {
+        let mut list = Self::default();
+        let mut preamble: Vec<&str> = Vec::new();
+        // (indent, id) of the items enclosing the current line
+        let mut stack: Vec<(usize, u32)> = Vec::new();
+
+        for line in content.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let indent = line.len() - trimmed.len();
+
+            let Some(entry) = ["- ", "* ", "+ "]
+                .iter()
+                .find_map(|bullet| trimmed.strip_prefix(bullet))
+            else {
+                match list.items.last_mut() {
+                    Some(item) => append_note(item, trimmed),
+                    None => preamble.push(trimmed),
+                }
+                continue;
+            };
+
+            let (status, title) = match entry.get(..3) {
+                Some("[x]") | Some("[X]") => (TodoStatus::Done, entry.get(3..).unwrap_or("")),
+                Some("[ ]") => (TodoStatus::Pending, entry.get(3..).unwrap_or("")),
+                _ => (TodoStatus::Pending, entry),
+            };
+
+            while stack.last().is_some_and(|(depth, _)| *depth >= indent) {
+                stack.pop();
+            }
+            let parent_id = stack.last().map(|(_, id)| *id);
+            let id = list.push(title.trim().to_string(), status, parent_id, None);
+            stack.push((indent, id));
+        }
+
+        if !preamble.is_empty() {
+            let notes = preamble.join("\n");
+            match list.items.first_mut() {
+                Some(first) => {
+                    first.notes = Some(match first.notes.take() {
+                        Some(existing) => format!("{notes}\n{existing}"),
+                        None => notes,
+                    })
+                }
+                None => {
+                    list.push("Notes".to_string(), TodoStatus::Pending, None, Some(notes));
+                }
+            }
+        }
+        list
+    }
+
+    fn push(
+        &mut self,
+        title: String,
+        status: TodoStatus,
+        parent_id: Option<u32>,
+        notes: Option<String>,
+    ) -> u32 {
+        self.next_id = self
+            .next_id
+            .max(self.items.iter().map(|i| i.id).max().unwrap_or(0))
+            + 1;
+        let id = self.next_id;
+        self.items.push(TodoItem {
+            id,
+            title,
+            status,
+            parent_id,
+            notes,
+        });
+        id
+    }
+
+    /// Append an item and return its id
+    pub fn add(
+        &mut self,
+        title: String,
+        status: TodoStatus,
+        parent_id: Option<u32>,
+        notes: Option<String>,
+    ) -> Result<u32> {
+        if title.trim().is_empty() {
+            bail!("Todo title cannot be empty");
+        }
+        if let Some(parent) = parent_id {
+            if self.get(parent).is_none() {
+                bail!("No todo item with id {}", parent);
+            }
+        }
+        Ok(self.push(title, status, parent_id, notes))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&TodoItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut TodoItem> {
+        self.items.iter_mut().find(|item| item.id == id)
+    }
+
+    /// Remove an item along with everything nested under it, returning how many were removed
+    pub fn remove(&mut self, id: u32) -> usize {
+        let mut doomed = vec![id];
+        let mut i = 0;
+        while i < doomed.len() {
+            let parent = doomed[i];
+            doomed.extend(
+                self.items
+                    .iter()
+                    .filter(|item| item.parent_id == Some(parent))
+                    .map(|item| item.id),
+            );
+            i += 1;
+        }
+        let before = self.items.len();
+        self.items.retain(|item| !doomed.contains(&item.id));
+        before - self.items.len()
+    }
+
+    /// Number of done items and the total
+    pub fn progress(&self) -> (usize, usize) {
+        let done = self
+            .items
+            .iter()
+            .filter(|item| item.status == TodoStatus::Done)
+            .count();
+        (done, self.items.len())
+    }
+
+    /// The first item being worked on
+    pub fn current(&self) -> Option<&TodoItem> {
+        self.items
+            .iter()
+            .find(|item| item.status == TodoStatus::InProgress)
+    }
+
+    /// Render as a nested markdown checklist with ids, e.g. `- [~] #2 Write tests`
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let is_root = |item: &TodoItem| item.parent_id.is_none_or(|p| self.get(p).is_none());
+        for item in self.items.iter().filter(|item| is_root(item)) {
+            self.render_item(item, 0, &mut out);
+        }
+        out
+    }
+
+    fn render_item(&self, item: &TodoItem, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!(
+            "{}- {} #{} {}\n",
+            indent,
+            item.status.checkbox(),
+            item.id,
+            item.title
+        ));
+        if let Some(notes) = &item.notes {
+            for line in notes.lines() {
+                out.push_str(&format!("{}    {}\n", indent, line));
+            }
+        }
+        for child in self
+            .items
+            .iter()
+            .filter(|child| child.parent_id == Some(item.id))
+        {
+            self.render_item(child, depth + 1, out);
+        }
+    }
+}
+
+fn append_note(item: &mut TodoItem, line: &str) {
+    item.notes = Some(match item.notes.take() {
+        Some(existing) => format!("{existing}\n{line}"),
+        None => line.to_string(),
+    });
+}
+
 /// Enabled extensions state implementation for storing which extensions are active
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnabledExtensionsState {
@@ -241,6 +485,68 @@ mod tests {
         assert_eq!(retrieved.unwrap().content, "- Task 1\n- Task 2");
     }
 
+    #[test]
+    fn test_todo_list_migrates_legacy_markdown() {
+        let mut extension_data = ExtensionData::new();
+        TodoState::new(
+            "Release plan\n- [x] Write code\n- [ ] Ship it\n  - [ ] Tag release\n    needs sign-off\n- Announce"
+                .to_string(),
+        )
+        .to_extension_data(&mut extension_data)
+        .unwrap();
+
+        let list = TodoList::load(&extension_data);
+        let summary: Vec<_> = list
+            .items
+            .iter()
+            .map(|i| (i.id, i.title.as_str(), i.status, i.parent_id))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "Write code", TodoStatus::Done, None),
+                (2, "Ship it", TodoStatus::Pending, None),
+                (3, "Tag release", TodoStatus::Pending, Some(2)),
+                (4, "Announce", TodoStatus::Pending, None),
+            ]
+        );
+        assert_eq!(list.items[0].notes.as_deref(), Some("Release plan"));
+        assert_eq!(list.items[2].notes.as_deref(), Some("needs sign-off"));
+        assert_eq!(list.progress(), (1, 4));
+
+        // Once saved, the structured list wins over the legacy text
+        let mut list = list;
+        list.remove(2);
+        list.to_extension_data(&mut extension_data).unwrap();
+        let reloaded = TodoList::load(&extension_data);
+        assert_eq!(reloaded.items.len(), 2);
+        assert_eq!(
+            reloaded.to_markdown(),
+            "- [x] #1 Write code\n    Release plan\n- [ ] #4 Announce\n"
+        );
+    }
+
+    #[test]
+    fn test_todo_list_ids_are_not_reused() {
+        let mut list = TodoList::default();
+        let first = list
+            .add("One".into(), TodoStatus::Pending, None, None)
+            .unwrap();
+        let child = list
+            .add("Child".into(), TodoStatus::InProgress, Some(first), None)
+            .unwrap();
+        assert!(list
+            .add("Orphan".into(), TodoStatus::Pending, Some(42), None)
+            .is_err());
+        assert_eq!(list.current().map(|i| i.id), Some(child));
+
+        assert_eq!(list.remove(first), 2);
+        let next = list
+            .add("Two".into(), TodoStatus::Pending, None, None)
+            .unwrap();
+        assert_eq!(next, 3);
+    }
+
     #[test]
     fn test_extension_data_serialization() {
         let mut extension_data = ExtensionData::new();
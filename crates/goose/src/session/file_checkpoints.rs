@@ -0,0 +1,339 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempPath};
+use utoipa::ToSchema;
+
+pub const CHECKPOINTS_TABLE: &str = "file_checkpoints";
+
+/// Files larger than this are not snapshotted, so changes to them cannot be undone.
+pub const MAX_CHECKPOINT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The content a file had before a tool changed it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCheckpoint {
+    pub id: i64,
+    pub path: String,
+    /// False when the tool created the file; restoring removes it again.
+    pub existed: bool,
+    pub tool_name: String,
+    /// Unix timestamp in seconds, comparable with message `created` timestamps.
+    pub created_timestamp: i64,
+    #[serde(skip)]
+    pub content: Option<Vec<u8>>,
+    /// Permission bits the file had, used when it has to be recreated.
+    #[serde(skip)]
+    pub mode: Option<u32>,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for FileCheckpoint {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        let content: Option<Vec<u8>> = row.try_get("content").ok().flatten();
+        let mode: Option<i64> = row.try_get("mode").ok().flatten();
+        Ok(FileCheckpoint {
+            id: row.try_get("id")?,
+            path: row.try_get("path")?,
+            existed: row.try_get("existed")?,
+            tool_name: row.try_get("tool_name")?,
+            created_timestamp: row.try_get("created_timestamp")?,
+            content,
+            mode: mode.map(|mode| mode as u32),
+        })
+    }
+}
+
+/// Captured state of a file about to be modified.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileSnapshot {
+    Missing,
+    Content { content: Vec<u8>, mode: Option<u32> },
+}
+
+impl FileSnapshot {
+    /// Reads the current state of `path`. Returns `None` when the file cannot be snapshotted,
+    /// either because it is too large or unreadable.
+    pub fn capture(path: &Path) -> Option<Self> {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.len() > MAX_CHECKPOINT_BYTES => None,
+            Ok(metadata) => fs::read(path).ok().map(|content| FileSnapshot::Content {
+                content,
+                mode: file_mode(&metadata),
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Some(FileSnapshot::Missing),
+            Err(_) => None,
+        }
+    }
+}
+
+impl FileCheckpoint {
+    /// The change that puts the file back the way it was when the checkpoint was taken.
+    pub fn change(&self) -> Result<FileChange> {
+        if self.existed && self.content.is_none() {
+            anyhow::bail!("Checkpoint for {} has no content", self.path);
+        }
+        Ok(FileChange {
+            path: PathBuf::from(&self.path),
+            content: self.content.clone().filter(|_| self.existed),
+            mode: self.mode,
+        })
+    }
+}
+
+/// New content for a file, or `None` to remove it.
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub content: Option<Vec<u8>>,
+    /// Permission bits for a file that does not exist yet. A file being replaced keeps its own.
+    pub mode: Option<u32>,
+}
+
+/// The permission bits of a file, on platforms that have them.
+pub fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode() & 0o7777)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Applies every change or none of them. New content is staged in a temporary file next to its
+/// target before anything is touched, and the files being replaced are moved aside until all
+/// changes are in place, so a failure part way through puts the earlier files back.
+pub fn apply_file_changes(changes: &[FileChange]) -> Result<()> {
+    let mut staged = Vec::with_capacity(changes.len());
+    for change in changes {
+        let file = match &change.content {
+            Some(content) => Some(
+                stage(&change.path, content, change.mode)
+                    .with_context(|| format!("Failed to stage {}", change.path.display()))?,
+            ),
+            None => None,
+        };
+        staged.push((&change.path, file));
+    }
+
+    let mut applied = Vec::with_capacity(staged.len());
+    for (path, file) in staged {
+        let wrote = file.is_some();
+        match apply(path, file) {
+            Ok(backup) => applied.push((path, backup, wrote)),
+            Err(e) => {
+                for (path, backup, wrote) in applied.into_iter().rev() {
+                    roll_back(path, backup, wrote);
+                }
+                return Err(e);
+            }
+        }
+    }
+    // Dropping the backups removes them
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+fn stage(path: &Path, content: &[u8], mode: Option<u32>) -> Result<NamedTempFile> {
+    let parent = parent_dir(path);
+    fs::create_dir_all(parent)?;
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".goose-staged");
+    // Temporary files are private by default; new files get the same mode `fs::write` would give
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let mut file = builder.tempfile_in(parent)?;
+    file.write_all(content)?;
+    if let Some(permissions) = target_permissions(path, mode) {
+        file.as_file().set_permissions(permissions)?;
+    }
+    file.as_file().sync_all()?;
+    Ok(file)
+}
+
+/// The permissions of the file being replaced, or of the mode recorded for it when it is gone.
+fn target_permissions(path: &Path, mode: Option<u32>) -> Option<fs::Permissions> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => Some(metadata.permissions()),
+        _ => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                mode.map(fs::Permissions::from_mode)
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = mode;
+                None
+            }
+        }
+    }
+}
+
+/// Moves the current file aside and puts the staged content in its place. Returns the backup.
+fn apply(path: &Path, staged: Option<NamedTempFile>) -> Result<Option<TempPath>> {
+    let backup = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => anyhow::bail!("{} is a directory", path.display()),
+        Ok(_) => {
+            let backup = tempfile::Builder::new()
+                .prefix(".goose-backup")
+                .tempfile_in(parent_dir(path))?
+                .into_temp_path();
+            fs::rename(path, &backup)
+                .with_context(|| format!("Failed to move {} aside", path.display()))?;
+            Some(backup)
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    if let Some(staged) = staged {
+        if let Err(e) = staged.persist(path) {
+            roll_back(path, backup, false);
+            return Err(e.error).with_context(|| format!("Failed to write {}", path.display()));
+        }
+    }
+    Ok(backup)
+}
+
+fn roll_back(path: &Path, backup: Option<TempPath>, wrote: bool) {
+    let result = match backup {
+        Some(backup) => fs::rename(&backup, path),
+        None if wrote => fs::remove_file(path),
+        None => Ok(()),
+    };
+    if let Err(e) = result {
+        tracing::error!("Failed to roll back {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(path: &Path, snapshot: FileSnapshot) -> FileCheckpoint {
+        let (existed, content, mode) = match snapshot {
+            FileSnapshot::Missing => (false, None, None),
+            FileSnapshot::Content { content, mode } => (true, Some(content), mode),
+        };
+        FileCheckpoint {
+            id: 1,
+            path: path.to_string_lossy().to_string(),
+            existed,
+            tool_name: "write".to_string(),
+            created_timestamp: 0,
+            content,
+            mode,
+        }
+    }
+
+    #[test]
+    fn test_restore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing.txt");
+        let created = dir.path().join("created.txt");
+        fs::write(&existing, "before").unwrap();
+
+        let existing_checkpoint = checkpoint(&existing, FileSnapshot::capture(&existing).unwrap());
+        let created_checkpoint = checkpoint(&created, FileSnapshot::capture(&created).unwrap());
+        fs::write(&existing, "after").unwrap();
+        fs::write(&created, "new").unwrap();
+
+        let changes = [
+            existing_checkpoint.change().unwrap(),
+            created_checkpoint.change().unwrap(),
+        ];
+        apply_file_changes(&changes).unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+
+        // Restoring a removal twice is harmless
+        apply_file_changes(&changes[1..]).unwrap();
+        let leftovers: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(leftovers.len(), 1);
+    }
+
+    #[test]
+    fn test_failed_change_rolls_back_earlier_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let removed = dir.path().join("removed.txt");
+        let blocked = dir.path().join("blocked");
+        fs::write(&first, "first").unwrap();
+        fs::write(&removed, "removed").unwrap();
+        // Directories are never replaced, so the last change fails after the others applied
+        fs::create_dir_all(blocked.join("child")).unwrap();
+
+        let result = apply_file_changes(&[
+            FileChange {
+                path: first.clone(),
+                content: Some(b"changed".to_vec()),
+                mode: None,
+            },
+            FileChange {
+                path: removed.clone(),
+                content: None,
+                mode: None,
+            },
+            FileChange {
+                path: blocked.clone(),
+                content: Some(b"file".to_vec()),
+                mode: None,
+            },
+        ]);
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&removed).unwrap(), "removed");
+        assert!(blocked.join("child").is_dir());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("run.sh");
+        let deleted = dir.path().join("deleted.sh");
+        for path in [&script, &deleted] {
+            fs::write(path, "#!/bin/sh\necho before\n").unwrap();
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let changes = [
+            checkpoint(&script, FileSnapshot::capture(&script).unwrap())
+                .change()
+                .unwrap(),
+            checkpoint(&deleted, FileSnapshot::capture(&deleted).unwrap())
+                .change()
+                .unwrap(),
+        ];
+        fs::write(&script, "#!/bin/sh\necho after\n").unwrap();
+        fs::remove_file(&deleted).unwrap();
+
+        apply_file_changes(&changes).unwrap();
+        for path in [&script, &deleted] {
+            assert_eq!(
+                fs::read_to_string(path).unwrap(),
+                "#!/bin/sh\necho before\n"
+            );
+            let mode = fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+            assert_eq!(mode, 0o755, "{}", path.display());
+        }
+    }
+}
@@ -1,11 +1,25 @@
-mod chat_history_search;
+pub mod branches;
+pub mod budget;
+pub mod chat_history_search;
 mod diagnostics;
 pub mod extension_data;
+pub mod file_checkpoints;
 mod legacy;
 pub mod session_manager;
+pub mod stats;
+pub mod usage_costs;
 
+pub use branches::SessionBranch;
+pub use budget::{BudgetLimits, BudgetSpend, BudgetStatus, DelegatedFrom};
+pub use chat_history_search::ChatHistorySearchOptions;
 pub use diagnostics::{generate_diagnostics, get_system_info, SystemInfo};
-pub use extension_data::{EnabledExtensionsState, ExtensionData, ExtensionState, TodoState};
+pub use extension_data::{
+    EnabledExtensionsState, ExtensionData, ExtensionState, TodoItem, TodoList, TodoState,
+    TodoStatus,
+};
+pub use file_checkpoints::{FileCheckpoint, FileSnapshot};
 pub use session_manager::{
     Session, SessionInsights, SessionManager, SessionType, SessionUpdateBuilder,
 };
+pub use stats::ToolUsage;
+pub use usage_costs::{ModelCost, SessionCost};
@@ -2,9 +2,19 @@ use crate::config::paths::Paths;
 use crate::conversation::message::Message;
 use crate::conversation::Conversation;
 use crate::model::ModelConfig;
-use crate::providers::base::{Provider, MSG_COUNT_FOR_SESSION_NAME_GENERATION};
+use crate::providers::base::{Provider, Usage, MSG_COUNT_FOR_SESSION_NAME_GENERATION};
 use crate::recipe::Recipe;
+use crate::session::branches::SessionBranch;
+use crate::session::budget::{recipe_budget_key, start_of_today, BudgetSpend};
+use crate::session::chat_history_search::{
+    searchable_text, ChatHistorySearchOptions, ChatRecallResults, FTS_TABLE,
+};
 use crate::session::extension_data::ExtensionData;
+use crate::session::file_checkpoints::{
+    apply_file_changes, FileCheckpoint, FileSnapshot, CHECKPOINTS_TABLE,
+};
+use crate::session::stats::{ToolUsage, TOOL_USAGE_QUERY};
+use crate::session::usage_costs::{ModelCost, SessionCost, USAGE_COSTS_TABLE};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use rmcp::model::Role;
@@ -18,9 +28,11 @@ use std::sync::{Arc, LazyLock};
 use tracing::{info, warn};
 use utoipa::ToSchema;
 
-pub const CURRENT_SCHEMA_VERSION: i32 = 7;
+pub const CURRENT_SCHEMA_VERSION: i32 = 14;
 pub const SESSIONS_FOLDER: &str = "sessions";
 pub const DB_NAME: &str = "sessions.db";
+/// Sessions created through a goose-server API token, keyed to the token that owns them.
+pub const SESSION_OWNERS_TABLE: &str = "session_owners";
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
@@ -93,6 +105,12 @@ pub struct Session {
     pub message_count: usize,
     pub provider_name: Option<String>,
     pub model_config: Option<ModelConfig>,
+    /// Session this one was branched from
+    #[serde(default)]
+    pub parent_session_id: Option<String>,
+    /// Last message of the parent that this branch shares
+    #[serde(default)]
+    pub fork_message_id: Option<String>,
 }
 
 pub struct SessionUpdateBuilder<'a> {
@@ -121,6 +139,10 @@ pub struct SessionUpdateBuilder<'a> {
 pub struct SessionInsights {
     pub total_sessions: usize,
     pub total_tokens: i64,
+    /// USD spent across all sessions with known model pricing
+    pub total_cost: f64,
+    /// USD spent since local midnight
+    pub today_cost: f64,
 }
 
 impl<'a> SessionUpdateBuilder<'a> {
@@ -323,12 +345,167 @@ impl SessionManager {
         self.storage.copy_session(self, session_id, new_name).await
     }
 
-    pub async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
+    /// Starts a branch of `session_id` that shares its history up to and including
+    /// `message_id`. Shared messages are read from the parent rather than copied, until either
+    /// side rewrites them.
+    pub async fn branch_session(
+        &self,
+        session_id: &str,
+        message_id: &str,
+        name: Option<String>,
+    ) -> Result<Session> {
+        self.storage
+            .branch_session(self, session_id, message_id, name)
+            .await
+    }
+
+    /// The branch tree `session_id` belongs to, starting from its root session.
+    pub async fn get_branch_tree(&self, session_id: &str) -> Result<SessionBranch> {
+        self.storage.get_branch_tree(session_id).await
+    }
+
+    /// Records that `session_id` was created through the API token `token_id`.
+    pub async fn set_session_owner(&self, session_id: &str, token_id: &str) -> Result<()> {
+        self.storage.set_session_owner(session_id, token_id).await
+    }
+
+    /// The API token that owns `session_id`, if it was created through one.
+    pub async fn get_session_owner(&self, session_id: &str) -> Result<Option<String>> {
+        self.storage.get_session_owner(session_id).await
+    }
+
+    /// Ids of the sessions owned by the API token `token_id`.
+    pub async fn list_owned_session_ids(&self, token_id: &str) -> Result<Vec<String>> {
+        self.storage.list_owned_session_ids(token_id).await
+    }
+
+    /// Removes all messages created at or after `timestamp`. With `restore_files`, files the
+    /// developer tools changed since then are rolled back to their checkpoints as well.
+    pub async fn truncate_conversation(
+        &self,
+        session_id: &str,
+        timestamp: i64,
+        restore_files: bool,
+    ) -> Result<()> {
+        if restore_files {
+            self.storage
+                .restore_file_checkpoints(session_id, timestamp)
+                .await?;
+        }
         self.storage
             .truncate_conversation(session_id, timestamp)
             .await
     }
 
+    /// Records the state of `path` before a tool modifies it.
+    pub async fn add_file_checkpoint(
+        &self,
+        session_id: &str,
+        path: &Path,
+        tool_name: &str,
+        snapshot: FileSnapshot,
+    ) -> Result<()> {
+        self.storage
+            .add_file_checkpoint(session_id, path, tool_name, snapshot)
+            .await
+    }
+
+    /// Lists a session's file checkpoints, oldest first, without their content.
+    pub async fn list_file_checkpoints(&self, session_id: &str) -> Result<Vec<FileCheckpoint>> {
+        self.storage.list_file_checkpoints(session_id).await
+    }
+
+    /// Rolls back every file changed at or after `timestamp` and drops those checkpoints.
+    /// Returns the checkpoints that were restored, one per file.
+    pub async fn restore_file_checkpoints(
+        &self,
+        session_id: &str,
+        timestamp: i64,
+    ) -> Result<Vec<FileCheckpoint>> {
+        self.storage
+            .restore_file_checkpoints(session_id, timestamp)
+            .await
+    }
+
+    /// Reverts the most recent file change in the session, optionally limited to one path.
+    pub async fn undo_file_change(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+    ) -> Result<Option<FileCheckpoint>> {
+        self.storage.undo_file_change(session_id, path).await
+    }
+
+    /// Takes the checkpoint [`SessionManager::undo_file_change`] would restore, with its
+    /// content, without touching the file, for callers that write the file some other way.
+    /// Hand it back with [`SessionManager::return_file_checkpoints`] if that fails.
+    pub async fn take_file_checkpoint(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+    ) -> Result<Option<FileCheckpoint>> {
+        self.storage.take_file_checkpoint(session_id, path).await
+    }
+
+    /// Puts checkpoints taken out of the session back, so the change can be undone again.
+    pub async fn return_file_checkpoints(
+        &self,
+        session_id: &str,
+        checkpoints: &[FileCheckpoint],
+    ) -> Result<()> {
+        self.storage
+            .return_file_checkpoints(session_id, checkpoints)
+            .await
+    }
+
+    /// Adds one provider call made by `session_id` to the cost ledger, counted against the
+    /// budgets of `billed_to`: the session itself, or the session that delegated to it. `cost`
+    /// is `None` for models without known pricing, which are still counted as requests.
+    pub async fn record_usage_cost(
+        &self,
+        session_id: &str,
+        provider: &str,
+        model: &str,
+        usage: &Usage,
+        cost: Option<f64>,
+        billed_to: &Session,
+    ) -> Result<()> {
+        self.storage
+            .record_usage_cost(session_id, provider, model, usage, cost, billed_to)
+            .await
+    }
+
+    /// Spend of a session and the subagents it delegated to, broken down by provider and
+    /// model, most expensive first.
+    pub async fn get_session_costs(&self, session_id: &str) -> Result<Vec<ModelCost>> {
+        self.storage.get_session_costs(session_id).await
+    }
+
+    /// Spend per session since `since`, most expensive first.
+    pub async fn list_session_costs(&self, since: DateTime<Utc>) -> Result<Vec<SessionCost>> {
+        self.storage.list_session_costs(since).await
+    }
+
+    /// Spend per provider/model across all sessions since `since`, most expensive first.
+    pub async fn get_model_costs(&self, since: DateTime<Utc>) -> Result<Vec<ModelCost>> {
+        self.storage.get_model_costs(since).await
+    }
+
+    /// Tool calls per tool, for one session or all of them, optionally only since `since`.
+    /// Messages a branch shares with its parent are counted for the parent.
+    pub async fn get_tool_usage(
+        &self,
+        session_id: Option<&str>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ToolUsage>> {
+        self.storage.get_tool_usage(session_id, since).await
+    }
+
+    /// Spend counted against the session, daily and recipe budgets of `session`.
+    pub async fn get_budget_spend(&self, session: &Session) -> Result<BudgetSpend> {
+        self.storage.get_budget_spend(session).await
+    }
+
     pub async fn maybe_update_name(&self, id: &str, provider: Arc<dyn Provider>) -> Result<()> {
         let session = self.get_session(id, true).await?;
 
@@ -357,14 +534,9 @@ impl SessionManager {
     pub async fn search_chat_history(
         &self,
         query: &str,
-        limit: Option<usize>,
-        after_date: Option<chrono::DateTime<chrono::Utc>>,
-        before_date: Option<chrono::DateTime<chrono::Utc>>,
-        exclude_session_id: Option<String>,
-    ) -> Result<crate::session::chat_history_search::ChatRecallResults> {
-        self.storage
-            .search_chat_history(query, limit, after_date, before_date, exclude_session_id)
-            .await
+        options: ChatHistorySearchOptions,
+    ) -> Result<ChatRecallResults> {
+        self.storage.search_chat_history(query, options).await
     }
 
     pub async fn update_message_metadata<F>(id: &str, message_id: &str, f: F) -> Result<()>
@@ -417,6 +589,8 @@ impl Default for Session {
             message_count: 0,
             provider_name: None,
             model_config: None,
+            parent_session_id: None,
+            fork_message_id: None,
         }
     }
 }
@@ -481,6 +655,8 @@ impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for Session {
             message_count: row.try_get("message_count").unwrap_or(0) as usize,
             provider_name: row.try_get("provider_name").ok().flatten(),
             model_config,
+            parent_session_id: row.try_get("parent_session_id").ok().flatten(),
+            fork_message_id: row.try_get("fork_message_id").ok().flatten(),
         })
     }
 }
@@ -579,7 +755,10 @@ impl SessionStorage {
                 recipe_json TEXT,
                 user_recipe_values_json TEXT,
                 provider_name TEXT,
-                model_config_json TEXT
+                model_config_json TEXT,
+                parent_session_id TEXT,
+                fork_message_id TEXT,
+                inherited_message_count INTEGER NOT NULL DEFAULT 0
             )
         "#,
         )
@@ -619,7 +798,161 @@ impl SessionStorage {
         sqlx::query("CREATE INDEX idx_sessions_type ON sessions(session_type)")
             .execute(pool)
             .await?;
+        sqlx::query("CREATE INDEX idx_sessions_parent ON sessions(parent_session_id)")
+            .execute(pool)
+            .await?;
+        sqlx::query(&Self::create_fts_table_sql())
+            .execute(pool)
+            .await?;
+        for statement in Self::create_checkpoints_table_sql() {
+            sqlx::query(&statement).execute(pool).await?;
+        }
+        sqlx::query(&Self::add_checkpoint_mode_sql())
+            .execute(pool)
+            .await?;
+        for statement in Self::create_usage_costs_table_sql() {
+            sqlx::query(&statement).execute(pool).await?;
+        }
+        for statement in Self::add_usage_costs_billing_sql() {
+            sqlx::query(&statement).execute(pool).await?;
+        }
+        for statement in Self::create_session_owners_table_sql() {
+            sqlx::query(&statement).execute(pool).await?;
+        }
+
+        Ok(())
+    }
+
+    fn create_checkpoints_table_sql() -> [String; 2] {
+        [
+            format!(
+                r#"
+            CREATE TABLE {} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL REFERENCES sessions(id),
+                path TEXT NOT NULL,
+                existed BOOLEAN NOT NULL,
+                content BLOB,
+                tool_name TEXT NOT NULL,
+                created_timestamp INTEGER NOT NULL
+            )
+        "#,
+                CHECKPOINTS_TABLE
+            ),
+            format!(
+                "CREATE INDEX idx_file_checkpoints_session ON {}(session_id, created_timestamp)",
+                CHECKPOINTS_TABLE
+            ),
+        ]
+    }
+
+    /// Records the permission bits of checkpointed files so restoring one recreates it as it was.
+    fn add_checkpoint_mode_sql() -> String {
+        format!("ALTER TABLE {} ADD COLUMN mode INTEGER", CHECKPOINTS_TABLE)
+    }
+
+    fn create_usage_costs_table_sql() -> [String; 2] {
+        [
+            format!(
+                r#"
+            CREATE TABLE {} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                recipe TEXT,
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0,
+                cost REAL,
+                created_timestamp INTEGER NOT NULL
+            )
+        "#,
+                USAGE_COSTS_TABLE
+            ),
+            format!(
+                "CREATE INDEX idx_usage_costs_created ON {}(created_timestamp, session_id)",
+                USAGE_COSTS_TABLE
+            ),
+        ]
+    }
+
+    /// Books a subagent's calls to the session that delegated to it, and keys recipe spend by
+    /// [`recipe_budget_key`] rather than by title.
+    fn add_usage_costs_billing_sql() -> [String; 3] {
+        [
+            format!(
+                "ALTER TABLE {} ADD COLUMN parent_session_id TEXT",
+                USAGE_COSTS_TABLE
+            ),
+            format!(
+                "ALTER TABLE {} ADD COLUMN recipe_key TEXT",
+                USAGE_COSTS_TABLE
+            ),
+            format!(
+                "CREATE INDEX idx_usage_costs_parent ON {}(parent_session_id)",
+                USAGE_COSTS_TABLE
+            ),
+        ]
+    }
+
+    fn create_session_owners_table_sql() -> [String; 2] {
+        [
+            format!(
+                r#"
+            CREATE TABLE {} (
+                session_id TEXT PRIMARY KEY REFERENCES sessions(id),
+                token_id TEXT NOT NULL
+            )
+        "#,
+                SESSION_OWNERS_TABLE
+            ),
+            format!(
+                "CREATE INDEX idx_session_owners_token ON {}(token_id)",
+                SESSION_OWNERS_TABLE
+            ),
+        ]
+    }
+
+    fn create_fts_table_sql() -> String {
+        format!(
+            "CREATE VIRTUAL TABLE {} USING fts5(content, tokenize = 'porter unicode61 remove_diacritics 2')",
+            FTS_TABLE
+        )
+    }
+
+    async fn index_message(
+        tx: &mut sqlx::Transaction<'_, Sqlite>,
+        row_id: i64,
+        message: &Message,
+    ) -> Result<()> {
+        let text = searchable_text(&message.content);
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO {} (rowid, content) VALUES (?, ?)",
+            FTS_TABLE
+        ))
+        .bind(row_id)
+        .bind(text)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    async fn backfill_fts_index(tx: &mut sqlx::Transaction<'_, Sqlite>) -> Result<()> {
+        let rows = sqlx::query_as::<_, (i64, String)>("SELECT id, content_json FROM messages")
+            .fetch_all(&mut **tx)
+            .await?;
 
+        for (row_id, content_json) in rows {
+            let Ok(content) = serde_json::from_str(&content_json) else {
+                continue;
+            };
+            let message = Message::new(Role::User, 0, content);
+            Self::index_message(tx, row_id, &message).await?;
+        }
         Ok(())
     }
 
@@ -887,6 +1220,50 @@ impl SessionStorage {
                     .execute(&mut **tx)
                     .await?;
             }
+            8 => {
+                sqlx::query(&Self::create_fts_table_sql())
+                    .execute(&mut **tx)
+                    .await?;
+
+                Self::backfill_fts_index(tx).await?;
+            }
+            9 => {
+                for statement in Self::create_checkpoints_table_sql() {
+                    sqlx::query(&statement).execute(&mut **tx).await?;
+                }
+            }
+            10 => {
+                for statement in Self::create_usage_costs_table_sql() {
+                    sqlx::query(&statement).execute(&mut **tx).await?;
+                }
+            }
+            11 => {
+                // Branches read the first `inherited_message_count` messages from their parent
+                // instead of storing copies of them.
+                for statement in [
+                    "ALTER TABLE sessions ADD COLUMN parent_session_id TEXT",
+                    "ALTER TABLE sessions ADD COLUMN fork_message_id TEXT",
+                    "ALTER TABLE sessions ADD COLUMN inherited_message_count INTEGER NOT NULL DEFAULT 0",
+                    "CREATE INDEX idx_sessions_parent ON sessions(parent_session_id)",
+                ] {
+                    sqlx::query(statement).execute(&mut **tx).await?;
+                }
+            }
+            12 => {
+                for statement in Self::create_session_owners_table_sql() {
+                    sqlx::query(&statement).execute(&mut **tx).await?;
+                }
+            }
+            13 => {
+                for statement in Self::add_usage_costs_billing_sql() {
+                    sqlx::query(&statement).execute(&mut **tx).await?;
+                }
+            }
+            14 => {
+                sqlx::query(&Self::add_checkpoint_mode_sql())
+                    .execute(&mut **tx)
+                    .await?;
+            }
             _ => {
                 anyhow::bail!("Unknown migration version: {}", version);
             }
@@ -920,7 +1297,11 @@ impl SessionStorage {
                     ?,
                     '{}'
                 )
-                RETURNING *
+                RETURNING id, working_dir, name, description, user_set_name, session_type, created_at, updated_at, extension_data,
+                          total_tokens, input_tokens, output_tokens,
+                          accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
+                          schedule_id, recipe_json, user_recipe_values_json,
+                          provider_name, model_config_json, parent_session_id, fork_message_id
                 "#,
         )
             .bind(&today)
@@ -944,7 +1325,7 @@ impl SessionStorage {
                total_tokens, input_tokens, output_tokens,
                accumulated_total_tokens, accumulated_input_tokens, accumulated_output_tokens,
                schedule_id, recipe_json, user_recipe_values_json,
-               provider_name, model_config_json
+               provider_name, model_config_json, parent_session_id, fork_message_id
         FROM sessions
         WHERE id = ?
     "#,
@@ -959,11 +1340,13 @@ impl SessionStorage {
             session.message_count = conv.messages().len();
             session.conversation = Some(conv);
         } else {
-            let count =
-                sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM messages WHERE session_id = ?")
-                    .bind(&session.id)
-                    .fetch_one(pool)
-                    .await? as usize;
+            let count = sqlx::query_scalar::<_, i64>(
+                "SELECT inherited_message_count + (SELECT COUNT(*) FROM messages WHERE messages.session_id = sessions.id)
+                 FROM sessions WHERE id = ?",
+            )
+            .bind(&session.id)
+            .fetch_one(pool)
+            .await? as usize;
             session.message_count = count;
         }
 
@@ -1084,6 +1467,21 @@ impl SessionStorage {
 
     async fn get_conversation(&self, session_id: &str) -> Result<Conversation> {
         let pool = self.pool().await?;
+
+        let mut messages = Vec::new();
+        let inherited = sqlx::query_as::<_, (Option<String>, i64)>(
+            "SELECT parent_session_id, inherited_message_count FROM sessions WHERE id = ?",
+        )
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?;
+        if let Some((Some(parent_id), count)) = inherited {
+            if count > 0 {
+                let parent = Box::pin(self.get_conversation(&parent_id)).await?;
+                messages.extend(parent.messages().iter().take(count as usize).cloned());
+            }
+        }
+
         let rows = sqlx::query_as::<_, (String, String, i64, Option<String>, Option<String>)>(
             // Order by created_timestamp, then by id to break ties. created_timestamp is in seconds,
             // so messages created in the same second (e.g., tool request and response) need to
@@ -1094,7 +1492,6 @@ impl SessionStorage {
             .fetch_all(pool)
             .await?;
 
-        let mut messages = Vec::new();
         for (role_str, content_json, created_timestamp, metadata_json, message_id) in
             rows.into_iter()
         {
@@ -1131,7 +1528,7 @@ impl SessionStorage {
             .clone()
             .unwrap_or_else(|| format!("msg_{}_{}", session_id, uuid::Uuid::new_v4()));
 
-        sqlx::query(
+        let row_id = sqlx::query(
             r#"
             INSERT INTO messages (message_id, session_id, role, content_json, created_timestamp, metadata_json)
             VALUES (?, ?, ?, ?, ?, ?)
@@ -1144,7 +1541,10 @@ impl SessionStorage {
         .bind(message.created)
         .bind(metadata_json)
         .execute(&mut *tx)
-        .await?;
+        .await?
+        .last_insert_rowid();
+
+        Self::index_message(&mut tx, row_id, message).await?;
 
         sqlx::query("UPDATE sessions SET updated_at = datetime('now') WHERE id = ?")
             .bind(session_id)
@@ -1162,6 +1562,7 @@ impl SessionStorage {
     ) -> Result<()> {
         let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
 
+        Self::delete_fts_rows(&mut tx, session_id, None).await?;
         sqlx::query("DELETE FROM messages WHERE session_id = ?")
             .bind(session_id)
             .execute(&mut *tx)
@@ -1175,7 +1576,7 @@ impl SessionStorage {
                 .clone()
                 .unwrap_or_else(|| format!("msg_{}_{}", session_id, uuid::Uuid::new_v4()));
 
-            sqlx::query(
+            let row_id = sqlx::query(
                 r#"
             INSERT INTO messages (message_id, session_id, role, content_json, created_timestamp, metadata_json)
             VALUES (?, ?, ?, ?, ?, ?)
@@ -1188,9 +1589,17 @@ impl SessionStorage {
             .bind(message.created)
             .bind(metadata_json)
             .execute(&mut *tx)
-            .await?;
+            .await?
+            .last_insert_rowid();
+
+            Self::index_message(&mut tx, row_id, message).await?;
         }
 
+        sqlx::query("UPDATE sessions SET inherited_message_count = 0 WHERE id = ?")
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
         tx.commit().await?;
         Ok(())
     }
@@ -1200,10 +1609,44 @@ impl SessionStorage {
         session_id: &str,
         conversation: &Conversation,
     ) -> Result<()> {
+        self.detach_branches(session_id).await?;
         let pool = self.pool().await?;
         Self::replace_conversation_inner(pool, session_id, conversation).await
     }
 
+    /// Gives a branch its own copy of the messages it shares with its parent.
+    async fn materialize_branch(&self, session_id: &str) -> Result<()> {
+        let conversation = self.get_conversation(session_id).await?;
+        let pool = self.pool().await?;
+        Self::replace_conversation_inner(pool, session_id, &conversation).await
+    }
+
+    /// Materializes the branches that share messages with `session_id`, so that rewriting or
+    /// deleting its history leaves them intact.
+    async fn detach_branches(&self, session_id: &str) -> Result<()> {
+        let pool = self.pool().await?;
+        let children = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM sessions WHERE parent_session_id = ? AND inherited_message_count > 0",
+        )
+        .bind(session_id)
+        .fetch_all(pool)
+        .await?;
+        for child in children {
+            self.materialize_branch(&child).await?;
+        }
+        Ok(())
+    }
+
+    async fn inherits_messages(&self, session_id: &str) -> Result<bool> {
+        let pool = self.pool().await?;
+        Ok(sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM sessions WHERE id = ? AND inherited_message_count > 0)",
+        )
+        .bind(session_id)
+        .fetch_one(pool)
+        .await?)
+    }
+
     async fn list_sessions_by_types(&self, types: &[SessionType]) -> Result<Vec<Session>> {
         if types.is_empty() {
             return Ok(Vec::new());
@@ -1216,12 +1659,13 @@ impl SessionStorage {
                    s.total_tokens, s.input_tokens, s.output_tokens,
                    s.accumulated_total_tokens, s.accumulated_input_tokens, s.accumulated_output_tokens,
                    s.schedule_id, s.recipe_json, s.user_recipe_values_json,
-                   s.provider_name, s.model_config_json,
-                   COUNT(m.id) as message_count
+                   s.provider_name, s.model_config_json, s.parent_session_id, s.fork_message_id,
+                   COUNT(m.id) + s.inherited_message_count as message_count
             FROM sessions s
-            INNER JOIN messages m ON s.id = m.session_id
+            LEFT JOIN messages m ON s.id = m.session_id
             WHERE s.session_type IN ({})
             GROUP BY s.id
+            HAVING COUNT(m.id) + s.inherited_message_count > 0
             ORDER BY s.updated_at DESC
             "#,
             placeholders
@@ -1242,6 +1686,7 @@ impl SessionStorage {
     }
 
     async fn delete_session(&self, session_id: &str) -> Result<()> {
+        self.detach_branches(session_id).await?;
         let pool = self.pool().await?;
         let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
 
@@ -1255,10 +1700,17 @@ impl SessionStorage {
             return Err(anyhow::anyhow!("Session not found"));
         }
 
+        Self::delete_fts_rows(&mut tx, session_id, None).await?;
         sqlx::query("DELETE FROM messages WHERE session_id = ?")
             .bind(session_id)
             .execute(&mut *tx)
             .await?;
+        for table in [CHECKPOINTS_TABLE, SESSION_OWNERS_TABLE] {
+            sqlx::query(&format!("DELETE FROM {} WHERE session_id = ?", table))
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+        }
 
         sqlx::query("DELETE FROM sessions WHERE id = ?")
             .bind(session_id)
@@ -1281,9 +1733,203 @@ impl SessionStorage {
             .fetch_one(pool)
             .await?;
 
+        let (total_cost, today_cost) = sqlx::query_as::<_, (f64, f64)>(&format!(
+            r#"
+            SELECT COALESCE(SUM(cost), 0.0),
+                   COALESCE(SUM(CASE WHEN created_timestamp >= ? THEN cost END), 0.0)
+            FROM {}
+            "#,
+            USAGE_COSTS_TABLE
+        ))
+        .bind(start_of_today().timestamp())
+        .fetch_one(pool)
+        .await?;
+
         Ok(SessionInsights {
             total_sessions: row.0 as usize,
             total_tokens: row.1.unwrap_or(0),
+            total_cost,
+            today_cost,
+        })
+    }
+
+    async fn record_usage_cost(
+        &self,
+        session_id: &str,
+        provider: &str,
+        model: &str,
+        usage: &Usage,
+        cost: Option<f64>,
+        billed_to: &Session,
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        let parent_session_id = (billed_to.id != session_id).then_some(billed_to.id.as_str());
+        let recipe = billed_to.recipe.as_ref();
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO {} (session_id, parent_session_id, provider, model, recipe, recipe_key, input_tokens, output_tokens, cost, created_timestamp)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            USAGE_COSTS_TABLE
+        ))
+        .bind(session_id)
+        .bind(parent_session_id)
+        .bind(provider)
+        .bind(model)
+        .bind(recipe.map(|recipe| recipe.title.as_str()))
+        .bind(recipe.map(recipe_budget_key))
+        .bind(usage.input_tokens.unwrap_or(0))
+        .bind(usage.output_tokens.unwrap_or(0))
+        .bind(cost)
+        .bind(Utc::now().timestamp())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_session_costs(&self, session_id: &str) -> Result<Vec<ModelCost>> {
+        let pool = self.pool().await?;
+        let costs = sqlx::query_as::<_, ModelCost>(&format!(
+            r#"
+            SELECT provider, model,
+                   COUNT(*) AS requests,
+                   SUM(input_tokens) AS input_tokens,
+                   SUM(output_tokens) AS output_tokens,
+                   COALESCE(SUM(cost), 0.0) AS cost,
+                   SUM(cost IS NULL) AS unpriced_requests
+            FROM {}
+            WHERE session_id = ? OR parent_session_id = ?
+            GROUP BY provider, model
+            ORDER BY cost DESC, requests DESC
+            "#,
+            USAGE_COSTS_TABLE
+        ))
+        .bind(session_id)
+        .bind(session_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(costs)
+    }
+
+    async fn set_session_owner(&self, session_id: &str, token_id: &str) -> Result<()> {
+        let pool = self.pool().await?;
+        sqlx::query(&format!(
+            "INSERT OR REPLACE INTO {} (session_id, token_id) VALUES (?, ?)",
+            SESSION_OWNERS_TABLE
+        ))
+        .bind(session_id)
+        .bind(token_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get_session_owner(&self, session_id: &str) -> Result<Option<String>> {
+        let pool = self.pool().await?;
+        let owner = sqlx::query_scalar::<_, String>(&format!(
+            "SELECT token_id FROM {} WHERE session_id = ?",
+            SESSION_OWNERS_TABLE
+        ))
+        .bind(session_id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(owner)
+    }
+
+    async fn list_owned_session_ids(&self, token_id: &str) -> Result<Vec<String>> {
+        let pool = self.pool().await?;
+        let ids = sqlx::query_scalar::<_, String>(&format!(
+            "SELECT session_id FROM {} WHERE token_id = ?",
+            SESSION_OWNERS_TABLE
+        ))
+        .bind(token_id)
+        .fetch_all(pool)
+        .await?;
+        Ok(ids)
+    }
+
+    async fn get_model_costs(&self, since: DateTime<Utc>) -> Result<Vec<ModelCost>> {
+        let pool = self.pool().await?;
+        let costs = sqlx::query_as::<_, ModelCost>(&format!(
+            r#"
+            SELECT provider, model,
+                   COUNT(*) AS requests,
+                   SUM(input_tokens) AS input_tokens,
+                   SUM(output_tokens) AS output_tokens,
+                   COALESCE(SUM(cost), 0.0) AS cost,
+                   SUM(cost IS NULL) AS unpriced_requests
+            FROM {}
+            WHERE created_timestamp >= ?
+            GROUP BY provider, model
+            ORDER BY cost DESC, requests DESC
+            "#,
+            USAGE_COSTS_TABLE
+        ))
+        .bind(since.timestamp())
+        .fetch_all(pool)
+        .await?;
+        Ok(costs)
+    }
+
+    async fn get_tool_usage(
+        &self,
+        session_id: Option<&str>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ToolUsage>> {
+        let pool = self.pool().await?;
+        let usage = sqlx::query_as::<_, ToolUsage>(TOOL_USAGE_QUERY)
+            .bind(session_id)
+            .bind(since.map(|since| since.timestamp()))
+            .fetch_all(pool)
+            .await?;
+        Ok(usage)
+    }
+
+    async fn list_session_costs(&self, since: DateTime<Utc>) -> Result<Vec<SessionCost>> {
+        let pool = self.pool().await?;
+        let costs = sqlx::query_as::<_, SessionCost>(&format!(
+            r#"
+            SELECT c.session_id, s.name, MAX(c.recipe) AS recipe,
+                   COUNT(*) AS requests,
+                   COALESCE(SUM(c.cost), 0.0) AS cost
+            FROM {} c
+            LEFT JOIN sessions s ON s.id = c.session_id
+            WHERE c.created_timestamp >= ?
+            GROUP BY c.session_id
+            ORDER BY cost DESC, c.session_id DESC
+            "#,
+            USAGE_COSTS_TABLE
+        ))
+        .bind(since.timestamp())
+        .fetch_all(pool)
+        .await?;
+        Ok(costs)
+    }
+
+    async fn get_budget_spend(&self, session: &Session) -> Result<BudgetSpend> {
+        let pool = self.pool().await?;
+        let recipe = session.recipe.as_ref().map(recipe_budget_key);
+        let (session_cost, daily, recipe_cost) = sqlx::query_as::<_, (f64, f64, f64)>(&format!(
+            r#"
+            SELECT COALESCE(SUM(CASE WHEN session_id = ? OR parent_session_id = ? THEN cost END), 0.0),
+                   COALESCE(SUM(CASE WHEN created_timestamp >= ? THEN cost END), 0.0),
+                   COALESCE(SUM(CASE WHEN created_timestamp >= ? AND recipe_key = ? THEN cost END), 0.0)
+            FROM {}
+            "#,
+            USAGE_COSTS_TABLE
+        ))
+        .bind(&session.id)
+        .bind(&session.id)
+        .bind(start_of_today().timestamp())
+        .bind(start_of_today().timestamp())
+        .bind(recipe)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(BudgetSpend {
+            session: session_cost,
+            daily,
+            recipe: recipe_cost,
         })
     }
 
@@ -1373,41 +2019,335 @@ impl SessionStorage {
                 .await?;
         }
 
+        let pool = self.pool().await?;
+        sqlx::query(&format!(
+            "INSERT INTO {table} (session_id, path, existed, content, mode, tool_name, created_timestamp)
+             SELECT ?, path, existed, content, mode, tool_name, created_timestamp
+             FROM {table} WHERE session_id = ? ORDER BY id",
+            table = CHECKPOINTS_TABLE
+        ))
+        .bind(&new_session.id)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+
         self.get_session(&new_session.id, true).await
     }
 
-    async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
-        let pool = self.pool().await?;
-        sqlx::query("DELETE FROM messages WHERE session_id = ? AND created_timestamp >= ?")
-            .bind(session_id)
-            .bind(timestamp)
-            .execute(pool)
-            .await?;
+    async fn branch_session(
+        &self,
+        session_manager: &SessionManager,
+        session_id: &str,
+        message_id: &str,
+        name: Option<String>,
+    ) -> Result<Session> {
+        let parent = self.get_session(session_id, true).await?;
+        let messages = parent
+            .conversation
+            .as_ref()
+            .map(|conversation| conversation.messages().as_slice())
+            .unwrap_or_default();
+        let position = messages
+            .iter()
+            .position(|message| message.id.as_deref() == Some(message_id))
+            .ok_or_else(|| {
+                anyhow::anyhow!("Message {} not found in session {}", message_id, session_id)
+            })?;
+        let fork_created = messages[position].created;
+
+        let branch = self
+            .create_session(
+                parent.working_dir.clone(),
+                name.unwrap_or_else(|| format!("{} (branch)", parent.name)),
+                parent.session_type,
+            )
+            .await?;
+
+        let mut builder = session_manager
+            .update(&branch.id)
+            .extension_data(parent.extension_data)
+            .recipe(parent.recipe)
+            .user_recipe_values(parent.user_recipe_values);
+        if let Some(provider_name) = parent.provider_name {
+            builder = builder.provider_name(provider_name);
+        }
+        if let Some(model_config) = parent.model_config {
+            builder = builder.model_config(model_config);
+        }
+        builder.apply().await?;
+
+        let pool = self.pool().await?;
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+        sqlx::query(
+            "UPDATE sessions SET parent_session_id = ?, fork_message_id = ?, inherited_message_count = ?
+             WHERE id = ?",
+        )
+        .bind(session_id)
+        .bind(message_id)
+        .bind((position + 1) as i64)
+        .bind(&branch.id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "INSERT INTO {table} (session_id, path, existed, content, mode, tool_name, created_timestamp)
+             SELECT ?, path, existed, content, mode, tool_name, created_timestamp
+             FROM {table} WHERE session_id = ? AND created_timestamp <= ? ORDER BY id",
+            table = CHECKPOINTS_TABLE
+        ))
+        .bind(&branch.id)
+        .bind(session_id)
+        .bind(fork_created)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.get_session(&branch.id, false).await
+    }
+
+    async fn get_branch_tree(&self, session_id: &str) -> Result<SessionBranch> {
+        let pool = self.pool().await?;
+
+        let mut root_id = session_id.to_string();
+        let mut visited = std::collections::HashSet::from([root_id.clone()]);
+        while let Some(parent_id) = sqlx::query_scalar::<_, String>(
+            "SELECT p.id FROM sessions s JOIN sessions p ON p.id = s.parent_session_id WHERE s.id = ?",
+        )
+        .bind(&root_id)
+        .fetch_optional(pool)
+        .await?
+        {
+            if !visited.insert(parent_id.clone()) {
+                break;
+            }
+            root_id = parent_id;
+        }
+
+        let rows = sqlx::query_as::<_, SessionBranch>(
+            r#"
+            WITH RECURSIVE tree(id) AS (
+                SELECT ?
+                UNION
+                SELECT s.id FROM sessions s JOIN tree t ON s.parent_session_id = t.id
+            )
+            SELECT s.id, s.name, s.parent_session_id, s.fork_message_id, s.created_at, s.updated_at,
+                   s.inherited_message_count
+                       + (SELECT COUNT(*) FROM messages m WHERE m.session_id = s.id) AS message_count
+            FROM sessions s
+            JOIN tree t ON s.id = t.id
+            ORDER BY s.created_at, s.id
+            "#,
+        )
+        .bind(&root_id)
+        .fetch_all(pool)
+        .await?;
+
+        SessionBranch::build_tree(&root_id, rows)
+            .ok_or_else(|| anyhow::anyhow!("Session not found"))
+    }
+
+    async fn truncate_conversation(&self, session_id: &str, timestamp: i64) -> Result<()> {
+        self.detach_branches(session_id).await?;
+        if self.inherits_messages(session_id).await? {
+            self.materialize_branch(session_id).await?;
+        }
+        let pool = self.pool().await?;
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        Self::delete_fts_rows(&mut tx, session_id, Some(timestamp)).await?;
+        sqlx::query("DELETE FROM messages WHERE session_id = ? AND created_timestamp >= ?")
+            .bind(session_id)
+            .bind(timestamp)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_file_checkpoint(
+        &self,
+        session_id: &str,
+        path: &Path,
+        tool_name: &str,
+        snapshot: FileSnapshot,
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        let (existed, content, mode) = match snapshot {
+            FileSnapshot::Missing => (false, None, None),
+            FileSnapshot::Content { content, mode } => (true, Some(content), mode),
+        };
+        sqlx::query(&format!(
+            "INSERT INTO {} (session_id, path, existed, content, mode, tool_name, created_timestamp)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            CHECKPOINTS_TABLE
+        ))
+        .bind(session_id)
+        .bind(path.to_string_lossy().as_ref())
+        .bind(existed)
+        .bind(content)
+        .bind(mode.map(i64::from))
+        .bind(tool_name)
+        .bind(Utc::now().timestamp())
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_file_checkpoints(&self, session_id: &str) -> Result<Vec<FileCheckpoint>> {
+        let pool = self.pool().await?;
+        Ok(sqlx::query_as::<_, FileCheckpoint>(&format!(
+            "SELECT id, path, existed, tool_name, created_timestamp FROM {}
+             WHERE session_id = ? ORDER BY id",
+            CHECKPOINTS_TABLE
+        ))
+        .bind(session_id)
+        .fetch_all(pool)
+        .await?)
+    }
+
+    async fn restore_file_checkpoints(
+        &self,
+        session_id: &str,
+        timestamp: i64,
+    ) -> Result<Vec<FileCheckpoint>> {
+        let pool = self.pool().await?;
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+        let taken = sqlx::query_as::<_, FileCheckpoint>(&format!(
+            "DELETE FROM {} WHERE session_id = ? AND created_timestamp >= ? RETURNING *",
+            CHECKPOINTS_TABLE
+        ))
+        .bind(session_id)
+        .bind(timestamp)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        // The oldest checkpoint of each file holds its content from before the rewind point.
+        let mut oldest_first = taken.clone();
+        oldest_first.sort_by_key(|checkpoint| checkpoint.id);
+        let mut seen = std::collections::HashSet::new();
+        let restored: Vec<FileCheckpoint> = oldest_first
+            .into_iter()
+            .filter(|checkpoint| seen.insert(checkpoint.path.clone()))
+            .collect();
+
+        self.apply_checkpoints(session_id, &restored, &taken)
+            .await?;
+        Ok(restored)
+    }
+
+    async fn undo_file_change(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+    ) -> Result<Option<FileCheckpoint>> {
+        let Some(checkpoint) = self.take_file_checkpoint(session_id, path).await? else {
+            return Ok(None);
+        };
+        let taken = std::slice::from_ref(&checkpoint);
+        self.apply_checkpoints(session_id, taken, taken).await?;
+        Ok(Some(checkpoint))
+    }
+
+    async fn take_file_checkpoint(
+        &self,
+        session_id: &str,
+        path: Option<&Path>,
+    ) -> Result<Option<FileCheckpoint>> {
+        let pool = self.pool().await?;
+        let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+        let path = path.map(|p| p.to_string_lossy().to_string());
+        let checkpoint = sqlx::query_as::<_, FileCheckpoint>(&format!(
+            "DELETE FROM {table} WHERE id = (
+                 SELECT id FROM {table} WHERE session_id = ? AND (? IS NULL OR path = ?)
+                 ORDER BY id DESC LIMIT 1
+             ) RETURNING *",
+            table = CHECKPOINTS_TABLE
+        ))
+        .bind(session_id)
+        .bind(&path)
+        .bind(&path)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(checkpoint)
+    }
+
+    /// Writes the files of checkpoints already taken out of the table. The files are changed
+    /// outside the database lock; if that fails, every taken checkpoint goes back in so the
+    /// restore can be retried.
+    async fn apply_checkpoints(
+        &self,
+        session_id: &str,
+        restore: &[FileCheckpoint],
+        taken: &[FileCheckpoint],
+    ) -> Result<()> {
+        let changes = restore
+            .iter()
+            .map(FileCheckpoint::change)
+            .collect::<Result<Vec<_>>>();
+        let Err(e) = changes.and_then(|changes| apply_file_changes(&changes)) else {
+            return Ok(());
+        };
+        self.return_file_checkpoints(session_id, taken).await?;
+        Err(e)
+    }
 
+    async fn return_file_checkpoints(
+        &self,
+        session_id: &str,
+        taken: &[FileCheckpoint],
+    ) -> Result<()> {
+        let pool = self.pool().await?;
+        let mut tx = pool.begin().await?;
+        for checkpoint in taken {
+            sqlx::query(&format!(
+                "INSERT INTO {} (id, session_id, path, existed, content, mode, tool_name, created_timestamp)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                CHECKPOINTS_TABLE
+            ))
+            .bind(checkpoint.id)
+            .bind(session_id)
+            .bind(&checkpoint.path)
+            .bind(checkpoint.existed)
+            .bind(&checkpoint.content)
+            .bind(checkpoint.mode.map(i64::from))
+            .bind(&checkpoint.tool_name)
+            .bind(checkpoint.created_timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes the search index entries of a session's messages, optionally only those
+    /// created at or after `from_timestamp`. Must run before the messages themselves are deleted.
+    async fn delete_fts_rows(
+        tx: &mut sqlx::Transaction<'_, Sqlite>,
+        session_id: &str,
+        from_timestamp: Option<i64>,
+    ) -> Result<()> {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE rowid IN (SELECT id FROM messages WHERE session_id = ? AND created_timestamp >= ?)",
+            FTS_TABLE
+        ))
+        .bind(session_id)
+        .bind(from_timestamp.unwrap_or(i64::MIN))
+        .execute(&mut **tx)
+        .await?;
         Ok(())
     }
 
     async fn search_chat_history(
         &self,
         query: &str,
-        limit: Option<usize>,
-        after_date: Option<chrono::DateTime<chrono::Utc>>,
-        before_date: Option<chrono::DateTime<chrono::Utc>>,
-        exclude_session_id: Option<String>,
-    ) -> Result<crate::session::chat_history_search::ChatRecallResults> {
+        options: ChatHistorySearchOptions,
+    ) -> Result<ChatRecallResults> {
         use crate::session::chat_history_search::ChatHistorySearch;
 
         let pool = self.pool().await?;
-        ChatHistorySearch::new(
-            pool,
-            query,
-            limit,
-            after_date,
-            before_date,
-            exclude_session_id,
-        )
-        .execute()
-        .await
+        ChatHistorySearch::new(pool, query, options).execute().await
     }
 
     async fn update_message_metadata<F>(
@@ -1421,7 +2361,19 @@ impl SessionStorage {
             crate::conversation::message::MessageMetadata,
         ) -> crate::conversation::message::MessageMetadata,
     {
+        self.detach_branches(session_id).await?;
         let pool = self.pool().await?;
+        let owned = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM messages WHERE message_id = ? AND session_id = ?)",
+        )
+        .bind(message_id)
+        .bind(session_id)
+        .fetch_one(pool)
+        .await?;
+        if !owned && self.inherits_messages(session_id).await? {
+            self.materialize_branch(session_id).await?;
+        }
+
         let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
 
         let current_metadata_json = sqlx::query_scalar::<_, String>(
@@ -1711,6 +2663,684 @@ mod tests {
         assert_eq!(conversation.messages()[1].role, Role::Assistant);
     }
 
+    async fn add_text_message(sm: &SessionManager, session_id: &str, role: Role, text: &str) {
+        sm.add_message(
+            session_id,
+            &Message::new(
+                role,
+                chrono::Utc::now().timestamp(),
+                vec![MessageContent::text(text)],
+            ),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_search_chat_history_ranks_and_filters() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+
+        let a = sm
+            .create_session(PathBuf::from("/tmp/a"), "a".to_string(), SessionType::User)
+            .await
+            .unwrap();
+        let b = sm
+            .create_session(PathBuf::from("/tmp/b"), "b".to_string(), SessionType::User)
+            .await
+            .unwrap();
+
+        add_text_message(
+            &sm,
+            &a.id,
+            Role::User,
+            "the postgres connection pool is exhausted",
+        )
+        .await;
+        add_text_message(
+            &sm,
+            &a.id,
+            Role::Assistant,
+            "raise max_connections on postgres",
+        )
+        .await;
+        add_text_message(
+            &sm,
+            &b.id,
+            Role::User,
+            "postgres postgres postgres migration",
+        )
+        .await;
+
+        let results = sm
+            .search_chat_history("postgres", ChatHistorySearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 3);
+        assert_eq!(results.results[0].session_id, b.id);
+        assert!(results.results[0].messages[0]
+            .snippet
+            .contains("**postgres**"));
+
+        let results = sm
+            .search_chat_history("\"connection pool\"", ChatHistorySearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 1);
+
+        let results = sm
+            .search_chat_history("migrat*", ChatHistorySearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 1);
+
+        let results = sm
+            .search_chat_history("postgres -migration", ChatHistorySearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 2);
+
+        let results = sm
+            .search_chat_history(
+                "postgres",
+                ChatHistorySearchOptions {
+                    role: Some(Role::Assistant),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 1);
+
+        let results = sm
+            .search_chat_history(
+                "postgres",
+                ChatHistorySearchOptions {
+                    working_dir: Some("/tmp/a".to_string()),
+                    session_types: vec![SessionType::User],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(results.results[0].session_id, a.id);
+
+        let results = sm
+            .search_chat_history(
+                "postgres",
+                ChatHistorySearchOptions {
+                    session_types: vec![SessionType::Scheduled],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 0);
+
+        // The owner filter is part of the query, so a limit of one still finds the owned
+        // session although the other one ranks higher.
+        sm.set_session_owner(&a.id, "tok1").await.unwrap();
+        let results = sm
+            .search_chat_history(
+                "postgres",
+                ChatHistorySearchOptions {
+                    limit: Some(1),
+                    owner_token_id: Some("tok1".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(results.results[0].session_id, a.id);
+    }
+
+    async fn count(sm: &SessionManager, query: &str) -> usize {
+        sm.search_chat_history(query, ChatHistorySearchOptions::default())
+            .await
+            .unwrap()
+            .total_matches
+    }
+
+    #[tokio::test]
+    async fn test_search_index_follows_conversation_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+
+        let session = sm
+            .create_session(PathBuf::from("/tmp"), "s".to_string(), SessionType::User)
+            .await
+            .unwrap();
+        add_text_message(&sm, &session.id, Role::User, "kubernetes rollout").await;
+
+        assert_eq!(count(&sm, "kubernetes").await, 1);
+
+        let conversation = Conversation::new_unvalidated(vec![Message::new(
+            Role::User,
+            0,
+            vec![MessageContent::text("terraform plan")],
+        )]);
+        sm.replace_conversation(&session.id, &conversation)
+            .await
+            .unwrap();
+        assert_eq!(count(&sm, "kubernetes").await, 0);
+        assert_eq!(count(&sm, "terraform").await, 1);
+
+        sm.truncate_conversation(&session.id, 0, false)
+            .await
+            .unwrap();
+        assert_eq!(count(&sm, "terraform").await, 0);
+
+        add_text_message(&sm, &session.id, Role::User, "helm chart").await;
+        sm.delete_session(&session.id).await.unwrap();
+        assert_eq!(count(&sm, "helm").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_migration_backfills_search_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = sm
+            .create_session(PathBuf::from("/tmp"), "s".to_string(), SessionType::User)
+            .await
+            .unwrap();
+        add_text_message(&sm, &session.id, Role::User, "legacy message text").await;
+
+        let pool = sm.storage().pool.clone();
+        // Roll back to before the search index existed; later migrations re-run too
+        for table in [
+            FTS_TABLE,
+            CHECKPOINTS_TABLE,
+            USAGE_COSTS_TABLE,
+            SESSION_OWNERS_TABLE,
+        ] {
+            sqlx::query(&format!("DROP TABLE {}", table))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        for statement in [
+            "DROP INDEX idx_sessions_parent",
+            "ALTER TABLE sessions DROP COLUMN parent_session_id",
+            "ALTER TABLE sessions DROP COLUMN fork_message_id",
+            "ALTER TABLE sessions DROP COLUMN inherited_message_count",
+        ] {
+            sqlx::query(statement).execute(&pool).await.unwrap();
+        }
+        sqlx::query("UPDATE schema_version SET version = ? WHERE version = ?")
+            .bind(7)
+            .bind(CURRENT_SCHEMA_VERSION)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let reopened = SessionManager::new(temp_dir.path().to_path_buf());
+        let results = reopened
+            .search_chat_history("legacy", ChatHistorySearchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.total_matches, 1);
+    }
+
+    fn texts(conversation: &Conversation) -> Vec<String> {
+        conversation
+            .messages()
+            .iter()
+            .map(|m| m.as_concat_text())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_branches_share_history_until_rewritten() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let root = sm
+            .create_session(PathBuf::from("/tmp"), "root".to_string(), SessionType::User)
+            .await
+            .unwrap();
+        for (role, text) in [
+            (Role::User, "one"),
+            (Role::Assistant, "two"),
+            (Role::User, "three"),
+            (Role::Assistant, "four"),
+        ] {
+            add_text_message(&sm, &root.id, role, text).await;
+        }
+        let root_messages = sm
+            .get_session(&root.id, true)
+            .await
+            .unwrap()
+            .conversation
+            .unwrap();
+        let fork_id = |i: usize| root_messages.messages()[i].id.clone().unwrap();
+
+        let first = sm
+            .branch_session(&root.id, &fork_id(1), None)
+            .await
+            .unwrap();
+        assert_eq!(first.name, "root (branch)");
+        assert_eq!(first.parent_session_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(first.message_count, 2);
+        add_text_message(&sm, &first.id, Role::User, "three, differently").await;
+
+        let second = sm
+            .branch_session(&root.id, &fork_id(2), Some("other".into()))
+            .await
+            .unwrap();
+        assert!(sm.branch_session(&root.id, "missing", None).await.is_err());
+
+        let first_conversation = sm.get_session(&first.id, true).await.unwrap().conversation;
+        assert_eq!(
+            texts(&first_conversation.unwrap()),
+            vec!["one", "two", "three, differently"]
+        );
+        assert_eq!(
+            sm.get_session(&root.id, false).await.unwrap().message_count,
+            4
+        );
+
+        let listed: HashMap<_, _> = sm
+            .list_sessions()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.id, s.message_count))
+            .collect();
+        assert_eq!(listed.get(&second.id), Some(&3));
+
+        let tree = sm.get_branch_tree(&second.id).await.unwrap();
+        assert_eq!(tree.session_id, root.id);
+        let children: Vec<_> = tree
+            .children
+            .iter()
+            .map(|c| c.session_id.as_str())
+            .collect();
+        assert_eq!(children, vec![first.id.as_str(), second.id.as_str()]);
+        assert_eq!(tree.children[0].message_count, 3);
+
+        // Rewriting the parent hands its branches their own copies first
+        let compacted = Conversation::new_unvalidated(vec![Message::user().with_text("summary")]);
+        sm.replace_conversation(&root.id, &compacted).await.unwrap();
+        sm.delete_session(&root.id).await.unwrap();
+
+        let first_conversation = sm.get_session(&first.id, true).await.unwrap().conversation;
+        assert_eq!(
+            texts(&first_conversation.unwrap()),
+            vec!["one", "two", "three, differently"]
+        );
+        let second_conversation = sm.get_session(&second.id, true).await.unwrap().conversation;
+        assert_eq!(
+            texts(&second_conversation.unwrap()),
+            vec!["one", "two", "three"]
+        );
+        let orphan = sm.get_branch_tree(&second.id).await.unwrap();
+        assert_eq!(orphan.session_id, second.id);
+        assert!(orphan.children.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_metadata_update_does_not_reach_branches() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let root = sm
+            .create_session(PathBuf::from("/tmp"), "root".to_string(), SessionType::User)
+            .await
+            .unwrap();
+        add_text_message(&sm, &root.id, Role::User, "one").await;
+        add_text_message(&sm, &root.id, Role::Assistant, "two").await;
+        let root_messages = sm
+            .get_session(&root.id, true)
+            .await
+            .unwrap()
+            .conversation
+            .unwrap();
+        let first_id = root_messages.messages()[0].id.clone().unwrap();
+        let branch = sm
+            .branch_session(
+                &root.id,
+                root_messages.messages()[1].id.as_ref().unwrap(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        sm.storage()
+            .update_message_metadata(&root.id, &first_id, |metadata| {
+                metadata.with_agent_invisible()
+            })
+            .await
+            .unwrap();
+
+        let visible = |session: Session| {
+            session
+                .conversation
+                .unwrap()
+                .messages()
+                .iter()
+                .map(|message| message.metadata.agent_visible)
+                .collect::<Vec<_>>()
+        };
+        let root = sm.get_session(&root.id, true).await.unwrap();
+        assert_eq!(visible(root), vec![false, true]);
+        let branch = sm.get_session(&branch.id, true).await.unwrap();
+        assert_eq!(visible(branch), vec![true, true]);
+    }
+
+    #[tokio::test]
+    async fn test_file_checkpoints_restore_with_truncate() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let session = sm
+            .create_session(PathBuf::from("/tmp"), "s".to_string(), SessionType::User)
+            .await
+            .unwrap();
+
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        let edited = workspace.join("edited.txt");
+        let created = workspace.join("created.txt");
+        std::fs::write(&edited, "v1").unwrap();
+
+        // A change made before the truncation point must survive
+        let checkpoint = |path: &Path| FileSnapshot::capture(path).unwrap();
+        sm.add_file_checkpoint(&session.id, &edited, "edit", checkpoint(&edited))
+            .await
+            .unwrap();
+        std::fs::write(&edited, "v2").unwrap();
+        let pool = sm.storage().pool.clone();
+        sqlx::query(&format!(
+            "UPDATE {} SET created_timestamp = 100",
+            CHECKPOINTS_TABLE
+        ))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        sm.add_file_checkpoint(&session.id, &edited, "edit", checkpoint(&edited))
+            .await
+            .unwrap();
+        std::fs::write(&edited, "v3").unwrap();
+        sm.add_file_checkpoint(&session.id, &created, "write", checkpoint(&created))
+            .await
+            .unwrap();
+        std::fs::write(&created, "new").unwrap();
+        assert_eq!(
+            sm.list_file_checkpoints(&session.id).await.unwrap().len(),
+            3
+        );
+
+        sm.truncate_conversation(&session.id, 200, true)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "v2");
+        assert!(!created.exists());
+
+        let remaining = sm.list_file_checkpoints(&session.id).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].created_timestamp, 100);
+        assert!(remaining[0].content.is_none());
+
+        let undone = sm.undo_file_change(&session.id, None).await.unwrap();
+        assert!(undone.unwrap().existed);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "v1");
+        assert!(sm
+            .undo_file_change(&session.id, None)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A restore that cannot be applied leaves the files and checkpoints as they were
+        let blocked = workspace.join("blocked");
+        sm.add_file_checkpoint(&session.id, &edited, "edit", checkpoint(&edited))
+            .await
+            .unwrap();
+        std::fs::write(&edited, "v4").unwrap();
+        sm.add_file_checkpoint(&session.id, &blocked, "write", checkpoint(&blocked))
+            .await
+            .unwrap();
+        std::fs::create_dir_all(blocked.join("child")).unwrap();
+        assert!(sm
+            .truncate_conversation(&session.id, 0, true)
+            .await
+            .is_err());
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "v4");
+        assert_eq!(
+            sm.list_file_checkpoints(&session.id).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_costs_feed_reports_and_budgets() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let create = |name: &str| {
+            sm.create_session(PathBuf::from("/tmp"), name.to_string(), SessionType::User)
+        };
+        let interactive = create("interactive").await.unwrap();
+        let scheduled = create("nightly run").await.unwrap();
+        let recipe = Recipe::builder()
+            .title("nightly")
+            .description("runs every night")
+            .instructions("do the thing")
+            .build()
+            .unwrap();
+        sm.update(&scheduled.id)
+            .recipe(Some(recipe))
+            .apply()
+            .await
+            .unwrap();
+        let scheduled = sm.get_session(&scheduled.id, false).await.unwrap();
+
+        let usage = Usage::new(Some(1000), Some(100), Some(1100));
+        for (session, model, cost) in [
+            (&interactive, "gpt-4o", Some(0.5)),
+            (&interactive, "gpt-4o", Some(0.25)),
+            (&interactive, "local", None),
+            (&scheduled, "gpt-4o", Some(2.0)),
+        ] {
+            sm.record_usage_cost(&session.id, "openai", model, &usage, cost, session)
+                .await
+                .unwrap();
+        }
+
+        let costs = sm.get_session_costs(&interactive.id).await.unwrap();
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs[0].model, "gpt-4o");
+        assert_eq!(costs[0].requests, 2);
+        assert_eq!(costs[0].input_tokens, 2000);
+        assert!((costs[0].cost - 0.75).abs() < 1e-9);
+        assert_eq!(costs[1].unpriced_requests, 1);
+
+        let spend = sm.get_budget_spend(&scheduled).await.unwrap();
+        assert!((spend.session - 2.0).abs() < 1e-9);
+        assert!((spend.daily - 2.75).abs() < 1e-9);
+        assert!((spend.recipe - 2.0).abs() < 1e-9);
+
+        // Deleting a session keeps its spend on the books
+        sm.delete_session(&scheduled.id).await.unwrap();
+        let sessions = sm.list_session_costs(start_of_today()).await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session_id, scheduled.id);
+        assert_eq!(sessions[0].name, "");
+        assert_eq!(sessions[0].recipe.as_deref(), Some("nightly"));
+
+        let insights = sm.get_insights().await.unwrap();
+        assert!((insights.total_cost - 2.75).abs() < 1e-9);
+        assert!((insights.today_cost - 2.75).abs() < 1e-9);
+
+        let models = sm.get_model_costs(start_of_today()).await.unwrap();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].requests, 3);
+        assert!((models[0].cost - 2.75).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_subagent_spend_is_billed_to_the_parent() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let sm = &sm;
+        let with_recipe = move |name: &'static str, description: &'static str| async move {
+            let session = sm
+                .create_session(PathBuf::from("/tmp"), name.to_string(), SessionType::User)
+                .await
+                .unwrap();
+            let recipe = Recipe::builder()
+                .title("nightly")
+                .description(description)
+                .instructions("do the thing")
+                .build()
+                .unwrap();
+            sm.update(&session.id)
+                .recipe(Some(recipe))
+                .apply()
+                .await
+                .unwrap();
+            sm.get_session(&session.id, false).await.unwrap()
+        };
+        let parent = with_recipe("parent", "runs every night").await;
+        let other = with_recipe("other", "a different recipe with the same title").await;
+        let child = sm
+            .create_session(
+                PathBuf::from("/tmp"),
+                "Delegated task".to_string(),
+                SessionType::SubAgent,
+            )
+            .await
+            .unwrap();
+
+        let usage = Usage::new(Some(1000), Some(100), Some(1100));
+        for (session_id, cost, billed_to) in [
+            (&parent.id, 1.0, &parent),
+            (&child.id, 0.5, &parent),
+            (&other.id, 4.0, &other),
+        ] {
+            sm.record_usage_cost(
+                session_id,
+                "openai",
+                "gpt-4o",
+                &usage,
+                Some(cost),
+                billed_to,
+            )
+            .await
+            .unwrap();
+        }
+
+        let spend = sm.get_budget_spend(&parent).await.unwrap();
+        assert!((spend.session - 1.5).abs() < 1e-9);
+        assert!((spend.recipe - 1.5).abs() < 1e-9);
+        assert!((spend.daily - 5.5).abs() < 1e-9);
+
+        let costs = sm.get_session_costs(&parent.id).await.unwrap();
+        assert_eq!(costs[0].requests, 2);
+        let costs = sm.get_session_costs(&child.id).await.unwrap();
+        assert!((costs[0].cost - 0.5).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_tool_usage_counts_calls_and_errors() {
+        use rmcp::model::{CallToolRequestParams, CallToolResult, Content, ErrorCode, ErrorData};
+
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let mut session_ids = Vec::new();
+        for name in ["first", "second"] {
+            let session = sm
+                .create_session(PathBuf::from("/tmp"), name.to_string(), SessionType::User)
+                .await
+                .unwrap();
+            let messages = [
+                Message::assistant()
+                    .with_tool_request("call_1", Ok(CallToolRequestParams::new("shell")))
+                    .with_tool_request("call_2", Ok(CallToolRequestParams::new("read_file"))),
+                Message::user()
+                    .with_tool_response("call_1", Ok(CallToolResult::success(vec![])))
+                    .with_tool_response(
+                        "call_2",
+                        Ok(CallToolResult::error(vec![Content::text("missing")])),
+                    ),
+                Message::assistant()
+                    .with_tool_request("call_3", Ok(CallToolRequestParams::new("shell"))),
+                Message::user().with_tool_response(
+                    "call_3",
+                    Err(ErrorData::new(ErrorCode::INTERNAL_ERROR, "boom", None)),
+                ),
+            ];
+            for message in &messages {
+                sm.add_message(&session.id, message).await.unwrap();
+            }
+            session_ids.push(session.id);
+        }
+
+        let usage = sm.get_tool_usage(None, None).await.unwrap();
+        assert_eq!(
+            usage,
+            vec![
+                ToolUsage {
+                    tool: "shell".to_string(),
+                    calls: 4,
+                    errors: 2,
+                },
+                ToolUsage {
+                    tool: "read_file".to_string(),
+                    calls: 2,
+                    errors: 2,
+                },
+            ]
+        );
+
+        let usage = sm
+            .get_tool_usage(Some(&session_ids[0]), None)
+            .await
+            .unwrap();
+        assert_eq!(usage[0].calls, 2);
+
+        let future = Utc::now() + chrono::Duration::days(1);
+        assert!(sm
+            .get_tool_usage(None, Some(future))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_owners_follow_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let sm = SessionManager::new(temp_dir.path().to_path_buf());
+        let owned = sm
+            .create_session(
+                PathBuf::from("/tmp"),
+                "owned".to_string(),
+                SessionType::User,
+            )
+            .await
+            .unwrap();
+        let other = sm
+            .create_session(
+                PathBuf::from("/tmp"),
+                "other".to_string(),
+                SessionType::User,
+            )
+            .await
+            .unwrap();
+
+        sm.set_session_owner(&owned.id, "tok1").await.unwrap();
+        assert_eq!(
+            sm.get_session_owner(&owned.id).await.unwrap().as_deref(),
+            Some("tok1")
+        );
+        assert_eq!(sm.get_session_owner(&other.id).await.unwrap(), None);
+        assert_eq!(
+            sm.list_owned_session_ids("tok1").await.unwrap(),
+            vec![owned.id.clone()]
+        );
+
+        sm.delete_session(&owned.id).await.unwrap();
+        assert!(sm.list_owned_session_ids("tok1").await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_import_session_with_description_field() {
         const OLD_FORMAT_JSON: &str = r#"{
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How often one tool was called, read from the tool requests and responses stored in messages.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolUsage {
+    pub tool: String,
+    pub calls: i64,
+    /// Calls whose response was an error.
+    pub errors: i64,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ToolUsage {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(ToolUsage {
+            tool: row.try_get("tool")?,
+            calls: row.try_get("calls")?,
+            errors: row.try_get("errors")?,
+        })
+    }
+}
+
+/// Counts tool calls per tool. Requests and responses are matched by id within a session,
+/// optionally restricted to one session and to messages created at or after a unix timestamp.
+pub(crate) const TOOL_USAGE_QUERY: &str = r#"
+    WITH items AS (
+        SELECT m.session_id, c.value AS item
+        FROM messages m, json_each(m.content_json) c
+        WHERE (?1 IS NULL OR m.session_id = ?1)
+          AND (?2 IS NULL OR m.created_timestamp >= ?2)
+    ),
+    requests AS (
+        SELECT session_id,
+               json_extract(item, '$.id') AS call_id,
+               json_extract(item, '$.toolCall.value.name') AS tool
+        FROM items
+        WHERE json_extract(item, '$.type') = 'toolRequest'
+          AND json_extract(item, '$.toolCall.status') = 'success'
+    ),
+    failures AS (
+        SELECT DISTINCT session_id, json_extract(item, '$.id') AS call_id
+        FROM items
+        WHERE json_extract(item, '$.type') = 'toolResponse'
+          AND (json_extract(item, '$.toolResult.status') = 'error'
+               OR json_extract(item, '$.toolResult.value.isError') = 1)
+    )
+    SELECT r.tool AS tool,
+           COUNT(*) AS calls,
+           COUNT(f.call_id) AS errors
+    FROM requests r
+    LEFT JOIN failures f ON f.session_id = r.session_id AND f.call_id = r.call_id
+    WHERE r.tool IS NOT NULL
+    GROUP BY r.tool
+    ORDER BY calls DESC, r.tool
+"#;
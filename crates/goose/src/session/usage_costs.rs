@@ -0,0 +1,115 @@
+use crate::providers::base::Usage;
+use crate::providers::canonical::{maybe_get_canonical_model, Pricing};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Ledger of priced provider calls. Rows outlive their session so that deleting a session
+/// does not hand back daily budget.
+pub const USAGE_COSTS_TABLE: &str = "usage_costs";
+
+const TOKENS_PER_PRICING_UNIT: f64 = 1_000_000.0;
+
+/// Cost in USD of `usage` at `pricing`, or `None` when the model has no known price.
+pub fn price_usage(pricing: &Pricing, usage: &Usage) -> Option<f64> {
+    if pricing.input.is_none() && pricing.output.is_none() {
+        return None;
+    }
+    let input = usage.input_tokens.unwrap_or(0).max(0) as f64;
+    let output = usage.output_tokens.unwrap_or(0).max(0) as f64;
+    Some(
+        (input * pricing.input.unwrap_or(0.0) + output * pricing.output.unwrap_or(0.0))
+            / TOKENS_PER_PRICING_UNIT,
+    )
+}
+
+/// Prices a call using the canonical model registry.
+pub fn usage_cost(provider: &str, model: &str, usage: &Usage) -> Option<f64> {
+    let canonical = maybe_get_canonical_model(provider, model)?;
+    price_usage(&canonical.cost, usage)
+}
+
+/// Spend of one provider/model pair.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCost {
+    pub provider: String,
+    pub model: String,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// USD, counting only requests with known pricing.
+    pub cost: f64,
+    /// Requests made with models that have no known pricing.
+    pub unpriced_requests: i64,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for ModelCost {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(ModelCost {
+            provider: row.try_get("provider")?,
+            model: row.try_get("model")?,
+            requests: row.try_get("requests")?,
+            input_tokens: row.try_get("input_tokens")?,
+            output_tokens: row.try_get("output_tokens")?,
+            cost: row.try_get("cost")?,
+            unpriced_requests: row.try_get("unpriced_requests")?,
+        })
+    }
+}
+
+/// Total spend of one session over a time window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCost {
+    pub session_id: String,
+    /// Session name, empty when the session has since been deleted.
+    pub name: String,
+    pub recipe: Option<String>,
+    pub requests: i64,
+    pub cost: f64,
+}
+
+impl sqlx::FromRow<'_, sqlx::sqlite::SqliteRow> for SessionCost {
+    fn from_row(row: &sqlx::sqlite::SqliteRow) -> Result<Self, sqlx::Error> {
+        use sqlx::Row;
+
+        Ok(SessionCost {
+            session_id: row.try_get("session_id")?,
+            name: row
+                .try_get::<Option<String>, _>("name")?
+                .unwrap_or_default(),
+            recipe: row.try_get("recipe")?,
+            requests: row.try_get("requests")?,
+            cost: row.try_get("cost")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_per_million_tokens() {
+        let pricing = Pricing {
+            input: Some(3.0),
+            output: Some(15.0),
+            cache_read: None,
+            cache_write: None,
+        };
+        let usage = Usage::new(Some(200_000), Some(10_000), Some(210_000));
+        let cost = price_usage(&pricing, &usage).unwrap();
+        assert!((cost - 0.75).abs() < 1e-9);
+
+        assert_eq!(price_usage(&Pricing::default(), &usage), None);
+    }
+
+    #[test]
+    fn prices_canonical_models() {
+        let usage = Usage::new(Some(1_000_000), Some(0), Some(1_000_000));
+        assert!(usage_cost("openai", "gpt-4o", &usage).is_some_and(|cost| cost > 0.0));
+        assert_eq!(usage_cost("openai", "not-a-real-model", &usage), None);
+    }
+}
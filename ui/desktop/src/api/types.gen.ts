@@ -36,6 +36,53 @@ export type Annotations = {
     priority?: number;
 };
 
+/**
+ * Public description of a token, without its secret.
+ */
+export type ApiToken = {
+    createdAt: string;
+    id: string;
+    name: string;
+    scopes: Array<TokenScope>;
+};
+
+export type AuditDecision = 'allowed' | 'denied';
+
+/**
+ * One line of the audit log.
+ */
+export type AuditEntry = {
+    /**
+     * Who made a config change, when known
+     */
+    actor?: string | null;
+    /**
+     * SHA-256 of the tool call arguments as JSON
+     */
+    argumentsHash?: string | null;
+    decision?: AuditDecision | null;
+    /**
+     * Config key, extension or provider that was changed
+     */
+    key?: string | null;
+    kind: AuditKind;
+    reason?: string | null;
+    sessionId?: string | null;
+    /**
+     * What made the decision: `user` for a confirmation, otherwise the tool inspector
+     * (`permission`, `security`, ...)
+     */
+    source?: string | null;
+    /**
+     * Tool calls: the arguments, truncated. Config changes: what was changed.
+     */
+    summary: string;
+    timestamp: string;
+    tool?: string | null;
+};
+
+export type AuditKind = 'tool_call' | 'config_change';
+
 export type Author = {
     contact?: string | null;
     metadata?: string | null;
@@ -46,6 +93,17 @@ export type AuthorRequest = {
     metadata?: string | null;
 };
 
+export type BranchSessionRequest = {
+    /**
+     * Last message of the session that the branch shares
+     */
+    messageId: string;
+    /**
+     * Name for the branch, defaults to the session's name with "(branch)" appended
+     */
+    name?: string | null;
+};
+
 export type CallToolRequest = {
     arguments: unknown;
     name: string;
@@ -132,6 +190,22 @@ export type Content = RawTextContent | RawImageContent | RawEmbeddedResource | R
 
 export type Conversation = Array<Message>;
 
+export type CreateApiTokenRequest = {
+    /**
+     * Unique name to recognise the token by
+     */
+    name: string;
+    scopes: Array<TokenScope>;
+};
+
+export type CreateApiTokenResponse = {
+    /**
+     * The token to send as `X-Secret-Key` or `Authorization: Bearer`. It is only shown once.
+     */
+    secret: string;
+    token: ApiToken;
+};
+
 export type CreateRecipeRequest = {
     author?: AuthorRequest | null;
     session_id: string;
@@ -416,6 +490,8 @@ export type ExtensionEntry = ExtensionConfig & {
     enabled: boolean;
 };
 
+export type ExtensionHealth = 'running' | 'stopped' | 'restarting' | 'failed' | 'crash_looping';
+
 export type ExtensionLoadResult = {
     error?: string | null;
     name: string;
@@ -433,8 +509,43 @@ export type ExtensionResponse = {
     warnings?: Array<string>;
 };
 
+export type ExtensionStatus = {
+    health: ExtensionHealth;
+    last_error?: string | null;
+    name: string;
+    /**
+     * Restarts since the extension was added
+     */
+    restarts: number;
+};
+
+export type ExtensionStatusQuery = {
+    session_id: string;
+};
+
+/**
+ * The content a file had before a tool changed it.
+ */
+export type FileCheckpoint = {
+    /**
+     * Unix timestamp in seconds, comparable with message `created` timestamps.
+     */
+    createdTimestamp: number;
+    /**
+     * False when the tool created the file; restoring removes it again.
+     */
+    existed: boolean;
+    id: number;
+    path: string;
+    toolName: string;
+};
+
 export type ForkRequest = {
     copy: boolean;
+    /**
+     * When truncating, also roll back files changed since the timestamp
+     */
+    restoreFiles?: boolean;
     timestamp?: number | null;
     truncate: boolean;
 };
@@ -612,6 +723,18 @@ export type Message = {
 /**
  * Content passed inside a message, which can be both simple content and tool content
  */
+export type MessageCheckpoints = {
+    checkpoints: Array<FileCheckpoint>;
+    /**
+     * The message whose tool calls changed the files, if it is still in the conversation
+     */
+    messageId?: string | null;
+    /**
+     * Timestamp to restore from to undo these changes and everything after them
+     */
+    timestamp: number;
+};
+
 export type MessageContent = (TextContent & {
     type: 'text';
 }) | (ImageContent & {
@@ -650,6 +773,7 @@ export type MessageEvent = {
 } | {
     mode: string;
     model: string;
+    provider?: string | null;
     type: 'ModelChange';
 } | {
     message: {
@@ -1054,6 +1178,12 @@ export type ResourceMetadata = {
     ui?: UiMetadata | null;
 };
 
+export type ResourceSubscriptionRequest = {
+    extension_name: string;
+    session_id: string;
+    uri: string;
+};
+
 export type Response = {
     json_schema?: unknown;
 };
@@ -1066,6 +1196,20 @@ export type RestartAgentResponse = {
     extension_results: Array<ExtensionLoadResult>;
 };
 
+export type RestoreCheckpointsRequest = {
+    /**
+     * Roll back every file change made at or after this timestamp
+     */
+    timestamp: number;
+};
+
+export type RestoreCheckpointsResponse = {
+    /**
+     * One entry per file that was rolled back
+     */
+    restored: Array<FileCheckpoint>;
+};
+
 export type ResumeAgentRequest = {
     load_model_and_extensions: boolean;
     session_id: string;
@@ -1124,6 +1268,39 @@ export type SamplingConfig = {
     type: 'MirostatV2';
 };
 
+/**
+ * What a sandboxed command may do. Set globally with `GOOSE_SANDBOX`, in a recipe's
+ * `settings.sandbox`, or for a single session.
+ */
+export type SandboxPolicy = {
+    /**
+     * Cut sandboxed commands off from the network
+     */
+    block_network?: boolean;
+    /**
+     * CPU time limit per process in seconds
+     */
+    cpu_secs?: number | null;
+    enabled?: boolean;
+    /**
+     * Address space limit per process in megabytes
+     */
+    memory_mb?: number | null;
+    /**
+     * Only sandbox in these goose modes (`auto`, `approve`, `smart_approve`, `chat`); all
+     * modes when empty
+     */
+    modes?: Array<string>;
+    /**
+     * Wall clock limit per command in seconds, applied on top of the tool's own timeout
+     */
+    timeout_secs?: number | null;
+    /**
+     * Writable in addition to the working directory
+     */
+    writable_paths?: Array<string>;
+};
+
 export type SavePromptRequest = {
     content: string;
 };
@@ -1168,12 +1345,20 @@ export type Session = {
     conversation?: Conversation | null;
     created_at: string;
     extension_data: ExtensionData;
+    /**
+     * Last message of the parent that this branch shares
+     */
+    fork_message_id?: string | null;
     id: string;
     input_tokens?: number | null;
     message_count: number;
     model_config?: ModelConfig | null;
     name: string;
     output_tokens?: number | null;
+    /**
+     * Session this one was branched from
+     */
+    parent_session_id?: string | null;
     provider_name?: string | null;
     recipe?: Recipe | null;
     schedule_id?: string | null;
@@ -1187,6 +1372,33 @@ export type Session = {
     working_dir: string;
 };
 
+/**
+ * A session and the branches forked from it.
+ */
+export type SessionBranch = {
+    /**
+     * Branches forked from this session, oldest first
+     */
+    children: Array<SessionBranch>;
+    createdAt: string;
+    /**
+     * Last message of the parent that this branch shares
+     */
+    forkMessageId?: string | null;
+    /**
+     * Messages in the branch, including those shared with its parent
+     */
+    messageCount: number;
+    name: string;
+    parentSessionId?: string | null;
+    sessionId: string;
+    updatedAt: string;
+};
+
+export type SessionCheckpointsResponse = {
+    messages: Array<MessageCheckpoints>;
+};
+
 export type SessionDisplayInfo = {
     accumulatedInputTokens?: number | null;
     accumulatedOutputTokens?: number | null;
@@ -1207,6 +1419,14 @@ export type SessionExtensionsResponse = {
 };
 
 export type SessionInsights = {
+    /**
+     * USD spent since local midnight
+     */
+    todayCost: number;
+    /**
+     * USD spent across all sessions with known model pricing
+     */
+    totalCost: number;
     totalSessions: number;
     totalTokens: number;
 };
@@ -1235,9 +1455,18 @@ export type SetSlashCommandRequest = {
 };
 
 export type Settings = {
+    /**
+     * Maximum spend in USD across all runs of this recipe in a day
+     */
+    budget?: number | null;
     goose_model?: string | null;
     goose_provider?: string | null;
     max_turns?: number | null;
+    /**
+     * Replay identical provider requests from the on-disk response cache
+     */
+    response_cache?: boolean | null;
+    sandbox?: SandboxPolicy | null;
     temperature?: number | null;
 };
 
@@ -1342,6 +1571,29 @@ export type ThinkingContent = {
     thinking: string;
 };
 
+export type TodoItem = {
+    id: number;
+    notes?: string | null;
+    parentId?: number | null;
+    status: TodoStatus;
+    title: string;
+};
+
+/**
+ * TODO extension state: an ordered list of items, nested through `parent_id`
+ */
+export type TodoList = {
+    items: Array<TodoItem>;
+    nextId?: number;
+};
+
+export type TodoStatus = 'pending' | 'in_progress' | 'done' | 'blocked';
+
+/**
+ * What a goose-server API token is allowed to do.
+ */
+export type TokenScope = 'read-sessions' | 'reply' | 'manage-config' | 'manage-extensions' | 'schedule';
+
 export type TokenState = {
     accumulatedInputTokens: number;
     accumulatedOutputTokens: number;
@@ -1697,6 +1949,38 @@ export type ExportAppResponses = {
 
 export type ExportAppResponse = ExportAppResponses[keyof ExportAppResponses];
 
+export type GetExtensionStatusData = {
+    body?: never;
+    path?: never;
+    query: {
+        /**
+         * Session whose extensions to report on
+         */
+        session_id: string;
+    };
+    url: '/agent/extension_status';
+};
+
+export type GetExtensionStatusErrors = {
+    /**
+     * Unauthorized - invalid secret key
+     */
+    401: unknown;
+    /**
+     * Agent not initialized
+     */
+    424: unknown;
+};
+
+export type GetExtensionStatusResponses = {
+    /**
+     * Health of each extension
+     */
+    200: Array<ExtensionStatus>;
+};
+
+export type GetExtensionStatusResponse = GetExtensionStatusResponses[keyof GetExtensionStatusResponses];
+
 export type ImportAppData = {
     body: ImportAppRequest;
     path?: never;
@@ -1949,6 +2233,39 @@ export type StopAgentResponses = {
 
 export type StopAgentResponse = StopAgentResponses[keyof StopAgentResponses];
 
+export type SubscribeResourceData = {
+    body: ResourceSubscriptionRequest;
+    path?: never;
+    query?: never;
+    url: '/agent/subscribe_resource';
+};
+
+export type SubscribeResourceErrors = {
+    /**
+     * Extension not found or does not support subscriptions
+     */
+    400: unknown;
+    /**
+     * Unauthorized - invalid secret key
+     */
+    401: unknown;
+    /**
+     * Agent not initialized
+     */
+    424: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type SubscribeResourceResponses = {
+    /**
+     * Subscribed; updates arrive as notifications in the reply stream
+     */
+    200: unknown;
+};
+
 export type GetToolsData = {
     body?: never;
     path?: never;
@@ -1989,6 +2306,39 @@ export type GetToolsResponses = {
 
 export type GetToolsResponse = GetToolsResponses[keyof GetToolsResponses];
 
+export type UnsubscribeResourceData = {
+    body: ResourceSubscriptionRequest;
+    path?: never;
+    query?: never;
+    url: '/agent/unsubscribe_resource';
+};
+
+export type UnsubscribeResourceErrors = {
+    /**
+     * Extension not found or does not support subscriptions
+     */
+    400: unknown;
+    /**
+     * Unauthorized - invalid secret key
+     */
+    401: unknown;
+    /**
+     * Agent not initialized
+     */
+    424: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type UnsubscribeResourceResponses = {
+    /**
+     * Unsubscribed
+     */
+    200: unknown;
+};
+
 export type UpdateFromSessionData = {
     body: UpdateFromSessionRequest;
     path?: never;
@@ -2080,6 +2430,164 @@ export type UpdateWorkingDirResponses = {
     200: unknown;
 };
 
+export type QueryAuditLogData = {
+    body?: never;
+    path?: never;
+    query?: {
+        /**
+         * Only entries at or after this time (RFC 3339)
+         */
+        since?: string | null;
+        /**
+         * Only entries before this time (RFC 3339)
+         */
+        until?: string | null;
+        /**
+         * Only tool calls in this session
+         */
+        sessionId?: string | null;
+        /**
+         * Only calls to this tool
+         */
+        tool?: string | null;
+        /**
+         * Only tool calls or only config changes
+         */
+        kind?: AuditKind | null;
+        /**
+         * Maximum entries (default: 100, max: 1000)
+         */
+        limit?: number | null;
+    };
+    url: '/audit';
+};
+
+export type QueryAuditLogErrors = {
+    /**
+     * Bad request - Invalid filter
+     */
+    400: unknown;
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type QueryAuditLogResponses = {
+    /**
+     * Audit entries, most recent first
+     */
+    200: Array<AuditEntry>;
+};
+
+export type QueryAuditLogResponse = QueryAuditLogResponses[keyof QueryAuditLogResponses];
+
+export type ListApiTokensData = {
+    body?: never;
+    path?: never;
+    query?: never;
+    url: '/auth/tokens';
+};
+
+export type ListApiTokensErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Forbidden - Only the server's secret key can manage tokens
+     */
+    403: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type ListApiTokensResponses = {
+    /**
+     * API tokens, without their secrets
+     */
+    200: Array<ApiToken>;
+};
+
+export type ListApiTokensResponse = ListApiTokensResponses[keyof ListApiTokensResponses];
+
+export type CreateApiTokenData = {
+    body: CreateApiTokenRequest;
+    path?: never;
+    query?: never;
+    url: '/auth/tokens';
+};
+
+export type CreateApiTokenErrors = {
+    /**
+     * Bad request - Missing name or scopes, or name already taken
+     */
+    400: unknown;
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Forbidden - Only the server's secret key can manage tokens
+     */
+    403: unknown;
+};
+
+export type CreateApiTokenResponses = {
+    /**
+     * Token created
+     */
+    200: CreateApiTokenResponse;
+};
+
+export type CreateApiTokenResponse2 = CreateApiTokenResponses[keyof CreateApiTokenResponses];
+
+export type RevokeApiTokenData = {
+    body?: never;
+    path: {
+        /**
+         * Id or name of the token
+         */
+        id: string;
+    };
+    query?: never;
+    url: '/auth/tokens/{id}';
+};
+
+export type RevokeApiTokenErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Forbidden - Only the server's secret key can manage tokens
+     */
+    403: unknown;
+    /**
+     * Token not found
+     */
+    404: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type RevokeApiTokenResponses = {
+    /**
+     * Token revoked
+     */
+    204: void;
+};
+
+export type RevokeApiTokenResponse = RevokeApiTokenResponses[keyof RevokeApiTokenResponses];
+
 export type ReadAllConfigData = {
     body?: never;
     path?: never;
@@ -3993,6 +4501,18 @@ export type SearchSessionsData = {
          * Filter before date (ISO 8601)
          */
         before_date?: string | null;
+        /**
+         * Only match messages from this role (user or assistant)
+         */
+        role?: string | null;
+        /**
+         * Only match sessions with this working directory
+         */
+        workingDir?: string | null;
+        /**
+         * Comma-separated session types (default: user,scheduled)
+         */
+        sessionType?: string | null;
     };
     url: '/sessions/search';
 };
@@ -4014,7 +4534,7 @@ export type SearchSessionsErrors = {
 
 export type SearchSessionsResponses = {
     /**
-     * Matching sessions
+     * Matching sessions, most relevant first
      */
     200: Array<Session>;
 };
@@ -4091,6 +4611,146 @@ export type GetSessionResponses = {
 
 export type GetSessionResponse = GetSessionResponses[keyof GetSessionResponses];
 
+export type BranchSessionData = {
+    body: BranchSessionRequest;
+    path: {
+        /**
+         * Unique identifier for the session
+         */
+        session_id: string;
+    };
+    query?: never;
+    url: '/sessions/{session_id}/branch';
+};
+
+export type BranchSessionErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Session or message not found
+     */
+    404: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type BranchSessionResponses = {
+    /**
+     * Branch created successfully
+     */
+    200: Session;
+};
+
+export type BranchSessionResponse = BranchSessionResponses[keyof BranchSessionResponses];
+
+export type GetSessionBranchesData = {
+    body?: never;
+    path: {
+        /**
+         * Unique identifier for the session
+         */
+        session_id: string;
+    };
+    query?: never;
+    url: '/sessions/{session_id}/branches';
+};
+
+export type GetSessionBranchesErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Session not found
+     */
+    404: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type GetSessionBranchesResponses = {
+    /**
+     * Branch tree containing the session, from its root
+     */
+    200: SessionBranch;
+};
+
+export type GetSessionBranchesResponse = GetSessionBranchesResponses[keyof GetSessionBranchesResponses];
+
+export type ListSessionCheckpointsData = {
+    body?: never;
+    path: {
+        /**
+         * Unique identifier for the session
+         */
+        session_id: string;
+    };
+    query?: never;
+    url: '/sessions/{session_id}/checkpoints';
+};
+
+export type ListSessionCheckpointsErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Session not found
+     */
+    404: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type ListSessionCheckpointsResponses = {
+    /**
+     * File checkpoints grouped by message, oldest first
+     */
+    200: SessionCheckpointsResponse;
+};
+
+export type ListSessionCheckpointsResponse = ListSessionCheckpointsResponses[keyof ListSessionCheckpointsResponses];
+
+export type RestoreSessionCheckpointsData = {
+    body: RestoreCheckpointsRequest;
+    path: {
+        /**
+         * Unique identifier for the session
+         */
+        session_id: string;
+    };
+    query?: never;
+    url: '/sessions/{session_id}/checkpoints/restore';
+};
+
+export type RestoreSessionCheckpointsErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type RestoreSessionCheckpointsResponses = {
+    /**
+     * Files restored successfully
+     */
+    200: RestoreCheckpointsResponse;
+};
+
+export type RestoreSessionCheckpointsResponse = RestoreSessionCheckpointsResponses[keyof RestoreSessionCheckpointsResponses];
+
 export type ExportSessionData = {
     body?: never;
     path: {
@@ -4241,6 +4901,42 @@ export type UpdateSessionNameResponses = {
     200: unknown;
 };
 
+export type GetSessionTodosData = {
+    body?: never;
+    path: {
+        /**
+         * Unique identifier for the session
+         */
+        session_id: string;
+    };
+    query?: never;
+    url: '/sessions/{session_id}/todos';
+};
+
+export type GetSessionTodosErrors = {
+    /**
+     * Unauthorized - Invalid or missing API key
+     */
+    401: unknown;
+    /**
+     * Session not found
+     */
+    404: unknown;
+    /**
+     * Internal server error
+     */
+    500: unknown;
+};
+
+export type GetSessionTodosResponses = {
+    /**
+     * The session's todo list
+     */
+    200: TodoList;
+};
+
+export type GetSessionTodosResponse = GetSessionTodosResponses[keyof GetSessionTodosResponses];
+
 export type UpdateSessionUserRecipeValuesData = {
     body: UpdateSessionUserRecipeValuesRequest;
     path: {
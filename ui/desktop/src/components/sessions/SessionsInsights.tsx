@@ -39,6 +39,8 @@ export function SessionInsights() {
         setInsights({
           totalSessions: 0,
           totalTokens: 0,
+          totalCost: 0,
+          todayCost: 0,
         });
       } finally {
         setIsLoading(false);
@@ -67,6 +69,8 @@ export function SessionInsights() {
             mostActiveDirs: [],
             avgSessionDuration: 0,
             totalTokens: 0,
+            totalCost: 0,
+            todayCost: 0,
             recentActivity: [],
           };
         }